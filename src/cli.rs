@@ -3,7 +3,11 @@
 //! This module defines the CLI structure using `clap` derive macros,
 //! providing a familiar interface similar to the standard `unzip` utility.
 
-use clap::Parser;
+use anyhow::{Result, anyhow, bail};
+use clap::{ArgGroup, Parser, ValueEnum};
+use std::path::{Path, PathBuf};
+
+use crate::zip::{Charset, Preserve, Recode, sanitize_path};
 
 /// Command-line arguments for the runzip utility.
 ///
@@ -12,6 +16,7 @@ use clap::Parser;
 /// while adding support for HTTP URLs.
 #[derive(Parser, Debug)]
 #[command(name = "runzip")]
+#[command(group(ArgGroup::new("output_dir").args(["extract_dir", "auto_dir"])))]
 #[command(version)]
 #[command(about = "A Rust unzip utility with HTTP URL support", long_about = None)]
 #[command(after_help = "Examples:\n  \
@@ -19,10 +24,10 @@ use clap::Parser;
   runzip -p foo.zip | more       send contents of foo.zip via pipe into more\n  \
   runzip -l https://example.com/archive.zip   list files from remote ZIP")]
 pub struct Cli {
-    /// ZIP file path or HTTP URL.
+    /// ZIP file path or URL.
     ///
-    /// Can be either a local filesystem path or an HTTP/HTTPS URL.
-    /// When an HTTP URL is provided, the tool uses Range requests
+    /// Can be a local filesystem path, a `file://` URL or an HTTP/HTTPS
+    /// URL. When an HTTP URL is provided, the tool uses Range requests
     /// to efficiently access specific parts of the archive.
     #[arg(value_name = "FILE")]
     pub file: String,
@@ -49,6 +54,161 @@ pub struct Cli {
     #[arg(short = 'v')]
     pub verbose: bool,
 
+    /// List files as CSV.
+    ///
+    /// Prints a header row, then one
+    /// `name,size,compressed,method,crc,modified,is_dir` row per entry.
+    /// Names containing commas or quotes are quoted.
+    #[arg(long = "csv", conflicts_with_all = ["list", "verbose"])]
+    pub csv: bool,
+
+    /// Stop listing after N entries.
+    ///
+    /// Only applies to listing (`-l`/`-v`/`--csv`). The remaining entries
+    /// are not parsed; a footer reports how many were left out, except in
+    /// CSV output.
+    #[arg(long = "entries-limit", value_name = "N")]
+    pub entries_limit: Option<usize>,
+
+    /// Only work on entries at most N path components deep.
+    ///
+    /// Applies to listing and extraction. `--depth 1` keeps only the
+    /// top-level files and directories, `--depth 2` also their direct
+    /// children, and so on.
+    #[arg(long = "depth", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub depth: Option<u32>,
+
+    /// List directory names without their trailing slash.
+    ///
+    /// Only changes how listings (`-l`/`-v`/`--csv`) print directories;
+    /// they are still recognised and extracted as directories.
+    #[arg(long = "strip-trailing-slash")]
+    pub strip_trailing_slash: bool,
+
+    /// Print only the archive comment.
+    ///
+    /// Reads just the end of the archive in a single request and never
+    /// touches the Central Directory, so it's the cheapest way to get
+    /// metadata such as build information from a remote archive.
+    #[arg(long = "zip-comment", conflicts_with_all = ["list", "verbose", "csv", "count"])]
+    pub zip_comment: bool,
+
+    /// Print only the number of entries.
+    ///
+    /// Reads just the Central Directory. `--count=all` (the default)
+    /// counts files and directories, `--count=files` and `--count=dirs`
+    /// count only one kind.
+    #[arg(
+        long = "count",
+        value_name = "WHAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "all"
+    )]
+    pub count: Option<CountMode>,
+
+    /// With `--count`, also print the total uncompressed size in bytes.
+    #[arg(long = "sum-sizes", requires = "count")]
+    pub sum_sizes: bool,
+
+    /// Search only the last N bytes for the end of central directory.
+    ///
+    /// Defaults to 65557 bytes, enough for the largest archive comment.
+    /// Smaller values reduce the initial download for remote archives
+    /// without comments; larger ones can skip over appended data.
+    #[arg(long = "eocd-search-window", value_name = "BYTES")]
+    pub eocd_search_window: Option<u64>,
+
+    /// Character set of entry names not flagged as UTF-8 (default: cp437).
+    ///
+    /// Archives made on non-Western systems often use the local code
+    /// page, e.g. `shift_jis` for Japanese or `gbk` for Chinese. Any
+    /// WHATWG encoding label is accepted.
+    #[arg(long = "charset", value_name = "NAME", value_parser = parse_charset)]
+    pub charset: Option<Charset>,
+
+    /// Longest entry name accepted, in bytes (default: 4096).
+    ///
+    /// Archives with longer names are rejected as malicious, unless
+    /// `--skip-long-names` is given.
+    #[arg(long = "max-name-length", value_name = "BYTES")]
+    pub max_name_length: Option<usize>,
+
+    /// Skip entries whose names exceed `--max-name-length` instead of
+    /// rejecting the archive.
+    #[arg(long = "skip-long-names")]
+    pub skip_long_names: bool,
+
+    /// Convert the contents of text files between encodings, e.g.
+    /// `shift_jis:utf-8`.
+    ///
+    /// Files are converted only if they contain no NUL bytes and are
+    /// valid in the source encoding, so binary files are left alone. Use
+    /// `--recode-only` to choose the files explicitly instead.
+    #[arg(long = "recode", value_name = "FROM:TO", value_parser = parse_recode)]
+    pub recode: Option<Recode>,
+
+    /// With `--recode`, convert exactly the files matching these
+    /// patterns (can be repeated).
+    #[arg(long = "recode-only", value_name = "PATTERN", requires = "recode")]
+    pub recode_only: Vec<String>,
+
+    /// Test compressed archive data.
+    ///
+    /// Stream the selected files through decompression and check their
+    /// CRC-32 without writing anything to disk. File patterns and `-x`
+    /// exclusions apply, so only the chosen files are read.
+    #[arg(short = 't', long = "test")]
+    pub test: bool,
+
+    /// With `-t`, show the overall percentage of data checked.
+    ///
+    /// The percentage is based on the uncompressed size of the selected
+    /// files and is updated on stderr while each file is streamed.
+    #[arg(long = "progress", requires = "test")]
+    pub progress: bool,
+
+    /// Recover files from a damaged archive (best-effort).
+    ///
+    /// Ignore the Central Directory and scan the whole archive for local
+    /// file headers instead, recovering every entry whose header and data
+    /// are intact. Use it when the archive can't be opened normally. Works
+    /// with listing, testing and extraction; permissions are not restored.
+    #[arg(long = "salvage")]
+    pub salvage: bool,
+
+    /// Retry mislabeled entries with other compression methods.
+    ///
+    /// When an entry fails to decode with its declared method, or fails
+    /// its CRC check, try the other supported methods and keep whichever
+    /// matches the recorded CRC-32, with a warning. Best-effort recovery
+    /// for archives with a wrong method field.
+    #[arg(long = "try-methods")]
+    pub try_methods: bool,
+
+    /// Check data descriptors against the Central Directory.
+    ///
+    /// Entries written by streaming tools are followed by a data
+    /// descriptor repeating their CRC-32 and sizes; such entries fail if
+    /// the two disagree, which points to a truncated or tampered archive.
+    /// Extracted data is always verified, which includes this check, so
+    /// the flag is only accepted for compatibility.
+    #[arg(long = "check-descriptors", hide = true)]
+    pub check_descriptors: bool,
+
+    /// Skip entries using unsupported compression methods.
+    ///
+    /// Such entries are reported and skipped while the rest of the
+    /// archive is extracted or tested. If any were skipped and nothing
+    /// else failed, runzip exits with status 1 instead of 0.
+    #[arg(long = "skip-unsupported", conflicts_with = "fail_on_unsupported")]
+    pub skip_unsupported: bool,
+
+    /// Treat entries using unsupported compression methods as errors
+    /// (default).
+    #[arg(long = "fail-on-unsupported")]
+    pub fail_on_unsupported: bool,
+
     /// Extract files to pipe, no messages.
     ///
     /// Write extracted file contents directly to stdout.
@@ -57,6 +217,21 @@ pub struct Cli {
     #[arg(short = 'p')]
     pub pipe: bool,
 
+    /// Password for encrypted files (traditional PKWARE or WinZip AES).
+    ///
+    /// Like unzip's `-P`, the password may be visible to other users in
+    /// the process list.
+    #[arg(short = 'P', long = "password", value_name = "PASSWORD")]
+    pub password: Option<String>,
+
+    /// Give up on an entry that takes longer than SECONDS to extract.
+    ///
+    /// Unlike the HTTP timeout, which applies to each request, this
+    /// bounds the whole entry. The partial file is removed and the
+    /// entry reported as failed.
+    #[arg(long = "entry-timeout", value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub entry_timeout: Option<u64>,
+
     /// Extract files into exdir.
     ///
     /// Specify a target directory for extraction.
@@ -64,6 +239,27 @@ pub struct Cli {
     #[arg(short = 'd', value_name = "DIR")]
     pub extract_dir: Option<String>,
 
+    /// Extract into a directory named after the archive.
+    ///
+    /// `foo.zip` is extracted into `foo`, whether it's a local file or a
+    /// URL. Combine with `--only-new-dirs` to be sure that directory
+    /// holds nothing but the archive's files.
+    #[arg(long = "auto-dir", conflicts_with = "pipe")]
+    pub auto_dir: bool,
+
+    /// Only extract into a directory created by this run.
+    ///
+    /// The `-d` or `--auto-dir` directory is created before extraction;
+    /// if it already exists and isn't empty, nothing is extracted unless
+    /// `-o` is given. This guarantees the output isn't mixed with
+    /// existing files.
+    #[arg(
+        long = "only-new-dirs",
+        requires = "output_dir",
+        conflicts_with = "pipe"
+    )]
+    pub only_new_dirs: bool,
+
     /// Exclude files that follow.
     ///
     /// Specify patterns for files to exclude from extraction.
@@ -71,6 +267,29 @@ pub struct Cli {
     #[arg(short = 'x', value_name = "FILE", num_args = 1..)]
     pub exclude: Vec<String>,
 
+    /// Exclude the directory PATH and everything below it.
+    ///
+    /// Skips every entry whose name starts with `PATH/`, even if it was
+    /// requested by name. Can be given multiple times.
+    #[arg(long = "exclude-dir", value_name = "PATH")]
+    pub exclude_dir: Vec<String>,
+
+    /// Only work on files with one of these extensions.
+    ///
+    /// Takes a comma-separated list such as `jpg,png`, compared without
+    /// regard to case; a leading dot is ignored. Only the part after the
+    /// last dot of the file name counts, so `.tar.gz` files have the
+    /// extension `gz`, and dotfiles like `.bashrc` have none.
+    #[arg(long = "include-ext", value_name = "EXT", value_delimiter = ',')]
+    pub include_ext: Vec<String>,
+
+    /// Skip files with one of these extensions.
+    ///
+    /// Takes a comma-separated list such as `log,tmp`, matched like
+    /// `--include-ext`.
+    #[arg(long = "exclude-ext", value_name = "EXT", value_delimiter = ',')]
+    pub exclude_ext: Vec<String>,
+
     /// Never overwrite existing files.
     ///
     /// Skip extraction of files that already exist in the target location.
@@ -85,6 +304,16 @@ pub struct Cli {
     #[arg(short = 'o')]
     pub overwrite: bool,
 
+    /// Overwrite existing files only if their content differs.
+    ///
+    /// Existing files with the entry's size and CRC-32 are left alone
+    /// without a warning; all others are overwritten without `-o`.
+    #[arg(
+        long = "overwrite-if-different",
+        conflicts_with_all = ["never_overwrite", "overwrite"]
+    )]
+    pub overwrite_if_different: bool,
+
     /// Junk paths (do not make directories).
     ///
     /// Extract all files to the target directory without creating
@@ -92,6 +321,119 @@ pub struct Cli {
     #[arg(short = 'j')]
     pub junk_paths: bool,
 
+    /// Strip the archive's single top-level directory, if it has one.
+    ///
+    /// When every entry lies inside the same top-level directory, that
+    /// directory is left out of the extracted paths, so `project/src/a.c`
+    /// is written as `src/a.c`. Archives without such a wrapper extract
+    /// normally.
+    #[arg(long = "auto-strip")]
+    pub auto_strip: bool,
+
+    /// Extract every entry below DIR inside the output directory.
+    ///
+    /// `--add-prefix v1 -d out` writes `docs/a.txt` to
+    /// `out/v1/docs/a.txt`, which keeps several archives apart in one
+    /// tree. DIR must be a relative path without `..`.
+    #[arg(
+        long = "add-prefix",
+        value_name = "DIR",
+        value_parser = parse_prefix,
+        conflicts_with_all = ["pipe", "flatten_into"]
+    )]
+    pub add_prefix: Option<PathBuf>,
+
+    /// Rename entries on extraction using a mapping file.
+    ///
+    /// Each line of the file holds `oldpath<TAB>newpath`. Matching
+    /// entries are written to `newpath` (relative to the output
+    /// directory); other entries extract normally.
+    #[arg(long = "destination-map", value_name = "PATH")]
+    pub destination_map: Option<String>,
+
+    /// Only extract entries listed in the destination map.
+    #[arg(long = "map-only", requires = "destination_map")]
+    pub map_only: bool,
+
+    /// Metadata to restore on extracted files.
+    ///
+    /// `all`, `none`, or a comma-separated list of `perms` (Unix mode
+    /// bits), `times` (modification time), `owner` (UID/GID, usually
+    /// needs root) and `links` (recreate symbolic links).
+    #[arg(
+        long = "preserve",
+        value_name = "LIST",
+        default_value = "perms,times,links"
+    )]
+    pub preserve: Preserve,
+
+    /// Never create directories while extracting.
+    ///
+    /// Only files whose parent directory already exists are extracted;
+    /// entries that would need a new directory are reported and skipped.
+    #[arg(long = "no-directory-creation")]
+    pub no_directory_creation: bool,
+
+    /// Check each output path right before writing it.
+    ///
+    /// The path is resolved through any existing symlinks and must end
+    /// up inside the output directory, otherwise the file is not
+    /// written. Always on for HTTP sources and when symbolic links are
+    /// restored (`--preserve links`).
+    #[arg(long = "no-overwrite-outside-root")]
+    pub no_overwrite_outside_root: bool,
+
+    /// Flatten all files into DIR, deduplicating identical files.
+    ///
+    /// Like `-j -d DIR`, but files with the same CRC-32 and size are
+    /// written only once, and different files with the same name get a
+    /// numeric suffix. A report of deduplicated files is printed.
+    #[arg(
+        long = "flatten-into",
+        value_name = "DIR",
+        conflicts_with_all = ["extract_dir", "destination_map"]
+    )]
+    pub flatten_into: Option<String>,
+
+    /// Write a manifest of extracted files.
+    ///
+    /// After extraction, writes one `crc32  size  path` line per
+    /// successfully extracted file, sorted by path.
+    #[arg(long = "manifest", value_name = "PATH")]
+    pub manifest: Option<String>,
+
+    /// Check files against a trusted list of SHA-256 hashes.
+    ///
+    /// FILE holds `sha256  path` lines as written by `sha256sum`, with
+    /// paths as named in the archive. After extraction or testing, each
+    /// selected file listed there is read again and hashed; mismatches
+    /// and listed files missing from the selection are reported and make
+    /// the run fail.
+    #[arg(long = "verify-manifest", value_name = "FILE")]
+    pub verify_manifest: Option<String>,
+
+    /// Record finished entries in STATEFILE and skip them when rerun.
+    ///
+    /// Each extracted file is appended to the state file as soon as it is
+    /// written. After a crash, running the same command again skips the
+    /// recorded entries whose file on disk still matches their CRC-32 and
+    /// extracts the rest. Combine with `-o` so a file left half-written
+    /// by the crash is replaced.
+    #[arg(long = "resume", value_name = "STATEFILE", conflicts_with = "pipe")]
+    pub resume: Option<String>,
+
+    /// Convert the selected files to a gzip-compressed tar archive.
+    ///
+    /// Writes the files, and the directories leading to them, to OUT
+    /// (`-` for stdout) in one pass instead of extracting them. Names,
+    /// sizes, permissions, modification times and owner IDs are kept.
+    #[arg(
+        long = "to-tgz",
+        value_name = "OUT",
+        conflicts_with_all = ["pipe", "test", "extract_dir", "resume", "manifest"]
+    )]
+    pub to_tgz: Option<String>,
+
     /// Quiet mode (-qq => quieter).
     ///
     /// Suppress informational output. Can be specified multiple times
@@ -100,6 +442,92 @@ pub struct Cli {
     /// - `-qq`: Suppress all messages except errors
     #[arg(short = 'q', action = clap::ArgAction::Count)]
     pub quiet: u8,
+
+    /// Force permissions of extracted files (octal, Unix only).
+    ///
+    /// Sets a fixed mode such as `0644` on every extracted file,
+    /// overriding any mode derived from the archive.
+    #[arg(long = "chmod", value_name = "MODE", value_parser = parse_mode)]
+    pub file_mode: Option<u32>,
+
+    /// Force permissions of extracted directories (octal, Unix only).
+    ///
+    /// Sets a fixed mode such as `0755` on every directory created
+    /// from the archive's paths.
+    #[arg(long = "dir-chmod", value_name = "MODE", value_parser = parse_mode)]
+    pub dir_mode: Option<u32>,
+
+    /// Create missing directories with this mode (octal, Unix only).
+    ///
+    /// Unlike `--dir-chmod`, the mode is set as each directory is
+    /// created, regardless of the umask, and directories that already
+    /// exist are left alone. Directory entries still get their stored
+    /// mode when permissions are preserved.
+    #[arg(long = "dir-mode", value_name = "MODE", value_parser = parse_mode)]
+    pub create_dir_mode: Option<u32>,
+
+    /// Proxy URL for HTTP sources.
+    ///
+    /// Routes all requests through the given proxy. When not set,
+    /// the `HTTP_PROXY`/`HTTPS_PROXY` environment variables are used.
+    #[arg(long = "proxy", value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// Proxy credentials as `user:pass`.
+    ///
+    /// Authenticates to the proxy using HTTP Basic authentication.
+    #[arg(long = "proxy-auth", value_name = "USER:PASS", value_parser = parse_credentials)]
+    pub proxy_auth: Option<(String, String)>,
+
+    /// HTTP status codes to retry for HTTP sources (default: 429,503).
+    ///
+    /// Comma-separated list. Range requests answered with one of these
+    /// statuses are retried with backoff; other errors fail immediately.
+    #[arg(long = "retry-status", value_name = "CODES", value_delimiter = ',')]
+    pub retry_status: Option<Vec<u16>>,
+
+    /// Most HTTP requests in flight at once for HTTP sources (default: 4).
+    ///
+    /// Shared by all concurrent reads, to avoid overwhelming small
+    /// servers.
+    #[arg(
+        long = "max-connections",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub max_connections: Option<u32>,
+
+    /// Trust the CA certificate(s) in a PEM file for HTTPS sources.
+    ///
+    /// Use this for servers with self-signed or internally issued
+    /// certificates.
+    #[arg(long = "cacert", value_name = "PATH")]
+    pub cacert: Option<String>,
+
+    /// Skip TLS certificate verification for HTTPS sources (unsafe).
+    ///
+    /// Accepts any certificate, which exposes the download to
+    /// man-in-the-middle attacks. Prefer `--cacert` where possible.
+    #[arg(long = "insecure")]
+    pub insecure: bool,
+
+    /// Print the time spent in each phase of the run to stderr.
+    ///
+    /// For HTTP sources, also prints the requests and bytes per phase.
+    /// Meant for diagnosing slow remote extractions.
+    #[arg(long = "benchmark", hide = true)]
+    pub benchmark: bool,
+}
+
+/// Which entries `--count` counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CountMode {
+    /// Files and directories
+    All,
+    /// Files only
+    Files,
+    /// Directories only
+    Dirs,
 }
 
 impl Cli {
@@ -112,6 +540,81 @@ impl Cli {
         self.file.starts_with("http://") || self.file.starts_with("https://")
     }
 
+    /// Get the local path of the input file.
+    ///
+    /// `file://` URLs are converted to paths: percent-encoded characters
+    /// are decoded, and the host must be empty (`file:///path`) or
+    /// `localhost`. Anything else is taken as a path as is.
+    ///
+    /// # Returns
+    ///
+    /// The path of the ZIP file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is a malformed `file://` URL or one
+    /// naming another host.
+    pub fn local_path(&self) -> Result<PathBuf> {
+        let is_file_url = self
+            .file
+            .get(..7)
+            .is_some_and(|scheme| scheme.eq_ignore_ascii_case("file://"));
+        if !is_file_url {
+            return Ok(PathBuf::from(&self.file));
+        }
+
+        let url = reqwest::Url::parse(&self.file)
+            .map_err(|e| anyhow!("Invalid file URL {}: {}", self.file, e))?;
+        url.to_file_path().map_err(|_| {
+            anyhow!(
+                "Unsupported file URL {}: only local paths can be read",
+                self.file
+            )
+        })
+    }
+
+    /// Apply `--auto-dir`, setting the `-d` directory to the archive's
+    /// name without its extension.
+    ///
+    /// For URLs the name is the last segment of the path, as it appears
+    /// in the URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is a malformed URL or no directory
+    /// name can be derived from it, e.g. for a URL ending in `/`.
+    pub fn resolve_auto_dir(&mut self) -> Result<()> {
+        if !self.auto_dir {
+            return Ok(());
+        }
+
+        let name = if self.is_http_url() {
+            let url = reqwest::Url::parse(&self.file)
+                .map_err(|e| anyhow!("Invalid URL {}: {}", self.file, e))?;
+            url.path_segments()
+                .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+                .map(str::to_string)
+        } else {
+            self.local_path()?
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        };
+        let Some(stem) = name
+            .as_deref()
+            .map(Path::new)
+            .and_then(Path::file_stem)
+            .map(|stem| stem.to_string_lossy().into_owned())
+        else {
+            bail!(
+                "Cannot name an output directory after {} (use -d instead)",
+                self.file
+            );
+        };
+
+        self.extract_dir = Some(stem);
+        Ok(())
+    }
+
     /// Check if quiet mode is enabled.
     ///
     /// Quiet mode is enabled either by the `-q` flag or by pipe mode (`-p`).
@@ -134,3 +637,79 @@ impl Cli {
         self.quiet > 1
     }
 }
+
+/// Parse credentials given as `user:pass`.
+///
+/// Only the first `:` separates the username from the password,
+/// so passwords may themselves contain colons.
+///
+/// # Arguments
+///
+/// * `s` - The credentials string given on the command line
+///
+/// # Returns
+///
+/// A tuple of (username, password), or an error message if no `:` is present.
+fn parse_credentials(s: &str) -> Result<(String, String), String> {
+    s.split_once(':')
+        .map(|(user, pass)| (user.to_string(), pass.to_string()))
+        .ok_or_else(|| "expected credentials in the form user:pass".to_string())
+}
+
+/// Parse a character set name such as `cp437` or `shift_jis`.
+///
+/// # Arguments
+///
+/// * `s` - The character set name given on the command line
+///
+/// # Returns
+///
+/// The character set, or an error message if the name is unknown.
+fn parse_charset(s: &str) -> Result<Charset, String> {
+    Charset::for_label(s).map_err(|e| e.to_string())
+}
+
+/// Parse a path prefix added below the output directory.
+///
+/// # Arguments
+///
+/// * `s` - The prefix given on the command line
+///
+/// # Returns
+///
+/// The normalized prefix, or an error message if it is absolute or
+/// contains `..`.
+fn parse_prefix(s: &str) -> Result<PathBuf, String> {
+    sanitize_path(s).map_err(|e| e.to_string())
+}
+
+/// Parse a content encoding conversion such as `shift_jis:utf-8`.
+///
+/// # Arguments
+///
+/// * `s` - The conversion given on the command line
+///
+/// # Returns
+///
+/// The conversion, or an error message if it is malformed.
+fn parse_recode(s: &str) -> Result<Recode, String> {
+    Recode::parse(s).map_err(|e| e.to_string())
+}
+
+/// Parse an octal permission mode such as `644`, `0644` or `0o644`.
+///
+/// # Arguments
+///
+/// * `s` - The mode string given on the command line
+///
+/// # Returns
+///
+/// The numeric mode, or an error message if it isn't a valid octal mode.
+fn parse_mode(s: &str) -> Result<u32, String> {
+    let digits = s.strip_prefix("0o").unwrap_or(s);
+    let mode = u32::from_str_radix(digits, 8).map_err(|_| format!("invalid octal mode: {}", s))?;
+    if mode > 0o7777 {
+        return Err(format!("mode out of range: {}", s));
+    }
+    Ok(mode)
+}
@@ -0,0 +1,114 @@
+//! Delay strategies between retries of failed HTTP requests.
+//!
+//! A server that is overloaded or restarting recovers faster when clients
+//! back off. [`Backoff`] decides how long
+//! [`HttpRangeReader`](super::HttpRangeReader) waits before each retry;
+//! the default grows the delay exponentially, caps it at 30 seconds, and
+//! adds jitter so many clients failing at once don't retry in lockstep.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let reader = HttpRangeReader::builder(url)
+//!     .backoff(Backoff::Linear {
+//!         step: Duration::from_millis(250),
+//!         max_delay: Duration::from_secs(5),
+//!     })
+//!     .build()
+//!     .await?;
+//! ```
+
+use std::hash::{BuildHasher, RandomState};
+use std::time::Duration;
+
+/// How long to wait before retrying a failed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// Wait the same time before every retry
+    Fixed {
+        /// Delay before each retry
+        delay: Duration,
+    },
+    /// Wait `step` times the retry number: `step`, `2 * step`, ...
+    Linear {
+        /// Delay added for each retry
+        step: Duration,
+        /// Upper bound on any single delay
+        max_delay: Duration,
+    },
+    /// Double the delay on each retry: `base`, `2 * base`, `4 * base`, ...
+    Exponential {
+        /// Delay before the first retry
+        base: Duration,
+        /// Upper bound on any single delay, applied before jitter
+        max_delay: Duration,
+        /// Wait a random time between half and all of the computed delay
+        jitter: bool,
+    },
+}
+
+impl Default for Backoff {
+    /// Exponential from 500ms with jitter, capped at 30s.
+    fn default() -> Self {
+        Backoff::Exponential {
+            base: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl Backoff {
+    /// Get the delay before a retry.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempt` - The retry number, starting at 1
+    ///
+    /// # Returns
+    ///
+    /// How long to wait. With jitter the result is random, between half
+    /// and all of the capped exponential delay.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let backoff = Backoff::Exponential {
+    ///     base: Duration::from_millis(100),
+    ///     max_delay: Duration::from_secs(1),
+    ///     jitter: false,
+    /// };
+    /// assert_eq!(backoff.delay(3), Duration::from_millis(400));
+    /// assert_eq!(backoff.delay(10), Duration::from_secs(1));
+    /// ```
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let attempt = attempt.max(1);
+        match *self {
+            Backoff::Fixed { delay } => delay,
+            Backoff::Linear { step, max_delay } => step.saturating_mul(attempt).min(max_delay),
+            Backoff::Exponential {
+                base,
+                max_delay,
+                jitter,
+            } => {
+                let factor = 1u32.checked_shl(attempt - 1).unwrap_or(u32::MAX);
+                let delay = base.saturating_mul(factor).min(max_delay);
+                if jitter {
+                    let half = delay / 2;
+                    half + half.mul_f64(random_fraction())
+                } else {
+                    delay
+                }
+            }
+        }
+    }
+}
+
+/// Get a pseudo-random number in `[0, 1)`.
+///
+/// Uses the randomly seeded hasher from the standard library, which is
+/// plenty for spreading out retries and avoids a dependency.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().hash_one(std::time::Instant::now());
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -5,12 +5,16 @@
 //! downloads of ZIP archives, fetching only the necessary data.
 
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::path::PathBuf;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
 
-use super::ReadAt;
-use anyhow::{Result, anyhow, bail};
+use super::{Backoff, ReadAt};
+use crate::warning::{Warning, Warnings};
+use anyhow::{Context, Result, anyhow, bail};
 
 /// HTTP Range reader for remote ZIP files.
 ///
@@ -24,16 +28,21 @@ use anyhow::{Result, anyhow, bail};
 /// - Support HTTP Range requests (indicated by `Accept-Ranges: bytes` header)
 /// - Provide a `Content-Length` header in HEAD responses
 ///
+/// If the first Range response reports a different total size in its
+/// `Content-Range` header, that size is used instead, with a warning.
+///
 /// ## Features
 ///
-/// - Automatic retry with exponential backoff for transient network errors
+/// - Automatic retry with configurable backoff (exponential with jitter
+///   by default) for transient network errors and configurable HTTP
+///   statuses (429 and 503 by default)
 /// - Transfer statistics tracking for monitoring bandwidth usage
 /// - Connection pooling via reqwest for efficient HTTP requests
 ///
 /// ## Example
 ///
 /// ```no_run
-/// use runzip::HttpRangeReader;
+/// use runzip::{HttpRangeReader, ReadAt};
 ///
 /// # async fn example() -> anyhow::Result<()> {
 /// let reader = HttpRangeReader::new("https://example.com/large.zip".to_string()).await?;
@@ -50,8 +59,24 @@ pub struct HttpRangeReader {
     size: u64,
     /// Cumulative bytes transferred from the network
     transferred_bytes: AtomicU64,
+    /// Cumulative number of Range requests sent, including retries
+    request_count: AtomicU64,
+    /// Limits the Range requests in flight at once, across all tasks
+    connections: Semaphore,
+    /// Most bytes requested by a single Range request
+    max_range_size: u64,
+    /// Reads smaller than this are padded up to it
+    min_range_size: u64,
+    /// The last padded range, as (offset, data), serving later small reads
+    padded: Mutex<Option<(u64, Vec<u8>)>>,
     /// Maximum number of retries for failed requests
     max_retry: u32,
+    /// HTTP status codes that are retried instead of failing immediately
+    retry_statuses: Vec<u16>,
+    /// How long to wait before each retry
+    backoff: Backoff,
+    /// Retries recorded since the caller last drained them
+    warnings: Warnings,
 }
 
 impl HttpRangeReader {
@@ -62,6 +87,9 @@ impl HttpRangeReader {
     /// 2. Check for Range request support via `Accept-Ranges` header
     /// 3. Obtain the file size from `Content-Length` header
     ///
+    /// Use [`HttpRangeReader::builder`] to customize the HTTP client
+    /// (e.g. to route requests through a proxy).
+    ///
     /// # Arguments
     ///
     /// * `url` - The HTTP or HTTPS URL of the ZIP file
@@ -77,11 +105,562 @@ impl HttpRangeReader {
     /// - The server doesn't support Range requests
     /// - The server doesn't provide Content-Length
     pub async fn new(url: String) -> Result<Self> {
-        // Create HTTP client with reasonable timeout
-        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        Self::builder(url).build().await
+    }
+
+    /// Create a builder for configuring an HTTP Range reader.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The HTTP or HTTPS URL of the ZIP file
+    ///
+    /// # Returns
+    ///
+    /// A builder with default settings.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let reader = HttpRangeReader::builder(url)
+    ///     .proxy("http://proxy.local:3128")
+    ///     .proxy_auth("user", "secret")
+    ///     .build()
+    ///     .await?;
+    /// ```
+    pub fn builder(url: String) -> HttpRangeReaderBuilder {
+        HttpRangeReaderBuilder::new(url)
+    }
+
+    /// Get the total bytes transferred from the network.
+    ///
+    /// This counter tracks all successful data transfers and can be used
+    /// to display bandwidth usage statistics to the user.
+    ///
+    /// # Returns
+    ///
+    /// The cumulative number of bytes received from the server.
+    pub fn transferred_bytes(&self) -> u64 {
+        self.transferred_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of Range requests sent so far.
+    ///
+    /// Every attempt counts, so retried requests are counted once per try.
+    ///
+    /// # Returns
+    ///
+    /// The cumulative number of Range requests.
+    pub fn request_count(&self) -> u64 {
+        self.request_count.load(Ordering::Relaxed)
+    }
+
+    /// Start measuring the bytes transferred by a group of operations.
+    ///
+    /// The returned guard snapshots the transfer counters on creation and
+    /// reports how many bytes and requests there have been since, which avoids
+    /// manual before/after bookkeeping when a reader is reused across
+    /// several operations. The counter itself is never reset, so scopes
+    /// may overlap and be used from concurrent tasks.
+    ///
+    /// # Returns
+    ///
+    /// A guard reporting bytes transferred and requests sent during its
+    /// lifetime.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let scope = reader.stats_scope();
+    /// extractor.extract_to_file(&entry, path).await?;
+    /// println!("Downloaded {} bytes", scope.transferred_bytes());
+    /// ```
+    pub fn stats_scope(&self) -> TransferScope<'_> {
+        TransferScope {
+            counter: &self.transferred_bytes,
+            start: self.transferred_bytes(),
+            requests: &self.request_count,
+            requests_start: self.request_count(),
+        }
+    }
+
+    /// Fetch a range with as many Range requests as needed.
+    ///
+    /// Requests cover at most `max_range_size` bytes each, and are sent
+    /// one after another.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The byte offset to start reading from, before EOF
+    /// * `buf` - The non-empty buffer to read data into
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes read, short only at the end of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails for good.
+    async fn fetch(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        // Calculate the byte range to request
+        // Clamp end to file size to avoid requesting beyond EOF
+        let end = offset + buf.len() as u64 - 1;
+        let end = end.min(self.size - 1);
+        let expected_size = (end - offset + 1) as usize;
+
+        let mut received = 0;
+        let mut retry_count = 0;
+
+        // Loop until we've received all expected data or exhausted retries
+        while received < expected_size {
+            let current_start = offset + received as u64;
+            let current_end = end.min(current_start.saturating_add(self.max_range_size - 1));
+            let range = format!("bytes={}-{}", current_start, current_end);
+            let (mut resp, permit) = self.send_range(&range, &mut retry_count).await?;
+
+            // Stream the response body into the buffer chunk by chunk, so a
+            // dropped connection only loses the bytes not yet received
+            let response_start = received;
+            loop {
+                match resp.chunk().await {
+                    Ok(Some(bytes)) => {
+                        let chunk_len = bytes.len().min(expected_size - received);
+                        buf[received..received + chunk_len].copy_from_slice(&bytes[..chunk_len]);
+                        received += chunk_len;
+
+                        // Update transfer statistics
+                        self.transferred_bytes
+                            .fetch_add(chunk_len as u64, Ordering::Relaxed);
+
+                        if received >= expected_size {
+                            break;
+                        }
+                    }
+                    Ok(None) => {
+                        // A response that made no progress counts as a failed
+                        // attempt, otherwise we could re-request forever
+                        if received == response_start {
+                            drop(permit);
+                            self.backoff(&mut retry_count, &"empty response body")
+                                .await?;
+                        }
+                        break;
+                    }
+                    Err(e) if is_transient(&e) => {
+                        // Resume from the first missing byte on the next request
+                        drop(permit);
+                        self.backoff(&mut retry_count, &e).await?;
+                        break;
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
+        Ok(received)
+    }
+
+    /// Send a Range request until the server answers with Partial Content.
+    ///
+    /// Transient network errors and the configured retry statuses are
+    /// retried with backoff. The body is left unread.
+    ///
+    /// # Arguments
+    ///
+    /// * `range` - The `Range` header value, e.g. `bytes=0-99`
+    /// * `retry_count` - The number of retries so far, shared with the
+    ///   caller's own retries
+    ///
+    /// # Returns
+    ///
+    /// The 206 response, and the connection permit to hold while its body
+    /// is read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range lies outside the file, the server
+    /// answers with any other status, or retries are used up.
+    async fn send_range(
+        &self,
+        range: &str,
+        retry_count: &mut u32,
+    ) -> Result<(reqwest::Response, SemaphorePermit<'_>)> {
+        loop {
+            // Wait for a free connection; the permit is released before
+            // any backoff so waiting tasks aren't held up by the sleep
+            let permit = self.connections.acquire().await?;
+
+            // Send Range request
+            self.request_count.fetch_add(1, Ordering::Relaxed);
+            let result = self
+                .client
+                .get(&self.url)
+                .header("Range", range)
+                .send()
+                .await;
+
+            let resp = match result {
+                Ok(resp) => resp,
+                Err(e) if is_transient(&e) => {
+                    // Retry on transient network errors with backoff
+                    drop(permit);
+                    self.backoff(retry_count, &e).await?;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            // The server says the range lies outside the file, e.g. because
+            // the remote file shrank since the HEAD request
+            if resp.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+                let actual = resp
+                    .headers()
+                    .get("content-range")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("unknown");
+                bail!(
+                    "Requested range {} exceeds the remote file (expected size {} bytes, server reports Content-Range: {})",
+                    range,
+                    self.size,
+                    actual
+                );
+            }
+
+            // Retry statuses the server uses for temporary failures
+            if self.retry_statuses.contains(&resp.status().as_u16()) {
+                drop(permit);
+                self.backoff(retry_count, &resp.status()).await?;
+                continue;
+            }
+
+            // Verify we got a Partial Content response (206); any other
+            // body, e.g. the whole file, is dropped unread
+            if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                bail!(
+                    "HTTP request for range {} failed with status: {}",
+                    range,
+                    resp.status()
+                );
+            }
+            return Ok((resp, permit));
+        }
+    }
+
+    /// Check the size reported by HEAD against a one-byte Range request.
+    ///
+    /// Goes through [`send_range()`](Self::send_range) like every other
+    /// read, so it is retried and counted the same way.
+    ///
+    /// # Returns
+    ///
+    /// The total size from the response's `Content-Range`, or `None` if
+    /// the server didn't send a usable one, in which case the body isn't
+    /// read.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`send_range()`](Self::send_range), and an
+    /// error if the body cannot be read.
+    async fn probe_size(&self) -> Result<Option<u64>> {
+        let mut retry_count = 0;
+        let (resp, _permit) = self.send_range("bytes=0-0", &mut retry_count).await?;
+        let Some(total) = content_range_total(&resp) else {
+            return Ok(None);
+        };
+        let body = resp.bytes().await?;
+        self.transferred_bytes
+            .fetch_add(body.len() as u64, Ordering::Relaxed);
+        Ok(Some(total))
+    }
+
+    /// Record a failed attempt and wait before retrying.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_count` - The number of retries so far, incremented by this call
+    /// * `reason` - The error that caused the retry, for display
+    ///
+    /// # Errors
+    ///
+    /// Returns an error once `max_retry` attempts have been used up.
+    async fn backoff(
+        &self,
+        retry_count: &mut u32,
+        reason: &(dyn std::fmt::Display + Sync),
+    ) -> Result<()> {
+        *retry_count += 1;
+        if *retry_count >= self.max_retry {
+            bail!("Max retries exceeded");
+        }
+        self.warnings.push(Warning::Retry {
+            attempt: *retry_count,
+            max: self.max_retry,
+            reason: reason.to_string(),
+        });
+        tokio::time::sleep(self.backoff.delay(*retry_count)).await;
+        Ok(())
+    }
+}
+
+/// Guard measuring bytes transferred while it is alive.
+///
+/// Created with [`HttpRangeReader::stats_scope`].
+pub struct TransferScope<'a> {
+    /// The reader's cumulative transfer counter
+    counter: &'a AtomicU64,
+    /// Counter value when the scope was created
+    start: u64,
+    /// The reader's cumulative request counter
+    requests: &'a AtomicU64,
+    /// Request counter value when the scope was created
+    requests_start: u64,
+}
+
+impl TransferScope<'_> {
+    /// Get the number of bytes transferred since the scope was created.
+    pub fn transferred_bytes(&self) -> u64 {
+        self.counter.load(Ordering::Relaxed) - self.start
+    }
+
+    /// Get the number of Range requests sent since the scope was created.
+    pub fn request_count(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed) - self.requests_start
+    }
+}
+
+/// Builder for [`HttpRangeReader`].
+///
+/// Configures the underlying HTTP client before the initial HEAD request
+/// is sent. Created with [`HttpRangeReader::builder`].
+///
+/// ## Proxy Support
+///
+/// When no proxy is configured explicitly, the standard `HTTP_PROXY`,
+/// `HTTPS_PROXY` and `NO_PROXY` environment variables are honored.
+/// Proxy credentials set with [`proxy_auth`](Self::proxy_auth) apply to
+/// the explicit proxy, or to the environment proxy if none is given;
+/// hosts listed in `NO_PROXY` are still reached directly then. An
+/// explicit proxy is used for every host.
+///
+/// ## TLS
+///
+/// Servers with self-signed or privately issued certificates can be
+/// reached by trusting an extra root with
+/// [`add_root_certificate`](Self::add_root_certificate), or (unsafely)
+/// by disabling verification with
+/// [`danger_accept_invalid_certs`](Self::danger_accept_invalid_certs).
+pub struct HttpRangeReaderBuilder {
+    /// The URL of the remote file
+    url: String,
+    /// Timeout applied to each HTTP request
+    timeout: Duration,
+    /// Maximum number of retries for failed requests
+    max_retry: u32,
+    /// HTTP status codes treated as transient
+    retry_statuses: Vec<u16>,
+    /// Delay strategy between retries
+    backoff: Backoff,
+    /// Most Range requests in flight at once
+    max_connections: usize,
+    /// Most bytes requested by a single Range request
+    max_range_size: u64,
+    /// Reads smaller than this are padded up to it
+    min_range_size: u64,
+    /// Explicit proxy URL
+    proxy: Option<String>,
+    /// Proxy credentials as (username, password)
+    proxy_auth: Option<(String, String)>,
+    /// PEM files with additional trusted root certificates
+    root_certificates: Vec<PathBuf>,
+    /// Skip TLS certificate verification entirely
+    accept_invalid_certs: bool,
+}
+
+impl HttpRangeReaderBuilder {
+    /// Create a builder with default settings for the given URL.
+    fn new(url: String) -> Self {
+        Self {
+            url,
+            timeout: Duration::from_secs(30),
+            max_retry: 10,
+            retry_statuses: vec![429, 503],
+            backoff: Backoff::default(),
+            max_connections: 4,
+            max_range_size: u64::MAX,
+            min_range_size: 0,
+            proxy: None,
+            proxy_auth: None,
+            root_certificates: Vec::new(),
+            accept_invalid_certs: false,
+        }
+    }
+
+    /// Set the timeout applied to each HTTP request (default: 30s).
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of retries for transient errors (default: 10).
+    pub fn max_retry(mut self, max_retry: u32) -> Self {
+        self.max_retry = max_retry;
+        self
+    }
+
+    /// Set the HTTP status codes that are retried (default: 429, 503).
+    ///
+    /// A range request answered with one of these statuses is retried
+    /// with backoff, like a network error. Any other unexpected status
+    /// fails immediately. The given list replaces the default set.
+    pub fn retry_statuses(mut self, statuses: impl IntoIterator<Item = u16>) -> Self {
+        self.retry_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Set how long to wait before each retry.
+    ///
+    /// The default is exponential backoff from 500ms with jitter, capped
+    /// at 30s. See [`Backoff`] for the available strategies.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set how many Range requests may be in flight at once (default: 4).
+    ///
+    /// The limit is shared by every task reading through the reader, so
+    /// concurrent extraction can't overwhelm a small server. Reads beyond
+    /// the limit wait for a request to finish. Values below 1 are raised
+    /// to 1.
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Split reads into Range requests of at most this many bytes
+    /// (default: unlimited).
+    ///
+    /// For servers that cap the size of a single Range response. The
+    /// requests for one read are sent one after another. Values below 1
+    /// are raised to 1.
+    pub fn max_range_size(mut self, bytes: u64) -> Self {
+        self.max_range_size = bytes.max(1);
+        self
+    }
+
+    /// Pad reads smaller than this many bytes up to it (default: 0, no
+    /// padding).
+    ///
+    /// The ZIP parser issues many small reads, for headers and records
+    /// that lie close together. With padding, a small read fetches the
+    /// following bytes too, and later small reads within that range are
+    /// served without a request. Only the most recent padded range is
+    /// kept.
+    pub fn min_range_size(mut self, bytes: u64) -> Self {
+        self.min_range_size = bytes;
+        self
+    }
+
+    /// Route all requests through the given proxy URL.
+    ///
+    /// Supports `http://`, `https://` and (if enabled in reqwest)
+    /// `socks5://` proxies.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Authenticate to the proxy with HTTP Basic credentials.
+    pub fn proxy_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.proxy_auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Trust the root certificates in a PEM file, in addition to the
+    /// built-in roots.
+    ///
+    /// The file may contain several certificates. It is read when
+    /// [`build`](Self::build) is called.
+    pub fn add_root_certificate(mut self, pem_path: impl Into<PathBuf>) -> Self {
+        self.root_certificates.push(pem_path.into());
+        self
+    }
+
+    /// Disable TLS certificate verification.
+    ///
+    /// **Warning:** any certificate is accepted, including expired ones
+    /// and ones for other hosts, so the connection is open to
+    /// man-in-the-middle attacks. Prefer
+    /// [`add_root_certificate`](Self::add_root_certificate).
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Build the HTTP client and probe the remote file.
+    ///
+    /// After the HEAD request, a one-byte Range request checks the size:
+    /// some servers report a wrong `Content-Length` for HEAD (e.g. the
+    /// size of a compressed representation), while the total in
+    /// `Content-Range` describes the bytes Range requests address. It is
+    /// retried and counted like any other read.
+    ///
+    /// # Returns
+    ///
+    /// A configured reader ready for random-access reads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The proxy URL is invalid
+    /// - A root certificate file cannot be read or parsed
+    /// - The HTTP request fails
+    /// - The server doesn't support Range requests, or answers the probe
+    ///   with anything but Partial Content
+    /// - The server doesn't provide Content-Length
+    pub async fn build(self) -> Result<HttpRangeReader> {
+        let mut builder = Client::builder()
+            .timeout(self.timeout)
+            .pool_max_idle_per_host(self.max_connections);
+
+        // Credentials without an explicit proxy apply to the environment
+        // proxy, which must then also honor NO_PROXY like reqwest's own
+        let (proxy_url, no_proxy) = match self.proxy {
+            Some(ref proxy) => (Some(proxy.clone()), None),
+            None => (
+                self.proxy_auth.as_ref().and_then(|_| env_proxy(&self.url)),
+                NoProxy::from_env(),
+            ),
+        };
+
+        if let Some(proxy_url) = proxy_url {
+            let mut proxy = Proxy::all(&proxy_url)?.no_proxy(no_proxy);
+            if let Some((username, password)) = &self.proxy_auth {
+                proxy = proxy.basic_auth(username, password);
+            }
+            builder = builder.proxy(proxy);
+        }
+
+        for path in &self.root_certificates {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("Failed to read CA certificate {}", path.display()))?;
+            let certs = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid CA certificate {}", path.display()))?;
+            if certs.is_empty() {
+                bail!("No certificates found in {}", path.display());
+            }
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if self.accept_invalid_certs {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+
+        let client = builder.build()?;
 
         // Send HEAD request to check server capabilities
-        let resp = client.head(&url).send().await?;
+        let resp = client.head(&self.url).send().await?;
 
         // Verify successful response
         if !resp.status().is_success() {
@@ -107,26 +686,73 @@ impl HttpRangeReader {
             .and_then(|s| s.parse().ok())
             .ok_or_else(|| anyhow!("Remote server did not return Content-Length"))?;
 
-        Ok(Self {
+        let mut reader = HttpRangeReader {
             client,
-            url,
+            url: self.url,
             size,
             transferred_bytes: AtomicU64::new(0),
-            max_retry: 10,
-        })
+            request_count: AtomicU64::new(0),
+            connections: Semaphore::new(self.max_connections),
+            max_range_size: self.max_range_size,
+            min_range_size: self.min_range_size,
+            padded: Mutex::new(None),
+            max_retry: self.max_retry,
+            retry_statuses: self.retry_statuses,
+            backoff: self.backoff,
+            warnings: Warnings::default(),
+        };
+
+        // Check the size against the total of the first Range response
+        if let Some(total) = reader.probe_size().await?
+            && total != size
+        {
+            reader.warnings.push(Warning::SizeCorrected {
+                reported: size,
+                actual: total,
+            });
+            reader.size = total;
+        }
+        Ok(reader)
     }
+}
 
-    /// Get the total bytes transferred from the network.
-    ///
-    /// This counter tracks all successful data transfers and can be used
-    /// to display bandwidth usage statistics to the user.
-    ///
-    /// # Returns
-    ///
-    /// The cumulative number of bytes received from the server.
-    pub fn transferred_bytes(&self) -> u64 {
-        self.transferred_bytes.load(Ordering::Relaxed)
+/// Read the total size from a Partial Content response.
+///
+/// # Returns
+///
+/// The size after the `/` of `Content-Range: bytes START-END/SIZE`, or
+/// `None` if the response isn't a 206 or the size is missing or `*`.
+fn content_range_total(resp: &reqwest::Response) -> Option<u64> {
+    if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return None;
     }
+    let value = resp.headers().get("content-range")?.to_str().ok()?;
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// Check whether a request error is worth retrying.
+///
+/// Timeouts, connection failures, and errors while streaming the response
+/// body (reported by reqwest as body or decode errors) are treated as
+/// transient network problems.
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.is_body() || e.is_decode()
+}
+
+/// Look up the proxy configured in the environment for a URL.
+///
+/// Checks `HTTPS_PROXY` for https URLs and `HTTP_PROXY` for http URLs,
+/// in both upper and lower case, falling back to `ALL_PROXY`.
+fn env_proxy(url: &str) -> Option<String> {
+    let names: &[&str] = if url.starts_with("https://") {
+        &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+    } else {
+        &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+    };
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
 }
 
 #[async_trait]
@@ -137,6 +763,11 @@ impl ReadAt for HttpRangeReader {
     /// the requested data. Implements automatic retry with exponential
     /// backoff for transient network errors (timeouts, connection failures).
     ///
+    /// Reads larger than [`HttpRangeReaderBuilder::max_range_size`] are
+    /// split into several requests, and reads smaller than
+    /// [`HttpRangeReaderBuilder::min_range_size`] are padded and may be
+    /// served from the previous padded range.
+    ///
     /// # Arguments
     ///
     /// * `offset` - The byte offset to start reading from
@@ -148,78 +779,64 @@ impl ReadAt for HttpRangeReader {
     ///
     /// # Retry Behavior
     ///
-    /// - Retries on timeout and connection errors
-    /// - Uses exponential backoff (500ms * retry_count)
+    /// - Retries on timeout, connection errors, and connections dropped
+    ///   while the response body is streaming
+    /// - Waits between attempts as configured with
+    ///   [`HttpRangeReaderBuilder::backoff`] (by default exponentially,
+    ///   with jitter, up to 30s)
     /// - Gives up after `max_retry` attempts (default: 10)
+    ///
+    /// # Resumption
+    ///
+    /// The response body is consumed incrementally, so a transfer that
+    /// fails partway only re-requests the bytes not yet received rather
+    /// than the whole range. This makes large STORED reads resumable.
+    /// Resumption happens below the decompressor: a DEFLATE stream whose
+    /// read ultimately fails cannot be resumed mid-stream and must be
+    /// extracted again from the start.
     async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
-        // Handle empty buffer case
-        if buf.is_empty() {
+        // Handle empty buffer case, and reads starting at or past EOF
+        // (like pread, these return zero bytes rather than failing)
+        if buf.is_empty() || offset >= self.size {
             return Ok(0);
         }
+        if buf.len() as u64 >= self.min_range_size {
+            return self.fetch(offset, buf).await;
+        }
 
-        // Calculate the byte range to request
-        // Clamp end to file size to avoid requesting beyond EOF
-        let end = offset + buf.len() as u64 - 1;
-        let end = end.min(self.size - 1);
-        let expected_size = (end - offset + 1) as usize;
-
-        let mut received = 0;
-        let mut retry_count = 0;
-
-        // Loop until we've received all expected data or exhausted retries
-        while received < expected_size {
-            let current_start = offset + received as u64;
-            let range = format!("bytes={}-{}", current_start, end);
-
-            // Send Range request
-            let result = self
-                .client
-                .get(&self.url)
-                .header("Range", &range)
-                .send()
-                .await;
-
-            match result {
-                Ok(resp) => {
-                    // Verify we got a Partial Content response (206)
-                    if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
-                        bail!("HTTP request failed with status: {}", resp.status());
-                    }
-
-                    // Read response body and copy to buffer
-                    let bytes = resp.bytes().await?;
-                    let chunk_len = bytes.len().min(expected_size - received);
-                    buf[received..received + chunk_len].copy_from_slice(&bytes[..chunk_len]);
-                    received += chunk_len;
-
-                    // Update transfer statistics
-                    self.transferred_bytes
-                        .fetch_add(chunk_len as u64, Ordering::Relaxed);
-                }
-                Err(e) if e.is_timeout() || e.is_connect() => {
-                    // Retry on transient network errors with backoff
-                    retry_count += 1;
-                    if retry_count >= self.max_retry {
-                        bail!("Max retries exceeded");
-                    }
-                    eprintln!(
-                        "Connection error, retry {}/{}: {}",
-                        retry_count, self.max_retry, e
-                    );
-                    // Exponential backoff: 500ms, 1000ms, 1500ms, ...
-                    tokio::time::sleep(Duration::from_millis(500 * retry_count as u64)).await;
-                }
-                Err(e) => return Err(e.into()),
-            }
+        // Small read: serve it from the last padded range if possible
+        let available = (self.size - offset).min(buf.len() as u64) as usize;
+        if let Some((start, ref data)) = *self.padded.lock().unwrap()
+            && offset >= start
+            && offset + available as u64 <= start + data.len() as u64
+        {
+            let from = (offset - start) as usize;
+            buf[..available].copy_from_slice(&data[from..from + available]);
+            return Ok(available);
         }
 
-        Ok(received)
+        // Otherwise fetch min_range_size bytes and keep them for later
+        let len = (self.size - offset).min(self.min_range_size) as usize;
+        let mut data = vec![0u8; len];
+        let n = self.fetch(offset, &mut data).await?;
+        data.truncate(n);
+        let n = n.min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        *self.padded.lock().unwrap() = Some((offset, data));
+        Ok(n)
     }
 
     /// Get the total size of the remote file.
     ///
-    /// Returns the Content-Length value obtained during construction.
+    /// Returns the size determined during construction: the total from
+    /// the probe's `Content-Range` header if the server sent one,
+    /// otherwise the `Content-Length` of the HEAD response.
     fn size(&self) -> u64 {
         self.size
     }
+
+    /// Take the retries recorded since the last call.
+    fn take_warnings(&self) -> Vec<Warning> {
+        self.warnings.take()
+    }
 }
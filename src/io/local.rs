@@ -8,6 +8,16 @@ use anyhow::Result;
 use async_trait::async_trait;
 use std::path::Path;
 
+/// Largest gap between two ranges that [`LocalFileReader::read_many`]
+/// reads through rather than issuing a separate call
+#[cfg(unix)]
+const MAX_GAP: u64 = 4096;
+
+/// Most iovecs passed to a single `preadv(2)` call (POSIX guarantees at
+/// least 16; Linux and the BSDs allow 1024)
+#[cfg(unix)]
+const MAX_IOVECS: usize = 1024;
+
 /// Local file reader with random access support.
 ///
 /// This reader provides efficient random-access reads from local files,
@@ -15,15 +25,20 @@ use std::path::Path;
 ///
 /// - **Unix**: Uses `pread(2)` via `FileExt::read_at`, which reads at an
 ///   offset without changing the file position (thread-safe)
-/// - **Windows**: Uses seek + read with handle duplication to avoid
-///   modifying the original file's position
+/// - **Windows**: Uses `ReadFile` with an explicit offset via
+///   `FileExt::seek_read`, so concurrent reads don't depend on a shared
+///   file position
 /// - **Other platforms**: Falls back to seek + read
 ///
+/// On Unix, [`read_many`](ReadAt::read_many) reads nearby ranges with a
+/// single `preadv(2)` call, filling the gaps between them into a scratch
+/// buffer.
+///
 /// ## Example
 ///
 /// ```no_run
 /// use std::path::Path;
-/// use runzip::LocalFileReader;
+/// use runzip::{LocalFileReader, ReadAt};
 ///
 /// # fn main() -> anyhow::Result<()> {
 /// let reader = LocalFileReader::new(Path::new("archive.zip"))?;
@@ -70,7 +85,7 @@ impl ReadAt for LocalFileReader {
     ///
     /// Uses platform-specific optimizations:
     /// - Unix: `pread(2)` for atomic positioned reads
-    /// - Windows: Duplicates handle to avoid position conflicts
+    /// - Windows: `seek_read` for positioned reads
     /// - Other: Standard seek + read
     ///
     /// # Arguments
@@ -84,9 +99,10 @@ impl ReadAt for LocalFileReader {
     ///
     /// # Platform Notes
     ///
-    /// On Unix, this operation is atomic and thread-safe. On Windows and
-    /// other platforms, concurrent reads may have race conditions, though
-    /// this is generally safe in the single-threaded async context used here.
+    /// On Unix and Windows, every read carries its own offset, so
+    /// concurrent reads are safe. On other platforms, concurrent reads may
+    /// have race conditions, though this is generally safe in the
+    /// single-threaded async context used here.
     async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
         #[cfg(unix)]
         {
@@ -97,22 +113,11 @@ impl ReadAt for LocalFileReader {
 
         #[cfg(windows)]
         {
-            use std::io::{Read, Seek, SeekFrom};
-            // Windows doesn't have pread, need to seek and read
-            // We duplicate the handle to avoid affecting the original file position
-            let file = &self.file;
-            let mut file = unsafe {
-                // Create a temporary handle copy for this read operation
-                // SAFETY: We're creating a new File from the same raw handle,
-                // and we call forget() at the end to prevent double-close
-                use std::os::windows::io::AsRawHandle;
-                use std::os::windows::io::FromRawHandle;
-                std::fs::File::from_raw_handle(file.as_raw_handle())
-            };
-            file.seek(SeekFrom::Start(offset))?;
-            let n = file.read(buf)?;
-            std::mem::forget(file); // Don't close the handle - original owns it
-            Ok(n)
+            // Windows: ReadFile with the offset in an OVERLAPPED structure.
+            // It moves the file pointer, but no read depends on it, so
+            // concurrent reads can't interfere
+            use std::os::windows::fs::FileExt;
+            Ok(self.file.seek_read(buf, offset)?)
         }
 
         #[cfg(not(any(unix, windows)))]
@@ -125,6 +130,47 @@ impl ReadAt for LocalFileReader {
         }
     }
 
+    /// Fill several buffers from scattered offsets with few system calls.
+    ///
+    /// Requests are sorted by offset and grouped while the gap to the next
+    /// one is at most [`MAX_GAP`] bytes. Each group is read with one
+    /// `preadv(2)` call (more if it exceeds the kernel's iovec limit or
+    /// the read comes back short), with gaps read into a scratch buffer.
+    /// Overlapping requests start a new group.
+    #[cfg(unix)]
+    async fn read_many(&self, requests: &mut [(u64, &mut [u8])]) -> Result<()> {
+        let mut order: Vec<usize> = (0..requests.len()).collect();
+        order.sort_by_key(|&i| requests[i].0);
+
+        let mut scratch = vec![0u8; MAX_GAP as usize];
+        let mut iovecs: Vec<libc::iovec> = Vec::new();
+        let mut group_start = 0;
+        let mut group_end = 0;
+
+        for i in order {
+            let (offset, ref mut buf) = requests[i];
+            let joins = !iovecs.is_empty() && offset >= group_end && offset - group_end <= MAX_GAP;
+            if !joins {
+                preadv_exact(&self.file, &mut iovecs, group_start)?;
+                iovecs.clear();
+                group_start = offset;
+            } else if offset > group_end {
+                iovecs.push(libc::iovec {
+                    iov_base: scratch.as_mut_ptr().cast(),
+                    iov_len: (offset - group_end) as usize,
+                });
+            }
+            iovecs.push(libc::iovec {
+                iov_base: buf.as_mut_ptr().cast(),
+                iov_len: buf.len(),
+            });
+            group_end = offset + buf.len() as u64;
+        }
+        preadv_exact(&self.file, &mut iovecs, group_start)?;
+
+        Ok(())
+    }
+
     /// Get the total size of the local file.
     ///
     /// Returns the cached file size obtained during construction.
@@ -132,3 +178,71 @@ impl ReadAt for LocalFileReader {
         self.size
     }
 }
+
+/// Fill a list of iovecs from consecutive file positions.
+///
+/// Repeats `preadv(2)` until every iovec is full, resuming after short
+/// reads and splitting lists longer than [`MAX_IOVECS`].
+///
+/// # Arguments
+///
+/// * `file` - The file to read
+/// * `iovecs` - The buffers to fill, in file order; consumed by the call
+/// * `offset` - File position of the first buffer
+///
+/// # Errors
+///
+/// Returns an error if a read fails or the file ends first.
+#[cfg(unix)]
+fn preadv_exact(file: &std::fs::File, iovecs: &mut [libc::iovec], mut offset: u64) -> Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut start = 0;
+    loop {
+        // Skip buffers that are already full (or empty to begin with)
+        while start < iovecs.len() && iovecs[start].iov_len == 0 {
+            start += 1;
+        }
+        if start == iovecs.len() {
+            return Ok(());
+        }
+
+        let batch = &iovecs[start..iovecs.len().min(start + MAX_IOVECS)];
+        // SAFETY: every iovec points into a live buffer of at least
+        // `iov_len` bytes: a request's buffer or the scratch buffer, which
+        // both outlive this call
+        let n = unsafe {
+            libc::preadv(
+                file.as_raw_fd(),
+                batch.as_ptr(),
+                batch.len() as libc::c_int,
+                libc::off_t::try_from(offset)?,
+            )
+        };
+        if n < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+        if n == 0 {
+            anyhow::bail!("Unexpected end of data at offset {}", offset);
+        }
+
+        // Advance past the bytes read
+        offset += n as u64;
+        let mut remaining = n as usize;
+        while remaining > 0 {
+            let iov = &mut iovecs[start];
+            let step = remaining.min(iov.iov_len);
+            // SAFETY: `step` is within the buffer the iovec points into
+            iov.iov_base = unsafe { iov.iov_base.cast::<u8>().add(step).cast() };
+            iov.iov_len -= step;
+            remaining -= step;
+            if iov.iov_len == 0 {
+                start += 1;
+            }
+        }
+    }
+}
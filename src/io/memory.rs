@@ -0,0 +1,124 @@
+//! In-memory reader over a buffer that may hold only part of a file.
+//!
+//! This module lets the ZIP parser work on bytes the caller already has,
+//! for example the tail of a remote archive fetched by other means.
+
+use super::ReadAt;
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+
+/// Reader over bytes held in memory.
+///
+/// The buffer covers either the whole data source or only its end. Reads
+/// that reach before the start of the buffer fail, so a parser working on
+/// a partial file gets an error rather than made-up data.
+///
+/// ## Example
+///
+/// ```ignore
+/// // The last 64 KiB of a 10 MB archive
+/// let reader = MemoryReader::tail(tail, 10_000_000)?;
+/// let entries = ZipParser::new(Arc::new(reader)).list_files().await?;
+/// ```
+pub struct MemoryReader {
+    /// The available bytes
+    data: Vec<u8>,
+    /// Offset of the first available byte in the data source
+    start: u64,
+    /// Total size of the data source in bytes
+    size: u64,
+}
+
+impl MemoryReader {
+    /// Create a reader over a complete data source.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The whole contents
+    ///
+    /// # Returns
+    ///
+    /// A reader whose size is the buffer's length.
+    pub fn new(data: Vec<u8>) -> Self {
+        let size = data.len() as u64;
+        Self {
+            data,
+            start: 0,
+            size,
+        }
+    }
+
+    /// Create a reader over the last bytes of a larger data source.
+    ///
+    /// # Arguments
+    ///
+    /// * `tail` - The last `tail.len()` bytes of the source
+    /// * `total_size` - Size of the whole source in bytes
+    ///
+    /// # Returns
+    ///
+    /// A reader reporting `total_size` as its size, serving only the tail.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the tail is longer than the whole source.
+    pub fn tail(tail: Vec<u8>, total_size: u64) -> Result<Self> {
+        let Some(start) = total_size.checked_sub(tail.len() as u64) else {
+            bail!(
+                "Tail of {} bytes is longer than the {} byte file",
+                tail.len(),
+                total_size
+            );
+        };
+        Ok(Self {
+            data: tail,
+            start,
+            size: total_size,
+        })
+    }
+}
+
+#[async_trait]
+impl ReadAt for MemoryReader {
+    /// Copy bytes at the specified offset from the buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The byte offset to start reading from
+    /// * `buf` - The buffer to read data into
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes read, which is short only at the end of the
+    /// data source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read starts before the available bytes.
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        if buf.is_empty() || offset >= self.size {
+            return Ok(0);
+        }
+        if offset < self.start {
+            bail!(
+                "Data at offset {} is not available: only bytes from offset {} on are in memory",
+                offset,
+                self.start
+            );
+        }
+
+        let from = (offset - self.start) as usize;
+        let n = buf.len().min(self.data.len() - from);
+        buf[..n].copy_from_slice(&self.data[from..from + n]);
+        Ok(n)
+    }
+
+    /// Get the total size of the data source.
+    ///
+    /// # Returns
+    ///
+    /// The size of the whole source, not just the bytes held in memory.
+    fn size(&self) -> u64 {
+        self.size
+    }
+}
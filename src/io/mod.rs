@@ -14,17 +14,26 @@
 //! - [`LocalFileReader`]: Reads from local filesystem using platform-specific
 //!   optimizations (pread on Unix, seek+read on Windows)
 //! - [`HttpRangeReader`]: Reads from HTTP servers using Range requests,
-//!   enabling efficient partial downloads of remote archives
+//!   enabling efficient partial downloads of remote archives, retrying
+//!   failed requests as set by [`Backoff`]
+//! - [`MemoryReader`]: Reads from bytes already in memory, which may be
+//!   only the end of a larger file
 
+mod backoff;
 mod http;
 mod local;
+mod memory;
 
-pub use http::HttpRangeReader;
+pub use backoff::Backoff;
+pub use http::{HttpRangeReader, HttpRangeReaderBuilder, TransferScope};
 pub use local::LocalFileReader;
+pub use memory::MemoryReader;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use async_trait::async_trait;
 
+use crate::warning::Warning;
+
 /// Trait for random access reading from a data source.
 ///
 /// This trait abstracts over different data sources (local files, HTTP, etc.)
@@ -34,10 +43,9 @@ use async_trait::async_trait;
 /// # Example
 ///
 /// ```ignore
-/// async fn read_header<R: ReadAt>(reader: &R) -> Result<[u8; 4]> {
-///     let mut buf = [0u8; 4];
-///     reader.read_at(0, &mut buf).await?;
-///     Ok(buf)
+/// async fn read_header<R: ReadAt>(reader: &R) -> Result<Vec<u8>> {
+///     // Fails instead of returning a short buffer near EOF
+///     reader.read_vec(0, 4).await
 /// }
 /// ```
 #[async_trait]
@@ -46,7 +54,9 @@ pub trait ReadAt: Send + Sync {
     ///
     /// Reads up to `buf.len()` bytes starting at `offset` into `buf`.
     /// Returns the number of bytes actually read, which may be less than
-    /// the buffer size if EOF is reached.
+    /// the buffer size if EOF is reached. Callers that need the whole
+    /// buffer filled should use [`read_vec`](Self::read_vec), which turns
+    /// a short read into an error instead of leaving the tail untouched.
     ///
     /// # Arguments
     ///
@@ -63,4 +73,113 @@ pub trait ReadAt: Send + Sync {
     /// For local files, this is the file size.
     /// For HTTP sources, this is the Content-Length from the server.
     fn size(&self) -> u64;
+
+    /// Remove and return the warnings recorded since the last call.
+    ///
+    /// Readers that retry failed requests report each retry here. The
+    /// default implementation records nothing.
+    fn take_warnings(&self) -> Vec<Warning> {
+        Vec::new()
+    }
+
+    /// Read exactly `len` bytes at the specified offset into a new vector.
+    ///
+    /// Unlike [`read_at`](Self::read_at), short reads are retried until
+    /// the whole range has been read, and reaching EOF first is an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The byte offset to start reading from
+    /// * `len` - The number of bytes to read
+    ///
+    /// # Returns
+    ///
+    /// A vector of exactly `len` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails or the data source ends before
+    /// `len` bytes have been read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let header = reader.read_vec(0, 4).await?;
+    /// ```
+    async fn read_vec(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; len];
+        self.read_exact_at(offset, &mut buf).await?;
+        Ok(buf)
+    }
+
+    /// Fill a buffer completely with data at the specified offset.
+    ///
+    /// Like [`read_vec`](Self::read_vec), but into a buffer the caller
+    /// provides, so it can be reused across reads.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The byte offset to start reading from
+    /// * `buf` - The buffer to fill
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails or the data source ends before
+    /// the buffer has been filled.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut header = [0u8; 30];
+    /// reader.read_exact_at(lfh_offset, &mut header).await?;
+    /// ```
+    async fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let n = self
+                .read_at(offset + filled as u64, &mut buf[filled..])
+                .await?;
+            if n == 0 {
+                bail!(
+                    "Unexpected end of data: read {} of {} bytes at offset {}",
+                    filled,
+                    buf.len(),
+                    offset
+                );
+            }
+            filled += n;
+        }
+
+        Ok(())
+    }
+
+    /// Fill several buffers from scattered offsets.
+    ///
+    /// Each `(offset, buf)` request is filled completely, as with
+    /// [`read_vec`](Self::read_vec). The default implementation reads the
+    /// requests one at a time; readers that can serve many ranges at once
+    /// override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests` - The offsets to read from and the buffers to fill
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a read fails or the data source ends before a
+    /// buffer has been filled.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (mut a, mut b) = ([0u8; 30], [0u8; 30]);
+    /// reader.read_many(&mut [(0, &mut a[..]), (4096, &mut b[..])]).await?;
+    /// ```
+    async fn read_many(&self, requests: &mut [(u64, &mut [u8])]) -> Result<()> {
+        for (offset, buf) in requests.iter_mut() {
+            self.read_exact_at(*offset, buf).await?;
+        }
+
+        Ok(())
+    }
 }
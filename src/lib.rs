@@ -13,7 +13,8 @@
 //! - Extract ZIP files from HTTP/HTTPS URLs using Range requests
 //! - Support for ZIP64 format (archives larger than 4GB)
 //! - Support for STORED (uncompressed) and DEFLATE compression methods
-//! - Selective file extraction with glob pattern matching
+//! - Selective file extraction with glob pattern matching ([`Pattern`])
+//! - One-call extraction of a single named entry ([`extract_file_from_path`], [`extract_file_from_url`])
 //!
 //! ## Example
 //!
@@ -41,8 +42,21 @@
 
 pub mod cli;
 pub mod io;
+pub mod pattern;
+pub mod quick;
+pub mod warning;
 pub mod zip;
 
 pub use cli::Cli;
-pub use io::{HttpRangeReader, LocalFileReader, ReadAt};
-pub use zip::{ZipExtractor, ZipFileEntry};
+pub use io::{
+    Backoff, HttpRangeReader, HttpRangeReaderBuilder, LocalFileReader, MemoryReader, ReadAt,
+    TransferScope,
+};
+pub use pattern::Pattern;
+pub use quick::{extract_file_from_path, extract_file_from_url};
+pub use warning::Warning;
+pub use zip::{
+    CancelToken, Charset, CompressionMethod, DosDateTime, EntryOutcome, ExtractReport,
+    ExtractSummary, ExtractedData, FileOptions, ListOptions, Preserve, Recode, SkipReason,
+    ZipError, ZipExtractor, ZipFileEntry, ZipWriter,
+};
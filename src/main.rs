@@ -3,372 +3,2269 @@
 //! This binary provides a command-line interface for extracting ZIP files
 //! from both local filesystem and remote HTTP URLs.
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use clap::Parser;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use runzip::{Cli, HttpRangeReader, LocalFileReader, ReadAt, ZipExtractor, ZipFileEntry};
+use runzip::cli::CountMode;
+use runzip::zip::{safe_join, sanitize_path};
+use runzip::{
+    Cli, CompressionMethod, EntryOutcome, ExtractReport, ExtractSummary, HttpRangeReader,
+    LocalFileReader, Pattern, ReadAt, SkipReason, TransferScope, Warning, ZipExtractor,
+    ZipFileEntry,
+};
+
+/// Exit status when entries were skipped (existing files that weren't
+/// overwritten, missing directories, unsupported methods) but nothing
+/// failed, like `unzip`'s warning status
+const EXIT_WARNING: u8 = 1;
+
+/// Exit status when the archive was processed but some entries failed
+/// to extract, test or verify (also used by clap for invalid arguments)
+const EXIT_ENTRY_ERROR: u8 = 2;
+
+/// Exit status when the archive couldn't be processed at all, e.g. it is
+/// missing, unreadable or not a valid ZIP file
+const EXIT_FATAL: u8 = 3;
+
+/// Error for a run that processed the archive but had failing entries.
+///
+/// Maps to [`EXIT_ENTRY_ERROR`] rather than [`EXIT_FATAL`].
+#[derive(Debug)]
+struct EntriesFailed(String);
+
+impl std::fmt::Display for EntriesFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for EntriesFailed {}
 
 /// Application entry point.
 ///
-/// Parses command-line arguments and dispatches to the appropriate handler
-/// based on whether the input is a local file or HTTP URL.
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Parses command-line arguments, runs them and maps the outcome to an
+/// `unzip`-style exit status: 0 when everything succeeded,
+/// [`EXIT_WARNING`] when entries were skipped, [`EXIT_ENTRY_ERROR`] when
+/// entries failed and [`EXIT_FATAL`] when the archive couldn't be
+/// processed.
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut cli = Cli::parse();
+    let result = match cli.resolve_auto_dir() {
+        Ok(()) => run(&cli).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(status) => status,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            if e.is::<EntriesFailed>() {
+                ExitCode::from(EXIT_ENTRY_ERROR)
+            } else {
+                ExitCode::from(EXIT_FATAL)
+            }
+        }
+    }
+}
+
+/// Open the archive named on the command line and process it.
+///
+/// Dispatches to the appropriate reader based on whether the input is a
+/// local file or HTTP URL.
+///
+/// # Arguments
+///
+/// * `cli` - Parsed command-line arguments
+///
+/// # Returns
+///
+/// The exit status of a run that didn't fail. Returns an error if
+/// processing fails or any entry failed.
+async fn run(cli: &Cli) -> Result<ExitCode> {
+    if cli.is_http_url() {
+        // Handle remote ZIP file via HTTP Range requests
+        let mut builder = HttpRangeReader::builder(cli.file.clone());
+        if let Some(ref proxy) = cli.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some((ref user, ref pass)) = cli.proxy_auth {
+            builder = builder.proxy_auth(user, pass);
+        }
+        if let Some(ref statuses) = cli.retry_status {
+            builder = builder.retry_statuses(statuses.iter().copied());
+        }
+        if let Some(max) = cli.max_connections {
+            builder = builder.max_connections(max as usize);
+        }
+        if let Some(ref cacert) = cli.cacert {
+            builder = builder.add_root_certificate(cacert);
+        }
+        if cli.insecure {
+            eprintln!("warning: --insecure: TLS certificate verification is disabled");
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        let reader = Arc::new(builder.build().await?);
+        let stats = reader.stats_scope();
+
+        process_zip(reader.clone(), cli, Some(&stats)).await
+    } else {
+        // Handle local ZIP file
+        let path = cli.local_path()?;
+        let reader = Arc::new(LocalFileReader::new(&path)?);
+        process_zip(reader, cli, None).await
+    }
+}
+
+/// Process a ZIP archive based on CLI options.
+///
+/// This function handles listing, testing and extraction modes:
+/// - List mode (`-l` or `-v`): Display archive contents
+/// - Test mode (`-t`): Verify files matching the specified filters
+/// - Extract mode: Extract files matching the specified filters
+///
+/// # Arguments
+///
+/// * `reader` - A reader implementing the `ReadAt` trait for random access
+/// * `cli` - Parsed command-line arguments
+/// * `stats` - Transfer statistics to report, for HTTP sources
+///
+/// # Returns
+///
+/// The exit status: success, or [`EXIT_WARNING`] if entries were
+/// skipped. Returns an [`EntriesFailed`] error if any file could not be
+/// extracted, tested or verified, and another error if processing fails.
+async fn process_zip<R: ReadAt + 'static>(
+    reader: Arc<R>,
+    cli: &Cli,
+    stats: Option<&TransferScope<'_>>,
+) -> Result<ExitCode> {
+    let mut extractor = ZipExtractor::new(reader)
+        .with_preserve(cli.preserve)
+        .with_try_methods(cli.try_methods)
+        .with_check_descriptors(cli.check_descriptors)
+        .with_skip_long_names(cli.skip_long_names);
+    if let Some(window) = cli.eocd_search_window {
+        extractor = extractor.with_eocd_search_window(window)?;
+    }
+    if let Some(charset) = cli.charset {
+        extractor = extractor.with_charset(charset);
+    }
+    if let Some(max) = cli.max_name_length {
+        extractor = extractor.with_max_name_length(max);
+    }
+    if let Some(mode) = cli.create_dir_mode {
+        extractor = extractor.with_dir_mode(mode);
+    }
+    if let Some(ref password) = cli.password {
+        extractor = extractor.with_password(password);
+    }
+    if let Some(secs) = cli.entry_timeout {
+        extractor = extractor.with_entry_timeout(Duration::from_secs(secs));
+    }
+    if let Some(ref recode) = cli.recode {
+        let patterns = cli.recode_only.iter().map(|p| Pattern::parse(p)).collect();
+        extractor = extractor.with_recode(recode.clone().with_patterns(patterns));
+    }
+
+    // Set up per-run extraction state (fails fast on a malformed map)
+    let mut state = ExtractState::new(cli)?;
+    let trusted = match cli.verify_manifest {
+        Some(ref path) => Some(read_hash_manifest(Path::new(path))?),
+        None => None,
+    };
+
+    // --benchmark: time locating the directory on its own; salvaging
+    // and printing the comment don't use it
+    let mut benchmark = Benchmark::new(stats);
+    if cli.benchmark && !cli.salvage && !cli.zip_comment {
+        extractor.validate().await?;
+        benchmark.phase("EOCD discovery");
+    }
+
+    // Comment mode: print the archive comment and exit
+    if cli.zip_comment {
+        let comment = extractor.tail_comment().await?;
+        if !comment.is_empty() {
+            println!("{}", comment);
+        }
+        benchmark.phase("EOCD discovery");
+        print_warnings(&extractor.take_warnings(), cli);
+        print_transfer_stats(cli, stats);
+        benchmark.print(cli);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Count mode: print the number of entries and exit
+    if let Some(mode) = cli.count {
+        count_entries(&extractor, cli, mode).await?;
+        benchmark.phase("Central Directory");
+        print_warnings(&extractor.take_warnings(), cli);
+        print_transfer_stats(cli, stats);
+        benchmark.print(cli);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // List mode: display archive contents and exit
+    if cli.csv {
+        list_csv(&extractor, cli).await?;
+        benchmark.phase("Central Directory");
+        print_warnings(&extractor.take_warnings(), cli);
+        print_transfer_stats(cli, stats);
+        benchmark.print(cli);
+        return Ok(ExitCode::SUCCESS);
+    }
+    if cli.list || cli.verbose {
+        list_files(&extractor, cli).await?;
+        benchmark.phase("Central Directory");
+        print_warnings(&extractor.take_warnings(), cli);
+        print_transfer_stats(cli, stats);
+        benchmark.print(cli);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Get all entries from the archive and pick the ones to work on
+    let entries = if cli.salvage {
+        extractor.salvage().await?
+    } else {
+        extractor.list_files().await?
+    };
+    let files_to_extract = select(&entries, cli, &state);
+    if cli.auto_strip {
+        state.strip_prefix = common_prefix(&entries);
+    }
+    let mut report = ExtractReport::new();
+    collect_warnings(&extractor, &mut report, cli);
+    benchmark.phase(if cli.salvage {
+        "Salvage scan"
+    } else {
+        "Central Directory"
+    });
+
+    // Test mode: verify the selected files without writing anything
+    if cli.test {
+        let mut result = test_files(&extractor, &files_to_extract, cli).await;
+        if let Some(ref trusted) = trusted {
+            let verified = verify_manifest(&extractor, &files_to_extract, trusted, cli).await;
+            result = result.and_then(|skipped| verified.map(|()| skipped));
+        }
+        benchmark.phase("Testing");
+        print_warnings(&extractor.take_warnings(), cli);
+        print_transfer_stats(cli, stats);
+        benchmark.print(cli);
+        return result.map(exit_status);
+    }
+
+    // Conversion mode: write the selected files to a tar.gz instead
+    if let Some(ref out) = cli.to_tgz {
+        let result = write_tgz(&extractor, &entries, &files_to_extract, out).await;
+        benchmark.phase("Conversion");
+        print_warnings(&extractor.take_warnings(), cli);
+        print_transfer_stats(cli, stats);
+        benchmark.print(cli);
+        return result.map(|()| ExitCode::SUCCESS);
+    }
+
+    // --only-new-dirs: start from a fresh output directory
+    if cli.only_new_dirs
+        && let Some(ref dir) = cli.extract_dir
+    {
+        create_fresh_dir(Path::new(dir), cli.overwrite)?;
+    }
+
+    // Extract each matching file, recording what happened to each one
+    let multiple_files = cli.pipe && files_to_extract.len() > 1;
+    for entry in &files_to_extract {
+        // Keep going after a failure so one bad entry doesn't stop the rest
+        let outcome = match extract_file(&extractor, entry, cli, &mut state, multiple_files).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("error: {}: {:#}", entry.file_name, e);
+                EntryOutcome::Failed(format!("{:#}", e))
+            }
+        };
+        if let EntryOutcome::Skipped(ref reason) = outcome {
+            let warning = Warning::Skipped {
+                name: entry.file_name.clone(),
+                reason: reason.clone(),
+            };
+            print_warnings(std::slice::from_ref(&warning), cli);
+            report.warn(warning);
+        }
+        report.push(entry, outcome);
+        collect_warnings(&extractor, &mut report, cli);
+    }
+
+    // Report files deduplicated by --flatten-into
+    if state.flatten.is_some() && !cli.is_quiet() {
+        print_dedup_report(&report);
+    }
+
+    // Restore directory metadata once all files are in place, as writing
+    // a file updates its directory's modification time
+    if !cli.junk_paths && state.flatten.is_none() {
+        restore_directories(&extractor, &entries, &report, cli, &state).await?;
+    }
+
+    // Apply forced directory permissions once all files are in place
+    if let Some(mode) = cli.dir_mode {
+        apply_dir_mode(&report, cli, mode)?;
+    }
+
+    // Write the manifest of extracted files
+    if let Some(ref path) = cli.manifest {
+        write_manifest(&report, cli, Path::new(path))?;
+    }
+
+    benchmark.phase("Extraction");
+
+    let summary = report.summary();
+    if !cli.is_quiet() {
+        print_summary(&summary, stats);
+    }
+    benchmark.print(cli);
+    if summary.failed > 0 {
+        bail!(EntriesFailed(format!(
+            "{} file(s) failed to extract",
+            summary.failed
+        )));
+    }
+
+    // Check the files against the trusted hashes
+    if let Some(ref trusted) = trusted {
+        verify_manifest(&extractor, &files_to_extract, trusted, cli).await?;
+    }
+
+    // Skips the user asked for (duplicates, --resume, unchanged files)
+    // aren't warnings
+    let skipped = report
+        .skipped()
+        .filter(|(_, reason)| {
+            matches!(
+                reason,
+                SkipReason::AlreadyExists
+                    | SkipReason::MissingDirectory(_)
+                    | SkipReason::UnsupportedMethod(_)
+            )
+        })
+        .count();
+    Ok(exit_status(skipped))
+}
+
+/// Per-phase timings of a run, printed with `--benchmark`.
+///
+/// Each phase lasts from the end of the previous one (or the creation of
+/// the recorder) to the call to [`phase`](Self::phase) naming it.
+struct Benchmark<'a> {
+    /// Transfer statistics, for HTTP sources
+    stats: Option<&'a TransferScope<'a>>,
+    /// When the current phase started
+    start: Instant,
+    /// Requests and bytes transferred when the current phase started
+    start_transfer: (u64, u64),
+    /// Finished phases: label, duration, requests and bytes transferred
+    phases: Vec<(&'static str, Duration, u64, u64)>,
+}
+
+impl<'a> Benchmark<'a> {
+    /// Start timing the first phase.
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - Transfer statistics, or `None` for local files
+    fn new(stats: Option<&'a TransferScope<'a>>) -> Self {
+        Self {
+            stats,
+            start: Instant::now(),
+            start_transfer: transfer_counts(stats),
+            phases: Vec::new(),
+        }
+    }
+
+    /// End the current phase and start the next one.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Name of the phase that just ended
+    fn phase(&mut self, label: &'static str) {
+        let (requests, bytes) = transfer_counts(self.stats);
+        let (start_requests, start_bytes) = self.start_transfer;
+        self.phases.push((
+            label,
+            self.start.elapsed(),
+            requests - start_requests,
+            bytes - start_bytes,
+        ));
+        self.start = Instant::now();
+        self.start_transfer = (requests, bytes);
+    }
+
+    /// Print the finished phases and their total to stderr, if
+    /// `--benchmark` was given.
+    ///
+    /// # Arguments
+    ///
+    /// * `cli` - Parsed command-line arguments
+    fn print(&self, cli: &Cli) {
+        if !cli.benchmark {
+            return;
+        }
+
+        let total = (
+            "Total",
+            self.phases.iter().map(|p| p.1).sum(),
+            self.phases.iter().map(|p| p.2).sum(),
+            self.phases.iter().map(|p| p.3).sum(),
+        );
+        eprintln!("Benchmark:");
+        for (label, duration, requests, bytes) in self.phases.iter().chain([&total]) {
+            let millis = duration.as_secs_f64() * 1000.0;
+            if self.stats.is_some() {
+                eprintln!(
+                    "  {:<18} {:>10.1} ms  {:>6} requests  {:>12}",
+                    label,
+                    millis,
+                    requests,
+                    format_size(*bytes)
+                );
+            } else {
+                eprintln!("  {:<18} {:>10.1} ms", label, millis);
+            }
+        }
+    }
+}
+
+/// Get the requests sent and bytes transferred so far.
+///
+/// # Returns
+///
+/// Both counts, or zeros for local files.
+fn transfer_counts(stats: Option<&TransferScope<'_>>) -> (u64, u64) {
+    stats.map_or((0, 0), |s| (s.request_count(), s.transferred_bytes()))
+}
+
+/// Get the exit status for a run that didn't fail.
+///
+/// # Arguments
+///
+/// * `skipped` - Number of entries skipped without being asked to
+///
+/// # Returns
+///
+/// Success, or [`EXIT_WARNING`] if any entries were skipped.
+fn exit_status(skipped: usize) -> ExitCode {
+    if skipped > 0 {
+        ExitCode::from(EXIT_WARNING)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Move the extractor's pending warnings into the report, printing them.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `report` - The report of the current run
+/// * `cli` - Parsed command-line arguments
+fn collect_warnings<R: ReadAt + 'static>(
+    extractor: &ZipExtractor<R>,
+    report: &mut ExtractReport,
+    cli: &Cli,
+) {
+    let warnings = extractor.take_warnings();
+    print_warnings(&warnings, cli);
+    report.warnings.extend(warnings);
+}
+
+/// Print warnings to stderr.
+///
+/// Skipped entries are reported unless in quiet mode; duplicates from
+/// `--flatten-into` are left to the dedup report, and entries finished
+/// by an earlier `--resume` run or left alone by `--overwrite-if-different`
+/// only count towards the summary. Other warnings are always shown.
+///
+/// # Arguments
+///
+/// * `warnings` - The warnings to print
+/// * `cli` - Parsed command-line arguments
+fn print_warnings(warnings: &[Warning], cli: &Cli) {
+    for warning in warnings {
+        match warning {
+            Warning::Skipped { .. } if cli.is_quiet() => {}
+            Warning::Skipped {
+                reason: SkipReason::Duplicate(_) | SkipReason::Resumed | SkipReason::Unchanged,
+                ..
+            } => {}
+            Warning::Skipped {
+                name,
+                reason: SkipReason::AlreadyExists,
+            } if !cli.never_overwrite => {
+                eprintln!("Skipping: {} (use -o to overwrite)", name);
+            }
+            Warning::Skipped { .. } | Warning::Retry { .. } => eprintln!("{}", warning),
+            _ => eprintln!("Warning: {}", warning),
+        }
+    }
+}
+
+/// Print a one-line summary of an extraction run.
+///
+/// For example: `Extracted 12 files (3.40 MB), skipped 2, 0 errors`,
+/// followed by the network transfer for HTTP sources.
+///
+/// # Arguments
+///
+/// * `summary` - Counts from the extraction report
+/// * `stats` - Transfer statistics, for HTTP sources
+fn print_summary(summary: &ExtractSummary, stats: Option<&TransferScope<'_>>) {
+    let mut line = format!(
+        "Extracted {} file{} ({}), skipped {}, {} error{}",
+        summary.extracted,
+        if summary.extracted == 1 { "" } else { "s" },
+        format_size(summary.extracted_bytes),
+        summary.skipped,
+        summary.failed,
+        if summary.failed == 1 { "" } else { "s" },
+    );
+    if let Some(stats) = stats {
+        line.push_str(&format!(
+            "; {} transferred",
+            format_size(stats.transferred_bytes())
+        ));
+    }
+    println!("{}", line);
+}
+
+/// Print the network transfer total for HTTP sources.
+///
+/// # Arguments
+///
+/// * `cli` - Parsed command-line arguments
+/// * `stats` - Transfer statistics, or `None` for local files
+fn print_transfer_stats(cli: &Cli, stats: Option<&TransferScope<'_>>) {
+    if let Some(stats) = stats
+        && !cli.is_quiet()
+    {
+        eprintln!(
+            "\nTotal bytes transferred: {}",
+            format_size(stats.transferred_bytes())
+        );
+    }
+}
+
+/// Pick the entries an extraction or test run works on.
+///
+/// Applies the filters in order:
+/// 1. Skip directories (they are created automatically during extraction)
+/// 2. With `--depth`, skip entries nested too deeply
+/// 3. With `--map-only`, skip entries the destination map doesn't mention
+/// 4. If specific files are requested, only include matching entries
+/// 5. Exclude files matching the exclusion patterns
+/// 6. Exclude files below the `--exclude-dir` directories
+/// 7. With `--include-ext`, skip files with other extensions
+/// 8. Exclude files with an extension given to `--exclude-ext`
+///
+/// # Arguments
+///
+/// * `entries` - All entries in the archive
+/// * `cli` - Parsed command-line arguments
+/// * `state` - Per-run extraction state (for the destination map)
+///
+/// # Returns
+///
+/// The selected entries, in archive order.
+fn select<'a>(
+    entries: &'a [ZipFileEntry],
+    cli: &Cli,
+    state: &ExtractState,
+) -> Vec<&'a ZipFileEntry> {
+    let files: Vec<Pattern> = cli.files.iter().map(|f| Pattern::parse(f)).collect();
+    // Exclusions without wildcards match any part of the name
+    let exclude: Vec<Pattern> = cli
+        .exclude
+        .iter()
+        .map(|x| {
+            let pattern = Pattern::parse(x);
+            let anchored = pattern.has_wildcards();
+            pattern.with_anchored(anchored)
+        })
+        .collect();
+    let include_ext = normalize_extensions(&cli.include_ext);
+    let exclude_ext = normalize_extensions(&cli.exclude_ext);
+
+    entries
+        .iter()
+        .filter(|e| {
+            // Skip directory entries
+            if e.is_directory {
+                return false;
+            }
+
+            // With --depth, skip entries nested too deeply
+            if !within_depth(e, cli.depth) {
+                return false;
+            }
+
+            // With --map-only, skip entries the destination map doesn't mention
+            if cli.map_only
+                && state
+                    .dest_map
+                    .as_ref()
+                    .is_none_or(|m| m.get(&e.file_name).is_none())
+            {
+                return false;
+            }
+
+            // If specific files are requested via positional arguments,
+            // only include entries that match
+            if !cli.files.is_empty() {
+                let matches = files.iter().any(|f| {
+                    if f.has_wildcards() {
+                        // Pattern contains wildcards: use glob matching
+                        f.matches(&e.file_name)
+                    } else {
+                        // No wildcards: exact match on filename or full path
+                        let basename = Path::new(&e.file_name)
+                            .file_name()
+                            .map(|s| s.to_string_lossy())
+                            .unwrap_or_default();
+                        f.matches(&e.file_name) || f.matches(&basename)
+                    }
+                });
+                if !matches {
+                    return false;
+                }
+            }
+
+            // Exclude files matching the -x patterns
+            if exclude.iter().any(|x| x.matches(&e.file_name)) {
+                return false;
+            }
+
+            // Exclude whole subtrees given with --exclude-dir
+            if cli.exclude_dir.iter().any(|dir| {
+                let dir = dir.trim_end_matches('/');
+                e.file_name
+                    .strip_prefix(dir)
+                    .is_some_and(|rest| rest.starts_with('/'))
+            }) {
+                return false;
+            }
+
+            // Filter by extension with --include-ext and --exclude-ext
+            let ext = extension(&e.file_name);
+            if !include_ext.is_empty() && ext.as_ref().is_none_or(|ext| !include_ext.contains(ext))
+            {
+                return false;
+            }
+            if ext.is_some_and(|ext| exclude_ext.contains(&ext)) {
+                return false;
+            }
+
+            true
+        })
+        .collect()
+}
+
+/// Normalize extensions given on the command line for comparison.
+///
+/// # Arguments
+///
+/// * `extensions` - Extensions, with or without a leading dot
+///
+/// # Returns
+///
+/// The non-empty extensions, lowercased and without the dot.
+fn normalize_extensions(extensions: &[String]) -> Vec<String> {
+    extensions
+        .iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// Get the lowercased extension of an entry name.
+///
+/// # Arguments
+///
+/// * `name` - The entry name
+///
+/// # Returns
+///
+/// The part of the last path component after its last dot, or `None`
+/// if there is no dot or the component starts with its only dot.
+fn extension(name: &str) -> Option<String> {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+/// Check whether an entry is at most `depth` path components deep.
+///
+/// A directory's trailing slash doesn't count as a component, so `a/` and
+/// `b.txt` have depth 1 and `a/b.txt` has depth 2.
+///
+/// # Arguments
+///
+/// * `entry` - The entry to check
+/// * `depth` - The maximum depth, or `None` for no limit
+fn within_depth(entry: &ZipFileEntry, depth: Option<u32>) -> bool {
+    depth.is_none_or(|depth| {
+        let components = entry.file_name.trim_end_matches('/').split('/').count();
+        components <= depth as usize
+    })
+}
+
+/// Test files in the ZIP archive.
+///
+/// Streams each entry through decompression and checks its size and
+/// CRC-32 without writing anything, printing one line per entry like
+/// `unzip -t`. Only the given entries are read, so over HTTP just their
+/// data is fetched, and memory use stays bounded however large they
+/// are. With `--progress` the share of the selection's uncompressed
+/// size checked so far is shown on stderr.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `entries` - The entries to test
+/// * `cli` - Parsed command-line arguments
+///
+/// # Returns
+///
+/// The number of entries skipped by `--skip-unsupported`.
+///
+/// # Errors
+///
+/// Returns an error if any entry fails verification.
+async fn test_files<R: ReadAt + 'static>(
+    extractor: &ZipExtractor<R>,
+    entries: &[&ZipFileEntry],
+    cli: &Cli,
+) -> Result<usize> {
+    let mut failures = 0;
+    let mut skipped = 0;
+    // Declared sizes can be anything, so the sums saturate
+    let total = entries
+        .iter()
+        .fold(0u64, |sum, e| sum.saturating_add(e.uncompressed_size));
+    let mut checked = 0u64;
+
+    for entry in entries {
+        if cli.skip_unsupported && is_unsupported(entry) {
+            skipped += 1;
+            checked = checked.saturating_add(entry.uncompressed_size);
+            let reason = SkipReason::UnsupportedMethod(entry.compression_method);
+            println!("    testing: {:<40}  skipped ({})", entry.file_name, reason);
+            continue;
+        }
+
+        let result = extractor
+            .verify_with_progress(entry, |done| {
+                if cli.progress {
+                    show_percent(
+                        checked.saturating_add(done.min(entry.uncompressed_size)),
+                        total,
+                    )
+                }
+            })
+            .await;
+        checked = checked.saturating_add(entry.uncompressed_size);
+        if cli.progress {
+            show_percent(checked, total);
+            eprint!("\r");
+        }
+
+        match result {
+            Ok(()) => {
+                if !cli.is_quiet() {
+                    println!("    testing: {:<40}  OK", entry.file_name);
+                }
+            }
+            Err(e) => {
+                failures += 1;
+                println!("    testing: {:<40}  {}", entry.file_name, e);
+            }
+        }
+    }
+
+    if cli.progress {
+        show_percent(checked, total);
+        eprintln!();
+    }
+
+    if failures > 0 {
+        bail!(EntriesFailed(format!(
+            "{} of {} tested files failed in {}",
+            failures,
+            entries.len(),
+            cli.file
+        )));
+    }
+
+    if !cli.is_very_quiet() {
+        println!("No errors detected in compressed data of {}.", cli.file);
+    }
+
+    Ok(skipped)
+}
+
+/// Show how much of the tested data was checked, on stderr.
+///
+/// # Arguments
+///
+/// * `checked` - Uncompressed bytes checked so far
+/// * `total` - Uncompressed size of all tested entries
+fn show_percent(checked: u64, total: u64) {
+    let percent = match total {
+        0 => 100,
+        _ => checked.min(total) as u128 * 100 / total as u128,
+    };
+    eprint!("\r{:>3}%", percent);
+}
+
+/// Check whether an entry uses a compression method runzip can't decode.
+///
+/// # Arguments
+///
+/// * `entry` - The entry to check
+///
+/// # Returns
+///
+/// `true` for entries that are neither STORED nor DEFLATE.
+fn is_unsupported(entry: &ZipFileEntry) -> bool {
+    matches!(entry.compression_method, CompressionMethod::Unknown(_))
+}
+
+/// Print the number of entries in the ZIP archive.
+///
+/// Output is a single number, followed by the total uncompressed size
+/// in bytes (tab-separated) with `--sum-sizes`.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `cli` - Parsed command-line arguments
+/// * `mode` - Which kinds of entries to count
+///
+/// # Errors
+///
+/// Returns an error if the archive cannot be read.
+async fn count_entries<R: ReadAt + 'static>(
+    extractor: &ZipExtractor<R>,
+    cli: &Cli,
+    mode: CountMode,
+) -> Result<()> {
+    let mut count = 0u64;
+    // Declared sizes are untrusted and may add up past u64::MAX
+    let mut total_size = 0u128;
+
+    let (entries, _) = archive_entries(extractor, cli).await?;
+    for entry in entries {
+        let entry = entry?;
+        let counted = match mode {
+            CountMode::All => true,
+            CountMode::Files => !entry.is_directory,
+            CountMode::Dirs => entry.is_directory,
+        };
+        if counted {
+            count += 1;
+            total_size += entry.uncompressed_size as u128;
+        }
+    }
+
+    if cli.sum_sizes {
+        println!("{}\t{}", count, total_size);
+    } else {
+        println!("{}", count);
+    }
+
+    Ok(())
+}
+
+/// Entries read lazily from the Central Directory, or salvaged ones
+type EntryIter<'a> = Box<dyn Iterator<Item = Result<ZipFileEntry>> + 'a>;
+
+/// Get the archive's entries for listing or counting.
+///
+/// Streams the Central Directory, or with `--salvage` recovers the
+/// entries by scanning the archive.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `cli` - Parsed command-line arguments
+///
+/// # Returns
+///
+/// The entries and how many there are in total.
+///
+/// # Errors
+///
+/// Returns an error if the archive cannot be read.
+async fn archive_entries<'a, R: ReadAt + 'static>(
+    extractor: &'a ZipExtractor<R>,
+    cli: &Cli,
+) -> Result<(EntryIter<'a>, u64)> {
+    if cli.salvage {
+        let entries = extractor.salvage().await?;
+        let total = entries.len() as u64;
+        Ok((Box::new(entries.into_iter().map(Ok)), total))
+    } else {
+        let entries = extractor.entries().await?;
+        let total = entries.total_entries();
+        Ok((Box::new(entries), total))
+    }
+}
+
+/// List files in the ZIP archive.
+///
+/// Supports two output formats:
+/// - Simple format (`-l`): Just file names, one per line
+/// - Verbose format (`-v`): Detailed table with size, method, compression ratio, and timestamps
+///
+/// With `--entries-limit`, only the first N entries are parsed and shown,
+/// followed by a footer with the number of entries left out. With
+/// `--depth`, entries nested too deeply are left out of the listing. The
+/// verbose format ends with the archive comment, if any, like `unzip -z`
+/// prints it.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `cli` - Parsed command-line arguments
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if listing fails.
+async fn list_files<R: ReadAt + 'static>(extractor: &ZipExtractor<R>, cli: &Cli) -> Result<()> {
+    let verbose = cli.verbose;
+    let (entries, total_entries) = archive_entries(extractor, cli).await?;
+    let mut entries = entries.filter(|e| e.as_ref().map_or(true, |e| within_depth(e, cli.depth)));
+    let limit = cli.entries_limit.unwrap_or(usize::MAX);
+
+    if verbose {
+        // Print table header for verbose output
+        println!(
+            "{:>10}  {:<7}  {:>10}  {:>5}  {:>10}  {:>5}  Name",
+            "Length", "Method", "Size", "Cmpr", "Date", "Time"
+        );
+        println!("{}", "-".repeat(79));
+    }
+
+    // Track totals for summary line
+    let mut total_uncompressed = 0u64;
+    let mut total_compressed = 0u64;
+    let mut file_count = 0usize;
+    let mut listed = 0u64;
+
+    for entry in entries.by_ref().take(limit) {
+        let entry = entry?;
+        listed += 1;
+
+        if verbose {
+            // Parse DOS timestamp into human-readable format
+            let modified = match entry.modified() {
+                Some(t) => format!(
+                    "{:04}-{:02}-{:02}  {:02}:{:02}",
+                    t.year(),
+                    t.month(),
+                    t.day(),
+                    t.hour(),
+                    t.minute()
+                ),
+                None => "----------  --:--".to_string(),
+            };
+
+            let ratio = compression_ratio(entry.compressed_size, entry.uncompressed_size);
+
+            // Print detailed entry information
+            println!(
+                "{:>10}  {:<7}  {:>10}  {}  {}  {}",
+                entry.uncompressed_size,
+                entry.method_label(),
+                entry.compressed_size,
+                ratio,
+                modified,
+                listed_name(&entry, cli)
+            );
+
+            // Accumulate totals (excluding directories)
+            if !entry.is_directory {
+                total_uncompressed = total_uncompressed.saturating_add(entry.uncompressed_size);
+                total_compressed = total_compressed.saturating_add(entry.compressed_size);
+                file_count += 1;
+            }
+        } else {
+            // Simple format: just the file name
+            println!("{}", listed_name(&entry, cli));
+        }
+    }
+
+    // Report entries left out by --entries-limit. With --depth or
+    // --skip-long-names, parse the rest to count those that would have
+    // been listed.
+    let more = if cli.depth.is_none() && !cli.skip_long_names {
+        total_entries.saturating_sub(listed)
+    } else {
+        entries.map_while(Result::ok).count() as u64
+    };
+    if more > 0 {
+        println!("... and {} more", more);
+    }
+
+    // Print summary line in verbose mode
+    if verbose {
+        println!("{}", "-".repeat(79));
+        let total_ratio = compression_ratio(total_compressed, total_uncompressed);
+        println!(
+            "{:>10}  {:<7}  {:>10}  {}  {:>21}  {} files",
+            total_uncompressed, "", total_compressed, total_ratio, "", file_count
+        );
+        if let Some(comment) = extractor.archive_comment().await? {
+            println!("{}", comment);
+        }
+    }
+
+    Ok(())
+}
+
+/// Format the percentage of space saved by compression, e.g. `  42%`.
+///
+/// Negative when encryption or a poor method made the data grow. The
+/// sizes come from the archive and can be anything, so the arithmetic is
+/// done in 128 bits.
+///
+/// # Arguments
+///
+/// * `compressed` - Size of the stored data
+/// * `uncompressed` - Size of the original data
+///
+/// # Returns
+///
+/// The percentage, right-aligned in 5 columns; `  0%` for empty data.
+fn compression_ratio(compressed: u64, uncompressed: u64) -> String {
+    match (compressed as i128 * 100).checked_div(uncompressed as i128) {
+        Some(pct) => format!("{:>4}%", 100 - pct),
+        None => "  0%".to_string(),
+    }
+}
+
+/// Get the name to print for an entry in a listing.
+///
+/// Directory names keep the trailing slash they are stored with, unless
+/// `--strip-trailing-slash` is given.
+///
+/// # Arguments
+///
+/// * `entry` - The entry being listed
+/// * `cli` - Parsed command-line arguments
+fn listed_name<'a>(entry: &'a ZipFileEntry, cli: &Cli) -> &'a str {
+    if cli.strip_trailing_slash && entry.is_directory {
+        entry.file_name.trim_end_matches('/')
+    } else {
+        &entry.file_name
+    }
+}
+
+/// List the archive's entries as CSV.
+///
+/// Prints a header row, then one row per entry. Honors `--depth`,
+/// `--entries-limit` and `--strip-trailing-slash` like the other listing
+/// formats, but prints no footer so the output stays valid CSV.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `cli` - Parsed command-line arguments
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if listing fails.
+async fn list_csv<R: ReadAt + 'static>(extractor: &ZipExtractor<R>, cli: &Cli) -> Result<()> {
+    let (entries, _) = archive_entries(extractor, cli).await?;
+    let limit = cli.entries_limit.unwrap_or(usize::MAX);
+
+    println!("{}", ListingRow::CSV_HEADER);
+    let entries = entries.filter(|e| e.as_ref().map_or(true, |e| within_depth(e, cli.depth)));
+    for entry in entries.take(limit) {
+        let entry = entry?;
+        println!("{}", ListingRow::new(&entry, cli).to_csv());
+    }
+
+    Ok(())
+}
+
+/// The fields of an entry shown in machine-readable listings.
+struct ListingRow<'a> {
+    /// Entry name, as listed
+    name: &'a str,
+    /// Uncompressed size in bytes
+    size: u64,
+    /// Compressed size in bytes
+    compressed: u64,
+    /// Compression method label
+    method: String,
+    /// CRC-32 of the uncompressed data
+    crc: u32,
+    /// DOS modification time as `YYYY-MM-DD HH:MM:SS`, empty if invalid
+    modified: String,
+    /// Whether the entry is a directory
+    is_dir: bool,
+}
+
+impl<'a> ListingRow<'a> {
+    /// Column names, in the order [`to_csv`](Self::to_csv) writes them
+    const CSV_HEADER: &'static str = "name,size,compressed,method,crc,modified,is_dir";
+
+    /// Collect the listed fields of an entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry to describe
+    /// * `cli` - Parsed command-line arguments
+    fn new(entry: &'a ZipFileEntry, cli: &Cli) -> Self {
+        Self {
+            name: listed_name(entry, cli),
+            size: entry.uncompressed_size,
+            compressed: entry.compressed_size,
+            method: entry.method_label(),
+            crc: entry.crc32,
+            modified: entry
+                .modified()
+                .map_or_else(String::new, |modified| modified.to_string()),
+            is_dir: entry.is_directory,
+        }
+    }
+
+    /// Format the row as a CSV line, without the line break.
+    fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{:08x},{},{}",
+            csv_field(self.name),
+            self.size,
+            self.compressed,
+            csv_field(&self.method),
+            self.crc,
+            self.modified,
+            self.is_dir
+        )
+    }
+}
+
+/// Quote a CSV field if needed (RFC 4180).
+///
+/// Fields containing commas, quotes or line breaks are wrapped in double
+/// quotes, with embedded quotes doubled.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Extract a single file from the archive.
+///
+/// Handles various extraction options:
+/// - Pipe mode (`-p`): Write to stdout instead of file
+/// - Custom output directory (`-d`): Extract to specified directory
+/// - Junk paths (`-j`): Ignore directory structure in archive
+/// - Overwrite control (`-n`, `-o`, `--overwrite-if-different`): Handle
+///   existing files
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `entry` - The ZIP file entry to extract
+/// * `cli` - Parsed command-line arguments
+/// * `state` - Per-run extraction state (destination map, flattening)
+/// * `show_filename` - If true, print filename marker before content (for pipe mode with multiple files)
+///
+/// # Returns
+///
+/// The outcome for the entry, or an error if extraction fails.
+async fn extract_file<R: ReadAt + 'static>(
+    extractor: &ZipExtractor<R>,
+    entry: &ZipFileEntry,
+    cli: &Cli,
+    state: &mut ExtractState,
+    show_filename: bool,
+) -> Result<EntryOutcome> {
+    // --skip-unsupported: leave entries we can't decompress alone
+    if cli.skip_unsupported && is_unsupported(entry) {
+        return Ok(EntryOutcome::Skipped(SkipReason::UnsupportedMethod(
+            entry.compression_method,
+        )));
+    }
+
+    // Pipe mode: write file contents directly to stdout
+    if cli.pipe {
+        if show_filename {
+            use tokio::io::AsyncWriteExt;
+            let mut stdout = tokio::io::stdout();
+            stdout
+                .write_all(format!("--- {} ---\n", entry.file_name).as_bytes())
+                .await?;
+            // Flush so the marker precedes the streamed data
+            stdout.flush().await?;
+        }
+        extractor.extract_to_stdout(entry).await?;
+        return Ok(EntryOutcome::Piped);
+    }
+
+    // Determine the output path based on CLI options
+    let output_path = match state.flatten {
+        Some(ref mut flattener) => match flattener.place(entry)? {
+            Placement::Path(path) => path,
+            Placement::Duplicate(original) => {
+                return Ok(EntryOutcome::Skipped(SkipReason::Duplicate(original)));
+            }
+        },
+        None => output_path(entry, cli, state)?,
+    };
+
+    // Resume mode: skip entries a previous run finished, if still intact
+    if let Some(ref resume) = state.resume
+        && resume.is_done(entry, &output_path)
+    {
+        return Ok(EntryOutcome::Skipped(SkipReason::Resumed));
+    }
+
+    // Handle existing files based on overwrite options
+    if output_path.exists() {
+        if cli.overwrite_if_different {
+            // --overwrite-if-different: keep files that already match
+            if file_matches(&output_path, entry) {
+                return Ok(EntryOutcome::Skipped(SkipReason::Unchanged));
+            }
+        } else if cli.never_overwrite || !cli.overwrite {
+            // -n flag or default behavior: skip (without -n the warning
+            // suggests -o); -o flag: overwrite without prompting
+            return Ok(EntryOutcome::Skipped(SkipReason::AlreadyExists));
+        }
+    }
+
+    // Strict mode: never create directories from archive paths
+    if cli.no_directory_creation
+        && let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+        && !parent.is_dir()
+    {
+        return Ok(EntryOutcome::Skipped(SkipReason::MissingDirectory(
+            parent.to_path_buf(),
+        )));
+    }
+
+    // Display extraction progress
+    if !cli.is_quiet() {
+        println!("  extracting: {}", entry.file_name);
+    }
+
+    // Last line of defense against path bugs: wherever the path came
+    // from, it must resolve to somewhere inside the output directory.
+    // Symlinks restored from the archive can redirect later paths, so
+    // the check always runs when they are
+    if cli.no_overwrite_outside_root || cli.is_http_url() || cli.preserve.links {
+        check_within_root(&output_base(cli), &output_path)?;
+    }
+
+    // The archive name alone doesn't say where a link ends up pointing
+    // once -j, --strip or earlier links moved it, so check its target
+    // from where it is really placed
+    #[cfg(unix)]
+    if cli.preserve.links && entry.is_symlink() {
+        let target = extractor.extract_to_memory(entry).await?;
+        check_link_within_root(
+            &output_base(cli),
+            entry,
+            &output_path,
+            &String::from_utf8_lossy(&target),
+        )?;
+    }
+
+    // Perform the actual extraction
+    extractor.extract_to_file(entry, &output_path).await?;
+
+    // Force file permissions if requested
+    if let Some(mode) = cli.file_mode {
+        set_mode(&output_path, mode)?;
+    }
+
+    if let Some(ref mut resume) = state.resume {
+        resume.record(entry)?;
+    }
+
+    Ok(EntryOutcome::Extracted(output_path))
+}
+
+/// Convert the selected files to a gzip-compressed tar archive.
+///
+/// Directory entries leading to the selected files are included too, so
+/// their metadata is kept. Entries are written in archive order.
+///
+/// # Arguments
+///
+/// * `extractor` - The ZIP extractor instance
+/// * `entries` - All entries in the archive
+/// * `selected` - The files to convert
+/// * `out` - Path of the archive to write, or `-` for stdout
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if an entry cannot be
+/// converted or the output cannot be written.
+async fn write_tgz<R: ReadAt + 'static>(
+    extractor: &ZipExtractor<R>,
+    entries: &[ZipFileEntry],
+    selected: &[&ZipFileEntry],
+    out: &str,
+) -> Result<()> {
+    use std::io::{BufWriter, Write};
+
+    let names: HashSet<&str> = selected.iter().map(|e| e.file_name.as_str()).collect();
+    let parents: HashSet<&str> = selected
+        .iter()
+        .flat_map(|e| {
+            e.file_name
+                .match_indices('/')
+                .map(|(i, _)| &e.file_name[..=i])
+        })
+        .collect();
+    let members = entries.iter().filter(|e| {
+        let names = if e.is_directory { &parents } else { &names };
+        names.contains(e.file_name.as_str())
+    });
+
+    if out == "-" {
+        let stdout = BufWriter::new(std::io::stdout());
+        extractor.write_tar_gz(members, stdout).await?.flush()?;
+    } else {
+        let file = std::fs::File::create(out).with_context(|| format!("Cannot create {}", out))?;
+        extractor
+            .write_tar_gz(members, BufWriter::new(file))
+            .await?
+            .flush()?;
+    }
+
+    Ok(())
+}
+
+/// Create the output directory for `--only-new-dirs`.
+///
+/// An existing empty directory is accepted, as extracting into it can't
+/// mix with other files.
+///
+/// # Arguments
+///
+/// * `dir` - The output directory
+/// * `overwrite` - Accept an existing directory with content (`-o`)
+///
+/// # Returns
+///
+/// Returns `Ok(())` once the directory exists, or an error if it can't
+/// be created or already has content and `overwrite` is false.
+fn create_fresh_dir(dir: &Path, overwrite: bool) -> Result<()> {
+    if let Some(parent) = dir.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    match std::fs::create_dir(dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            if overwrite {
+                return Ok(());
+            }
+            let empty = dir.is_dir() && std::fs::read_dir(dir)?.next().is_none();
+            if !empty {
+                bail!(
+                    "{} already exists and is not empty (use -o to extract into it anyway)",
+                    dir.display()
+                );
+            }
+            Ok(())
+        }
+        Err(e) => Err(e).with_context(|| format!("Cannot create {}", dir.display())),
+    }
+}
+
+/// Compute the filesystem path an entry should be extracted to.
+///
+/// Honors the destination map (`--destination-map`), output directory
+/// (`-d`), junk paths (`-j`), `--auto-strip` and `--add-prefix` options.
+/// Mapped entries are written to their mapped path and ignore `-j` and
+/// `--auto-strip`.
+///
+/// # Arguments
+///
+/// * `entry` - The ZIP file entry being extracted
+/// * `cli` - Parsed command-line arguments
+/// * `state` - Per-run extraction state (destination map, stripped prefix)
+///
+/// # Returns
+///
+/// The destination path for the entry.
+///
+/// # Errors
+///
+/// Returns an error if the path would escape the output directory
+/// (Zip Slip), e.g. because it is absolute or contains `..`.
+fn output_path(entry: &ZipFileEntry, cli: &Cli, state: &ExtractState) -> Result<PathBuf> {
+    let dest_map = state.dest_map.as_ref();
+    let file_name = if let Some(mapped) = dest_map.and_then(|m| m.get(&entry.file_name)) {
+        // Mapped entry: use the destination from the map
+        mapped.to_string()
+    } else if cli.junk_paths {
+        // Junk paths: use only the base filename, ignore directory structure
+        Path::new(&entry.file_name)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| entry.file_name.clone())
+    } else {
+        // Preserve directory structure from archive, less the wrapper
+        // directory removed by --auto-strip
+        let prefix = state.strip_prefix.as_deref().unwrap_or_default();
+        entry
+            .file_name
+            .strip_prefix(prefix)
+            .unwrap_or(&entry.file_name)
+            .to_string()
+    };
+
+    // Namespace the entry below --add-prefix; the combined path is
+    // validated as a whole
+    let file_name = match cli.add_prefix {
+        Some(ref prefix) => prefix.join(file_name),
+        None => PathBuf::from(file_name),
+    };
+
+    // Extract to the custom directory, or to the current one
+    let root = cli.extract_dir.as_deref().map_or(Path::new(""), Path::new);
+    safe_join(root, &file_name)
+}
+
+/// Mutable state shared across the entries of one extraction run.
+struct ExtractState {
+    /// Mapping of entry names to destination paths (`--destination-map`)
+    dest_map: Option<DestinationMap>,
+    /// Flattening and deduplication state (`--flatten-into`)
+    flatten: Option<Flattener>,
+    /// Entries finished by earlier runs (`--resume`)
+    resume: Option<ResumeLog>,
+    /// Top-level directory left out of extracted paths (`--auto-strip`),
+    /// with its trailing slash
+    strip_prefix: Option<String>,
+}
+
+impl ExtractState {
+    /// Set up the state for an extraction run from CLI options.
+    ///
+    /// # Arguments
+    ///
+    /// * `cli` - Parsed command-line arguments
+    ///
+    /// # Returns
+    ///
+    /// The initial state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the destination map or resume state file
+    /// cannot be loaded.
+    fn new(cli: &Cli) -> Result<Self> {
+        let dest_map = match cli.destination_map {
+            Some(ref path) => Some(DestinationMap::load(Path::new(path))?),
+            None => None,
+        };
+        let flatten = cli
+            .flatten_into
+            .as_deref()
+            .map(|dir| Flattener::new(dir.into()));
+        let resume = match cli.resume {
+            Some(ref path) => Some(ResumeLog::open(Path::new(path))?),
+            None => None,
+        };
+
+        Ok(Self {
+            dest_map,
+            flatten,
+            resume,
+            strip_prefix: None,
+        })
+    }
+}
+
+/// Find the single top-level directory all entries lie in.
+///
+/// # Arguments
+///
+/// * `entries` - All entries in the archive
+///
+/// # Returns
+///
+/// The directory's name with a trailing slash, e.g. `project/`, or
+/// `None` if some entry is at the top level or entries are spread over
+/// several top-level directories.
+fn common_prefix(entries: &[ZipFileEntry]) -> Option<String> {
+    let mut prefix: Option<&str> = None;
+    for entry in entries {
+        let (first, _) = entry.file_name.split_once('/')?;
+        match prefix {
+            Some(prefix) if prefix != first => return None,
+            Some(_) => {}
+            None => prefix = Some(first),
+        }
+    }
+    prefix.map(|prefix| format!("{}/", prefix))
+}
+
+/// Record of the entries extracted so far, kept in a `--resume` state file.
+///
+/// The file has one `crc32  name` line per extracted entry, with the CRC in
+/// hex. Lines are appended and flushed as each entry finishes, so the file
+/// survives a crash; a line cut short by one simply matches no entry.
+struct ResumeLog {
+    /// Entry names and CRC-32s recorded as extracted
+    done: HashSet<(String, u32)>,
+    /// The state file, open for appending
+    file: std::fs::File,
+}
+
+impl ResumeLog {
+    /// Load the state file, creating it if it doesn't exist yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the state file
+    ///
+    /// # Returns
+    ///
+    /// The entries recorded so far, ready for appending.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or created.
+    fn open(path: &Path) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Cannot open state file {}", path.display()))?;
+        let content = std::fs::read_to_string(path)?;
+
+        // End a line cut short by a crash, so the next record starts on
+        // a line of its own
+        if !content.is_empty() && !content.ends_with('\n') {
+            use std::io::Write;
+            writeln!(&file)?;
+        }
+
+        let done = content
+            .lines()
+            .filter_map(|line| {
+                let (crc, name) = line.split_once("  ")?;
+                let crc = u32::from_str_radix(crc, 16).ok()?;
+                Some((name.to_string(), crc))
+            })
+            .collect();
+
+        Ok(Self { done, file })
+    }
+
+    /// Check whether an entry was extracted by an earlier run.
+    ///
+    /// The entry must be recorded with the same CRC-32, and the file at
+    /// its destination must still have the recorded size and CRC-32, so
+    /// files changed or damaged since are extracted again.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry about to be extracted
+    /// * `path` - Where the entry is extracted to
+    fn is_done(&self, entry: &ZipFileEntry, path: &Path) -> bool {
+        self.done.contains(&(entry.file_name.clone(), entry.crc32)) && file_matches(path, entry)
+    }
+
+    /// Record an entry as extracted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state file can't be written.
+    fn record(&mut self, entry: &ZipFileEntry) -> Result<()> {
+        use std::io::Write;
+
+        writeln!(self.file, "{:08x}  {}", entry.crc32, entry.file_name)?;
+        self.file.sync_data()?;
+        self.done.insert((entry.file_name.clone(), entry.crc32));
+        Ok(())
+    }
+}
+
+/// Check whether a file on disk has an entry's size and CRC-32.
+///
+/// # Arguments
+///
+/// * `path` - The file to check
+/// * `entry` - The entry to compare against
+///
+/// # Returns
+///
+/// `true` if both match; `false` if they differ or the file can't be read.
+fn file_matches(path: &Path, entry: &ZipFileEntry) -> bool {
+    file_crc(path).is_ok_and(|(size, crc)| size == entry.uncompressed_size && crc == entry.crc32)
+}
+
+/// Compute the size and CRC-32 of an extracted file.
+///
+/// Symbolic links are not followed: their target path is checked, the
+/// way it is stored in the archive.
+///
+/// # Arguments
+///
+/// * `path` - The file to check
+///
+/// # Returns
+///
+/// The size in bytes and the CRC-32.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read.
+fn file_crc(path: &Path) -> Result<(u64, u32)> {
+    use std::io::Read;
+
+    let mut crc = flate2::Crc::new();
+    if std::fs::symlink_metadata(path)?.file_type().is_symlink() {
+        let target = std::fs::read_link(path)?;
+        crc.update(target.as_os_str().as_encoded_bytes());
+        return Ok((crc.amount() as u64, crc.sum()));
+    }
+
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        crc.update(&buf[..n]);
+        size += n as u64;
+    }
+
+    Ok((size, crc.sum()))
+}
+
+/// Where a flattened entry should go.
+enum Placement {
+    /// Write the entry to this path
+    Path(PathBuf),
+    /// Identical content was already written to this path
+    Duplicate(PathBuf),
+}
+
+/// Places entries into a single directory, deduplicating identical files.
+///
+/// Entries are identified by CRC-32 and size: the first entry with given
+/// content is written under its base name, later identical entries are
+/// skipped. Entries whose base name is already taken by different content
+/// get a numeric suffix (`name-1.ext`, `name-2.ext`, ...).
+struct Flattener {
+    /// Directory all entries are written into
+    dir: PathBuf,
+    /// Path written for each distinct (CRC-32, size)
+    by_content: HashMap<(u32, u64), PathBuf>,
+    /// File names already used in the directory
+    used_names: HashSet<String>,
+}
+
+impl Flattener {
+    /// Create a flattener writing into the given directory.
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            by_content: HashMap::new(),
+            used_names: HashSet::new(),
+        }
+    }
+
+    /// Decide where an entry goes, reserving its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry being extracted
+    ///
+    /// # Returns
+    ///
+    /// The destination path, or the path of an identical earlier entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry has no usable base name.
+    fn place(&mut self, entry: &ZipFileEntry) -> Result<Placement> {
+        let key = (entry.crc32, entry.uncompressed_size);
+        if let Some(original) = self.by_content.get(&key) {
+            return Ok(Placement::Duplicate(original.clone()));
+        }
+
+        let base = Path::new(&entry.file_name)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| entry.file_name.clone());
+        let base = sanitize_path(&base)?.to_string_lossy().to_string();
+
+        // Find a free name, suffixing the stem on collisions
+        let mut name = base.clone();
+        let mut counter = 1;
+        while self.used_names.contains(&name) {
+            let path = Path::new(&base);
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            name = match path.extension() {
+                Some(ext) => format!("{}-{}.{}", stem, counter, ext.to_string_lossy()),
+                None => format!("{}-{}", stem, counter),
+            };
+            counter += 1;
+        }
+
+        let path = safe_join(&self.dir, &name)?;
+        self.used_names.insert(name);
+        self.by_content.insert(key, path.clone());
+        Ok(Placement::Path(path))
+    }
+}
+
+/// Print which entries were deduplicated during flattening.
+///
+/// # Arguments
+///
+/// * `report` - The results of the extraction run
+fn print_dedup_report(report: &ExtractReport) {
+    let duplicates: Vec<_> = report
+        .skipped()
+        .filter_map(|(entry, reason)| match reason {
+            SkipReason::Duplicate(original) => Some((entry, original)),
+            _ => None,
+        })
+        .collect();
 
-    if cli.is_http_url() {
-        // Handle remote ZIP file via HTTP Range requests
-        let reader = HttpRangeReader::new(cli.file.clone()).await?;
-        let transferred_before = reader.transferred_bytes();
-        let reader = Arc::new(reader);
+    if duplicates.is_empty() {
+        return;
+    }
+
+    println!("Deduplicated {} file(s):", duplicates.len());
+    for (entry, original) in duplicates {
+        println!("  {} -> {}", entry.file_name, original.display());
+    }
+}
+
+/// Mapping of archive entry names to destination paths.
+///
+/// Loaded from a `--destination-map` file with one `oldpath<TAB>newpath`
+/// pair per line. Empty lines and lines starting with `#` are ignored.
+struct DestinationMap {
+    /// Entry name to destination path
+    paths: HashMap<String, String>,
+}
 
-        process_zip(reader.clone(), &cli).await?;
+impl DestinationMap {
+    /// Load a destination map from a file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the mapping file
+    ///
+    /// # Returns
+    ///
+    /// The parsed mapping.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or a line has no tab.
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let mut paths = HashMap::new();
 
-        // Display network transfer statistics for HTTP sources
-        if !cli.is_quiet() {
-            let transferred = reader.transferred_bytes() - transferred_before;
-            eprintln!("\nTotal bytes transferred: {}", format_size(transferred));
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((old, new)) = line.split_once('\t') else {
+                bail!(
+                    "{}:{}: expected 'oldpath<TAB>newpath'",
+                    path.display(),
+                    index + 1
+                );
+            };
+            paths.insert(old.to_string(), new.to_string());
         }
-    } else {
-        // Handle local ZIP file
-        let reader = Arc::new(LocalFileReader::new(Path::new(&cli.file))?);
-        process_zip(reader, &cli).await?;
+
+        Ok(Self { paths })
     }
 
-    Ok(())
+    /// Look up the destination for an entry name.
+    fn get(&self, name: &str) -> Option<&str> {
+        self.paths.get(name).map(String::as_str)
+    }
 }
 
-/// Process a ZIP archive based on CLI options.
+/// Restore the metadata of the archive's directory entries.
 ///
-/// This function handles both listing and extraction modes:
-/// - List mode (`-l` or `-v`): Display archive contents
-/// - Extract mode: Extract files matching the specified filters
+/// Only directories that received files in this run are touched. They are
+/// restored deepest-first, so a parent's timestamp isn't disturbed by
+/// work on its children afterwards, the way careful `tar` does it.
 ///
 /// # Arguments
 ///
-/// * `reader` - A reader implementing the `ReadAt` trait for random access
+/// * `extractor` - The ZIP extractor instance
+/// * `entries` - All entries in the archive
+/// * `report` - The results of the extraction run
 /// * `cli` - Parsed command-line arguments
+/// * `state` - Per-run extraction state (for the destination map)
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on success, or an error if processing fails.
-async fn process_zip<R: ReadAt + 'static>(reader: Arc<R>, cli: &Cli) -> Result<()> {
-    let extractor = ZipExtractor::new(reader);
+/// Returns `Ok(())` on success, or an error if metadata cannot be restored.
+async fn restore_directories<R: ReadAt + 'static>(
+    extractor: &ZipExtractor<R>,
+    entries: &[ZipFileEntry],
+    report: &ExtractReport,
+    cli: &Cli,
+    state: &ExtractState,
+) -> Result<()> {
+    let written: HashSet<&Path> = report
+        .extracted()
+        .flat_map(|(_, path)| path.ancestors().skip(1))
+        .collect();
 
-    // List mode: display archive contents and exit
-    if cli.list || cli.verbose {
-        return list_files(&extractor, cli.verbose).await;
+    let mut dirs = Vec::new();
+    for entry in entries.iter().filter(|e| e.is_directory) {
+        // Entries with unsafe names were never extracted into
+        let Ok(path) = output_path(entry, cli, state) else {
+            continue;
+        };
+        if written.contains(path.as_path()) {
+            dirs.push((entry, path));
+        }
     }
 
-    // Extract mode: get all entries from the archive
-    let entries = extractor.list_files().await?;
+    dirs.sort_by_key(|(_, path)| std::cmp::Reverse(path.components().count()));
+    for (entry, path) in dirs {
+        extractor.restore_directory(entry, &path).await?;
+    }
 
-    // Apply filters to determine which files to extract:
-    // 1. Skip directories (they are created automatically during extraction)
-    // 2. If specific files are requested, only include matching entries
-    // 3. Exclude files matching the exclusion patterns
-    let files_to_extract: Vec<_> = entries
-        .iter()
-        .filter(|e| {
-            // Skip directory entries
-            if e.is_directory {
-                return false;
-            }
+    Ok(())
+}
 
-            // If specific files are requested via positional arguments,
-            // only include entries that match
-            if !cli.files.is_empty() {
-                let matches = cli.files.iter().any(|f| {
-                    if has_glob_chars(f) {
-                        // Pattern contains wildcards: use glob matching
-                        glob_match(f, &e.file_name)
-                    } else {
-                        // No wildcards: exact match on filename or full path
-                        let basename = Path::new(&e.file_name)
-                            .file_name()
-                            .map(|s| s.to_string_lossy())
-                            .unwrap_or_default();
-                        e.file_name == *f || basename == *f
-                    }
-                });
-                if !matches {
-                    return false;
-                }
-            }
+/// Apply a fixed mode to every directory created for the extracted files.
+///
+/// Directories are collected from the path components of each written
+/// file below the output directory and updated deepest-first, so that
+/// restrictive modes on a parent don't prevent reaching its children.
+///
+/// # Arguments
+///
+/// * `report` - The results of the extraction run
+/// * `cli` - Parsed command-line arguments
+/// * `mode` - The permission bits to apply
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if a mode cannot be set.
+fn apply_dir_mode(report: &ExtractReport, cli: &Cli, mode: u32) -> Result<()> {
+    let base = output_base(cli);
 
-            // Exclude files matching the -x patterns
-            if cli
-                .exclude
-                .iter()
-                .any(|x| e.file_name.contains(x) || glob_match(x, &e.file_name))
-            {
-                return false;
+    let mut dirs = BTreeSet::new();
+    for (_, path) in report.extracted() {
+        let relative = path.strip_prefix(&base).unwrap_or(path);
+        for ancestor in relative.ancestors().skip(1) {
+            if !ancestor.as_os_str().is_empty() {
+                dirs.insert(base.join(ancestor));
             }
+        }
+    }
 
-            true
-        })
-        .collect();
-
-    // Extract each matching file
-    let multiple_files = cli.pipe && files_to_extract.len() > 1;
-    for entry in files_to_extract {
-        extract_file(&extractor, entry, cli, multiple_files).await?;
+    for dir in dirs.iter().rev() {
+        if dir.is_dir() {
+            set_mode(dir, mode)?;
+        }
     }
 
     Ok(())
 }
 
-/// List files in the ZIP archive.
+/// Write a manifest of the files extracted in this run.
 ///
-/// Supports two output formats:
-/// - Simple format (`-l`): Just file names, one per line
-/// - Verbose format (`-v`): Detailed table with size, compression ratio, and timestamps
+/// Each line has the form `crc32  size  path`, with the CRC in hex and the
+/// path relative to the output directory. Lines are sorted by path so the
+/// manifest is reproducible regardless of archive order.
 ///
 /// # Arguments
 ///
-/// * `extractor` - The ZIP extractor instance
-/// * `verbose` - If true, display detailed information in table format
+/// * `report` - The results of the extraction run
+/// * `cli` - Parsed command-line arguments
+/// * `path` - Where to write the manifest
 ///
 /// # Returns
 ///
-/// Returns `Ok(())` on success, or an error if listing fails.
-async fn list_files<R: ReadAt + 'static>(extractor: &ZipExtractor<R>, verbose: bool) -> Result<()> {
-    let entries = extractor.list_files().await?;
+/// Returns `Ok(())` on success, or an error if the manifest cannot be written.
+fn write_manifest(report: &ExtractReport, cli: &Cli, path: &Path) -> Result<()> {
+    let base = output_base(cli);
 
-    if verbose {
-        // Print table header for verbose output
-        println!(
-            "{:>10}  {:>10}  {:>5}  {:>10}  {:>5}  Name",
-            "Length", "Size", "Cmpr", "Date", "Time"
-        );
-        println!("{}", "-".repeat(70));
-    }
+    let mut lines: Vec<(String, String)> = report
+        .extracted()
+        .map(|(entry, output)| {
+            let relative = output.strip_prefix(&base).unwrap_or(output);
+            let name = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let line = format!(
+                "{:08x}  {}  {}\n",
+                entry.crc32, entry.uncompressed_size, name
+            );
+            (name, line)
+        })
+        .collect();
+    lines.sort();
 
-    // Track totals for summary line
-    let mut total_uncompressed = 0u64;
-    let mut total_compressed = 0u64;
-    let mut file_count = 0usize;
+    let content: String = lines.into_iter().map(|(_, line)| line).collect();
+    std::fs::write(path, content)?;
 
-    for entry in &entries {
-        if verbose {
-            // Parse DOS timestamp into human-readable format
-            let (year, month, day) = entry.mod_date();
-            let (hour, minute, _second) = entry.mod_time();
-
-            // Calculate compression ratio as percentage saved
-            let ratio = if entry.uncompressed_size > 0 {
-                format!(
-                    "{:>4}%",
-                    100 - (entry.compressed_size * 100 / entry.uncompressed_size)
-                )
-            } else {
-                "  0%".to_string()
-            };
+    Ok(())
+}
 
-            // Print detailed entry information
-            println!(
-                "{:>10}  {:>10}  {}  {:04}-{:02}-{:02}  {:02}:{:02}  {}",
-                entry.uncompressed_size,
-                entry.compressed_size,
-                ratio,
-                year,
-                month,
-                day,
-                hour,
-                minute,
-                entry.file_name
-            );
+/// Read a manifest of trusted SHA-256 hashes.
+///
+/// Each line has the form `sha256  path`, as written by `sha256sum`; a
+/// `*` before the path (binary mode) and a leading `./` are ignored, as
+/// are blank lines and lines starting with `#`.
+///
+/// # Arguments
+///
+/// * `path` - The manifest file
+///
+/// # Returns
+///
+/// The listed paths with their hashes in lowercase hex, in file order.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or a line is malformed.
+fn read_hash_manifest(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read manifest {}", path.display()))?;
 
-            // Accumulate totals (excluding directories)
-            if !entry.is_directory {
-                total_uncompressed += entry.uncompressed_size;
-                total_compressed += entry.compressed_size;
-                file_count += 1;
-            }
-        } else {
-            // Simple format: just the file name
-            println!("{}", entry.file_name);
+    let mut hashes = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
         }
-    }
-
-    // Print summary line in verbose mode
-    if verbose {
-        println!("{}", "-".repeat(70));
-        let total_ratio = if total_uncompressed > 0 {
-            format!(
-                "{:>4}%",
-                100 - (total_compressed * 100 / total_uncompressed)
-            )
-        } else {
-            "  0%".to_string()
+        let parsed = line.split_once(' ').and_then(|(hash, name)| {
+            let name = name.strip_prefix([' ', '*'])?;
+            let name = name.strip_prefix("./").unwrap_or(name);
+            let valid = hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit());
+            (valid && !name.is_empty()).then(|| (name.to_string(), hash.to_ascii_lowercase()))
+        });
+        let Some(entry) = parsed else {
+            bail!(
+                "{}:{}: expected 'sha256  path', got {:?}",
+                path.display(),
+                number + 1,
+                line
+            );
         };
-        println!(
-            "{:>10}  {:>10}  {}  {:>21}  {} files",
-            total_uncompressed, total_compressed, total_ratio, "", file_count
-        );
+        hashes.push(entry);
     }
-
-    Ok(())
+    Ok(hashes)
 }
 
-/// Extract a single file from the archive.
+/// Check the selected entries against trusted SHA-256 hashes.
 ///
-/// Handles various extraction options:
-/// - Pipe mode (`-p`): Write to stdout instead of file
-/// - Custom output directory (`-d`): Extract to specified directory
-/// - Junk paths (`-j`): Ignore directory structure in archive
-/// - Overwrite control (`-n`, `-o`): Handle existing files
+/// Every listed path must be among the selected entries, and its
+/// contents must hash to the listed value. Selected entries the manifest
+/// doesn't mention aren't checked. Each failure is reported on stderr.
 ///
 /// # Arguments
 ///
 /// * `extractor` - The ZIP extractor instance
-/// * `entry` - The ZIP file entry to extract
+/// * `entries` - The entries selected for this run
+/// * `trusted` - Paths and hashes from [`read_hash_manifest()`]
 /// * `cli` - Parsed command-line arguments
-/// * `show_filename` - If true, print filename marker before content (for pipe mode with multiple files)
 ///
-/// # Returns
+/// # Errors
 ///
-/// Returns `Ok(())` on success, or an error if extraction fails.
-async fn extract_file<R: ReadAt + 'static>(
+/// Returns an error if any listed file is missing, can't be read or
+/// doesn't match its hash.
+async fn verify_manifest<R: ReadAt + 'static>(
     extractor: &ZipExtractor<R>,
-    entry: &ZipFileEntry,
+    entries: &[&ZipFileEntry],
+    trusted: &[(String, String)],
     cli: &Cli,
-    show_filename: bool,
 ) -> Result<()> {
-    // Pipe mode: write file contents directly to stdout
-    if cli.pipe {
-        if show_filename {
-            use tokio::io::AsyncWriteExt;
-            let mut stdout = tokio::io::stdout();
-            stdout
-                .write_all(format!("--- {} ---\n", entry.file_name).as_bytes())
-                .await?;
-        }
-        return extractor.extract_to_stdout(entry).await;
-    }
+    let by_name: HashMap<&str, &ZipFileEntry> =
+        entries.iter().map(|e| (e.file_name.as_str(), *e)).collect();
 
-    // Determine the output path based on CLI options
-    let output_path = if let Some(ref dir) = cli.extract_dir {
-        // Extract to custom directory
-        let file_name = if cli.junk_paths {
-            // Junk paths: use only the base filename, ignore directory structure
-            Path::new(&entry.file_name)
-                .file_name()
-                .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| entry.file_name.clone())
-        } else {
-            // Preserve directory structure from archive
-            entry.file_name.clone()
-        };
-        PathBuf::from(dir).join(&file_name)
-    } else {
-        // Extract to current directory
-        let file_name = if cli.junk_paths {
-            Path::new(&entry.file_name)
-                .file_name()
-                .map(|s| s.to_string_lossy().to_string())
-                .unwrap_or_else(|| entry.file_name.clone())
-        } else {
-            entry.file_name.clone()
+    let mut failures = 0;
+    for (name, expected) in trusted {
+        let Some(entry) = by_name.get(name.as_str()) else {
+            eprintln!(
+                "error: {}: listed in the manifest but not selected from the archive",
+                name
+            );
+            failures += 1;
+            continue;
         };
-        PathBuf::from(&file_name)
-    };
-
-    // Handle existing files based on overwrite options
-    if output_path.exists() {
-        if cli.never_overwrite {
-            // -n flag: never overwrite, skip silently (unless quiet)
-            if !cli.is_quiet() {
-                eprintln!("Skipping: {} (file exists)", entry.file_name);
+        match extractor.sha256(entry).await {
+            Ok(hash) => {
+                let actual: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+                if actual != *expected {
+                    eprintln!(
+                        "error: {}: SHA-256 mismatch, expected {} but got {}",
+                        name, expected, actual
+                    );
+                    failures += 1;
+                }
             }
-            return Ok(());
-        }
-
-        if !cli.overwrite {
-            // Default behavior: skip with suggestion to use -o
-            if !cli.is_quiet() {
-                eprintln!("Skipping: {} (use -o to overwrite)", entry.file_name);
+            Err(e) => {
+                eprintln!("error: {}: {:#}", name, e);
+                failures += 1;
             }
-            return Ok(());
         }
-        // -o flag: overwrite without prompting (fall through to extraction)
     }
 
-    // Display extraction progress
+    if failures > 0 {
+        bail!(EntriesFailed(format!(
+            "{} of {} manifest entries failed verification",
+            failures,
+            trusted.len()
+        )));
+    }
     if !cli.is_quiet() {
-        println!("  extracting: {}", entry.file_name);
+        println!("Verified {} files against the manifest.", trusted.len());
     }
+    Ok(())
+}
 
-    // Perform the actual extraction
-    extractor.extract_to_file(entry, &output_path).await?;
+/// Check that a path resolves to a location inside a directory.
+///
+/// Both paths are resolved through existing symlinks, so a symlinked
+/// directory or file leading out of `root` is caught, and the part of
+/// `path` that doesn't exist yet may only consist of plain names.
+///
+/// # Arguments
+///
+/// * `root` - The output directory, empty for the current one
+/// * `path` - The path about to be written
+///
+/// # Errors
+///
+/// Returns an error if `path` resolves to a location outside `root`, or
+/// if either path cannot be resolved.
+fn check_within_root(root: &Path, path: &Path) -> Result<()> {
+    let root = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    let resolved_root = resolve_path(root)?;
+    let resolved = resolve_path(path)?;
+    if !resolved.starts_with(&resolved_root) {
+        bail!(
+            "Refusing to write {}: it resolves to {}, outside {}",
+            path.display(),
+            resolved.display(),
+            resolved_root.display()
+        );
+    }
+    Ok(())
+}
 
+/// Check that a symbolic link about to be created points inside a
+/// directory.
+///
+/// The target is resolved from the link's real parent directory, through
+/// any links extracted before, so neither a changed output path nor a
+/// chain of links can lead out of `root`.
+///
+/// # Arguments
+///
+/// * `root` - The output directory, empty for the current one
+/// * `entry` - The symlink entry
+/// * `path` - Where the link is about to be created
+/// * `target` - The link target stored as the entry's data
+///
+/// # Errors
+///
+/// Returns an error if the target is absolute, or doesn't resolve to a
+/// location inside `root`.
+#[cfg(unix)]
+fn check_link_within_root(
+    root: &Path,
+    entry: &ZipFileEntry,
+    path: &Path,
+    target: &str,
+) -> Result<()> {
+    if Path::new(target).is_absolute() {
+        bail!(
+            "Refusing symlink {} -> {}: absolute target",
+            entry.file_name,
+            target
+        );
+    }
+    let root = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let resolved_root = resolve_path(root)?;
+    let inside = resolve_link_target(&resolve_path(parent)?, target)
+        .is_some_and(|resolved| resolved.starts_with(&resolved_root));
+    if !inside {
+        bail!(
+            "Refusing symlink {} -> {}: target escapes the extraction directory",
+            entry.file_name,
+            target
+        );
+    }
     Ok(())
 }
 
-/// Check if a pattern contains glob wildcard characters.
+/// Resolve a symbolic link target the way the kernel will.
+///
+/// Existing components are resolved through links. Missing directories
+/// of `parent` are created as plain directories before the link, so
+/// `..` may climb out of them, but not out of a missing component of the
+/// target: a later entry could still make that a link of its own.
 ///
 /// # Arguments
 ///
-/// * `pattern` - The pattern to check
+/// * `parent` - The resolved directory the link is placed in
+/// * `target` - The relative link target
 ///
 /// # Returns
 ///
-/// Returns `true` if the pattern contains `*` or `?` wildcards.
-fn has_glob_chars(pattern: &str) -> bool {
-    pattern.contains('*') || pattern.contains('?')
+/// The location the link points at, or `None` if that depends on
+/// entries not extracted yet or on a dangling link.
+#[cfg(unix)]
+fn resolve_link_target(parent: &Path, target: &str) -> Option<PathBuf> {
+    use std::path::Component;
+
+    let mut resolved = parent.to_path_buf();
+    let mut target_missing = false;
+    for component in Path::new(target).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if std::fs::symlink_metadata(&resolved).is_ok() {
+                    resolved = std::fs::canonicalize(&resolved).ok()?;
+                } else if target_missing {
+                    return None;
+                }
+                resolved.pop();
+            }
+            Component::Normal(name) => {
+                resolved.push(name);
+                if std::fs::symlink_metadata(&resolved).is_ok() {
+                    resolved = std::fs::canonicalize(&resolved).ok()?;
+                } else {
+                    target_missing = true;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
 }
 
-/// Simple glob pattern matching supporting `*` and `?` wildcards.
+/// Resolve a path that may not exist yet.
+///
+/// # Returns
 ///
-/// This is a basic implementation for file matching:
-/// - `*` matches zero or more characters
-/// - `?` matches exactly one character
+/// The canonical form of the path's longest existing ancestor, followed
+/// by the remaining components.
+///
+/// # Errors
+///
+/// Returns an error if the existing part cannot be canonicalized, or the
+/// rest contains anything but plain names.
+fn resolve_path(path: &Path) -> Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let mut missing = Vec::new();
+    let mut existing = path.as_path();
+    while std::fs::symlink_metadata(existing).is_err() {
+        let Some(parent) = existing.parent() else {
+            break;
+        };
+        missing.push(existing.components().next_back());
+        existing = parent;
+    }
+
+    let mut resolved = std::fs::canonicalize(existing)
+        .with_context(|| format!("Cannot resolve {}", existing.display()))?;
+    for component in missing.into_iter().rev() {
+        match component {
+            Some(std::path::Component::Normal(name)) => resolved.push(name),
+            _ => bail!("Cannot resolve {}", path.display()),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Get the directory extracted files are placed under.
 ///
 /// # Arguments
 ///
-/// * `pattern` - The glob pattern to match against
-/// * `text` - The text to check for a match
+/// * `cli` - Parsed command-line arguments
 ///
 /// # Returns
 ///
-/// Returns `true` if the text matches the pattern, `false` otherwise.
+/// The `--flatten-into` or `-d` directory, or an empty path for the
+/// current directory.
+fn output_base(cli: &Cli) -> PathBuf {
+    cli.flatten_into
+        .as_deref()
+        .or(cli.extract_dir.as_deref())
+        .map(PathBuf::from)
+        .unwrap_or_default()
+}
+
+/// Set the permission bits of a file or directory.
 ///
-/// # Examples
+/// This is a no-op on platforms without Unix permissions.
 ///
-/// ```ignore
-/// assert!(glob_match("*.txt", "readme.txt"));
-/// assert!(glob_match("file?.dat", "file1.dat"));
-/// assert!(!glob_match("*.txt", "readme.md"));
-/// ```
-fn glob_match(pattern: &str, text: &str) -> bool {
-    let pattern_chars: Vec<char> = pattern.chars().collect();
-    let text_chars: Vec<char> = text.chars().collect();
-
-    /// Recursive helper function for glob matching.
-    ///
-    /// Uses a simple backtracking algorithm to handle `*` wildcards.
-    fn do_match(pattern: &[char], text: &[char]) -> bool {
-        match (pattern.first(), text.first()) {
-            // Both exhausted: match successful
-            (None, None) => true,
-            // Star matches zero or more characters
-            (Some('*'), _) => {
-                // Try matching zero characters (skip the star)
-                // OR matching one character (keep the star for more)
-                do_match(&pattern[1..], text) || (!text.is_empty() && do_match(pattern, &text[1..]))
-            }
-            // Question mark matches exactly one character
-            (Some('?'), Some(_)) => do_match(&pattern[1..], &text[1..]),
-            // Literal character match
-            (Some(p), Some(t)) if *p == *t => do_match(&pattern[1..], &text[1..]),
-            // No match
-            _ => false,
-        }
+/// # Arguments
+///
+/// * `path` - The path to update
+/// * `mode` - The permission bits to apply
+///
+/// # Returns
+///
+/// Returns `Ok(())` on success, or an error if the mode cannot be set.
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
     }
 
-    do_match(&pattern_chars, &text_chars)
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+
+    Ok(())
 }
 
 /// Format a byte size into a human-readable string.
@@ -0,0 +1,260 @@
+//! Wildcard patterns for selecting archive entries by name.
+//!
+//! Patterns support two wildcards:
+//! - `*` matches zero or more characters, including `/`
+//! - `?` matches exactly one character
+//!
+//! Every other character matches itself. A pattern is anchored by
+//! default, so it has to match the whole name; an unanchored pattern
+//! matches if it matches any part of the name.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let pattern = Pattern::parse("*.TXT").with_case_sensitive(false);
+//! assert!(pattern.matches("docs/readme.txt"));
+//!
+//! let pattern = Pattern::parse("test").with_anchored(false);
+//! assert!(pattern.matches("src/tests/data.bin"));
+//! ```
+
+/// A parsed wildcard pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The pattern's characters
+    chars: Vec<char>,
+    /// Whether letters must match in case
+    case_sensitive: bool,
+    /// Whether the pattern must match the whole text
+    anchored: bool,
+}
+
+impl Pattern {
+    /// Parse a pattern.
+    ///
+    /// The result is case-sensitive and anchored; see
+    /// [`with_case_sensitive()`](Self::with_case_sensitive) and
+    /// [`with_anchored()`](Self::with_anchored).
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - The pattern text
+    ///
+    /// # Returns
+    ///
+    /// The parsed pattern.
+    pub fn parse(pattern: &str) -> Self {
+        Self {
+            chars: pattern.chars().collect(),
+            case_sensitive: true,
+            anchored: true,
+        }
+    }
+
+    /// Set whether letters must match in case.
+    ///
+    /// # Arguments
+    ///
+    /// * `case_sensitive` - `false` to ignore case when matching
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Set whether the pattern must match the whole text.
+    ///
+    /// # Arguments
+    ///
+    /// * `anchored` - `false` to also accept matches of part of the text,
+    ///   as if the pattern started and ended with `*`
+    pub fn with_anchored(mut self, anchored: bool) -> Self {
+        self.anchored = anchored;
+        self
+    }
+
+    /// Check whether the pattern contains wildcards.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the pattern contains `*` or `?`.
+    pub fn has_wildcards(&self) -> bool {
+        self.chars.iter().any(|&c| c == '*' || c == '?')
+    }
+
+    /// Check whether a text matches the pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to check, typically an entry name
+    ///
+    /// # Returns
+    ///
+    /// `true` if the text matches.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// assert!(Pattern::parse("file?.dat").matches("file1.dat"));
+    /// assert!(!Pattern::parse("*.txt").matches("readme.md"));
+    /// ```
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let pattern = &self.chars[..];
+
+        // Walk both sequences, remembering the last `*` seen and where in
+        // the text it started matching. On a mismatch, let that star
+        // swallow one more character and retry from there. An unanchored
+        // pattern behaves as if wrapped in stars.
+        let mut p = 0;
+        let mut t = 0;
+        let mut star = (!self.anchored).then_some((0, 0));
+        while t < text.len() {
+            match pattern.get(p) {
+                Some('*') => {
+                    p += 1;
+                    star = Some((p, t));
+                }
+                Some(&c) if c == '?' || self.char_eq(c, text[t]) => {
+                    p += 1;
+                    t += 1;
+                }
+                _ => match star {
+                    Some((star_p, star_t)) => {
+                        p = star_p;
+                        t = star_t + 1;
+                        star = Some((star_p, t));
+                    }
+                    None => return false,
+                },
+            }
+            // The whole pattern matched part of the text
+            if !self.anchored && p == pattern.len() {
+                return true;
+            }
+        }
+
+        // Only stars may be left over
+        pattern[p..].iter().all(|&c| c == '*')
+    }
+
+    /// Compare a pattern character with a text character.
+    fn char_eq(&self, p: char, t: char) -> bool {
+        p == t || (!self.case_sensitive && p.to_lowercase().eq(t.to_lowercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Pattern::parse(pattern).matches(text)
+    }
+
+    fn matches_unanchored(pattern: &str, text: &str) -> bool {
+        Pattern::parse(pattern).with_anchored(false).matches(text)
+    }
+
+    #[test]
+    fn literal_patterns_match_the_whole_name() {
+        assert!(matches("readme.txt", "readme.txt"));
+        assert!(!matches("readme.txt", "readme.txt.bak"));
+        assert!(!matches("readme.txt", "docs/readme.txt"));
+        assert!(!matches("readme.txt", "readme.tx"));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+        assert!(!matches("a", ""));
+    }
+
+    #[test]
+    fn star_matches_any_run_including_slashes() {
+        assert!(matches("*", ""));
+        assert!(matches("*", "a/b/c"));
+        assert!(matches("*.txt", "a.txt"));
+        assert!(matches("*.txt", "docs/a.txt"));
+        assert!(matches("*.txt", ".txt"));
+        assert!(!matches("*.txt", "a.txt.gz"));
+        assert!(matches("docs/*", "docs/"));
+        assert!(matches("docs/*", "docs/a/b.txt"));
+        assert!(matches("a*b*c", "abc"));
+        assert!(matches("a*b*c", "aXXbYYc"));
+        assert!(!matches("a*b*c", "aXXcYYb"));
+        assert!(matches("**", "anything"));
+        assert!(matches("a**", "a"));
+    }
+
+    #[test]
+    fn star_backtracks_to_later_occurrences() {
+        assert!(matches("*ab", "aab"));
+        assert!(matches("*abc", "ababc"));
+        assert!(matches("a*aab", "aaaab"));
+        assert!(!matches("*ab", "aba"));
+        assert!(matches("*a*a*a*", "banana"));
+        assert!(!matches("*a*a*a*a*", "banana"));
+    }
+
+    #[test]
+    fn question_mark_matches_exactly_one_character() {
+        assert!(matches("file?.dat", "file1.dat"));
+        assert!(!matches("file?.dat", "file.dat"));
+        assert!(!matches("file?.dat", "file12.dat"));
+        assert!(matches("??", "ab"));
+        assert!(!matches("?", ""));
+        assert!(matches("?", "/"));
+        assert!(matches("*?", "a"));
+        assert!(!matches("*?", ""));
+    }
+
+    #[test]
+    fn characters_are_compared_not_bytes() {
+        assert!(matches("?.txt", "é.txt"));
+        assert!(matches("日本*", "日本語.txt"));
+        assert!(!matches("??.txt", "é.txt"));
+    }
+
+    #[test]
+    fn case_sensitivity_is_configurable() {
+        assert!(!matches("*.TXT", "readme.txt"));
+        let insensitive = Pattern::parse("*.TXT").with_case_sensitive(false);
+        assert!(insensitive.matches("readme.txt"));
+        assert!(insensitive.matches("README.Txt"));
+        assert!(!insensitive.matches("readme.md"));
+        assert!(
+            Pattern::parse("ÉTÉ")
+                .with_case_sensitive(false)
+                .matches("été")
+        );
+    }
+
+    #[test]
+    fn unanchored_patterns_match_any_part() {
+        assert!(matches_unanchored("test", "src/tests/data.bin"));
+        assert!(matches_unanchored("test", "test"));
+        assert!(matches_unanchored("data", "src/tests/data.bin"));
+        assert!(!matches_unanchored("tset", "src/tests/data.bin"));
+        assert!(matches_unanchored("", "anything"));
+        assert!(matches_unanchored("", ""));
+        assert!(!matches_unanchored("a", ""));
+        assert!(matches_unanchored("t?st", "my-tast-file"));
+        assert!(matches_unanchored("a*c", "xxabyycxx"));
+        assert!(matches_unanchored("ana", "banana"));
+        assert!(!matches_unanchored("nab", "banana"));
+    }
+
+    #[test]
+    fn anchoring_can_be_restored() {
+        let pattern = Pattern::parse("test")
+            .with_anchored(false)
+            .with_anchored(true);
+        assert!(!pattern.matches("tests"));
+        assert_eq!(pattern, Pattern::parse("test"));
+    }
+
+    #[test]
+    fn wildcards_are_detected() {
+        assert!(Pattern::parse("*.txt").has_wildcards());
+        assert!(Pattern::parse("file?.dat").has_wildcards());
+        assert!(!Pattern::parse("docs/readme.txt").has_wildcards());
+        assert!(!Pattern::parse("").has_wildcards());
+    }
+}
@@ -0,0 +1,131 @@
+//! One-call extraction of a single named entry.
+//!
+//! These functions open the archive, look up one entry by its exact name,
+//! extract it and check its integrity, without the caller managing
+//! readers or extractors. Use [`ZipExtractor`] directly to extract
+//! several entries from the same archive.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! let written = runzip::extract_file_from_url(
+//!     "https://example.com/archive.zip",
+//!     "docs/README.md",
+//!     "README.md",
+//! )
+//! .await?;
+//! println!("wrote {} bytes", written);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+use tokio::fs;
+
+use crate::io::{HttpRangeReader, LocalFileReader, ReadAt};
+use crate::zip::ZipExtractor;
+
+/// Extract one entry of a remote ZIP archive to a file.
+///
+/// # Arguments
+///
+/// * `url` - The HTTP or HTTPS URL of the ZIP file
+/// * `name` - The exact name of the entry in the archive
+/// * `dest` - Path of the file to write; parent directories are created
+///
+/// # Returns
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The archive cannot be read or parsed
+/// - No file entry has the given name
+/// - The entry is corrupt (size or CRC-32 mismatch)
+/// - The output file cannot be written
+///
+/// # Example
+///
+/// ```ignore
+/// let written = runzip::extract_file_from_url(url, "data.csv", "out/data.csv").await?;
+/// ```
+pub async fn extract_file_from_url(url: &str, name: &str, dest: impl AsRef<Path>) -> Result<u64> {
+    let reader = HttpRangeReader::new(url.to_string()).await?;
+    extract_named(Arc::new(reader), name, dest.as_ref()).await
+}
+
+/// Extract one entry of a local ZIP archive to a file.
+///
+/// # Arguments
+///
+/// * `path` - Path to the ZIP file
+/// * `name` - The exact name of the entry in the archive
+/// * `dest` - Path of the file to write; parent directories are created
+///
+/// # Returns
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The archive cannot be opened, read or parsed
+/// - No file entry has the given name
+/// - The entry is corrupt (size or CRC-32 mismatch)
+/// - The output file cannot be written
+///
+/// # Example
+///
+/// ```ignore
+/// let written = runzip::extract_file_from_path("archive.zip", "data.csv", "data.csv").await?;
+/// ```
+pub async fn extract_file_from_path(
+    path: impl AsRef<Path>,
+    name: &str,
+    dest: impl AsRef<Path>,
+) -> Result<u64> {
+    let path = path.as_ref();
+    let reader =
+        LocalFileReader::new(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    extract_named(Arc::new(reader), name, dest.as_ref()).await
+}
+
+/// Find the named entry, extract it to memory, check it and write it out.
+///
+/// The data is checked before the destination is touched, so a corrupt
+/// entry never leaves a partial file behind. The destination path comes
+/// from the caller, never from the archive.
+async fn extract_named<R: ReadAt + 'static>(
+    reader: Arc<R>,
+    name: &str,
+    dest: &Path,
+) -> Result<u64> {
+    let extractor = ZipExtractor::new(reader);
+    let entries = extractor.list_files().await?;
+
+    let Some(entry) = entries.iter().find(|e| e.file_name == name) else {
+        bail!("No entry named {} in the archive", name);
+    };
+    if entry.is_directory {
+        bail!("{} is a directory, not a file", name);
+    }
+
+    // Checked against the recorded size and CRC-32
+    let data = extractor.extract_to_memory(entry).await?;
+
+    if let Some(parent) = dest.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(dest, &data)
+        .await
+        .with_context(|| format!("Cannot write {}", dest.display()))?;
+
+    Ok(data.len() as u64)
+}
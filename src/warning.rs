@@ -0,0 +1,214 @@
+//! Non-fatal conditions noticed while reading or extracting an archive.
+//!
+//! The library never prints warnings itself. Readers, parsers and
+//! extractors collect them as [`Warning`] values, which callers drain
+//! with `take_warnings()` and render or inspect as they see fit.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let entries = extractor.list_files().await?;
+//! for warning in extractor.take_warnings() {
+//!     eprintln!("Warning: {}", warning);
+//! }
+//! ```
+
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::zip::{CompressionMethod, SkipReason};
+
+/// A non-fatal condition worth reporting to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// The EOCD's Central Directory size disagrees with the space between
+    /// the directory's start and the end record
+    CentralDirectorySizeMismatch {
+        /// Size recorded in the EOCD
+        declared: u64,
+        /// Bytes actually available before the end record
+        actual: u64,
+    },
+    /// The Central Directory holds a different number of entries than
+    /// the EOCD declares
+    EntryCountMismatch {
+        /// Count recorded in the EOCD
+        declared: u64,
+        /// Entries actually found
+        found: u64,
+    },
+    /// The server's HEAD response reported a different file size than
+    /// its Range responses
+    SizeCorrected {
+        /// Size from the HEAD response's `Content-Length`
+        reported: u64,
+        /// Size from the `Content-Range` total, used from now on
+        actual: u64,
+    },
+    /// The end record claims a non-zero disk number, but the whole
+    /// Central Directory is present, so the archive is read as a single
+    /// disk
+    DiskNumberIgnored {
+        /// The disk number recorded in the end record
+        disk: u32,
+    },
+    /// Data precedes the archive without its offsets accounting for it,
+    /// as when a self-extractor stub is prepended to an existing archive
+    PrependedData {
+        /// Number of bytes the recorded offsets are shifted by
+        length: u64,
+    },
+    /// A request failed transiently and is being retried
+    Retry {
+        /// Retry number, starting at 1
+        attempt: u32,
+        /// Maximum number of attempts
+        max: u32,
+        /// What went wrong
+        reason: String,
+    },
+    /// An entry's data didn't decode with its declared compression method
+    /// but did with another one
+    MethodMismatch {
+        /// Name of the entry in the archive
+        name: String,
+        /// Method recorded in the archive
+        declared: CompressionMethod,
+        /// Method that decoded the data correctly
+        actual: CompressionMethod,
+    },
+    /// An entry name was stripped of a byte order mark or leading slashes
+    NameNormalized {
+        /// Name as stored in the archive
+        original: String,
+        /// Name used from now on
+        name: String,
+    },
+    /// An entry's Local File Header records a different CRC-32 or sizes
+    /// than its Central Directory record, without deferring them to a
+    /// data descriptor
+    LocalHeaderMismatch {
+        /// Name of the entry in the archive
+        name: String,
+        /// CRC-32 and compressed and uncompressed sizes in the local header
+        local: (u32, u64, u64),
+        /// The same values from the Central Directory, which are used
+        central: (u32, u64, u64),
+    },
+    /// A Local File Header found while salvaging couldn't be turned into
+    /// an entry
+    SalvageFailed {
+        /// Offset of the header in the archive
+        offset: u64,
+        /// Why the entry couldn't be recovered
+        reason: String,
+    },
+    /// An entry was left out of the listing because its name is longer
+    /// than the configured maximum
+    LongNameSkipped {
+        /// Offset of the entry's Central Directory File Header
+        offset: u64,
+        /// Length of the name in bytes
+        length: usize,
+    },
+    /// An entry was not extracted
+    Skipped {
+        /// Name of the entry in the archive
+        name: String,
+        /// Why it was skipped
+        reason: SkipReason,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::CentralDirectorySizeMismatch { declared, actual } => write!(
+                f,
+                "Central Directory size is {} bytes but {} bytes precede the end record",
+                declared, actual
+            ),
+            Warning::EntryCountMismatch { declared, found } => write!(
+                f,
+                "Central Directory has {} entries but {} were declared",
+                found, declared
+            ),
+            Warning::SizeCorrected { reported, actual } => write!(
+                f,
+                "Server reported a size of {} bytes but Range responses give {} bytes; using {}",
+                reported, actual, actual
+            ),
+            Warning::DiskNumberIgnored { disk } => write!(
+                f,
+                "End record claims disk number {} but the archive is complete; ignoring it",
+                disk
+            ),
+            Warning::PrependedData { length } => write!(
+                f,
+                "{} extra bytes at the beginning of the archive; adjusting offsets",
+                length
+            ),
+            Warning::Retry {
+                attempt,
+                max,
+                reason,
+            } => write!(f, "Connection error, retry {}/{}: {}", attempt, max, reason),
+            Warning::MethodMismatch {
+                name,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "{} is labeled {} (method {}) but decodes as {}",
+                name,
+                declared.name(),
+                declared.as_u16(),
+                actual.name()
+            ),
+            Warning::NameNormalized { original, name } => {
+                write!(f, "Stripped {:?} to {}", original, name)
+            }
+            Warning::LocalHeaderMismatch {
+                name,
+                local,
+                central,
+            } => write!(
+                f,
+                "Local header of {} records CRC-32 {:08x}, {} compressed and {} uncompressed bytes, \
+                 but the Central Directory has {:08x}, {} and {}; using the latter",
+                name, local.0, local.1, local.2, central.0, central.1, central.2
+            ),
+            Warning::SalvageFailed { offset, reason } => {
+                write!(f, "Cannot salvage entry at offset {}: {}", offset, reason)
+            }
+            Warning::LongNameSkipped { offset, length } => write!(
+                f,
+                "Skipping entry at offset {}: name is {} bytes long",
+                offset, length
+            ),
+            Warning::Skipped { name, reason } => write!(f, "Skipping: {} ({})", name, reason),
+        }
+    }
+}
+
+/// Thread-safe collection of warnings, drained by the owner's caller.
+#[derive(Debug, Default)]
+pub(crate) struct Warnings {
+    /// Warnings recorded since the last drain
+    items: Mutex<Vec<Warning>>,
+}
+
+impl Warnings {
+    /// Record a warning.
+    pub(crate) fn push(&self, warning: Warning) {
+        self.items
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(warning);
+    }
+
+    /// Remove and return all recorded warnings.
+    pub(crate) fn take(&self) -> Vec<Warning> {
+        std::mem::take(&mut *self.items.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
@@ -0,0 +1,456 @@
+//! Decryption of WinZip AES-encrypted entries (AE-1 and AE-2).
+//!
+//! The password and the entry's salt give, through PBKDF2-HMAC-SHA1 with
+//! 1000 iterations, an AES key, an HMAC key and a two-byte password
+//! verification value. The compressed data is encrypted with AES in
+//! counter mode, using a little-endian block counter starting at 1, and
+//! authenticated with the first ten bytes of an HMAC-SHA1 over the
+//! encrypted data. See [`AesInfo`] for the layout.
+
+use std::num::NonZeroU32;
+
+use anyhow::{Result, anyhow, bail};
+use aws_lc_rs::cipher::{AES_128, AES_192, AES_256, EncryptingKey, UnboundCipherKey};
+use aws_lc_rs::{constant_time, hmac, pbkdf2};
+
+use super::error::ZipError;
+use super::structures::{AesInfo, ZipFileEntry};
+
+/// PBKDF2 iterations used by all WinZip AES writers
+const ITERATIONS: NonZeroU32 = NonZeroU32::new(1000).unwrap();
+
+/// Size of an AES block
+const BLOCK: usize = 16;
+
+/// Blocks of key stream generated at a time
+const STREAM_BLOCKS: usize = 256;
+
+/// Get the size of the header before an entry's encrypted data.
+///
+/// # Arguments
+///
+/// * `entry` - The encrypted entry
+/// * `aes` - The entry's encryption parameters
+///
+/// # Returns
+///
+/// The length of the salt and the password verification value.
+///
+/// # Errors
+///
+/// Returns an error if the key strength is unknown.
+pub(crate) fn header_len(entry: &ZipFileEntry, aes: AesInfo) -> Result<usize> {
+    match aes.key_len() {
+        Some(key_len) => Ok(key_len / 2 + AesInfo::VERIFIER_SIZE),
+        None => bail!(
+            "{} uses unknown AES key strength {}",
+            entry.file_name,
+            aes.strength
+        ),
+    }
+}
+
+/// Decrypt and authenticate an entry's data held in memory.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the data belongs to
+/// * `aes` - The entry's encryption parameters
+/// * `password` - The password to try
+/// * `data` - The entry's `compressed_size` bytes of stored data
+///
+/// # Returns
+///
+/// The compressed data, ready to be decompressed with the entry's
+/// compression method.
+///
+/// # Errors
+///
+/// Returns [`ZipError::WrongPassword`] if the password verification value
+/// doesn't match, [`ZipError::AuthenticationFailed`] if the data was
+/// modified, and an error if the key strength is unknown or the data is
+/// too short.
+pub(crate) fn decrypt(
+    entry: &ZipFileEntry,
+    aes: AesInfo,
+    password: &str,
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let header_len = header_len(entry, aes)?;
+    let overhead = header_len + AesInfo::AUTH_CODE_SIZE;
+    if data.len() < overhead {
+        bail!(
+            "Encrypted data of {} is truncated: {} bytes, at least {} expected",
+            entry.file_name,
+            data.len(),
+            overhead
+        );
+    }
+    let (header, rest) = data.split_at(header_len);
+    let (ciphertext, auth_code) = rest.split_at(rest.len() - AesInfo::AUTH_CODE_SIZE);
+
+    let mut decryptor = Decryptor::new(entry, aes, password, header)?;
+    let mut plaintext = ciphertext.to_vec();
+    decryptor.decrypt(&mut plaintext)?;
+    decryptor.finish(entry, auth_code)?;
+    Ok(plaintext)
+}
+
+/// Incremental decryption and authentication of an entry's data.
+///
+/// The data can be fed in chunks of any size, so entries are decrypted
+/// as they are streamed. The password is checked when the decryptor is
+/// created, but the authentication code only by
+/// [`finish()`](Self::finish), once all data went through.
+pub(crate) struct Decryptor {
+    /// AES in ECB mode, to encrypt counter blocks
+    key: EncryptingKey,
+    /// Counter of the next key stream block
+    counter: u128,
+    /// Key stream generated ahead
+    stream: Vec<u8>,
+    /// Bytes of `stream` already used
+    used: usize,
+    /// HMAC-SHA1 over the encrypted data seen so far
+    auth: hmac::Context,
+}
+
+impl Decryptor {
+    /// Derive the keys for an entry and check the password.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The encrypted entry
+    /// * `aes` - The entry's encryption parameters
+    /// * `password` - The password to try
+    /// * `header` - The salt and password verification value preceding
+    ///   the encrypted data, [`header_len()`] bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ZipError::WrongPassword`] if the password verification
+    /// value doesn't match, and an error if the key strength is unknown.
+    pub(crate) fn new(
+        entry: &ZipFileEntry,
+        aes: AesInfo,
+        password: &str,
+        header: &[u8],
+    ) -> Result<Self> {
+        let salt_len = header_len(entry, aes)? - AesInfo::VERIFIER_SIZE;
+        let key_len = 2 * salt_len;
+        let (salt, verifier) = header[..salt_len + AesInfo::VERIFIER_SIZE].split_at(salt_len);
+
+        // Encryption key, authentication key and verification value
+        let mut keys = vec![0u8; 2 * key_len + AesInfo::VERIFIER_SIZE];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA1,
+            ITERATIONS,
+            salt,
+            password.as_bytes(),
+            &mut keys,
+        );
+        let (encryption_key, rest) = keys.split_at(key_len);
+        let (auth_key, expected_verifier) = rest.split_at(key_len);
+
+        if constant_time::verify_slices_are_equal(verifier, expected_verifier).is_err() {
+            bail!(ZipError::WrongPassword {
+                name: entry.file_name.clone(),
+            });
+        }
+
+        let algorithm = match key_len {
+            16 => &AES_128,
+            24 => &AES_192,
+            _ => &AES_256,
+        };
+        let key = UnboundCipherKey::new(algorithm, encryption_key)
+            .and_then(EncryptingKey::ecb)
+            .map_err(|_| anyhow!("Cannot set up AES decryption"))?;
+        let auth_key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, auth_key);
+        Ok(Self {
+            key,
+            counter: 1,
+            stream: Vec::new(),
+            used: 0,
+            auth: hmac::Context::with_key(&auth_key),
+        })
+    }
+
+    /// Decrypt the next chunk of data in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Encrypted data following the previous chunk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if AES fails.
+    pub(crate) fn decrypt(&mut self, data: &mut [u8]) -> Result<()> {
+        // The code authenticates the encrypted data
+        self.auth.update(data);
+
+        let mut done = 0;
+        while done < data.len() {
+            if self.used == self.stream.len() {
+                self.refill()?;
+            }
+            let len = (self.stream.len() - self.used).min(data.len() - done);
+            let stream = &self.stream[self.used..self.used + len];
+            for (byte, key_byte) in data[done..done + len].iter_mut().zip(stream) {
+                *byte ^= key_byte;
+            }
+            self.used += len;
+            done += len;
+        }
+        Ok(())
+    }
+
+    /// Check the authentication code once all data was decrypted.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The encrypted entry
+    /// * `auth_code` - The code stored after the encrypted data
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ZipError::AuthenticationFailed`] if the data was
+    /// modified.
+    pub(crate) fn finish(self, entry: &ZipFileEntry, auth_code: &[u8]) -> Result<()> {
+        let tag = self.auth.sign();
+        if constant_time::verify_slices_are_equal(
+            &tag.as_ref()[..AesInfo::AUTH_CODE_SIZE],
+            auth_code,
+        )
+        .is_err()
+        {
+            bail!(ZipError::AuthenticationFailed {
+                name: entry.file_name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Generate the next blocks of key stream.
+    ///
+    /// WinZip's counter mode encrypts a 16-byte little-endian counter
+    /// that starts at 1, unlike the big-endian counters of standard CTR,
+    /// so the key stream is built by encrypting counter blocks in ECB
+    /// mode.
+    fn refill(&mut self) -> Result<()> {
+        self.stream.resize(STREAM_BLOCKS * BLOCK, 0);
+        for block in self.stream.chunks_exact_mut(BLOCK) {
+            block.copy_from_slice(&self.counter.to_le_bytes());
+            self.counter = self.counter.wrapping_add(1);
+        }
+        self.key
+            .encrypt(&mut self.stream)
+            .map_err(|_| anyhow!("AES decryption failed"))?;
+        self.used = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::io::MemoryReader;
+    use crate::zip::ZipExtractor;
+
+    /// XOR data with WinZip's AES-CTR key stream, all at once.
+    fn xor_key_stream(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let key = UnboundCipherKey::new(&AES_256, key)
+            .and_then(EncryptingKey::ecb)
+            .unwrap();
+        let mut stream: Vec<u8> = (1..=data.len().div_ceil(BLOCK) as u128)
+            .flat_map(u128::to_le_bytes)
+            .collect();
+        key.encrypt(&mut stream).unwrap();
+        data.iter().zip(&stream).map(|(a, b)| a ^ b).collect()
+    }
+
+    /// Encrypt data with AES-256 the way WinZip does.
+    fn encrypt(password: &str, plaintext: &[u8]) -> Vec<u8> {
+        let salt = [7u8; 16];
+        let mut keys = vec![0u8; 2 * 32 + AesInfo::VERIFIER_SIZE];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA1,
+            ITERATIONS,
+            &salt,
+            password.as_bytes(),
+            &mut keys,
+        );
+        let ciphertext = xor_key_stream(&keys[..32], plaintext);
+        let auth_key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, &keys[32..64]);
+        let tag = hmac::sign(&auth_key, &ciphertext);
+
+        [
+            &salt[..],
+            &keys[64..],
+            &ciphertext,
+            &tag.as_ref()[..AesInfo::AUTH_CODE_SIZE],
+        ]
+        .concat()
+    }
+
+    /// Build an archive holding one stored AE-2 entry.
+    fn archive(name: &str, data: &[u8]) -> Vec<u8> {
+        archive_with(name, data, 0, data.len() - 28)
+    }
+
+    /// Build an archive holding one AE-2 entry compressed with `method`.
+    fn archive_with(name: &str, data: &[u8], method: u8, uncompressed: usize) -> Vec<u8> {
+        // Header ID, size, AE-2, vendor "AE", AES-256, actual method
+        let extra = [0x01, 0x99, 7, 0, 2, 0, b'A', b'E', 3, method, 0];
+        let common = |zip: &mut Vec<u8>| {
+            zip.extend_from_slice(&51u16.to_le_bytes()); // Version needed
+            zip.extend_from_slice(&1u16.to_le_bytes()); // Encrypted
+            zip.extend_from_slice(&AesInfo::METHOD.to_le_bytes());
+            zip.extend_from_slice(&0u16.to_le_bytes()); // Time
+            zip.extend_from_slice(&0x21u16.to_le_bytes()); // 1980-01-01
+            zip.extend_from_slice(&0u32.to_le_bytes()); // No CRC-32 in AE-2
+            zip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            zip.extend_from_slice(&(uncompressed as u32).to_le_bytes());
+            zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            zip.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        };
+
+        let mut zip = b"PK\x03\x04".to_vec();
+        common(&mut zip);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(&extra);
+        zip.extend_from_slice(data);
+
+        let cd_offset = zip.len();
+        zip.extend_from_slice(b"PK\x01\x02");
+        zip.extend_from_slice(&0x031Eu16.to_le_bytes()); // Made by Unix
+        common(&mut zip);
+        zip.extend_from_slice(&[0; 6]); // Comment, disk, internal attributes
+        zip.extend_from_slice(&[0; 4]); // External attributes
+        zip.extend_from_slice(&0u32.to_le_bytes()); // Local header offset
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(&extra);
+        let cd_size = zip.len() - cd_offset;
+
+        zip.extend_from_slice(b"PK\x05\x06");
+        zip.extend_from_slice(&[0; 4]);
+        zip.extend_from_slice(&1u16.to_le_bytes());
+        zip.extend_from_slice(&1u16.to_le_bytes());
+        zip.extend_from_slice(&(cd_size as u32).to_le_bytes());
+        zip.extend_from_slice(&(cd_offset as u32).to_le_bytes());
+        zip.extend_from_slice(&0u16.to_le_bytes());
+        zip
+    }
+
+    async fn extract(zip: Vec<u8>, password: &str) -> Result<Vec<u8>> {
+        let extractor = ZipExtractor::new(Arc::new(MemoryReader::new(zip))).with_password(password);
+        let entry = extractor.list_files().await?.remove(0);
+        assert_eq!(
+            entry.aes.map(|aes| (aes.vendor_version, aes.strength)),
+            Some((2, 3))
+        );
+        extractor.extract_to_memory(&entry).await
+    }
+
+    #[tokio::test]
+    async fn correct_password_decrypts() {
+        let zip = archive("secret.txt", &encrypt("s3cret", b"attack at dawn"));
+        assert_eq!(extract(zip, "s3cret").await.unwrap(), b"attack at dawn");
+    }
+
+    #[tokio::test]
+    async fn wrong_password_is_told_apart_from_corrupt_data() {
+        let zip = archive("secret.txt", &encrypt("s3cret", b"attack at dawn"));
+        let err = extract(zip.clone(), "guess").await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<ZipError>(),
+                Some(ZipError::WrongPassword { .. })
+            ),
+            "{:#}",
+            err
+        );
+
+        // Flip a byte of the ciphertext, after the salt and verifier
+        let mut corrupt = zip;
+        let data_start = 30 + "secret.txt".len() + 11;
+        corrupt[data_start + 16 + 2 + 3] ^= 0x01;
+        let err = extract(corrupt, "s3cret").await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<ZipError>(),
+                Some(ZipError::AuthenticationFailed { .. })
+            ),
+            "{:#}",
+            err
+        );
+    }
+
+    /// Data spanning several stream chunks and not repeating within one.
+    fn big_data() -> Vec<u8> {
+        (0..600_000u32).map(|i| (i * 7 + i / 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn streamed_entries_are_decrypted_chunk_by_chunk() {
+        let data = big_data();
+        let mut deflated = flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+        std::io::Write::write_all(&mut deflated, &data).unwrap();
+        let deflated = deflated.finish().unwrap();
+
+        for zip in [
+            archive("big.bin", &encrypt("s3cret", &data)),
+            archive_with("big.bin", &encrypt("s3cret", &deflated), 8, data.len()),
+        ] {
+            let extractor =
+                ZipExtractor::new(Arc::new(MemoryReader::new(zip))).with_password("s3cret");
+            let entry = extractor.list_files().await.unwrap().remove(0);
+            let mut out = Vec::new();
+            let written = extractor.extract_to_writer(&entry, &mut out).await.unwrap();
+            assert_eq!(written, data.len() as u64);
+            assert!(out == data);
+        }
+    }
+
+    #[tokio::test]
+    async fn streamed_entries_are_authenticated() {
+        let encrypted = encrypt("s3cret", &big_data());
+        let zip = archive("big.bin", &encrypted);
+
+        // A wrong password is caught before anything is written
+        let extractor =
+            ZipExtractor::new(Arc::new(MemoryReader::new(zip.clone()))).with_password("guess");
+        let entry = extractor.list_files().await.unwrap().remove(0);
+        let mut out = Vec::new();
+        let err = extractor
+            .extract_to_writer(&entry, &mut out)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::WrongPassword { .. })
+        ));
+        assert!(out.is_empty());
+
+        // Tampering with the last byte of ciphertext is caught at the end
+        let mut corrupt = zip;
+        let data_end = 30 + "big.bin".len() + 11 + encrypted.len();
+        corrupt[data_end - AesInfo::AUTH_CODE_SIZE - 1] ^= 0x01;
+        let extractor =
+            ZipExtractor::new(Arc::new(MemoryReader::new(corrupt))).with_password("s3cret");
+        let err = extractor
+            .extract_to_writer(&entry, &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<ZipError>(),
+                Some(ZipError::AuthenticationFailed { .. })
+            ),
+            "{:#}",
+            err
+        );
+    }
+}
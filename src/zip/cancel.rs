@@ -0,0 +1,90 @@
+//! Bounding and cancelling long-running listings.
+//!
+//! Over HTTP, reading a large Central Directory can take a long time, and
+//! per-request timeouts don't bound the listing as a whole.
+//! [`ListOptions`] adds an overall timeout and a [`CancelToken`] to
+//! [`ZipExtractor::list_files_with()`](super::ZipExtractor::list_files_with).
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let cancel = CancelToken::new();
+//! let options = ListOptions {
+//!     timeout: Some(Duration::from_secs(60)),
+//!     cancel: Some(cancel.clone()),
+//! };
+//! // Elsewhere, e.g. when the user gives up: cancel.cancel();
+//! let entries = extractor.list_files_with(options).await?;
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+/// A handle for cancelling an operation from another task.
+///
+/// Clones share the same state, so cancelling any clone cancels the
+/// operation holding another. Once cancelled, a token stays cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    /// State shared between clones
+    inner: Arc<CancelState>,
+}
+
+/// Shared state of a [`CancelToken`].
+#[derive(Debug, Default)]
+struct CancelState {
+    /// Set once the token is cancelled
+    cancelled: AtomicBool,
+    /// Wakes tasks waiting in [`CancelToken::cancelled()`]
+    notify: Notify,
+}
+
+impl CancelToken {
+    /// Create a token that isn't cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel the operations holding this token or a clone of it.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Check whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled.
+    ///
+    /// Returns immediately if it already is.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            tokio::pin!(notified);
+            // Register before checking, so a cancel in between isn't missed
+            notified.as_mut().enable();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Options for [`ZipExtractor::list_files_with()`](super::ZipExtractor::list_files_with).
+///
+/// The default has no timeout and no cancellation, like
+/// [`list_files()`](super::ZipExtractor::list_files).
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// Longest time the whole listing may take, including locating and
+    /// reading the Central Directory
+    pub timeout: Option<Duration>,
+    /// Token that aborts the listing when cancelled
+    pub cancel: Option<CancelToken>,
+}
@@ -0,0 +1,112 @@
+//! Decoding of entry names that aren't flagged as UTF-8.
+//!
+//! The ZIP format stores names as raw bytes. When general purpose bit 11
+//! is set they are UTF-8; otherwise the specification says CP437, the
+//! original IBM PC code page. In practice archives made on Japanese or
+//! Chinese systems use the local code page (Shift-JIS, GBK, ...) instead,
+//! and nothing in the archive says which one. [`Charset`] lets the caller
+//! choose.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let charset = Charset::for_label("shift_jis")?;
+//! let extractor = ZipExtractor::new(reader).with_charset(charset);
+//! ```
+
+use anyhow::{Result, bail};
+use encoding_rs::Encoding;
+
+/// General purpose flag: the name is encoded as UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+
+/// Characters for bytes 0x80 to 0xFF in code page 437
+#[rustfmt::skip]
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+/// The character set used for names without the UTF-8 flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    /// Code page 437, as the specification requires.
+    ///
+    /// Names that happen to be valid UTF-8 are kept as UTF-8, since many
+    /// tools write UTF-8 without setting the flag; ASCII names read the
+    /// same either way.
+    #[default]
+    Cp437,
+    /// Any encoding known to `encoding_rs`, such as Shift-JIS or GBK
+    Encoding(&'static Encoding),
+}
+
+impl Charset {
+    /// Look up a character set by name.
+    ///
+    /// Accepts `cp437` (also `ibm437`) and every label of the WHATWG
+    /// Encoding Standard, such as `shift_jis`, `gbk`, `euc-kr` or `big5`.
+    /// Case is ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The character set name
+    ///
+    /// # Returns
+    ///
+    /// The matching character set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is unknown.
+    pub fn for_label(label: &str) -> Result<Self> {
+        let label = label.trim();
+        if label.eq_ignore_ascii_case("cp437") || label.eq_ignore_ascii_case("ibm437") {
+            return Ok(Charset::Cp437);
+        }
+        match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) => Ok(Charset::Encoding(encoding)),
+            None => bail!("Unknown character set: {}", label),
+        }
+    }
+
+    /// Decode an entry name.
+    ///
+    /// Names flagged as UTF-8 are always decoded as UTF-8. Invalid
+    /// sequences are replaced with U+FFFD rather than failing.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw name
+    /// * `flags` - The entry's general purpose flags
+    ///
+    /// # Returns
+    ///
+    /// The decoded name.
+    pub fn decode(&self, bytes: &[u8], flags: u16) -> String {
+        if flags & FLAG_UTF8 != 0 {
+            return String::from_utf8_lossy(bytes).into_owned();
+        }
+        match self {
+            Charset::Cp437 => match std::str::from_utf8(bytes) {
+                Ok(name) => name.to_string(),
+                Err(_) => bytes
+                    .iter()
+                    .map(|&b| match b {
+                        0x00..=0x7F => b as char,
+                        _ => CP437_HIGH[(b - 0x80) as usize],
+                    })
+                    .collect(),
+            },
+            Charset::Encoding(encoding) => {
+                encoding.decode_without_bom_handling(bytes).0.into_owned()
+            }
+        }
+    }
+}
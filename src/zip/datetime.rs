@@ -0,0 +1,338 @@
+//! MS-DOS dates and times, as stored in ZIP headers.
+//!
+//! Every entry records its modification time as two 16-bit fields in the
+//! MS-DOS format: local time, no time zone, a two-second resolution and
+//! years from 1980 to 2107. [`DosDateTime`] holds the unpacked fields
+//! and converts between them, the packed form and [`SystemTime`]; with
+//! the `chrono` feature, also `chrono::NaiveDateTime`.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! if let Some(modified) = entry.modified() {
+//!     println!("{}: {}", entry.file_name, modified);
+//!     let mtime = modified.to_system_time();
+//! }
+//! ```
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, bail};
+
+/// First year the DOS format can represent
+const MIN_YEAR: u16 = 1980;
+
+/// Last year the DOS format can represent
+const MAX_YEAR: u16 = 2107;
+
+/// A date and time in the range and resolution of the DOS format.
+///
+/// Fields are validated on construction, so a value always names a real
+/// calendar date between 1980-01-01 and 2107-12-31. Values order
+/// chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DosDateTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl DosDateTime {
+    /// Create a date and time from its fields.
+    ///
+    /// The DOS format stores seconds halved, so an odd second is rounded
+    /// down by [`to_dos()`](Self::to_dos).
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, 1980 to 2107
+    /// * `month` - The month, 1 to 12
+    /// * `day` - The day of the month, starting at 1
+    /// * `hour` - The hour, 0 to 23
+    /// * `minute` - The minute, 0 to 59
+    /// * `second` - The second, 0 to 59
+    ///
+    /// # Returns
+    ///
+    /// The date and time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is out of range or the day doesn't
+    /// exist in the month.
+    pub fn new(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> Result<Self> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            bail!(
+                "Year {} is outside the DOS range {}-{}",
+                year,
+                MIN_YEAR,
+                MAX_YEAR
+            );
+        }
+        if !(1..=12).contains(&month) {
+            bail!("Invalid month {}", month);
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            bail!("Invalid day {} for {:04}-{:02}", day, year, month);
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            bail!("Invalid time {:02}:{:02}:{:02}", hour, minute, second);
+        }
+
+        Ok(Self {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+
+    /// Unpack the date and time fields of a ZIP header.
+    ///
+    /// The date packs the day (bits 0-4), month (bits 5-8) and years
+    /// since 1980 (bits 9-15); the time packs the seconds divided by two
+    /// (bits 0-4), the minute (bits 5-10) and the hour (bits 11-15).
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The packed date
+    /// * `time` - The packed time
+    ///
+    /// # Returns
+    ///
+    /// The date and time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field is out of range, e.g. the all-zero
+    /// date some writers store when the time is unknown.
+    pub fn from_dos(date: u16, time: u16) -> Result<Self> {
+        Self::new(
+            (date >> 9) + MIN_YEAR,
+            ((date >> 5) & 0x0F) as u8,
+            (date & 0x1F) as u8,
+            (time >> 11) as u8,
+            ((time >> 5) & 0x3F) as u8,
+            ((time & 0x1F) * 2) as u8,
+        )
+    }
+
+    /// Pack the date and time into the fields of a ZIP header.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of (date, time).
+    pub fn to_dos(&self) -> (u16, u16) {
+        let date = (self.year - MIN_YEAR) << 9 | (self.month as u16) << 5 | self.day as u16;
+        let time = (self.hour as u16) << 11 | (self.minute as u16) << 5 | (self.second / 2) as u16;
+        (date, time)
+    }
+
+    /// Create a date and time from seconds since the Unix epoch.
+    ///
+    /// The DOS format has no time zone; the result is the UTC date and
+    /// time, matching [`to_unix()`](Self::to_unix).
+    ///
+    /// # Arguments
+    ///
+    /// * `secs` - Seconds since 1970-01-01 00:00:00 UTC
+    ///
+    /// # Returns
+    ///
+    /// The date and time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the time is outside the DOS range.
+    pub fn from_unix(secs: i64) -> Result<Self> {
+        let days = secs.div_euclid(86400);
+        let rest = secs.rem_euclid(86400);
+
+        // Civil-from-days algorithm
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let Ok(year) = u16::try_from(year) else {
+            bail!(
+                "Year {} is outside the DOS range {}-{}",
+                year,
+                MIN_YEAR,
+                MAX_YEAR
+            );
+        };
+        Self::new(
+            year,
+            month as u8,
+            day as u8,
+            (rest / 3600) as u8,
+            (rest / 60 % 60) as u8,
+            (rest % 60) as u8,
+        )
+    }
+
+    /// Get the date and time as seconds since the Unix epoch.
+    ///
+    /// The DOS format has no time zone, so the fields are interpreted as
+    /// UTC.
+    ///
+    /// # Returns
+    ///
+    /// Seconds since 1970-01-01 00:00:00 UTC.
+    pub fn to_unix(&self) -> i64 {
+        // Days since 1970-01-01 (civil-from-days algorithm, inverted)
+        let (y, m) = if self.month <= 2 {
+            (self.year as i64 - 1, self.month as i64 + 9)
+        } else {
+            (self.year as i64, self.month as i64 - 3)
+        };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * m + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    /// Create a date and time from a [`SystemTime`], in UTC.
+    ///
+    /// Fractions of a second are dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `time` - The time to convert
+    ///
+    /// # Returns
+    ///
+    /// The date and time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the time is outside the DOS range.
+    pub fn from_system_time(time: SystemTime) -> Result<Self> {
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(after) => after.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs_f64().ceil() as i64),
+        };
+        Self::from_unix(secs)
+    }
+
+    /// Get the date and time as a [`SystemTime`], interpreting the fields
+    /// as UTC.
+    ///
+    /// # Returns
+    ///
+    /// The corresponding system time.
+    pub fn to_system_time(&self) -> SystemTime {
+        // Every DOS date is after the epoch
+        UNIX_EPOCH + Duration::from_secs(self.to_unix() as u64)
+    }
+
+    /// Get the year, 1980 to 2107.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Get the month, 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// Get the day of the month, starting at 1.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Get the hour, 0 to 23.
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// Get the minute, 0 to 59.
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// Get the second, 0 to 59; even for values read from an archive.
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+}
+
+impl fmt::Display for DosDateTime {
+    /// Format as `YYYY-MM-DD HH:MM:SS`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DosDateTime> for chrono::NaiveDateTime {
+    fn from(dt: DosDateTime) -> Self {
+        // Every DosDateTime is a valid calendar date and time
+        chrono::NaiveDate::from_ymd_opt(dt.year as i32, dt.month as u32, dt.day as u32)
+            .and_then(|date| date.and_hms_opt(dt.hour as u32, dt.minute as u32, dt.second as u32))
+            .expect("DosDateTime fields are validated")
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDateTime> for DosDateTime {
+    type Error = anyhow::Error;
+
+    /// Convert a naive date and time, dropping fractions of a second.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the date is outside the DOS range.
+    fn try_from(dt: chrono::NaiveDateTime) -> Result<Self> {
+        use chrono::{Datelike, Timelike};
+
+        let Ok(year) = u16::try_from(dt.year()) else {
+            bail!(
+                "Year {} is outside the DOS range {}-{}",
+                dt.year(),
+                MIN_YEAR,
+                MAX_YEAR
+            );
+        };
+        // A leap second is reported as second 59 plus a fraction
+        Self::new(
+            year,
+            dt.month() as u8,
+            dt.day() as u8,
+            dt.hour() as u8,
+            dt.minute() as u8,
+            dt.second() as u8,
+        )
+    }
+}
+
+/// Get the number of days in a month of the Gregorian calendar.
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
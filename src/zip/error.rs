@@ -0,0 +1,160 @@
+//! Error types for ZIP archive parsing and extraction.
+//!
+//! Most failures are reported through [`anyhow::Error`] with a descriptive
+//! message. Conditions that callers may want to detect programmatically are
+//! represented by [`ZipError`], which can be recovered from an
+//! [`anyhow::Error`] using `downcast_ref`.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! match extractor.list_files().await {
+//!     Err(e) if matches!(e.downcast_ref::<ZipError>(), Some(ZipError::MalformedZip64(_))) => {
+//!         eprintln!("Archive has broken ZIP64 structures");
+//!     }
+//!     other => { /* ... */ }
+//! }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+/// Typed errors raised by the ZIP parser and extractor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZipError {
+    /// The archive is marked as ZIP64 but its ZIP64 structures are
+    /// missing, truncated, or point outside the archive.
+    MalformedZip64(String),
+    /// An entry decompressed to more data than its declared uncompressed
+    /// size, which indicates a corrupt or malicious archive.
+    SizeExceeded {
+        /// Name of the offending entry
+        name: String,
+        /// The uncompressed size recorded in the Central Directory
+        declared: u64,
+    },
+    /// An entry needs a feature this implementation doesn't support, as
+    /// indicated by its "version needed to extract" or flags.
+    UnsupportedFeature {
+        /// Name of the entry
+        name: String,
+        /// Description of the missing feature
+        feature: &'static str,
+        /// The entry's "version needed to extract" field
+        version_needed: u16,
+    },
+    /// An entry's name is longer than the configured maximum, which
+    /// indicates a malicious archive.
+    NameTooLong {
+        /// Offset of the entry's Central Directory File Header
+        offset: u64,
+        /// Length of the name in bytes
+        length: usize,
+        /// The configured maximum
+        max: usize,
+    },
+    /// An entry is encrypted but no password was given.
+    PasswordRequired {
+        /// Name of the entry
+        name: String,
+    },
+    /// The password doesn't match the one an entry was encrypted with.
+    WrongPassword {
+        /// Name of the entry
+        name: String,
+    },
+    /// An encrypted entry's authentication code doesn't match its data,
+    /// so the data is corrupt or was tampered with.
+    AuthenticationFailed {
+        /// Name of the entry
+        name: String,
+    },
+    /// The archive is one disk of a multi-disk (spanned or split)
+    /// archive whose Central Directory isn't entirely in this source.
+    SpannedArchive {
+        /// The disk number recorded in the end record, starting at 0
+        disk: u32,
+    },
+    /// Extracting an entry took longer than the configured per-entry
+    /// timeout.
+    Timeout {
+        /// Name of the entry
+        name: String,
+        /// The configured timeout
+        timeout: Duration,
+    },
+    /// Listing the archive took longer than the timeout in its
+    /// [`ListOptions`](super::ListOptions).
+    ListTimeout {
+        /// The configured timeout
+        timeout: Duration,
+    },
+    /// The operation was cancelled through its
+    /// [`CancelToken`](super::CancelToken).
+    Cancelled,
+}
+
+impl fmt::Display for ZipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZipError::MalformedZip64(reason) => write!(f, "Malformed ZIP64 archive: {}", reason),
+            ZipError::SizeExceeded { name, declared } => write!(
+                f,
+                "{} decompresses to more than its declared size of {} bytes",
+                name, declared
+            ),
+            ZipError::UnsupportedFeature {
+                name,
+                feature,
+                version_needed,
+            } => {
+                let version = version_needed & 0xFF;
+                write!(
+                    f,
+                    "{} requires {} (version needed to extract {}.{}), which is not supported",
+                    name,
+                    feature,
+                    version / 10,
+                    version % 10
+                )
+            }
+            ZipError::NameTooLong {
+                offset,
+                length,
+                max,
+            } => write!(
+                f,
+                "Entry name at offset {} is {} bytes long, more than the maximum of {}",
+                offset, length, max
+            ),
+            ZipError::PasswordRequired { name } => {
+                write!(f, "{} is encrypted and needs a password", name)
+            }
+            ZipError::WrongPassword { name } => write!(f, "Incorrect password for {}", name),
+            ZipError::AuthenticationFailed { name } => write!(
+                f,
+                "Authentication code mismatch for {}: the encrypted data is corrupt",
+                name
+            ),
+            ZipError::SpannedArchive { disk } => write!(
+                f,
+                "The archive is disk {} of a multi-disk archive, which is not supported",
+                disk + 1
+            ),
+            ZipError::Timeout { name, timeout } => write!(
+                f,
+                "Extracting {} took longer than {:.1}s",
+                name,
+                timeout.as_secs_f64()
+            ),
+            ZipError::ListTimeout { timeout } => write!(
+                f,
+                "Listing the archive took longer than {:.1}s",
+                timeout.as_secs_f64()
+            ),
+            ZipError::Cancelled => write!(f, "Operation cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ZipError {}
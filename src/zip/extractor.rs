@@ -25,18 +25,52 @@
 //! # }
 //! ```
 
-use std::io::Read;
-use std::path::Path;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
 
-use crate::io::ReadAt;
-use anyhow::{Result, bail};
+use crate::io::{MemoryReader, ReadAt};
+use crate::warning::{Warning, Warnings};
+use anyhow::{Context, Result, bail};
+use aws_lc_rs::digest;
+use byteorder::{LittleEndian, ReadBytesExt};
 use flate2::read::DeflateDecoder;
+use flate2::write::GzEncoder;
+use flate2::{Compression, Crc, Decompress, FlushDecompress, Status};
 
-use super::parser::ZipParser;
-use super::structures::{CompressionMethod, ZipFileEntry};
+use super::aes;
+use super::cancel::ListOptions;
+use super::charset::Charset;
+use super::error::ZipError;
+use super::metadata::{self, Preserve};
+use super::parser::{CentralDirectoryEntries, ZipParser};
+use super::recode::Recode;
+use super::report::{EntryOutcome, ExtractReport};
+use super::structures::{
+    AesInfo, CompressionMethod, DATA_DESCRIPTOR_SIGNATURE, EndOfCentralDirectory, ZipFileEntry,
+};
+use super::tar::{BlockingWrite, Member, TarWriter};
+use super::zipcrypto;
+
+/// Most memory reserved up front for an entry's decoded data; the
+/// declared size is only a claim, so anything larger grows as data
+/// actually arrives
+const MAX_PREALLOCATION: u64 = 16 * 1024 * 1024;
+/// Size of each read when streaming an entry
+const STREAM_CHUNK: u64 = 256 * 1024;
+
+/// Bytes at the end of the archive read by
+/// [`ZipExtractor::prefetch_metadata()`]: the end records with the
+/// longest possible comment, plus a Central Directory of a few thousand
+/// entries
+const METADATA_PREFETCH: u64 = 256 * 1024;
 
 /// High-level ZIP file extractor.
 ///
@@ -49,6 +83,10 @@ use super::structures::{CompressionMethod, ZipFileEntry};
 /// - `STORED` (0): No compression, data is copied directly
 /// - `DEFLATE` (8): Standard ZIP compression using flate2
 ///
+/// Either may be combined with traditional PKWARE or WinZip AES
+/// encryption, given a password with
+/// [`with_password()`](Self::with_password).
+///
 /// ## Generic Parameter
 ///
 /// The extractor is generic over the reader type `R`, allowing it to
@@ -57,6 +95,27 @@ use super::structures::{CompressionMethod, ZipFileEntry};
 pub struct ZipExtractor<R: ReadAt> {
     /// The underlying parser for reading ZIP structures
     parser: ZipParser<R>,
+    /// Metadata restored by [`extract_to_file()`](Self::extract_to_file)
+    preserve: Preserve,
+    /// Fall back to other compression methods when decoding fails
+    try_methods: bool,
+    /// Compare data descriptors with the Central Directory even when
+    /// `verify_crc` is off
+    check_descriptors: bool,
+    /// Check extracted data against the recorded size and CRC-32
+    verify_crc: bool,
+    /// Mode of directories created for extracted files
+    dir_mode: Option<u32>,
+    /// Whether [`contains()`](Self::contains) compares names exactly
+    case_sensitive: bool,
+    /// Conversion applied to file contents on extraction
+    recode: Option<Recode>,
+    /// Password for encrypted entries
+    password: Option<String>,
+    /// Longest time a single entry may take to extract
+    entry_timeout: Option<Duration>,
+    /// Warnings recorded while extracting
+    warnings: Warnings,
 }
 
 impl<R: ReadAt> ZipExtractor<R> {
@@ -79,17 +138,82 @@ impl<R: ReadAt> ZipExtractor<R> {
     pub fn new(reader: Arc<R>) -> Self {
         Self {
             parser: ZipParser::new(reader),
+            preserve: Preserve::default(),
+            try_methods: false,
+            check_descriptors: false,
+            verify_crc: true,
+            dir_mode: None,
+            case_sensitive: true,
+            recode: None,
+            password: None,
+            entry_timeout: None,
+            warnings: Warnings::default(),
         }
     }
 
-    /// List all files in the archive.
+    /// Create an extractor and check right away that the source is a ZIP
+    /// archive.
     ///
-    /// Returns metadata for all entries in the ZIP file, including
-    /// both files and directories.
+    /// Unlike [`new()`](Self::new), which defers all reading to the first
+    /// operation, this locates the End of Central Directory record (and
+    /// any ZIP64 extensions) immediately. The location is cached and
+    /// reused by later operations.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - A shared reference to a reader implementing [`ReadAt`]
     ///
     /// # Returns
     ///
-    /// A vector of [`ZipFileEntry`] with metadata for each entry.
+    /// An extractor for a source known to be a ZIP archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not a valid ZIP archive or
+    /// cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let reader = Arc::new(LocalFileReader::new(Path::new("upload.bin"))?);
+    /// let extractor = ZipExtractor::open(reader).await?; // fails for non-zips
+    /// ```
+    pub async fn open(reader: Arc<R>) -> Result<Self> {
+        let extractor = Self::new(reader);
+        extractor.validate().await?;
+        Ok(extractor)
+    }
+
+    /// Check that the source is a ZIP archive, like [`open()`](Self::open)
+    /// does, on an extractor that is already configured.
+    ///
+    /// See [`ZipParser::validate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not a valid ZIP archive or
+    /// cannot be read.
+    pub async fn validate(&self) -> Result<()> {
+        self.parser.validate().await
+    }
+
+    /// Compute an identifier of the archive's contents, e.g. as a cache
+    /// key.
+    ///
+    /// The identifier is derived from the raw Central Directory, which
+    /// records every entry's name, sizes, CRC-32, timestamps and offset,
+    /// and from the archive's size. File data is not read, so this costs
+    /// no more than listing; after
+    /// [`prefetch_metadata()`](Self::prefetch_metadata) it usually costs
+    /// no request at all. Rewriting any entry changes the identifier;
+    /// the same archive always gives the same one. It is not a
+    /// cryptographic digest and doesn't protect against tampering.
+    ///
+    /// # Returns
+    ///
+    /// The identifier as hex digits: the CRC-32 of the Central
+    /// Directory, its length and the archive size, e.g.
+    /// `"3f0a12bc-2e4-1a3f0"`.
     ///
     /// # Errors
     ///
@@ -98,143 +222,2198 @@ impl<R: ReadAt> ZipExtractor<R> {
     /// # Example
     ///
     /// ```ignore
-    /// for entry in extractor.list_files().await? {
-    ///     println!("{}: {} bytes", entry.file_name, entry.uncompressed_size);
+    /// let key = extractor.fingerprint().await?;
+    /// if !cache.contains(&key) {
+    ///     cache.insert(key, extractor.list_files().await?);
     /// }
     /// ```
-    pub async fn list_files(&self) -> Result<Vec<ZipFileEntry>> {
-        self.parser.list_files().await
+    pub async fn fingerprint(&self) -> Result<String> {
+        let directory = self.parser.central_directory().await?;
+        let mut crc = Crc::new();
+        crc.update(&directory);
+        Ok(format!(
+            "{:08x}-{:x}-{:x}",
+            crc.sum(),
+            directory.len(),
+            self.parser.reader().size()
+        ))
     }
 
-    /// Extract a file's contents to memory.
+    /// Fetch the archive's metadata with a single read.
     ///
-    /// Reads and decompresses the file data, returning it as a byte vector.
-    /// This method handles both STORED and DEFLATE compression methods.
+    /// Reads the last 256 KiB of the archive, which covers the End of
+    /// Central Directory record, the ZIP64 structures and, for archives
+    /// with up to a few thousand entries, the Central Directory. Listing
+    /// and locating entries is then served from those bytes. Over HTTP
+    /// this replaces the several dependent Range requests needed to walk
+    /// from the end record to the directory with one round trip.
+    ///
+    /// See [`ZipParser::prefetch_tail`].
+    ///
+    /// # Returns
+    ///
+    /// The number of reads saved compared to locating and reading the
+    /// Central Directory without prefetching.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not a valid ZIP archive or
+    /// cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let saved = extractor.prefetch_metadata().await?;
+    /// // No further requests for a small archive
+    /// let entries = extractor.list_files().await?;
+    /// ```
+    pub async fn prefetch_metadata(&self) -> Result<u64> {
+        self.parser.prefetch_tail(METADATA_PREFETCH).await
+    }
+
+    /// Set how many bytes at the end of the archive are searched for the
+    /// End of Central Directory record.
+    ///
+    /// Changing the window discards a location found by
+    /// [`open()`](Self::open).
+    ///
+    /// See [`ZipParser::with_eocd_search_window`].
     ///
     /// # Arguments
     ///
-    /// * `entry` - The file entry to extract (from [`list_files()`])
+    /// * `window` - Search window in bytes, at least 22
     ///
     /// # Returns
     ///
-    /// The decompressed file contents as a byte vector.
+    /// The extractor with the new window.
     ///
     /// # Errors
     ///
-    /// Returns an error if:
-    /// - The file uses an unsupported compression method
-    /// - The data cannot be read or decompressed
+    /// Returns an error if the window is smaller than an EOCD record.
+    pub fn with_eocd_search_window(mut self, window: u64) -> Result<Self> {
+        self.parser = self.parser.with_eocd_search_window(window)?;
+        Ok(self)
+    }
+
+    /// Read an archive embedded at a known offset in a larger source.
     ///
-    /// # Memory Usage
+    /// Changing the base offset discards a location found by
+    /// [`open()`](Self::open).
     ///
-    /// This method loads the entire file into memory. For large files,
-    /// consider using [`extract_to_file()`] instead.
+    /// See [`ZipParser::with_base_offset`].
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Where the archive starts in the source
+    ///
+    /// # Returns
+    ///
+    /// The extractor reading the archive at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offset lies beyond the end of the source.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// let data = extractor.extract_to_memory(&entry).await?;
-    /// let text = String::from_utf8_lossy(&data);
-    /// println!("{}", text);
+    /// // A container with a 512-byte header in front of the ZIP data
+    /// let extractor = ZipExtractor::new(reader).with_base_offset(512)?;
     /// ```
-    pub async fn extract_to_memory(&self, entry: &ZipFileEntry) -> Result<Vec<u8>> {
-        // Calculate where the actual file data begins
-        let data_offset = self.parser.get_data_offset(entry).await?;
+    pub fn with_base_offset(mut self, offset: u64) -> Result<Self> {
+        self.parser = self.parser.with_base_offset(offset)?;
+        Ok(self)
+    }
 
-        match entry.compression_method {
-            CompressionMethod::Stored => {
-                // No compression - read data directly
-                let mut buf = vec![0u8; entry.uncompressed_size as usize];
-                self.parser.reader().read_at(data_offset, &mut buf).await?;
-                Ok(buf)
-            }
-            CompressionMethod::Deflate => {
-                // DEFLATE compression - read compressed data first
-                let mut compressed = vec![0u8; entry.compressed_size as usize];
-                self.parser
-                    .reader()
-                    .read_at(data_offset, &mut compressed)
-                    .await?;
+    /// Set the character set used for names without the UTF-8 flag
+    /// (default: CP437).
+    ///
+    /// See [`ZipParser::with_charset`].
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - The character set to decode names with
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new character set.
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.parser = self.parser.with_charset(charset);
+        self
+    }
 
-                // Decompress using flate2's DeflateDecoder
-                // Note: ZIP uses raw DEFLATE, not zlib or gzip wrapped
-                let mut decoder = DeflateDecoder::new(&compressed[..]);
-                let mut decompressed = Vec::with_capacity(entry.uncompressed_size as usize);
-                decoder.read_to_end(&mut decompressed)?;
+    /// Set the longest entry name accepted, in bytes (default: 4096).
+    ///
+    /// See [`ZipParser::with_max_name_length`].
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - Maximum name length in bytes, as stored in the archive
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new limit.
+    pub fn with_max_name_length(mut self, max: usize) -> Self {
+        self.parser = self.parser.with_max_name_length(max);
+        self
+    }
 
-                Ok(decompressed)
-            }
-            CompressionMethod::Unknown(method) => {
-                bail!("Unsupported compression method: {}", method);
-            }
-        }
+    /// Set whether extracted data is checked against the size and CRC-32
+    /// recorded in the archive (default: `true`).
+    ///
+    /// With checking on, every extraction method fails on a mismatch
+    /// rather than returning or writing corrupt data.
+    /// [`extract_to_file()`](Self::extract_to_file) removes the partial
+    /// file. Turn it off to salvage what a damaged entry still holds.
+    /// [`verify()`](Self::verify) always checks.
+    ///
+    /// # Arguments
+    ///
+    /// * `verify_crc` - `false` to accept data that doesn't match
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new setting.
+    pub fn with_verify_crc(mut self, verify_crc: bool) -> Self {
+        self.verify_crc = verify_crc;
+        self
     }
 
-    /// Extract a file to the filesystem.
+    /// Set the password for encrypted entries.
     ///
-    /// Reads, decompresses, and writes the file to the specified path.
-    /// Parent directories are created automatically if they don't exist.
+    /// Both traditional PKWARE encryption (ZipCrypto) and WinZip AES are
+    /// supported. A wrong password is detected before anything is
+    /// decrypted. The data is then decrypted as it is read, so streaming
+    /// extraction keeps memory use bounded, and authenticated or checked
+    /// once it was read in full: like a CRC-32 mismatch, tampering is only
+    /// reported after streamed data was written, and
+    /// [`extract_to_file()`](Self::extract_to_file) removes its output
+    /// then. Without a password, extracting such an entry fails with
+    /// [`ZipError::PasswordRequired`].
+    ///
+    /// ZipCrypto only checks one byte of the password up front: about
+    /// one wrong password in 256 gets past it and is then reported as a
+    /// CRC-32 mismatch.
     ///
     /// # Arguments
     ///
-    /// * `entry` - The file entry to extract
-    /// * `output_path` - The filesystem path to write the file to
+    /// * `password` - The password the entries were encrypted with
     ///
-    /// # Errors
+    /// # Returns
     ///
-    /// Returns an error if:
-    /// - The file cannot be read or decompressed
-    /// - Parent directories cannot be created
-    /// - The file cannot be written
+    /// The extractor with the password set.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// extractor.extract_to_file(&entry, Path::new("output/file.txt")).await?;
+    /// let extractor = ZipExtractor::new(reader).with_password("s3cret");
+    /// let data = extractor.extract_to_memory(&entry).await?;
     /// ```
-    pub async fn extract_to_file(&self, entry: &ZipFileEntry, output_path: &Path) -> Result<()> {
-        // Ensure parent directories exist
-        if let Some(parent) = output_path.parent()
-            && !parent.as_os_str().is_empty()
-        {
-            fs::create_dir_all(parent).await?;
-        }
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
 
-        // Extract file contents to memory
-        let data = self.extract_to_memory(entry).await?;
+    /// Limit how long extracting a single entry may take.
+    ///
+    /// HTTP timeouts apply to each request, so an entry read in many
+    /// chunks can stall for much longer in total. This bounds the whole
+    /// of [`extract_to_writer()`](Self::extract_to_writer), and with it
+    /// [`extract_to_file()`](Self::extract_to_file), which removes the
+    /// partial file, and [`extract_to_stdout()`](Self::extract_to_stdout).
+    /// An entry that runs over fails with [`ZipError::Timeout`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The longest time one entry may take
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the timeout set.
+    pub fn with_entry_timeout(mut self, timeout: Duration) -> Self {
+        self.entry_timeout = Some(timeout);
+        self
+    }
 
-        // Write to the output file
-        let mut file = fs::File::create(output_path).await?;
-        file.write_all(&data).await?;
+    /// Leave entries with overlong names out of the listing instead of
+    /// failing (default: `false`).
+    ///
+    /// See [`ZipParser::with_skip_long_names`].
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - `true` to skip such entries
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new policy.
+    pub fn with_skip_long_names(mut self, skip: bool) -> Self {
+        self.parser = self.parser.with_skip_long_names(skip);
+        self
+    }
 
-        Ok(())
+    /// Choose which metadata [`extract_to_file()`](Self::extract_to_file)
+    /// restores (default: permissions, modification times and symlinks).
+    ///
+    /// # Arguments
+    ///
+    /// * `preserve` - The metadata facets to restore
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new setting.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let extractor = ZipExtractor::new(reader).with_preserve(Preserve::ALL);
+    /// ```
+    pub fn with_preserve(mut self, preserve: Preserve) -> Self {
+        self.preserve = preserve;
+        self
     }
 
-    /// Extract a file's contents to stdout.
+    /// Recover entries whose compression method is mislabeled.
     ///
-    /// Reads, decompresses, and writes the file directly to standard output.
-    /// Useful for piping archive contents to other commands.
+    /// When enabled, an entry whose data fails to decode with its declared
+    /// method, or decodes to the wrong size or CRC-32, is decoded again
+    /// with the other supported methods. The first result matching the
+    /// recorded size and CRC-32 is used, and a
+    /// [`Warning::MethodMismatch`] is recorded. This is best-effort and
+    /// may read an entry's data several times.
     ///
     /// # Arguments
     ///
-    /// * `entry` - The file entry to extract
+    /// * `try_methods` - Whether to fall back to other methods
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new setting.
+    pub fn with_try_methods(mut self, try_methods: bool) -> Self {
+        self.try_methods = try_methods;
+        self
+    }
+
+    /// Set the mode of directories created for extracted files (Unix
+    /// only).
+    ///
+    /// [`extract_to_file()`](Self::extract_to_file) creates missing
+    /// parent directories with exactly this mode, regardless of the
+    /// umask. Existing directories are left alone, and
+    /// [`restore_directory()`](Self::restore_directory) still applies a
+    /// directory entry's stored mode when permissions are preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Permission bits such as `0o750`
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new setting.
+    pub fn with_dir_mode(mut self, mode: u32) -> Self {
+        self.dir_mode = Some(mode);
+        self
+    }
+
+    /// Compare each entry's data descriptor with the Central Directory,
+    /// even when [`with_verify_crc()`](Self::with_verify_crc) turned
+    /// checking off.
+    ///
+    /// Entries with general purpose bit 3 set are followed by a data
+    /// descriptor holding their CRC-32 and sizes. While extracted data is
+    /// verified (the default) the descriptor is read before the entry is
+    /// decoded, and extraction fails if it disagrees with the Central
+    /// Directory, which points to a truncated or tampered stream. This
+    /// costs one extra small read per such entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `check_descriptors` - Whether to check data descriptors without
+    ///   verifying the data
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new setting.
+    pub fn with_check_descriptors(mut self, check_descriptors: bool) -> Self {
+        self.check_descriptors = check_descriptors;
+        self
+    }
+
+    /// Choose whether name lookups with [`contains()`](Self::contains)
+    /// are case-sensitive (default: true).
+    ///
+    /// # Arguments
+    ///
+    /// * `case_sensitive` - `false` to ignore case when comparing names
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the new setting.
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Convert the contents of text files between encodings.
+    ///
+    /// Applies to [`extract_to_file()`](Self::extract_to_file) and
+    /// [`extract_to_stdout()`](Self::extract_to_stdout); the other
+    /// methods return the stored contents.
+    ///
+    /// # Arguments
+    ///
+    /// * `recode` - The conversion
+    ///
+    /// # Returns
+    ///
+    /// The extractor with the conversion set.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let extractor = ZipExtractor::new(reader).with_recode(Recode::parse("shift_jis:utf-8")?);
+    /// ```
+    pub fn with_recode(mut self, recode: Recode) -> Self {
+        self.recode = Some(recode);
+        self
+    }
+
+    /// List all files in the archive.
+    ///
+    /// Returns metadata for all entries in the ZIP file, including
+    /// both files and directories.
+    ///
+    /// # Returns
+    ///
+    /// A vector of [`ZipFileEntry`] with metadata for each entry.
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read, decompressed, or written.
+    /// Returns an error if the archive is invalid or cannot be read.
     ///
     /// # Example
     ///
     /// ```ignore
-    /// // Equivalent to: runzip -p archive.zip file.txt | cat
-    /// extractor.extract_to_stdout(&entry).await?;
+    /// for entry in extractor.list_files().await? {
+    ///     println!("{}: {} bytes", entry.file_name, entry.uncompressed_size);
+    /// }
     /// ```
-    pub async fn extract_to_stdout(&self, entry: &ZipFileEntry) -> Result<()> {
-        let data = self.extract_to_memory(entry).await?;
+    pub async fn list_files(&self) -> Result<Vec<ZipFileEntry>> {
+        self.parser.list_files().await
+    }
 
-        let mut stdout = tokio::io::stdout();
-        stdout.write_all(&data).await?;
+    /// List all files in the archive, bounded by a timeout and a
+    /// cancellation token.
+    ///
+    /// Like [`list_files()`](Self::list_files), but gives up when the
+    /// listing as a whole runs over the timeout or the token is
+    /// cancelled, even in the middle of reading the Central Directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The timeout and cancellation token
+    ///
+    /// # Returns
+    ///
+    /// A vector of [`ZipFileEntry`] with metadata for each entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The archive is invalid or cannot be read
+    /// - The listing takes longer than the timeout ([`ZipError::ListTimeout`])
+    /// - The token is cancelled ([`ZipError::Cancelled`])
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let options = ListOptions {
+    ///     timeout: Some(Duration::from_secs(60)),
+    ///     cancel: None,
+    /// };
+    /// let entries = extractor.list_files_with(options).await?;
+    /// ```
+    pub async fn list_files_with(&self, options: ListOptions) -> Result<Vec<ZipFileEntry>> {
+        self.parser.list_files_with(options).await
+    }
 
-        Ok(())
+    /// Check whether the archive has an entry with the given name.
+    ///
+    /// Entries are parsed one at a time and the search stops at the first
+    /// match, so no entry list is built. The Central Directory location
+    /// is cached after the first lookup, like for every other operation.
+    /// Names are compared as stored, honoring
+    /// [`with_case_sensitive()`](Self::with_case_sensitive); directory
+    /// entries end with `/`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The full entry name, e.g. `docs/README.md`
+    ///
+    /// # Returns
+    ///
+    /// `true` if an entry has that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if extractor.contains("META-INF/MANIFEST.MF").await? {
+    ///     println!("This is a JAR");
+    /// }
+    /// ```
+    pub async fn contains(&self, name: &str) -> Result<bool> {
+        let lowercase = (!self.case_sensitive).then(|| name.to_lowercase());
+        for entry in self.parser.entries().await? {
+            let entry = entry?;
+            let found = match lowercase {
+                Some(ref lowercase) => entry.file_name.to_lowercase() == *lowercase,
+                None => entry.file_name == name,
+            };
+            if found {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Get the archive comment and the comments of individual entries.
+    ///
+    /// Comments are decoded with the configured character set (see
+    /// [`with_charset()`](Self::with_charset)); entry comments flagged as
+    /// UTF-8 are decoded as UTF-8.
+    ///
+    /// # Returns
+    ///
+    /// The archive comment (empty if there is none) and a map from entry
+    /// name to comment, holding only the entries that have a comment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (archive_comment, comments) = extractor.comments().await?;
+    /// if let Some(comment) = comments.get("README.md") {
+    ///     println!("README.md: {}", comment);
+    /// }
+    /// ```
+    pub async fn comments(&self) -> Result<(String, HashMap<String, String>)> {
+        let archive_comment = self.parser.archive_comment().await?;
+
+        let mut comments = HashMap::new();
+        for entry in self.parser.entries().await? {
+            let entry = entry?;
+            if !entry.comment.is_empty() {
+                comments.insert(entry.file_name, entry.comment);
+            }
+        }
+
+        Ok((archive_comment, comments))
+    }
+
+    /// Get the archive comment stored after the End of Central Directory
+    /// record.
+    ///
+    /// The comment's location comes from the EOCD record found while
+    /// locating the Central Directory, including when a comment made the
+    /// search go backwards from the end. It is decoded with the
+    /// configured character set (see [`with_charset()`](Self::with_charset));
+    /// bytes that aren't valid in it are replaced rather than failing.
+    ///
+    /// # Returns
+    ///
+    /// The archive comment, or `None` if the archive has none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if let Some(comment) = extractor.archive_comment().await? {
+    ///     println!("{}", comment);
+    /// }
+    /// ```
+    pub async fn archive_comment(&self) -> Result<Option<String>> {
+        let comment = self.parser.archive_comment().await?;
+        Ok(Some(comment).filter(|c| !c.is_empty()))
+    }
+
+    /// Get just the archive comment, reading nothing but the end of the
+    /// archive.
+    ///
+    /// The cheapest way to read metadata such as build information from
+    /// a remote archive: a single request, and the Central Directory is
+    /// never touched. See [`ZipParser::tail_comment`].
+    ///
+    /// # Returns
+    ///
+    /// The archive comment, or an empty string if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no End of Central Directory record is found
+    /// or the read fails.
+    pub async fn tail_comment(&self) -> Result<String> {
+        self.parser.tail_comment().await
+    }
+
+    /// Recover entries from a damaged archive by scanning for local headers.
+    ///
+    /// A best-effort alternative to [`list_files()`](Self::list_files)
+    /// for archives whose End of Central Directory record or Central
+    /// Directory is damaged. The whole archive is read. Headers that can't
+    /// be recovered are reported through
+    /// [`take_warnings()`](Self::take_warnings).
+    ///
+    /// # Returns
+    ///
+    /// The recovered entries, ready to pass to the extraction methods.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// for entry in extractor.salvage().await? {
+    ///     extractor.extract_to_file(&entry, Path::new(&entry.file_name)).await?;
+    /// }
+    /// ```
+    pub async fn salvage(&self) -> Result<Vec<ZipFileEntry>> {
+        self.parser.salvage_entries().await
+    }
+
+    /// Remove and return the warnings recorded since the last call.
+    ///
+    /// Covers anomalies noticed while parsing the archive or extracting
+    /// entries, and retries made by the reader.
+    ///
+    /// # Returns
+    ///
+    /// The warnings recorded by the parser and reader, then those
+    /// recorded while extracting.
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        let mut warnings = self.parser.take_warnings();
+        warnings.extend(self.warnings.take());
+        warnings
+    }
+
+    /// Stream the entries in the archive.
+    ///
+    /// Like [`list_files()`](Self::list_files), but parses entries lazily
+    /// so callers can stop early on archives with huge directories.
+    ///
+    /// # Returns
+    ///
+    /// A lazy iterator over the archive's entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    pub async fn entries(&self) -> Result<CentralDirectoryEntries<'_, R>> {
+        self.parser.entries().await
+    }
+
+    /// Get the byte range covering the data of a selection of entries.
+    ///
+    /// Useful for planning downloads: if the span is not much larger than
+    /// the selection's total compressed size, the entries lie (nearly)
+    /// contiguously and fetching the span with one request beats
+    /// fetching each entry separately. This is typical when extracting a
+    /// subtree of an archive written in directory order.
+    ///
+    /// Local File Headers are read to find where each entry's data
+    /// begins; nearby headers are fetched together.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The selected entries
+    ///
+    /// # Returns
+    ///
+    /// The smallest data offset and the largest data end (exclusive)
+    /// across the selection, or `(0, 0)` for an empty selection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a Local File Header is invalid or cannot be
+    /// read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (start, end) = extractor.selection_byte_span(&selected).await?;
+    /// let total: u64 = selected.iter().map(|e| e.compressed_size).sum();
+    /// if end - start <= total + 64 * 1024 {
+    ///     // Nearly contiguous: fetch start..end in one request
+    /// }
+    /// ```
+    pub async fn selection_byte_span(&self, entries: &[ZipFileEntry]) -> Result<(u64, u64)> {
+        let offsets = self.parser.data_offsets(entries).await?;
+
+        let start = offsets.iter().copied().min().unwrap_or(0);
+        let end = offsets
+            .iter()
+            .zip(entries)
+            .map(|(offset, entry)| offset + entry.compressed_size)
+            .max()
+            .unwrap_or(0);
+        Ok((start, end))
+    }
+
+    /// Extract a file's contents to memory.
+    ///
+    /// Reads and decompresses the file data, returning it as a byte vector.
+    /// This method handles both STORED and DEFLATE compression methods.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract (from [`list_files()`])
+    ///
+    /// # Returns
+    ///
+    /// The decompressed file contents as a byte vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file needs an unsupported feature such as encryption
+    ///   ([`ZipError::UnsupportedFeature`])
+    /// - The file uses an unsupported compression method
+    /// - The data cannot be read or decompressed
+    /// - The data decompresses to more than the declared size
+    ///   ([`ZipError::SizeExceeded`])
+    /// - The data doesn't match the recorded size or CRC-32, unless
+    ///   turned off with [`with_verify_crc()`](Self::with_verify_crc)
+    /// - The data descriptor disagrees with the Central Directory, unless
+    ///   turned off with [`with_verify_crc()`](Self::with_verify_crc)
+    ///
+    /// With [`with_try_methods()`](Self::with_try_methods), these errors
+    /// are only returned if no other method decodes the data correctly.
+    ///
+    /// # Memory Usage
+    ///
+    /// This method loads the entire file into memory. For large files,
+    /// consider using [`extract_to_file()`] instead.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let data = extractor.extract_to_memory(&entry).await?;
+    /// let text = String::from_utf8_lossy(&data);
+    /// println!("{}", text);
+    /// ```
+    pub async fn extract_to_memory(&self, entry: &ZipFileEntry) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.extract_into(entry, &mut data).await?;
+        Ok(data)
+    }
+
+    /// Extract a file into a buffer the caller reuses across entries.
+    ///
+    /// The buffer is cleared and then filled with the decompressed
+    /// contents, which are checked like those of
+    /// [`extract_to_memory()`](Self::extract_to_memory). Its capacity is
+    /// kept, so extracting many small entries in a loop
+    /// doesn't allocate a new vector for each one as
+    /// [`extract_to_memory()`](Self::extract_to_memory) does.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `buf` - The buffer to fill
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`extract_to_memory()`](Self::extract_to_memory).
+    /// The buffer's contents are unspecified after an error.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut buf = Vec::new();
+    /// for entry in extractor.list_files().await? {
+    ///     extractor.extract_into(&entry, &mut buf).await?;
+    ///     handle(&entry, &buf);
+    /// }
+    /// ```
+    pub async fn extract_into(&self, entry: &ZipFileEntry, buf: &mut Vec<u8>) -> Result<()> {
+        self.read_into(entry, buf).await?;
+        if self.verify_crc {
+            check_data(entry, buf)?;
+        }
+        Ok(())
+    }
+
+    /// Decode a file into a buffer, replacing its contents.
+    ///
+    /// Does the work of [`extract_to_memory()`](Self::extract_to_memory),
+    /// including the fallback to other methods, except for the final
+    /// size and CRC-32 check.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `out` - The buffer to fill
+    ///
+    /// # Errors
+    ///
+    /// See [`extract_to_memory()`](Self::extract_to_memory).
+    async fn read_into(&self, entry: &ZipFileEntry, out: &mut Vec<u8>) -> Result<()> {
+        check_supported(entry)?;
+
+        // Calculate where the actual file data begins
+        let data_offset = self.parser.get_data_offset(entry).await?;
+        self.check_descriptor(entry, data_offset).await?;
+
+        // The compressed data must lie within the archive, which bounds
+        // what is read into memory
+        if data_offset
+            .checked_add(entry.compressed_size)
+            .is_none_or(|end| end > self.parser.reader().size())
+        {
+            bail!(
+                "Data of {} runs past the end of the archive",
+                entry.file_name
+            );
+        }
+
+        let result = self
+            .decode_into(entry, entry.compression_method, data_offset, out)
+            .await;
+        if !self.try_methods || (result.is_ok() && check_data(entry, out).is_ok()) {
+            return result;
+        }
+
+        // The declared method didn't work: see if another one does
+        for method in [CompressionMethod::Stored, CompressionMethod::Deflate] {
+            if method == entry.compression_method {
+                continue;
+            }
+            if self
+                .decode_into(entry, method, data_offset, out)
+                .await
+                .is_ok()
+                && check_data(entry, out).is_ok()
+            {
+                self.warnings.push(Warning::MethodMismatch {
+                    name: entry.file_name.clone(),
+                    declared: entry.compression_method,
+                    actual: method,
+                });
+                return Ok(());
+            }
+        }
+
+        // Report the declared method's result, not the last attempt's
+        self.decode_into(entry, entry.compression_method, data_offset, out)
+            .await
+    }
+
+    /// Read and decode an entry's data with the given method.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to decode
+    /// * `method` - The compression method to decode with
+    /// * `data_offset` - Where the entry's data begins
+    /// * `out` - Buffer replaced with the decoded data
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the method is unsupported, the data cannot be
+    /// read or decompressed, or it decompresses to more than the declared
+    /// size.
+    async fn decode_into(
+        &self,
+        entry: &ZipFileEntry,
+        method: CompressionMethod,
+        data_offset: u64,
+        out: &mut Vec<u8>,
+    ) -> Result<()> {
+        out.clear();
+        if entry.is_encrypted() {
+            let compressed = self.decrypt(entry, data_offset).await?;
+            return decode_buffer(entry, method, &compressed, out);
+        }
+        match method {
+            CompressionMethod::Stored => {
+                // No compression - read data directly. The compressed size
+                // has been checked against the archive, unlike the
+                // uncompressed one, so it bounds the allocation
+                out.resize(entry.compressed_size as usize, 0);
+                self.parser.reader().read_exact_at(data_offset, out).await?;
+                check_not_exceeded(entry, out)
+            }
+            CompressionMethod::Deflate => {
+                // DEFLATE compression - read compressed data first
+                let compressed = self
+                    .parser
+                    .reader()
+                    .read_vec(data_offset, entry.compressed_size as usize)
+                    .await?;
+
+                decode_buffer(entry, method, &compressed, out)
+            }
+            CompressionMethod::Unknown(_) => Err(unsupported_method(entry, method)),
+        }
+    }
+
+    /// Read and decrypt an encrypted entry's data.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The encrypted entry
+    /// * `data_offset` - Where the entry's data begins
+    ///
+    /// # Returns
+    ///
+    /// The decrypted data, still compressed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ZipError::PasswordRequired`] without a password, and the
+    /// errors of [`aes::decrypt()`] or [`zipcrypto::decrypt()`] otherwise.
+    async fn decrypt(&self, entry: &ZipFileEntry, data_offset: u64) -> Result<Vec<u8>> {
+        let password = self.password(entry)?;
+        let data = self
+            .parser
+            .reader()
+            .read_vec(data_offset, entry.compressed_size as usize)
+            .await?;
+        match entry.aes {
+            Some(aes) => aes::decrypt(entry, aes, password, &data),
+            None => zipcrypto::decrypt(entry, password, &data),
+        }
+    }
+
+    /// Get the password to decrypt an entry with.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The encrypted entry
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ZipError::PasswordRequired`] if no password was set.
+    fn password(&self, entry: &ZipFileEntry) -> Result<&str> {
+        match self.password {
+            Some(ref password) => Ok(password),
+            None => bail!(ZipError::PasswordRequired {
+                name: entry.file_name.clone(),
+            }),
+        }
+    }
+
+    /// Check a file's integrity without writing it anywhere.
+    ///
+    /// Decompresses the file in memory and compares the CRC-32 of the
+    /// result with the one recorded in the archive.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to verify
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be read or decompressed
+    /// - The decompressed data doesn't match the recorded size or CRC-32
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// extractor.verify(&entry).await?;
+    /// ```
+    pub async fn verify(&self, entry: &ZipFileEntry) -> Result<()> {
+        let mut data = Vec::new();
+        self.read_into(entry, &mut data).await?;
+        check_data(entry, &data)
+    }
+
+    /// Check a file's integrity while reporting progress.
+    ///
+    /// Like [`verify()`](Self::verify), but the data is decompressed
+    /// chunk by chunk and discarded, so memory use stays bounded, and
+    /// `progress` is called after each chunk. The size and CRC-32 are
+    /// checked even if [`with_verify_crc()`](Self::with_verify_crc)
+    /// turned checking off for extraction. With
+    /// [`with_try_methods()`](Self::with_try_methods) the entry is
+    /// verified in memory and `progress` is called once at the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to verify
+    /// * `progress` - Called with the number of decompressed bytes
+    ///   checked so far in this entry
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be read or decompressed
+    /// - The decompressed data doesn't match the recorded size or CRC-32
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let total = entry.uncompressed_size.max(1);
+    /// extractor
+    ///     .verify_with_progress(&entry, |done| eprint!("\r{}%", done * 100 / total))
+    ///     .await?;
+    /// ```
+    pub async fn verify_with_progress<F>(&self, entry: &ZipFileEntry, progress: F) -> Result<()>
+    where
+        F: FnMut(u64) + Unpin,
+    {
+        let mut progress = progress;
+        if self.try_methods {
+            self.verify(entry).await?;
+            progress(entry.uncompressed_size);
+            return Ok(());
+        }
+
+        let mut sink = CheckWriter {
+            crc: Crc::new(),
+            written: 0,
+            progress,
+        };
+        self.stream_to(entry, &mut sink).await?;
+        check_sums(entry, sink.written, sink.crc.sum())
+    }
+
+    /// Compute the SHA-256 hash of a file's extracted contents.
+    ///
+    /// The data is hashed as it is decompressed, like
+    /// [`extract_to_writer()`](Self::extract_to_writer) would write it
+    /// (so after [`with_recode()`](Self::with_recode) conversion), and
+    /// its size and CRC-32 are checked as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to hash
+    ///
+    /// # Returns
+    ///
+    /// The 32-byte SHA-256 digest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or decompressed, or
+    /// doesn't match the recorded size or CRC-32.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let digest = extractor.sha256(&entry).await?;
+    /// let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    /// println!("{}  {}", hex, entry.file_name);
+    /// ```
+    pub async fn sha256(&self, entry: &ZipFileEntry) -> Result<[u8; 32]> {
+        let mut sink = DigestWriter(digest::Context::new(&digest::SHA256));
+        self.extract_to_writer(entry, &mut sink).await?;
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(sink.0.finish().as_ref());
+        Ok(hash)
+    }
+
+    /// Extract a file to the filesystem.
+    ///
+    /// Reads, decompresses, and writes the file to the specified path.
+    /// Parent directories are created automatically if they don't exist,
+    /// with the mode set by [`with_dir_mode()`](Self::with_dir_mode).
+    ///
+    /// The data is streamed to disk chunk by chunk while its CRC-32 and
+    /// length are computed; both are checked against the entry at the
+    /// end, and on a mismatch, or any other failure once the file was
+    /// created, the file is removed, so no corrupt output is left
+    /// behind. With [`with_try_methods()`](Self::with_try_methods) or
+    /// [`with_recode()`](Self::with_recode) the entry is decoded and
+    /// checked in memory before anything is written.
+    ///
+    /// Afterwards the metadata selected with
+    /// [`with_preserve()`](Self::with_preserve) is restored; with `links`
+    /// enabled, symlink entries become symbolic links (Unix only). Their
+    /// targets are only checked against the entry's name in the archive:
+    /// callers that place entries elsewhere, or that let earlier links
+    /// redirect later paths, must check where the output really points.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `output_path` - The filesystem path to write the file to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be read or decompressed
+    /// - The decompressed data doesn't match the recorded size or CRC-32
+    /// - Parent directories cannot be created
+    /// - The file cannot be written
+    /// - The metadata cannot be restored
+    /// - A symlink target points outside the extraction directory
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// extractor.extract_to_file(&entry, Path::new("output/file.txt")).await?;
+    /// ```
+    pub async fn extract_to_file(&self, entry: &ZipFileEntry, output_path: &Path) -> Result<()> {
+        // Ensure parent directories exist
+        if let Some(parent) = output_path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            metadata::create_dirs(parent, self.dir_mode).await?;
+        }
+
+        // Recreate symlinks from their stored target
+        #[cfg(unix)]
+        if self.preserve.links && entry.is_symlink() {
+            let data = self.extract_to_memory(entry).await?;
+            let target = String::from_utf8(data)?;
+            metadata::check_link_target(entry, &target)?;
+            if fs::symlink_metadata(output_path).await.is_ok() {
+                fs::remove_file(output_path).await?;
+            }
+            fs::symlink(&target, output_path).await?;
+            return Ok(());
+        }
+
+        // Write to the output file, removing it if anything goes wrong
+        let mut file = fs::File::create(output_path).await?;
+        let written: Result<()> = async {
+            self.extract_to_writer(entry, &mut file).await?;
+            // Wait for the write to land before touching the file's metadata
+            file.flush().await?;
+            Ok(())
+        }
+        .await;
+        drop(file);
+        if let Err(e) = written {
+            let _ = fs::remove_file(output_path).await;
+            return Err(e);
+        }
+
+        metadata::restore(entry, output_path, self.preserve)?;
+
+        Ok(())
+    }
+
+    /// Restore the metadata of a directory entry on an existing directory.
+    ///
+    /// Applies the metadata selected with
+    /// [`with_preserve()`](Self::with_preserve). Writing files into a
+    /// directory updates its modification time, so call this after all
+    /// of the directory's contents have been extracted, deepest
+    /// directories first.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The directory entry
+    /// * `path` - The directory on disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata cannot be restored.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // After extracting "docs/guide.txt"
+    /// extractor.restore_directory(&docs_entry, Path::new("docs")).await?;
+    /// ```
+    pub async fn restore_directory(&self, entry: &ZipFileEntry, path: &Path) -> Result<()> {
+        metadata::restore(entry, path, self.preserve)
+    }
+
+    /// Extract all entries to destinations chosen by a closure.
+    ///
+    /// The closure is called once per entry, in archive order, and
+    /// returns the entry's destination relative to `root`, or `None` to
+    /// skip it. Returned paths are checked like entry names with
+    /// [`safe_join()`]: a path that is absolute or contains `..` is
+    /// recorded as failed instead of being written. Directory entries
+    /// are created, and their metadata is restored once all files have
+    /// been extracted.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory destinations are relative to
+    /// * `route` - Closure choosing each entry's destination
+    ///
+    /// # Returns
+    ///
+    /// A report with the outcome of every entry the closure routed.
+    /// Skipped entries are not recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be listed or a directory's
+    /// metadata cannot be restored. Failures of single entries are
+    /// recorded in the report instead.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Collect text files in "text/", keep everything else in place
+    /// let report = extractor
+    ///     .extract_all_with(Path::new("out"), |entry| {
+    ///         let name = Path::new(&entry.file_name);
+    ///         if name.extension().is_some_and(|ext| ext == "txt") {
+    ///             Some(Path::new("text").join(name.file_name()?))
+    ///         } else {
+    ///             Some(name.to_path_buf())
+    ///         }
+    ///     })
+    ///     .await?;
+    /// ```
+    pub async fn extract_all_with<F>(&self, root: &Path, mut route: F) -> Result<ExtractReport>
+    where
+        F: FnMut(&ZipFileEntry) -> Option<PathBuf>,
+    {
+        let mut report = ExtractReport::new();
+        let mut directories = Vec::new();
+        for entry in self.list_files().await? {
+            let Some(relative) = route(&entry) else {
+                continue;
+            };
+            let result = match safe_join(root, &relative) {
+                Ok(path) => {
+                    if entry.is_directory {
+                        metadata::create_dirs(&path, self.dir_mode)
+                            .await
+                            .map(|()| path)
+                    } else {
+                        self.extract_to_file(&entry, &path).await.map(|()| path)
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(path) => {
+                    if entry.is_directory {
+                        directories.push((entry.clone(), path.clone()));
+                    }
+                    report.push(&entry, EntryOutcome::Extracted(path));
+                }
+                Err(e) => report.push(&entry, EntryOutcome::Failed(format!("{:#}", e))),
+            }
+        }
+
+        // Restore directories after their contents, deepest first
+        directories.sort_by_key(|(_, path)| std::cmp::Reverse(path.components().count()));
+        for (entry, path) in &directories {
+            self.restore_directory(entry, path).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Extract a file into a file handle the caller has already opened.
+    ///
+    /// The data is decompressed in chunks and written at the handle's
+    /// current position as it arrives, so memory use stays bounded; as
+    /// with [`extract_to_writer()`](Self::extract_to_writer), a size or
+    /// CRC-32 mismatch is only reported after the data was written, and
+    /// [`with_try_methods()`](Self::with_try_methods) decodes the entry in
+    /// memory first. No paths are involved: directories aren't
+    /// created, metadata isn't restored, and the handle is neither
+    /// truncated nor closed. Useful when the caller manages placement,
+    /// e.g. pre-allocated files in server code.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `file` - An open, writable file handle
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be read or decompressed
+    /// - The decompressed data doesn't match the recorded size or CRC-32
+    /// - Writing to the handle fails
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut file = tokio::fs::File::create("slot-7.bin").await?;
+    /// extractor.extract_to_open_file(&entry, &mut file).await?;
+    /// ```
+    pub async fn extract_to_open_file(
+        &self,
+        entry: &ZipFileEntry,
+        file: &mut fs::File,
+    ) -> Result<u64> {
+        if !self.try_methods {
+            return self.stream_to(entry, file).await;
+        }
+
+        let data = self.extract_to_memory(entry).await?;
+        file.write_all(&data).await?;
+        file.flush().await?;
+
+        Ok(data.len() as u64)
+    }
+
+    /// Extract a file's contents into a writer as they are decompressed.
+    ///
+    /// The compressed data is read in chunks of 256 KiB and each chunk is
+    /// decompressed, written and flushed before the next one is read, so
+    /// memory use stays bounded regardless of the entry's size. As a
+    /// consequence a size or CRC-32 mismatch is only reported after the
+    /// data was written. With [`with_try_methods()`](Self::with_try_methods)
+    /// the entry is decoded in memory first, since a fallback method can't
+    /// take back bytes already written; likewise with
+    /// [`with_recode()`](Self::with_recode), which needs the whole text.
+    ///
+    /// With [`with_entry_timeout()`](Self::with_entry_timeout), the
+    /// extraction is abandoned once the timeout passes.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `writer` - Where to write the decompressed data
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be read or decompressed
+    /// - The decompressed data doesn't match the recorded size or CRC-32
+    /// - Writing fails
+    /// - The entry takes longer than the configured timeout
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut upload = storage.create_object("large.bin").await?;
+    /// extractor.extract_to_writer(&entry, &mut upload).await?;
+    /// ```
+    pub async fn extract_to_writer<W: AsyncWrite + Unpin>(
+        &self,
+        entry: &ZipFileEntry,
+        writer: &mut W,
+    ) -> Result<u64> {
+        let Some(timeout) = self.entry_timeout else {
+            return self.write_entry(entry, writer).await;
+        };
+        match tokio::time::timeout(timeout, self.write_entry(entry, writer)).await {
+            Ok(result) => result,
+            Err(_) => Err(ZipError::Timeout {
+                name: entry.file_name.clone(),
+                timeout,
+            }
+            .into()),
+        }
+    }
+
+    /// Extract a file's contents into a writer, without a timeout.
+    ///
+    /// See [`extract_to_writer()`](Self::extract_to_writer).
+    async fn write_entry<W: AsyncWrite + Unpin>(
+        &self,
+        entry: &ZipFileEntry,
+        writer: &mut W,
+    ) -> Result<u64> {
+        if !self.try_methods && self.recode.is_none() {
+            return self.stream_to(entry, writer).await;
+        }
+
+        let data = self.extract_to_memory(entry).await?;
+        // Convert text contents if requested
+        let data = match self.recode {
+            Some(ref recode) => recode.apply(&entry.file_name, data),
+            None => data,
+        };
+        writer.write_all(&data).await?;
+        writer.flush().await?;
+        Ok(data.len() as u64)
+    }
+
+    /// Extract a file into memory, or into a temporary file if it's large.
+    ///
+    /// The contents are kept in memory while they fit in `threshold`
+    /// bytes. Once more data arrives, everything decompressed so far is
+    /// moved to a new file in `spill_dir` and the rest is written there,
+    /// so heap use stays below the threshold (plus one chunk) however
+    /// large the entry is. The decision follows the actual data, not the
+    /// size recorded in the archive. Otherwise this behaves like
+    /// [`extract_to_writer()`](Self::extract_to_writer).
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `threshold` - Most bytes kept in memory
+    /// * `spill_dir` - Directory the temporary file is created in, e.g.
+    ///   [`std::env::temp_dir()`]
+    ///
+    /// # Returns
+    ///
+    /// The contents, or the path of the file holding them. The caller
+    /// owns the file and is responsible for removing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The file cannot be read or decompressed
+    /// - The decompressed data doesn't match the recorded size or CRC-32
+    /// - The temporary file cannot be created or written; it is removed
+    ///   again on any failure
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// match extractor.extract_spilling(&entry, 1 << 20, &std::env::temp_dir()).await? {
+    ///     ExtractedData::InMemory(data) => upload(&data).await?,
+    ///     ExtractedData::OnDisk(path) => {
+    ///         upload_file(&path).await?;
+    ///         std::fs::remove_file(path)?;
+    ///     }
+    /// }
+    /// ```
+    pub async fn extract_spilling(
+        &self,
+        entry: &ZipFileEntry,
+        threshold: usize,
+        spill_dir: &Path,
+    ) -> Result<ExtractedData> {
+        let mut spill = SpillWriter {
+            buffer: Vec::new(),
+            threshold,
+            spill_dir,
+            file: None,
+        };
+        let result = self
+            .extract_to_writer(entry, &mut BlockingWrite(&mut spill))
+            .await;
+
+        match (result, spill.file) {
+            (Ok(_), None) => Ok(ExtractedData::InMemory(spill.buffer)),
+            (Ok(_), Some((path, _))) => Ok(ExtractedData::OnDisk(path)),
+            (Err(e), file) => {
+                if let Some((path, file)) = file {
+                    drop(file);
+                    let _ = std::fs::remove_file(path);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Extract a file's contents to stdout.
+    ///
+    /// Reads, decompresses, and writes the file directly to standard output.
+    /// Useful for piping archive contents to other commands.
+    ///
+    /// The data is streamed with
+    /// [`extract_to_writer()`](Self::extract_to_writer), so a reader on the
+    /// other end of a pipe sees output while the download continues.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, decompressed, or written.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Equivalent to: runzip -p archive.zip file.txt | cat
+    /// extractor.extract_to_stdout(&entry).await?;
+    /// ```
+    pub async fn extract_to_stdout(&self, entry: &ZipFileEntry) -> Result<()> {
+        let mut stdout = tokio::io::stdout();
+        self.extract_to_writer(entry, &mut stdout).await?;
+        Ok(())
+    }
+
+    /// Convert entries to a gzip-compressed tar archive.
+    ///
+    /// Entries are written in the given order in a single pass: each one
+    /// is decompressed from the ZIP archive and recompressed with gzip
+    /// chunk by chunk, so memory use stays bounded. Names, sizes, Unix
+    /// permissions, modification times and owner IDs are kept, and
+    /// symlinks become tar symlinks. Like
+    /// [`extract_to_stdout()`](Self::extract_to_stdout), a size or CRC-32
+    /// mismatch is only noticed after the entry's data was written, and
+    /// [`with_try_methods()`](Self::with_try_methods) decodes each entry
+    /// in memory first.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The entries to include, directories included
+    /// * `out` - Where to write the compressed archive
+    ///
+    /// # Returns
+    ///
+    /// The writer, after the gzip stream was finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry cannot be read or decompressed, or
+    /// doesn't match its recorded size or CRC-32, or if writing fails.
+    /// The output is incomplete in that case.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let entries = extractor.list_files().await?;
+    /// let file = std::fs::File::create("archive.tgz")?;
+    /// extractor.write_tar_gz(&entries, file).await?;
+    /// ```
+    pub async fn write_tar_gz<'a, I, W>(&self, entries: I, out: W) -> Result<W>
+    where
+        I: IntoIterator<Item = &'a ZipFileEntry>,
+        W: Write,
+    {
+        let mut tar = TarWriter::new(GzEncoder::new(out, Compression::default()));
+
+        for entry in entries {
+            let result: Result<()> = async {
+                if entry.is_directory {
+                    tar.append(entry, &Member::Directory)?;
+                } else if entry.is_symlink() {
+                    let data = self.extract_to_memory(entry).await?;
+                    tar.append(entry, &Member::Symlink(std::str::from_utf8(&data)?))?;
+                } else if self.try_methods {
+                    let data = self.extract_to_memory(entry).await?;
+                    tar.append(entry, &Member::File)?;
+                    tar.write_all(&data)?;
+                    tar.pad(entry.uncompressed_size)?;
+                } else {
+                    tar.append(entry, &Member::File)?;
+                    self.stream_to(entry, &mut BlockingWrite(&mut tar)).await?;
+                    tar.pad(entry.uncompressed_size)?;
+                }
+                Ok(())
+            }
+            .await;
+            result.with_context(|| format!("Cannot convert {}", entry.file_name))?;
+        }
+
+        Ok(tar.finish()?.finish()?)
+    }
+
+    /// Compare an entry's data descriptor with its Central Directory
+    /// record, if data is verified or descriptor checking is enabled and
+    /// the entry has one.
+    ///
+    /// The descriptor's signature is optional and its sizes are 4 or 8
+    /// bytes each (the latter for ZIP64 entries), so every reading is
+    /// tried before reporting a mismatch.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry
+    /// * `data_offset` - Where the entry's data begins
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the descriptor cannot be read or doesn't match.
+    async fn check_descriptor(&self, entry: &ZipFileEntry, data_offset: u64) -> Result<()> {
+        if !(self.verify_crc || self.check_descriptors) || !entry.has_data_descriptor() {
+            return Ok(());
+        }
+
+        let reader = self.parser.reader();
+        let offset = data_offset + entry.compressed_size;
+        let len = reader.size().saturating_sub(offset).min(24);
+        let buf = reader.read_vec(offset, len as usize).await?;
+        let fields = buf.strip_prefix(DATA_DESCRIPTOR_SIGNATURE).unwrap_or(&buf);
+
+        let expected = (entry.crc32, entry.compressed_size, entry.uncompressed_size);
+        let classic = parse_descriptor(fields, false);
+        let zip64 = parse_descriptor(fields, true);
+        if classic == Some(expected) || zip64 == Some(expected) {
+            return Ok(());
+        }
+
+        // Report the reading that fits the entry's sizes
+        let large = entry.compressed_size >= 0xFFFFFFFF || entry.uncompressed_size >= 0xFFFFFFFF;
+        let found = if large { zip64 } else { classic };
+        let Some((crc32, compressed, uncompressed)) = found else {
+            bail!(
+                "Data descriptor for {} is truncated at offset {}",
+                entry.file_name,
+                offset
+            );
+        };
+        bail!(
+            "Data descriptor for {} disagrees with the Central Directory: \
+             CRC-32 {:08x}, {} compressed and {} uncompressed bytes, \
+             expected {:08x}, {} and {}",
+            entry.file_name,
+            crc32,
+            compressed,
+            uncompressed,
+            entry.crc32,
+            entry.compressed_size,
+            entry.uncompressed_size
+        )
+    }
+
+    /// Decompress an entry chunk by chunk into a writer, flushing each one.
+    ///
+    /// Only the compressed size is trusted to bound the reads. A STORED
+    /// entry whose sizes disagree is rejected before anything is written,
+    /// since its data would otherwise be copied out unchecked. Encrypted
+    /// entries are decrypted chunk by chunk too; the password is checked
+    /// first, but a WinZip AES authentication code only once all data was
+    /// written.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The file entry to extract
+    /// * `writer` - Where to write the decompressed data
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the method is unsupported, the password is
+    /// missing or wrong, the data cannot be read, decrypted, decompressed
+    /// or written, or it doesn't match the recorded size, CRC-32 or
+    /// authentication code.
+    async fn stream_to<W: AsyncWrite + Unpin>(
+        &self,
+        entry: &ZipFileEntry,
+        writer: &mut W,
+    ) -> Result<u64> {
+        check_supported(entry)?;
+        if let method @ CompressionMethod::Unknown(_) = entry.compression_method {
+            return Err(unsupported_method(entry, method));
+        }
+        let data_offset = self.parser.get_data_offset(entry).await?;
+        self.check_descriptor(entry, data_offset).await?;
+
+        // Encrypted data follows a header, and with AES precedes an
+        // authentication code
+        let (mut decryptor, data_start, input_len) = if entry.is_encrypted() {
+            let password = self.password(entry)?;
+            let (header_len, trailer_len) = match entry.aes {
+                Some(aes) => (aes::header_len(entry, aes)?, AesInfo::AUTH_CODE_SIZE),
+                None => (zipcrypto::HEADER_SIZE, 0),
+            };
+            let Some(input_len) = entry
+                .compressed_size
+                .checked_sub((header_len + trailer_len) as u64)
+            else {
+                bail!(
+                    "Encrypted data of {} is truncated: {} bytes",
+                    entry.file_name,
+                    entry.compressed_size
+                );
+            };
+            let header = self
+                .parser
+                .reader()
+                .read_vec(data_offset, header_len)
+                .await?;
+            let decryptor = match entry.aes {
+                Some(aes) => Decryptor::Aes(Box::new(aes::Decryptor::new(
+                    entry, aes, password, &header,
+                )?)),
+                None => Decryptor::ZipCrypto(zipcrypto::Decryptor::new(entry, password, &header)?),
+            };
+            (Some(decryptor), data_offset + header_len as u64, input_len)
+        } else {
+            (None, data_offset, entry.compressed_size)
+        };
+        if entry.compression_method == CompressionMethod::Stored {
+            check_stored_sizes(entry, input_len)?;
+        }
+
+        let mut inflater = Decompress::new(false);
+        let mut out = vec![0u8; STREAM_CHUNK as usize];
+        let mut crc = Crc::new();
+        let mut written = 0;
+        let mut pos = 0;
+        let mut ended = false;
+        while pos < input_len {
+            let len = STREAM_CHUNK.min(input_len - pos);
+            let mut chunk = self
+                .parser
+                .reader()
+                .read_vec(data_start + pos, len as usize)
+                .await?;
+            pos += len;
+
+            if let Some(ref mut decryptor) = decryptor {
+                decryptor.decrypt(&mut chunk)?;
+            }
+            // Data after the end of the DEFLATE stream is only read to
+            // authenticate it
+            if ended {
+                continue;
+            }
+
+            if entry.compression_method == CompressionMethod::Stored {
+                crc.update(&chunk);
+                writer.write_all(&chunk).await?;
+                writer.flush().await?;
+                written += len;
+                continue;
+            }
+
+            let chunk_start = inflater.total_in();
+            loop {
+                let consumed = (inflater.total_in() - chunk_start) as usize;
+                let produced = inflater.total_out();
+                let status =
+                    inflater.decompress(&chunk[consumed..], &mut out, FlushDecompress::None)?;
+                let new = &out[..(inflater.total_out() - produced) as usize];
+
+                // Stop at the first byte past the declared size
+                if inflater.total_out() > entry.uncompressed_size {
+                    bail!(ZipError::SizeExceeded {
+                        name: entry.file_name.clone(),
+                        declared: entry.uncompressed_size,
+                    });
+                }
+                crc.update(new);
+                writer.write_all(new).await?;
+                written += new.len() as u64;
+
+                if status == Status::StreamEnd {
+                    ended = true;
+                    if decryptor.is_none() {
+                        pos = input_len;
+                    }
+                    break;
+                }
+                // No progress means the chunk is used up
+                if inflater.total_in() - chunk_start == consumed as u64 && new.is_empty() {
+                    break;
+                }
+            }
+            writer.flush().await?;
+        }
+
+        if let Some(Decryptor::Aes(decryptor)) = decryptor {
+            let auth_code = self
+                .parser
+                .reader()
+                .read_vec(data_start + input_len, AesInfo::AUTH_CODE_SIZE)
+                .await?;
+            decryptor.finish(entry, &auth_code)?;
+        }
+        if self.verify_crc {
+            check_sums(entry, written, crc.sum())?;
+        }
+        Ok(written)
+    }
+}
+
+impl ZipExtractor<MemoryReader> {
+    /// List an archive's entries from its last bytes alone.
+    ///
+    /// For callers that fetched the end of an archive themselves, e.g. from
+    /// a CDN: the End of Central Directory record and the whole Central
+    /// Directory must lie within `tail`, and nothing else is read.
+    ///
+    /// # Arguments
+    ///
+    /// * `tail` - The last `tail.len()` bytes of the archive
+    /// * `total_size` - Size of the whole archive in bytes
+    ///
+    /// # Returns
+    ///
+    /// All entries in the archive, as [`list_files()`](Self::list_files)
+    /// returns them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The tail is longer than the archive or too short for an end record
+    /// - No valid End of Central Directory record is found in the tail
+    /// - The Central Directory starts before the tail
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Fetched with "Range: bytes=-65536"
+    /// let entries = ZipExtractor::list_from_tail(&tail, content_length).await?;
+    /// ```
+    pub async fn list_from_tail(tail: &[u8], total_size: u64) -> Result<Vec<ZipFileEntry>> {
+        if tail.len() < EndOfCentralDirectory::SIZE {
+            bail!(
+                "Tail of {} bytes is too short to hold an End of Central Directory record",
+                tail.len()
+            );
+        }
+        let reader = MemoryReader::tail(tail.to_vec(), total_size)?;
+
+        // Search only the tail; reading the directory fails if it starts
+        // before the tail
+        let extractor = Self::new(Arc::new(reader)).with_eocd_search_window(tail.len() as u64)?;
+        extractor.list_files().await.with_context(|| {
+            format!(
+                "Cannot list from the last {} bytes of the {} byte archive",
+                tail.len(),
+                total_size
+            )
+        })
+    }
+}
+
+/// Fail if decoded data is longer than the entry's declared size.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the data belongs to
+/// * `out` - The decoded data
+///
+/// # Errors
+///
+/// Returns [`ZipError::SizeExceeded`] if `out` is too long.
+fn check_not_exceeded(entry: &ZipFileEntry, out: &[u8]) -> Result<()> {
+    if out.len() as u64 > entry.uncompressed_size {
+        bail!(ZipError::SizeExceeded {
+            name: entry.file_name.clone(),
+            declared: entry.uncompressed_size,
+        });
+    }
+    Ok(())
+}
+
+/// Check that a STORED entry's data length and uncompressed size agree.
+///
+/// # Arguments
+///
+/// * `entry` - The STORED entry
+/// * `stored` - Length of its data: the compressed size, less any
+///   encryption header and authentication code
+///
+/// # Errors
+///
+/// Returns [`ZipError::SizeExceeded`] if more data is stored than the
+/// uncompressed size declares, or an error if less is.
+fn check_stored_sizes(entry: &ZipFileEntry, stored: u64) -> Result<()> {
+    if stored > entry.uncompressed_size {
+        bail!(ZipError::SizeExceeded {
+            name: entry.file_name.clone(),
+            declared: entry.uncompressed_size,
+        });
+    }
+    if stored < entry.uncompressed_size {
+        bail!(
+            "{} is stored with {} bytes of data but declares {} bytes",
+            entry.file_name,
+            stored,
+            entry.uncompressed_size
+        );
+    }
+    Ok(())
+}
+
+/// Validate a relative path for extraction.
+///
+/// Rejects absolute paths, drive prefixes, and `..` components so that
+/// the result can be safely joined onto the output directory. This is
+/// the Zip Slip check applied to entry names before extraction.
+///
+/// # Arguments
+///
+/// * `name` - The relative path to validate
+///
+/// # Returns
+///
+/// The normalized relative path, with `.` components removed.
+///
+/// # Errors
+///
+/// Returns an error if the path could escape the output directory.
+///
+/// # Example
+///
+/// ```ignore
+/// assert_eq!(sanitize_path("./docs/a.txt")?, Path::new("docs/a.txt"));
+/// assert!(sanitize_path("../etc/passwd").is_err());
+/// ```
+pub fn sanitize_path(name: impl AsRef<Path>) -> Result<PathBuf> {
+    let name = name.as_ref();
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                bail!(
+                    "Refusing to extract outside the output directory: {}",
+                    name.display()
+                );
+            }
+        }
+    }
+
+    if path.as_os_str().is_empty() {
+        bail!("Invalid empty path: {}", name.display());
+    }
+
+    Ok(path)
+}
+
+/// Join a path from the archive onto the output directory, safely.
+///
+/// The path is validated with [`sanitize_path()`]. On Windows, existing
+/// components below `root` must also not be junctions or symbolic
+/// links, which could redirect the write outside the output directory.
+///
+/// # Arguments
+///
+/// * `root` - The output directory; empty for the current directory
+/// * `name` - The relative path to place below it
+///
+/// # Returns
+///
+/// The path to write to.
+///
+/// # Errors
+///
+/// Returns an error if the path could escape the output directory.
+///
+/// # Example
+///
+/// ```ignore
+/// let path = safe_join(Path::new("out"), &entry.file_name)?;
+/// extractor.extract_to_file(&entry, &path).await?;
+/// ```
+pub fn safe_join(root: &Path, name: impl AsRef<Path>) -> Result<PathBuf> {
+    let relative = sanitize_path(name)?;
+    #[cfg(windows)]
+    metadata::check_reparse_points(root, &relative)?;
+    Ok(root.join(relative))
+}
+
+/// Decryption state of an entry streamed by
+/// [`ZipExtractor::stream_to()`].
+enum Decryptor {
+    /// WinZip AES, authenticated once all data was read
+    Aes(Box<aes::Decryptor>),
+    /// Traditional PKWARE encryption
+    ZipCrypto(zipcrypto::Decryptor),
+}
+
+impl Decryptor {
+    /// Decrypt the next chunk of data in place.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if AES decryption fails.
+    fn decrypt(&mut self, data: &mut [u8]) -> Result<()> {
+        match self {
+            Self::Aes(decryptor) => decryptor.decrypt(data),
+            Self::ZipCrypto(decryptor) => {
+                decryptor.decrypt(data);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Where [`ZipExtractor::extract_spilling()`] put an entry's contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtractedData {
+    /// The contents fit within the threshold
+    InMemory(Vec<u8>),
+    /// The contents were larger and are in this temporary file
+    OnDisk(PathBuf),
+}
+
+/// Writer collecting data in memory until a threshold, then in a file.
+struct SpillWriter<'a> {
+    /// Data collected while below the threshold
+    buffer: Vec<u8>,
+    /// Most bytes kept in `buffer`
+    threshold: usize,
+    /// Directory for the file
+    spill_dir: &'a Path,
+    /// The file and its path, once the threshold was exceeded
+    file: Option<(PathBuf, std::fs::File)>,
+}
+
+impl Write for SpillWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.file.is_none() && self.buffer.len() + buf.len() > self.threshold {
+            let (path, mut file) = create_spill_file(self.spill_dir)?;
+            let moved = file.write_all(&self.buffer);
+            // Keep the path even if writing failed, so it can be removed
+            self.file = Some((path, file));
+            moved?;
+            self.buffer = Vec::new();
+        }
+        match self.file {
+            Some((_, ref mut file)) => file.write(buf),
+            None => {
+                self.buffer.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.file {
+            Some((_, ref mut file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Create a file with a name not used yet in a directory.
+///
+/// # Returns
+///
+/// The file's path and the file, opened for writing.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be created.
+fn create_spill_file(dir: &Path) -> std::io::Result<(PathBuf, std::fs::File)> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    loop {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("runzip-{}-{}.tmp", std::process::id(), n));
+        match std::fs::File::create_new(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Writer discarding data after computing its CRC-32 and length.
+///
+/// Used by [`ZipExtractor::verify_with_progress()`], which reports the
+/// running length after every write.
+struct CheckWriter<F> {
+    /// CRC-32 of the data written so far
+    crc: Crc,
+    /// Number of bytes written so far
+    written: u64,
+    /// Called with `written` after every write
+    progress: F,
+}
+
+impl<F: FnMut(u64) + Unpin> AsyncWrite for CheckWriter<F> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        this.crc.update(buf);
+        this.written += buf.len() as u64;
+        (this.progress)(this.written);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Writer discarding data after feeding it to a digest.
+///
+/// Used by [`ZipExtractor::sha256()`].
+struct DigestWriter(digest::Context);
+
+impl AsyncWrite for DigestWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.get_mut().0.update(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Decompress data held in memory.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the data belongs to
+/// * `method` - The compression method to decode with
+/// * `compressed` - The entry's stored data
+/// * `out` - Buffer the decoded data is appended to
+///
+/// # Errors
+///
+/// Returns an error if the method is unsupported, the data cannot be
+/// decompressed, or it decompresses to more than the declared size.
+fn decode_buffer(
+    entry: &ZipFileEntry,
+    method: CompressionMethod,
+    compressed: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    match method {
+        CompressionMethod::Stored => out.extend_from_slice(compressed),
+        CompressionMethod::Deflate => {
+            // Decompress using flate2's DeflateDecoder
+            // Note: ZIP uses raw DEFLATE, not zlib or gzip wrapped
+            // Stop one byte past the declared size: a well-formed entry
+            // never produces more, so the extra byte exposes a lie
+            // without decompressing an unbounded amount of data
+            let decoder = DeflateDecoder::new(compressed);
+            out.reserve(entry.uncompressed_size.min(MAX_PREALLOCATION) as usize);
+            decoder.take(entry.uncompressed_size + 1).read_to_end(out)?;
+        }
+        CompressionMethod::Unknown(_) => return Err(unsupported_method(entry, method)),
+    }
+    check_not_exceeded(entry, out)
+}
+
+/// Check decoded data against the size and CRC-32 recorded for an entry.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the data belongs to
+/// * `data` - The decoded data
+///
+/// # Errors
+///
+/// Returns an error describing the first mismatch.
+fn check_data(entry: &ZipFileEntry, data: &[u8]) -> Result<()> {
+    let mut crc = Crc::new();
+    crc.update(data);
+    check_sums(entry, data.len() as u64, crc.sum())
+}
+
+/// Check the size and CRC-32 of decoded data against an entry's.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the data belongs to
+/// * `size` - Size of the decoded data
+/// * `crc` - CRC-32 of the decoded data
+///
+/// # Errors
+///
+/// Returns an error describing the first mismatch.
+fn check_sums(entry: &ZipFileEntry, size: u64, crc: u32) -> Result<()> {
+    if size != entry.uncompressed_size {
+        bail!(
+            "Size mismatch for {}: expected {} bytes, got {}",
+            entry.file_name,
+            entry.uncompressed_size,
+            size
+        );
+    }
+    // AE-2 entries store no CRC-32; their authentication code was
+    // checked on decryption instead
+    if crc != entry.crc32 && entry.aes.is_none_or(|aes| aes.has_crc()) {
+        bail!(
+            "CRC mismatch for {}: expected {:08x}, got {:08x}",
+            entry.file_name,
+            entry.crc32,
+            crc
+        );
+    }
+
+    Ok(())
+}
+
+/// Parse the CRC-32 and sizes of a data descriptor after its signature.
+///
+/// # Arguments
+///
+/// * `fields` - The descriptor's bytes, without the signature
+/// * `zip64` - Whether the sizes are 8 bytes each instead of 4
+///
+/// # Returns
+///
+/// The CRC-32, compressed size and uncompressed size, or `None` if
+/// `fields` is too short.
+fn parse_descriptor(fields: &[u8], zip64: bool) -> Option<(u32, u64, u64)> {
+    let mut cursor = std::io::Cursor::new(fields);
+    let crc32 = cursor.read_u32::<LittleEndian>().ok()?;
+    let (compressed, uncompressed) = if zip64 {
+        (
+            cursor.read_u64::<LittleEndian>().ok()?,
+            cursor.read_u64::<LittleEndian>().ok()?,
+        )
+    } else {
+        (
+            cursor.read_u32::<LittleEndian>().ok()? as u64,
+            cursor.read_u32::<LittleEndian>().ok()? as u64,
+        )
+    };
+    Some((crc32, compressed, uncompressed))
+}
+
+/// Reject an entry that needs a feature this implementation lacks.
+///
+/// # Arguments
+///
+/// * `entry` - The entry about to be decoded
+///
+/// # Errors
+///
+/// Returns [`ZipError::UnsupportedFeature`] naming the feature.
+fn check_supported(entry: &ZipFileEntry) -> Result<()> {
+    if let Some(feature) = entry.unsupported_feature() {
+        bail!(ZipError::UnsupportedFeature {
+            name: entry.file_name.clone(),
+            feature,
+            version_needed: entry.version_needed,
+        });
+    }
+    Ok(())
+}
+
+/// Build the error for decoding an entry with an unsupported method.
+fn unsupported_method(entry: &ZipFileEntry, method: CompressionMethod) -> anyhow::Error {
+    let id = method.as_u16();
+    match method.registered_name() {
+        Some(name) if method.is_obsolete() => anyhow::anyhow!(
+            "Compression method {} ({}) is obsolete and unsupported for {}",
+            id,
+            name,
+            entry.file_name
+        ),
+        Some(name) => anyhow::anyhow!(
+            "Unsupported compression method {} ({}) for {}",
+            id,
+            name,
+            entry.file_name
+        ),
+        None => anyhow::anyhow!(
+            "Unsupported compression method {} for {}",
+            id,
+            entry.file_name
+        ),
     }
 }
@@ -0,0 +1,313 @@
+//! Restoration of file metadata recorded in the archive.
+//!
+//! Besides file data, archives may record Unix permissions, modification
+//! times, owners and symbolic links. [`Preserve`] selects which of these
+//! [`ZipExtractor::extract_to_file`](super::ZipExtractor::extract_to_file)
+//! restores, trading faithfulness for portability.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let preserve: Preserve = "perms,times,owner".parse()?;
+//! let extractor = ZipExtractor::new(reader).with_preserve(preserve);
+//! ```
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Result, bail};
+
+use super::structures::ZipFileEntry;
+
+/// Which metadata to restore when extracting files.
+///
+/// The default restores permissions, modification times and symbolic
+/// links, which is what `unzip` does for an unprivileged user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preserve {
+    /// Restore Unix permission bits (Unix only)
+    pub perms: bool,
+    /// Restore modification times
+    pub times: bool,
+    /// Restore owner user and group IDs (Unix only, usually needs root)
+    pub owner: bool,
+    /// Create symbolic links for symlink entries (Unix only)
+    pub links: bool,
+}
+
+impl Preserve {
+    /// Restore everything the archive records.
+    pub const ALL: Self = Self {
+        perms: true,
+        times: true,
+        owner: true,
+        links: true,
+    };
+
+    /// Restore nothing; files get default permissions and the current time.
+    pub const NONE: Self = Self {
+        perms: false,
+        times: false,
+        owner: false,
+        links: false,
+    };
+}
+
+impl Default for Preserve {
+    fn default() -> Self {
+        Self {
+            perms: true,
+            times: true,
+            owner: false,
+            links: true,
+        }
+    }
+}
+
+impl FromStr for Preserve {
+    type Err = anyhow::Error;
+
+    /// Parse `all`, `none`, or a comma-separated list of `perms`, `times`,
+    /// `owner` and `links`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "all" => return Ok(Self::ALL),
+            "none" => return Ok(Self::NONE),
+            _ => {}
+        }
+
+        let mut preserve = Self::NONE;
+        for facet in s.split(',').map(str::trim) {
+            match facet {
+                "perms" => preserve.perms = true,
+                "times" => preserve.times = true,
+                "owner" => preserve.owner = true,
+                "links" => preserve.links = true,
+                _ => bail!(
+                    "Unknown preserve option '{}' (expected all, none, or perms,times,owner,links)",
+                    facet
+                ),
+            }
+        }
+        Ok(preserve)
+    }
+}
+
+impl fmt::Display for Preserve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let facets: Vec<&str> = [
+            (self.perms, "perms"),
+            (self.times, "times"),
+            (self.owner, "owner"),
+            (self.links, "links"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| *name)
+        .collect();
+
+        if facets.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", facets.join(","))
+        }
+    }
+}
+
+/// Check that a symlink target stays inside the extraction directory.
+///
+/// Rejects absolute targets and targets that climb above the archive
+/// root with `..`, so a link can't be used to write outside the output
+/// directory.
+///
+/// # Arguments
+///
+/// * `entry` - The symlink entry
+/// * `target` - The link target stored as the entry's data
+///
+/// # Errors
+///
+/// Returns an error if the target escapes the extraction directory.
+#[cfg(unix)]
+pub(crate) fn check_link_target(entry: &ZipFileEntry, target: &str) -> Result<()> {
+    // Depth of the directory containing the link, within the archive
+    let mut depth = Path::new(&entry.file_name)
+        .parent()
+        .map(|p| p.components().count() as i64)
+        .unwrap_or(0);
+
+    use std::path::Component;
+
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth -= 1;
+                if depth < 0 {
+                    bail!(
+                        "Refusing symlink {} -> {}: target escapes the extraction directory",
+                        entry.file_name,
+                        target
+                    );
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => bail!(
+                "Refusing symlink {} -> {}: absolute target",
+                entry.file_name,
+                target
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Check that no existing component of an output path is a reparse point.
+///
+/// On Windows, an NTFS junction or symbolic link planted inside the
+/// output directory would redirect writes below it to anywhere on the
+/// disk, even though the path itself passed the Zip Slip check. The
+/// root itself is trusted: the user chose it.
+///
+/// # Arguments
+///
+/// * `root` - The output directory
+/// * `relative` - The sanitized path below it
+///
+/// # Errors
+///
+/// Returns an error naming the first component that is a reparse point.
+#[cfg(windows)]
+pub(crate) fn check_reparse_points(root: &Path, relative: &Path) -> Result<()> {
+    use std::os::windows::fs::MetadataExt;
+
+    /// `FILE_ATTRIBUTE_REPARSE_POINT`, set on junctions and symlinks
+    const REPARSE_POINT: u32 = 0x400;
+
+    let mut path = root.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        match std::fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_attributes() & REPARSE_POINT != 0 => bail!(
+                "Refusing to extract through junction or symbolic link {}",
+                path.display()
+            ),
+            Ok(_) => {}
+            // Nothing below a missing component exists yet
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
+
+/// Restore the selected metadata on an extracted file or directory.
+///
+/// Facets the archive doesn't record for the entry are left alone.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the file was extracted from
+/// * `path` - The extracted file
+/// * `preserve` - Which metadata to restore
+///
+/// # Errors
+///
+/// Returns an error if the permissions or modification time cannot be
+/// set. Failing to change the owner is ignored, as it normally requires
+/// root privileges.
+pub(crate) fn restore(entry: &ZipFileEntry, path: &Path, preserve: Preserve) -> Result<()> {
+    // Set the time first: read-only permissions would prevent it
+    if preserve.times
+        && let Some(mtime) = entry.modified_unix()
+    {
+        let time = if mtime >= 0 {
+            UNIX_EPOCH + Duration::from_secs(mtime as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(mtime.unsigned_abs())
+        };
+        set_modified(path, time)?;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if preserve.owner && (entry.unix_uid.is_some() || entry.unix_gid.is_some()) {
+            let _ = std::os::unix::fs::chown(path, entry.unix_uid, entry.unix_gid);
+        }
+
+        // Only permission bits; setuid/setgid/sticky are not restored
+        if preserve.perms
+            && let Some(mode) = entry.unix_mode()
+        {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o777))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a directory and any missing parents.
+///
+/// With a mode, every directory this call creates gets exactly that
+/// mode, regardless of the umask; directories that already exist are
+/// left alone. The mode is applied deepest first, so a mode without
+/// search permission doesn't lock out the directories below.
+///
+/// # Arguments
+///
+/// * `path` - The directory to create
+/// * `mode` - Permission bits for new directories (Unix only)
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be created or its mode set.
+pub(crate) async fn create_dirs(path: &Path, mode: Option<u32>) -> Result<()> {
+    let Some(mode) = mode else {
+        tokio::fs::create_dir_all(path).await?;
+        return Ok(());
+    };
+
+    let mut missing = Vec::new();
+    for dir in path.ancestors() {
+        if dir.as_os_str().is_empty() || tokio::fs::try_exists(dir).await? {
+            break;
+        }
+        missing.push(dir);
+    }
+    tokio::fs::create_dir_all(path).await?;
+
+    #[cfg(unix)]
+    for dir in missing {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(mode))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (missing, mode);
+
+    Ok(())
+}
+
+/// Set a file's modification time.
+///
+/// # Arguments
+///
+/// * `path` - The file to update
+/// * `time` - The new modification time
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or updated.
+fn set_modified(path: &Path, time: SystemTime) -> Result<()> {
+    // Directories can't be opened for writing; as their owner, a
+    // read-only handle is enough
+    let file = if path.is_dir() {
+        std::fs::File::open(path)?
+    } else {
+        std::fs::File::options().write(true).open(path)?
+    };
+    file.set_modified(time)?;
+    Ok(())
+}
@@ -5,11 +5,21 @@
 //!
 //! ## Architecture
 //!
-//! The module is organized into three main components:
+//! The module is organized into the following components:
 //!
+//! - [`cancel`]: Timeouts and cancellation for listings
+//! - [`charset`]: Decoding of entry names that aren't flagged as UTF-8
+//! - [`datetime`]: MS-DOS modification dates and times
 //! - [`structures`]: Data structures representing ZIP format elements (EOCD, file headers, etc.)
 //! - [`parser`]: Low-level parsing of ZIP structures from raw bytes
 //! - [`extractor`]: High-level extraction API for end users
+//! - [`report`]: Per-entry results of an extraction run
+//! - [`metadata`]: Restoration of permissions, times, owners and symlinks
+//! - [`salvage`]: Best-effort recovery of entries from damaged archives
+//! - [`aes`]: Decryption of WinZip AES-encrypted entries
+//! - [`zipcrypto`]: Decryption of traditional PKWARE-encrypted entries
+//! - [`error`]: Typed errors for conditions callers may want to detect
+//! - [`writer`]: Creation of archives, for repacking transformed entries
 //!
 //! ## ZIP Format Overview
 //!
@@ -28,17 +38,39 @@
 //! - ZIP64 extensions for files > 4GB
 //! - STORED (no compression) method
 //! - DEFLATE compression method
+//! - WinZip AES encryption (AE-1 and AE-2)
+//! - Traditional PKWARE encryption (ZipCrypto)
 //!
 //! ## Limitations
 //!
-//! - No encryption support
+//! - No PKWARE strong encryption support
 //! - No multi-disk archive support
 //! - No BZIP2, LZMA, or other compression methods
 
+mod aes;
+mod cancel;
+mod charset;
+mod datetime;
+mod error;
 mod extractor;
+mod metadata;
 mod parser;
+mod recode;
+mod report;
+mod salvage;
 mod structures;
+mod tar;
+mod writer;
+mod zipcrypto;
 
-pub use extractor::ZipExtractor;
-pub use parser::ZipParser;
+pub use cancel::{CancelToken, ListOptions};
+pub use charset::Charset;
+pub use datetime::DosDateTime;
+pub use error::ZipError;
+pub use extractor::{ExtractedData, ZipExtractor, safe_join, sanitize_path};
+pub use metadata::Preserve;
+pub use parser::{CentralDirectoryEntries, ZipParser};
+pub use recode::Recode;
+pub use report::{EntryOutcome, EntryReport, ExtractReport, ExtractSummary, SkipReason};
 pub use structures::*;
+pub use writer::{FileOptions, ZipWriter};
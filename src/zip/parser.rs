@@ -16,11 +16,17 @@
 
 use byteorder::{LittleEndian, ReadBytesExt};
 use std::io::{Cursor, Read};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use crate::io::ReadAt;
-use anyhow::{Result, bail};
+use crate::warning::{Warning, Warnings};
+use anyhow::{Result, anyhow, bail};
 
+use super::cancel::{CancelToken, ListOptions};
+use super::charset::Charset;
+use super::error::ZipError;
+use super::salvage;
 use super::structures::*;
 
 /// Maximum ZIP comment size allowed by the format (65535 bytes).
@@ -28,6 +34,45 @@ use super::structures::*;
 /// This limits the search area when looking for EOCD with a comment.
 const MAX_COMMENT_SIZE: u64 = 65535;
 
+/// How many bytes the Central Directory may exceed its declared size by.
+///
+/// The directory is read up to the end record rather than trusting
+/// `cd_size`, but only this much beyond the declared size, so a bogus
+/// directory offset can't make us read a huge region of the file.
+const CD_SIZE_SLACK: u64 = 64 * 1024;
+
+/// Largest extensible data sector accepted in a ZIP64 End of Central
+/// Directory record (64 KiB).
+///
+/// The sector only matters to detect Central Directory encryption, whose
+/// parameters take a few dozen bytes, so a larger size field is treated
+/// as malformed instead of being read.
+const MAX_EOCD64_EXTENSIBLE: u64 = 64 * 1024;
+
+/// Largest gap between Local File Headers that is read through rather
+/// than split into a separate request when batching (64 KiB).
+///
+/// Over HTTP, fetching a few extra kilobytes is much cheaper than
+/// another round trip.
+const MAX_BATCH_GAP: u64 = 64 * 1024;
+
+/// Largest span of the archive read at once when batching Local File
+/// Headers (1 MiB).
+///
+/// The span includes the file data between the headers, so without a
+/// cap an archive of many small files would be read whole.
+const MAX_BATCH_SPAN: u64 = 1024 * 1024;
+
+/// Most bytes before the ZIP64 locator searched for a ZIP64 end record
+/// that prepended data moved away from its recorded offset (64 KiB).
+const ZIP64_EOCD_SEARCH: u64 = 64 * 1024;
+
+/// Default limit on the length of entry names in bytes.
+///
+/// Far beyond any real path, but keeps hostile archives from handing
+/// 64 KiB names to the filesystem and to every consumer of the listing.
+const DEFAULT_MAX_NAME_LENGTH: usize = 4096;
+
 /// Low-level ZIP file parser.
 ///
 /// This struct handles reading and parsing ZIP structures from
@@ -52,8 +97,47 @@ const MAX_COMMENT_SIZE: u64 = 65535;
 pub struct ZipParser<R: ReadAt> {
     /// The underlying data source
     reader: Arc<R>,
-    /// Total size of the archive in bytes
+    /// Total size of the source in bytes
     size: u64,
+    /// Offset of the archive in the source, added to the offsets the
+    /// archive records
+    base_offset: u64,
+    /// Length of data before the archive that its offsets don't account
+    /// for, detected while locating the Central Directory
+    prepended: AtomicU64,
+    /// Number of bytes at the end of the file searched for the EOCD
+    eocd_search_window: u64,
+    /// Warnings recorded since the caller last drained them
+    warnings: Warnings,
+    /// Character set for names without the UTF-8 flag
+    charset: Charset,
+    /// Longest entry name accepted, in bytes
+    max_name_length: usize,
+    /// Leave out entries with longer names instead of failing
+    skip_long_names: bool,
+    /// Central Directory location, once found
+    location: OnceLock<DirectoryLocation>,
+    /// The end of the archive, once prefetched: its offset and bytes
+    tail: OnceLock<(u64, Vec<u8>)>,
+    /// Number of metadata reads served from the prefetched tail
+    tail_hits: AtomicU64,
+}
+
+/// Where the Central Directory lies, as resolved from the (ZIP64) EOCD.
+#[derive(Debug, Clone, Copy)]
+struct DirectoryLocation {
+    /// Offset of the first Central Directory File Header
+    cd_offset: u64,
+    /// Central Directory size declared by the end record
+    cd_size: u64,
+    /// Number of entries declared by the end record
+    total_entries: u64,
+    /// Offset of the first end record, where the directory ends
+    cd_end: u64,
+    /// Offset of the archive comment, just after the EOCD record
+    comment_offset: u64,
+    /// Length of the archive comment in bytes
+    comment_len: u16,
 }
 
 impl<R: ReadAt> ZipParser<R> {
@@ -68,7 +152,156 @@ impl<R: ReadAt> ZipParser<R> {
     /// A new parser instance ready to read the archive.
     pub fn new(reader: Arc<R>) -> Self {
         let size = reader.size();
-        Self { reader, size }
+        Self {
+            reader,
+            size,
+            base_offset: 0,
+            prepended: AtomicU64::new(0),
+            eocd_search_window: MAX_COMMENT_SIZE + EndOfCentralDirectory::SIZE as u64,
+            warnings: Warnings::default(),
+            charset: Charset::default(),
+            max_name_length: DEFAULT_MAX_NAME_LENGTH,
+            skip_long_names: false,
+            location: OnceLock::new(),
+            tail: OnceLock::new(),
+            tail_hits: AtomicU64::new(0),
+        }
+    }
+
+    /// Remove and return the warnings recorded since the last call.
+    ///
+    /// Includes warnings from the underlying reader, such as retries.
+    ///
+    /// # Returns
+    ///
+    /// The warnings in the order they were recorded.
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        let mut warnings = self.warnings.take();
+        warnings.extend(self.reader.take_warnings());
+        warnings
+    }
+
+    /// Set how many bytes at the end of the file are searched for the EOCD.
+    ///
+    /// The default covers the largest possible archive comment. A smaller
+    /// window reduces the tail read over HTTP when archives are known to
+    /// have short or no comments; a larger one can find an EOCD followed
+    /// by appended data. The window is capped at the file size.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - Search window in bytes, at least the EOCD size (22)
+    ///
+    /// # Returns
+    ///
+    /// The parser with the new window.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the window is smaller than an EOCD record.
+    pub fn with_eocd_search_window(mut self, window: u64) -> Result<Self> {
+        if window < EndOfCentralDirectory::SIZE as u64 {
+            bail!(
+                "EOCD search window must be at least {} bytes, got {}",
+                EndOfCentralDirectory::SIZE,
+                window
+            );
+        }
+        self.eocd_search_window = window.min(self.size);
+        // A different window may find a different end record
+        self.location = OnceLock::new();
+        Ok(self)
+    }
+
+    /// Read an archive embedded at a known offset in a larger source.
+    ///
+    /// Offsets recorded in the archive (of the Central Directory, the
+    /// ZIP64 end record and each Local File Header) count from the
+    /// archive's first byte; the base offset is added to them. The end
+    /// record is searched for between the base offset and the end of the
+    /// source. Offsets in the returned entries
+    /// ([`lfh_offset`](ZipFileEntry::lfh_offset),
+    /// [`cdfh_offset`](ZipFileEntry::cdfh_offset)) are positions in the
+    /// source.
+    ///
+    /// Data simply prepended to an archive, like a self-extractor stub,
+    /// doesn't need this: the shift is detected from where the Central
+    /// Directory or ZIP64 end record actually lies, for regular and ZIP64
+    /// archives alike, and reported as [`Warning::PrependedData`].
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - Where the archive starts in the source
+    ///
+    /// # Returns
+    ///
+    /// The parser reading the archive at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offset lies beyond the end of the source.
+    pub fn with_base_offset(mut self, offset: u64) -> Result<Self> {
+        if offset > self.size {
+            bail!(
+                "Base offset {} lies beyond the end of the source ({} bytes)",
+                offset,
+                self.size
+            );
+        }
+        self.base_offset = offset;
+        self.prepended = AtomicU64::new(0);
+        self.location = OnceLock::new();
+        Ok(self)
+    }
+
+    /// Set the character set used for names without the UTF-8 flag
+    /// (default: CP437).
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - The character set to decode names with
+    ///
+    /// # Returns
+    ///
+    /// The parser with the new character set.
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Set the longest entry name accepted, in bytes (default: 4096).
+    ///
+    /// Parsing a Central Directory record with a longer name fails with
+    /// [`ZipError::NameTooLong`], unless
+    /// [`with_skip_long_names()`](Self::with_skip_long_names) is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `max` - Maximum name length in bytes, as stored in the archive
+    ///
+    /// # Returns
+    ///
+    /// The parser with the new limit.
+    pub fn with_max_name_length(mut self, max: usize) -> Self {
+        self.max_name_length = max;
+        self
+    }
+
+    /// Leave entries with overlong names out of the listing instead of
+    /// failing (default: `false`).
+    ///
+    /// Each skipped entry is recorded as [`Warning::LongNameSkipped`].
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - `true` to skip such entries
+    ///
+    /// # Returns
+    ///
+    /// The parser with the new policy.
+    pub fn with_skip_long_names(mut self, skip: bool) -> Self {
+        self.skip_long_names = skip;
+        self
     }
 
     /// Find and parse the End of Central Directory record.
@@ -77,6 +310,13 @@ impl<R: ReadAt> ZipParser<R> {
     /// handles both the simple case (no comment) and archives with
     /// comments by searching backwards for the signature.
     ///
+    /// Candidates whose comment length exactly reaches the end of the
+    /// file are preferred. If none of them is usable (e.g. the source has
+    /// extra bytes appended after the archive, as some servers and
+    /// concatenated blobs do), the latest candidate whose Central
+    /// Directory parses cleanly is used instead: it must lie within the
+    /// archive before its end record, and its first record must parse.
+    ///
     /// # Returns
     ///
     /// A tuple of (EOCD record, offset of EOCD in file).
@@ -86,48 +326,224 @@ impl<R: ReadAt> ZipParser<R> {
     /// Returns an error if no valid EOCD can be found, indicating
     /// the file is not a valid ZIP archive.
     pub async fn find_eocd(&self) -> Result<(EndOfCentralDirectory, u64)> {
+        // The archive runs from the base offset to the end of the source
+        let archive_len = self.size - self.base_offset;
+
         // Optimization: First try the simple case where there's no comment.
         // This avoids reading extra data in the common case.
-        if self.size >= EndOfCentralDirectory::SIZE as u64 {
+        if archive_len >= EndOfCentralDirectory::SIZE as u64 {
             let offset = self.size - EndOfCentralDirectory::SIZE as u64;
-            let mut buf = vec![0u8; EndOfCentralDirectory::SIZE];
-            self.reader.read_at(offset, &mut buf).await?;
+            let buf = self
+                .read_metadata(offset, EndOfCentralDirectory::SIZE)
+                .await?;
 
             // Check for signature and zero-length comment
             if &buf[0..4] == EndOfCentralDirectory::SIGNATURE && &buf[20..22] == b"\x00\x00" {
                 let eocd = EndOfCentralDirectory::from_bytes(&buf)?;
-                return Ok((eocd, offset));
+                if let Some(prepended) = self.is_plausible_eocd(&eocd, offset).await? {
+                    self.set_prepended(prepended);
+                    return Ok((eocd, offset));
+                }
             }
         }
 
+        if archive_len < EndOfCentralDirectory::SIZE as u64 {
+            bail!("Not a valid ZIP file");
+        }
+
         // EOCD not at expected location - search for it.
         // The EOCD could be earlier if there's a ZIP comment.
         // We need to search backwards from the end of the file.
-        let search_size = (MAX_COMMENT_SIZE + EndOfCentralDirectory::SIZE as u64).min(self.size);
+        let search_size = self.eocd_search_window.min(archive_len);
         let search_start = self.size - search_size;
 
-        let mut buf = vec![0u8; search_size as usize];
-        self.reader.read_at(search_start, &mut buf).await?;
+        let buf = self
+            .read_metadata(search_start, search_size as usize)
+            .await?;
 
         // Search backwards for EOCD signature (PK\x05\x06)
-        for i in (0..buf.len().saturating_sub(EndOfCentralDirectory::SIZE)).rev() {
+        let mut trailing_junk_candidates = Vec::new();
+        for i in (0..=buf.len().saturating_sub(EndOfCentralDirectory::SIZE)).rev() {
             if &buf[i..i + 4] == EndOfCentralDirectory::SIGNATURE {
-                // Found a potential EOCD - verify the comment length is correct.
-                // The comment length field should match the remaining bytes.
                 let comment_len = u16::from_le_bytes([buf[i + 20], buf[i + 21]]) as usize;
+                let remaining = buf.len() - i - EndOfCentralDirectory::SIZE;
+                let eocd =
+                    EndOfCentralDirectory::from_bytes(&buf[i..i + EndOfCentralDirectory::SIZE])?;
+                let offset = search_start + i as u64;
 
-                if comment_len == buf.len() - i - EndOfCentralDirectory::SIZE {
-                    let eocd = EndOfCentralDirectory::from_bytes(
-                        &buf[i..i + EndOfCentralDirectory::SIZE],
-                    )?;
-                    return Ok((eocd, search_start + i as u64));
+                // Found a potential EOCD - the comment length field should
+                // match the remaining bytes
+                if comment_len == remaining {
+                    if let Some(prepended) = self.is_plausible_eocd(&eocd, offset).await? {
+                        self.set_prepended(prepended);
+                        return Ok((eocd, offset));
+                    }
+                } else if comment_len < remaining {
+                    // Followed by bytes that aren't part of the archive;
+                    // only used if no exact match is found
+                    trailing_junk_candidates.push((eocd, offset));
                 }
             }
         }
 
+        // Fall back to the latest EOCD whose Central Directory checks out
+        for (eocd, offset) in trailing_junk_candidates {
+            if let Some(prepended) = self.is_clean_trailing_eocd(&eocd, offset).await? {
+                self.set_prepended(prepended);
+
+                return Ok((eocd, offset));
+            }
+        }
+
         bail!("Not a valid ZIP file")
     }
 
+    /// Check that an EOCD candidate points at a real Central Directory.
+    ///
+    /// Guards against signature bytes that happen to appear in a comment
+    /// or in data appended after the archive.
+    ///
+    /// Data prepended to an archive without adjusting its offsets, like a
+    /// self-extractor stub, moves the Central Directory away from where
+    /// the EOCD says it is. It then still ends right at the EOCD, so it is
+    /// also looked for there, and the shift is reported.
+    ///
+    /// # Arguments
+    ///
+    /// * `eocd` - The candidate record
+    /// * `eocd_offset` - Offset of the candidate in the file
+    ///
+    /// # Returns
+    ///
+    /// The number of prepended bytes the candidate's offsets don't
+    /// account for (usually 0) if its Central Directory starts with a
+    /// CDFH signature before the EOCD, or `None` if it doesn't. ZIP64
+    /// candidates are always accepted here; their shift is detected by
+    /// [`read_zip64_eocd()`](Self::read_zip64_eocd).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying read fails.
+    async fn is_plausible_eocd(
+        &self,
+        eocd: &EndOfCentralDirectory,
+        eocd_offset: u64,
+    ) -> Result<Option<u64>> {
+        // ZIP64 structures are validated by read_zip64_eocd, which can
+        // report exactly what is wrong with them
+        if eocd.is_zip64() {
+            return Ok(Some(0));
+        }
+
+        // Relative to the base offset only, so the shift found is the same
+        // however often the archive is located
+        let cd_offset = self.base_offset.saturating_add(eocd.cd_offset as u64);
+        if eocd.total_entries == 0 {
+            return Ok((cd_offset <= eocd_offset).then_some(0));
+        }
+        if cd_offset + 4 <= eocd_offset && self.read_metadata(cd_offset, 4).await? == CDFH_SIGNATURE
+        {
+            return Ok(Some(0));
+        }
+
+        // The directory may have been shifted by prepended data
+        let Some(actual) = eocd_offset.checked_sub(eocd.cd_size as u64) else {
+            return Ok(None);
+        };
+        if actual > cd_offset
+            && actual + 4 <= eocd_offset
+            && self.read_metadata(actual, 4).await? == CDFH_SIGNATURE
+        {
+            return Ok(Some(actual - cd_offset));
+        }
+        Ok(None)
+    }
+
+    /// Record that the archive's offsets are shifted by prepended data.
+    ///
+    /// The shift is stored rather than added, so locating the archive
+    /// again (or concurrently) doesn't apply it twice; the warning is
+    /// only recorded when the shift changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `length` - Number of bytes to add to every recorded offset
+    fn set_prepended(&self, length: u64) {
+        if length > 0 && self.prepended.swap(length, Ordering::Relaxed) != length {
+            self.warnings.push(Warning::PrependedData { length });
+        }
+    }
+
+    /// Check an EOCD candidate followed by bytes that aren't part of the
+    /// archive.
+    ///
+    /// Such candidates are only a fallback, and the trailing bytes may
+    /// hold stray signatures, so they are checked more strictly than
+    /// [`is_plausible_eocd()`](Self::is_plausible_eocd) does: the (ZIP64)
+    /// end records must be valid, the Central Directory must fit between
+    /// its offset and the end record, and its first record must parse.
+    ///
+    /// # Arguments
+    ///
+    /// * `eocd` - The candidate record
+    /// * `eocd_offset` - Offset of the candidate in the file
+    ///
+    /// # Returns
+    ///
+    /// The number of prepended bytes the candidate's offsets don't
+    /// account for if the candidate is usable, or `None` if it isn't.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the underlying read fails.
+    async fn is_clean_trailing_eocd(
+        &self,
+        eocd: &EndOfCentralDirectory,
+        eocd_offset: u64,
+    ) -> Result<Option<u64>> {
+        let (prepended, cd_offset, cd_size, cd_end, total_entries) = if eocd.is_zip64() {
+            // Malformed ZIP64 records just disqualify the candidate; valid
+            // ones record their shift themselves
+            let Ok((eocd64, eocd64_offset)) = self.read_zip64_eocd(eocd_offset).await else {
+                return Ok(None);
+            };
+            let prepended = self.prepended.load(Ordering::Relaxed);
+            (
+                prepended,
+                eocd64.cd_offset,
+                eocd64.cd_size,
+                eocd64_offset,
+                eocd64.total_entries,
+            )
+        } else {
+            let Some(prepended) = self.is_plausible_eocd(eocd, eocd_offset).await? else {
+                return Ok(None);
+            };
+            (
+                prepended,
+                eocd.cd_offset as u64,
+                eocd.cd_size as u64,
+                eocd_offset,
+                eocd.total_entries as u64,
+            )
+        };
+
+        let cd_offset = self
+            .base_offset
+            .saturating_add(prepended)
+            .saturating_add(cd_offset);
+        if cd_offset
+            .checked_add(cd_size)
+            .is_none_or(|end| end > cd_end)
+        {
+            return Ok(None);
+        }
+        if total_entries > 0 && self.parse_cdfh_at(cd_offset).await.is_err() {
+            return Ok(None);
+        }
+        Ok(Some(prepended))
+    }
+
     /// Read the ZIP64 End of Central Directory record.
     ///
     /// Called when the regular EOCD indicates ZIP64 extensions are needed
@@ -139,28 +555,182 @@ impl<R: ReadAt> ZipParser<R> {
     ///
     /// # Returns
     ///
-    /// The parsed ZIP64 EOCD with 64-bit field values.
+    /// A tuple of (ZIP64 EOCD with 64-bit field values, offset of ZIP64 EOCD in file).
     ///
     /// # Errors
     ///
-    /// Returns an error if the ZIP64 structures are missing or invalid.
-    pub async fn read_zip64_eocd(&self, eocd_offset: u64) -> Result<Zip64EOCD> {
-        // The ZIP64 EOCD Locator is located immediately before the regular EOCD
-        let locator_offset = eocd_offset - Zip64EOCDLocator::SIZE as u64;
-        let mut locator_buf = vec![0u8; Zip64EOCDLocator::SIZE];
-        self.reader
-            .read_at(locator_offset, &mut locator_buf)
+    /// Returns [`ZipError::MalformedZip64`] if the ZIP64 structures are
+    /// missing, have invalid signatures, point outside the archive or give
+    /// an oversized record, and [`ZipError::UnsupportedFeature`] for a
+    /// version 2 record, whose Central Directory is encrypted.
+    pub async fn read_zip64_eocd(&self, eocd_offset: u64) -> Result<(Zip64EOCD, u64)> {
+        // The ZIP64 EOCD Locator is located immediately before the regular EOCD,
+        // so there must be room for it at the start of the file
+        let Some(locator_offset) = eocd_offset.checked_sub(Zip64EOCDLocator::SIZE as u64) else {
+            bail!(ZipError::MalformedZip64(
+                "no room for ZIP64 locator before End of Central Directory".to_string()
+            ));
+        };
+
+        let locator_buf = self
+            .read_metadata(locator_offset, Zip64EOCDLocator::SIZE)
             .await?;
 
+        if &locator_buf[0..4] != Zip64EOCDLocator::SIGNATURE {
+            bail!(ZipError::MalformedZip64(
+                "ZIP64 locator not found before End of Central Directory".to_string()
+            ));
+        }
         let locator = Zip64EOCDLocator::from_bytes(&locator_buf)?;
+        // Relative to the base offset only, like in is_plausible_eocd()
+        let eocd64_offset = self.base_offset.saturating_add(locator.eocd64_offset);
+
+        // The ZIP64 EOCD must lie entirely before its locator
+        if eocd64_offset
+            .checked_add(Zip64EOCD::MIN_SIZE as u64)
+            .is_none_or(|end| end > locator_offset)
+        {
+            bail!(ZipError::MalformedZip64(format!(
+                "ZIP64 End of Central Directory offset {} is out of bounds",
+                locator.eocd64_offset
+            )));
+        }
 
         // Read the actual ZIP64 EOCD from the offset specified in the locator
-        let mut eocd64_buf = vec![0u8; Zip64EOCD::MIN_SIZE];
-        self.reader
-            .read_at(locator.eocd64_offset, &mut eocd64_buf)
+        let mut eocd64_offset = eocd64_offset;
+        let mut eocd64_buf = self
+            .read_metadata(eocd64_offset, Zip64EOCD::MIN_SIZE)
             .await?;
 
-        Zip64EOCD::from_bytes(&eocd64_buf)
+        if &eocd64_buf[0..4] != Zip64EOCD::SIGNATURE {
+            let Some(actual) = self
+                .find_shifted_zip64_eocd(eocd64_offset, locator_offset)
+                .await?
+            else {
+                bail!(ZipError::MalformedZip64(format!(
+                    "no ZIP64 End of Central Directory signature at offset {}",
+                    eocd64_offset
+                )));
+            };
+            self.set_prepended(actual - eocd64_offset);
+            eocd64_offset = actual;
+            eocd64_buf = self
+                .read_metadata(eocd64_offset, Zip64EOCD::MIN_SIZE)
+                .await?;
+        }
+
+        let mut eocd64 = Zip64EOCD::from_bytes(&eocd64_buf)?;
+
+        // The size field counts everything after itself, which can include
+        // an extensible data sector; the whole record must still fit
+        // before the locator
+        let record_len = eocd64.record_len();
+        if record_len < Zip64EOCD::MIN_SIZE as u64 {
+            bail!(ZipError::MalformedZip64(format!(
+                "ZIP64 End of Central Directory size {} is too small",
+                eocd64.eocd64_size
+            )));
+        }
+        if record_len > Zip64EOCD::MIN_SIZE as u64 + MAX_EOCD64_EXTENSIBLE {
+            bail!(ZipError::MalformedZip64(format!(
+                "ZIP64 End of Central Directory size {} is too large",
+                eocd64.eocd64_size
+            )));
+        }
+        if eocd64_offset
+            .checked_add(record_len)
+            .is_none_or(|end| end > locator_offset)
+        {
+            bail!(ZipError::MalformedZip64(format!(
+                "ZIP64 End of Central Directory size {} runs past its locator",
+                eocd64.eocd64_size
+            )));
+        }
+        if record_len > Zip64EOCD::MIN_SIZE as u64 {
+            let record = self
+                .read_metadata(eocd64_offset, record_len as usize)
+                .await?;
+            eocd64 = Zip64EOCD::from_bytes(&record)?;
+        }
+
+        if eocd64.is_v2() {
+            bail!(ZipError::UnsupportedFeature {
+                name: "The archive".to_string(),
+                feature: "Central Directory encryption",
+                version_needed: eocd64.version_needed,
+            });
+        }
+
+        Ok((eocd64, eocd64_offset))
+    }
+
+    /// Look for a ZIP64 end record moved by data prepended to the archive.
+    ///
+    /// The record still ends right at its locator, so the bytes before the
+    /// locator are searched for a signature whose record size reaches
+    /// exactly to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `recorded` - Where the locator says the record is
+    /// * `locator_offset` - Offset of the locator
+    ///
+    /// # Returns
+    ///
+    /// The offset of the record, if one is found after `recorded`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails.
+    async fn find_shifted_zip64_eocd(
+        &self,
+        recorded: u64,
+        locator_offset: u64,
+    ) -> Result<Option<u64>> {
+        // The caller checked that a whole record fits after `recorded`
+        let span = (locator_offset - recorded).min(ZIP64_EOCD_SEARCH);
+        let start = locator_offset - span;
+        let buf = self.read_metadata(start, span as usize).await?;
+
+        for i in (0..=buf.len() - Zip64EOCD::MIN_SIZE).rev() {
+            if &buf[i..i + 4] != Zip64EOCD::SIGNATURE {
+                continue;
+            }
+            let size = u64::from_le_bytes(buf[i + 4..i + 12].try_into()?);
+            let offset = start + i as u64;
+            if offset > recorded
+                && size.checked_add(Zip64EOCD::HEADER_SIZE as u64) == Some(locator_offset - offset)
+            {
+                return Ok(Some(offset));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Recover entries from a damaged archive without its Central Directory.
+    ///
+    /// Scans the whole archive for Local File Headers and rebuilds each
+    /// entry from its header. This is best-effort: see the
+    /// [`salvage`](super::salvage) module for what can and can't be
+    /// recovered. Headers that can't be recovered are recorded as
+    /// [`Warning::SalvageFailed`].
+    ///
+    /// # Returns
+    ///
+    /// The recovered entries, in archive order. Their `cdfh_offset`,
+    /// `version_made_by` and `external_attrs` are zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be read.
+    pub async fn salvage_entries(&self) -> Result<Vec<ZipFileEntry>> {
+        salvage::salvage_entries(
+            self.reader.as_ref(),
+            self.size,
+            self.charset,
+            &self.warnings,
+        )
+        .await
     }
 
     /// List all files in the ZIP archive.
@@ -178,36 +748,389 @@ impl<R: ReadAt> ZipParser<R> {
     ///
     /// Returns an error if the archive is invalid or cannot be read.
     pub async fn list_files(&self) -> Result<Vec<ZipFileEntry>> {
+        self.list_files_with(ListOptions::default()).await
+    }
+
+    /// List all files in the ZIP archive, bounded by a timeout and a
+    /// cancellation token.
+    ///
+    /// Reads like [`list_files()`](Self::list_files). Pending reads are
+    /// abandoned as soon as the token is cancelled or the timeout passes,
+    /// and the token is also checked between parsed entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The timeout and cancellation token
+    ///
+    /// # Returns
+    ///
+    /// A vector of [`ZipFileEntry`] structures, one for each file/directory
+    /// in the archive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The archive is invalid or cannot be read
+    /// - The listing takes longer than the timeout ([`ZipError::ListTimeout`])
+    /// - The token is cancelled ([`ZipError::Cancelled`])
+    pub async fn list_files_with(&self, options: ListOptions) -> Result<Vec<ZipFileEntry>> {
+        let cancel = options.cancel.as_ref();
+        let listing = async {
+            let mut files = Vec::new();
+            for entry in self.entries().await? {
+                if cancel.is_some_and(CancelToken::is_cancelled) {
+                    bail!(ZipError::Cancelled);
+                }
+                files.push(entry?);
+            }
+            Ok(files)
+        };
+        let cancellable = async {
+            let Some(cancel) = cancel else {
+                return listing.await;
+            };
+            tokio::select! {
+                result = listing => result,
+                () = cancel.cancelled() => Err(ZipError::Cancelled.into()),
+            }
+        };
+
+        match options.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, cancellable)
+                .await
+                .unwrap_or_else(|_| Err(ZipError::ListTimeout { timeout }.into())),
+            None => cancellable.await,
+        }
+    }
+
+    /// Read the Central Directory and return a lazy stream of its entries.
+    ///
+    /// The Central Directory is fetched in a single read, but each
+    /// Central Directory File Header is only parsed when the stream is
+    /// advanced. Callers that only need the first few entries can stop
+    /// early without paying for parsing the rest.
+    ///
+    /// The declared Central Directory size is treated as a hint: the
+    /// directory is read up to the start of the (ZIP64) EOCD record and
+    /// records are parsed until a non-CDFH signature is reached. A warning
+    /// is printed if the declared size or entry count doesn't match; a
+    /// directory more than 64 KiB larger than declared is an error.
+    ///
+    /// # Returns
+    ///
+    /// An iterator yielding one [`ZipFileEntry`] per Central Directory record.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let entries = parser.entries().await?;
+    /// let total = entries.total_entries();
+    /// for entry in entries.take(10) {
+    ///     println!("{}", entry?.file_name);
+    /// }
+    /// ```
+    pub async fn entries(&self) -> Result<CentralDirectoryEntries<'_, R>> {
+        let location = self.locate().await?;
+        let cd_data = self.read_directory(&location).await?;
+        if cd_data.len() as u64 != location.cd_size {
+            self.warnings.push(Warning::CentralDirectorySizeMismatch {
+                declared: location.cd_size,
+                actual: cd_data.len() as u64,
+            });
+        }
+
+        let DirectoryLocation {
+            cd_offset,
+            total_entries,
+            ..
+        } = location;
+        Ok(CentralDirectoryEntries {
+            parser: self,
+            cd_offset,
+            cursor: Cursor::new(cd_data),
+            parsed: 0,
+            done: false,
+            total_entries,
+        })
+    }
+
+    /// Read the raw bytes of the Central Directory.
+    ///
+    /// The directory is read up to the start of the (ZIP64) EOCD record,
+    /// as for [`entries()`](Self::entries).
+    ///
+    /// # Returns
+    ///
+    /// The Central Directory File Headers, unparsed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    pub async fn central_directory(&self) -> Result<Vec<u8>> {
+        let location = self.locate().await?;
+        self.read_directory(&location).await
+    }
+
+    /// Read the Central Directory at a resolved location.
+    ///
+    /// # Arguments
+    ///
+    /// * `location` - Where the directory lies
+    ///
+    /// # Returns
+    ///
+    /// The bytes between the directory's start and the end record.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory starts after the end record, is
+    /// far larger than declared, or cannot be read.
+    async fn read_directory(&self, location: &DirectoryLocation) -> Result<Vec<u8>> {
+        // Some archivers write a slightly wrong cd_size, so read everything
+        // between the Central Directory start and the end record instead
+        let Some(available) = location.cd_end.checked_sub(location.cd_offset) else {
+            bail!(
+                "Central Directory offset {} is beyond the End of Central Directory",
+                location.cd_offset
+            );
+        };
+        if available > location.cd_size.saturating_add(CD_SIZE_SLACK) {
+            bail!(
+                "Central Directory size is {} bytes but {} bytes precede the end record",
+                location.cd_size,
+                available
+            );
+        }
+
+        // Read the entire Central Directory in one request
+        // (efficient for HTTP as it's a single Range request)
+        self.read_metadata(location.cd_offset, available as usize)
+            .await
+    }
+
+    /// Read the archive comment stored after the End of Central Directory
+    /// record.
+    ///
+    /// The comment has no UTF-8 flag of its own, so it is decoded with
+    /// the configured character set (see [`with_charset`](Self::with_charset)).
+    ///
+    /// # Returns
+    ///
+    /// The archive comment, or an empty string if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    pub async fn archive_comment(&self) -> Result<String> {
+        let location = self.locate().await?;
+        let bytes = self
+            .read_metadata(location.comment_offset, location.comment_len as usize)
+            .await?;
+        Ok(self.charset.decode(&bytes, 0))
+    }
+
+    /// Read the archive comment with a single read of the end of the
+    /// archive.
+    ///
+    /// Unlike [`archive_comment()`](Self::archive_comment), this doesn't
+    /// check that the End of Central Directory record points at a real
+    /// Central Directory or follow ZIP64 records, so no further reads are
+    /// made. The last EOCD signature in the search window whose comment
+    /// reaches exactly to the end of the archive is used.
+    ///
+    /// # Returns
+    ///
+    /// The archive comment, or an empty string if there is none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no such EOCD record is found or the read fails.
+    pub async fn tail_comment(&self) -> Result<String> {
+        let archive_len = self.size - self.base_offset;
+        if archive_len < EndOfCentralDirectory::SIZE as u64 {
+            bail!("Not a valid ZIP file");
+        }
+        let search_size = self.eocd_search_window.min(archive_len);
+        let buf = self
+            .read_metadata(self.size - search_size, search_size as usize)
+            .await?;
+
+        for i in (0..=buf.len() - EndOfCentralDirectory::SIZE).rev() {
+            if &buf[i..i + 4] != EndOfCentralDirectory::SIGNATURE {
+                continue;
+            }
+            let comment_len = u16::from_le_bytes([buf[i + 20], buf[i + 21]]) as usize;
+            let comment = &buf[i + EndOfCentralDirectory::SIZE..];
+            if comment.len() == comment_len {
+                return Ok(self.charset.decode(comment, 0));
+            }
+        }
+
+        bail!("Not a valid ZIP file")
+    }
+
+    /// Check that the source is a ZIP archive by locating its Central
+    /// Directory.
+    ///
+    /// Finds the End of Central Directory record and resolves ZIP64
+    /// extensions. The result is cached, so later calls to
+    /// [`entries()`](Self::entries) and [`list_files()`](Self::list_files)
+    /// don't search again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no valid End of Central Directory record is
+    /// found or the ZIP64 structures are malformed.
+    pub async fn validate(&self) -> Result<()> {
+        self.locate().await.map(|_| ())
+    }
+
+    /// Read the end of the archive in one request and serve metadata
+    /// reads from it.
+    ///
+    /// Locates the Central Directory from the prefetched bytes. Later
+    /// reads of the end records, the archive comment and the Central
+    /// Directory itself don't reach the reader if they lie within the
+    /// prefetched range. Calling this again has no effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - Number of bytes to read from the end of the archive
+    ///
+    /// # Returns
+    ///
+    /// The number of reads saved: reads served from the prefetched
+    /// bytes while locating the directory, plus one if the whole Central
+    /// Directory is covered, minus the prefetch itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails or the archive is invalid.
+    pub async fn prefetch_tail(&self, window: u64) -> Result<u64> {
+        if self.tail.get().is_some() {
+            return Ok(0);
+        }
+
+        let len = window.min(self.size);
+        let offset = self.size - len;
+        let data = self.reader.read_vec(offset, len as usize).await?;
+        let (tail_offset, _) = self.tail.get_or_init(|| (offset, data));
+
+        let location = self.locate().await?;
+        let mut hits = self.tail_hits.load(Ordering::Relaxed);
+        if location.cd_offset >= *tail_offset {
+            hits += 1;
+        }
+        Ok(hits.saturating_sub(1))
+    }
+
+    /// Read metadata, from the prefetched tail if it covers the range.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The byte offset to start reading from
+    /// * `len` - The number of bytes to read
+    ///
+    /// # Returns
+    ///
+    /// A vector of exactly `len` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the read fails or the archive ends before
+    /// `len` bytes have been read.
+    async fn read_metadata(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
+        if let Some((tail_offset, tail)) = self.tail.get()
+            && offset >= *tail_offset
+            && offset - tail_offset + len as u64 <= tail.len() as u64
+        {
+            self.tail_hits.fetch_add(1, Ordering::Relaxed);
+            let start = (offset - tail_offset) as usize;
+            return Ok(tail[start..start + len].to_vec());
+        }
+        self.reader.read_vec(offset, len).await
+    }
+
+    /// Find where the Central Directory lies, using the cached location
+    /// if it was already found.
+    ///
+    /// # Returns
+    ///
+    /// The location of the Central Directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is invalid or cannot be read.
+    async fn locate(&self) -> Result<DirectoryLocation> {
+        if let Some(location) = self.location.get() {
+            return Ok(*location);
+        }
+
         // Find and parse the EOCD to get Central Directory location
         let (eocd, eocd_offset) = self.find_eocd().await?;
 
-        // Get Central Directory info, using ZIP64 if needed
-        let (cd_offset, cd_size, total_entries) = if eocd.is_zip64() {
-            let eocd64 = self.read_zip64_eocd(eocd_offset).await?;
-            (eocd64.cd_offset, eocd64.cd_size, eocd64.total_entries)
+        // Get Central Directory info, using ZIP64 if needed.
+        // The directory ends where the first end record begins.
+        let (cd_offset, cd_size, total_entries, cd_end, disks) = if eocd.is_zip64() {
+            let (eocd64, eocd64_offset) = self.read_zip64_eocd(eocd_offset).await?;
+            (
+                self.absolute(eocd64.cd_offset),
+                eocd64.cd_size,
+                eocd64.total_entries,
+                eocd64_offset,
+                (eocd64.disk_number, eocd64.disk_with_cd, eocd64.disk_entries),
+            )
         } else {
+            // Streaming writers such as `zip -` may add ZIP64 records
+            // without marking the EOCD; the directory then ends before them
+            let cd_offset = self.absolute(eocd.cd_offset as u64);
+            let cd_end = match self.read_zip64_eocd(eocd_offset).await {
+                Ok((_, eocd64_offset)) if eocd64_offset >= cd_offset => eocd64_offset,
+                _ => eocd_offset,
+            };
             (
-                eocd.cd_offset as u64,
+                cd_offset,
                 eocd.cd_size as u64,
                 eocd.total_entries as u64,
+                cd_end,
+                (
+                    eocd.disk_number as u32,
+                    eocd.disk_with_cd as u32,
+                    eocd.disk_entries as u64,
+                ),
             )
         };
 
-        // Read the entire Central Directory in one request
-        // (efficient for HTTP as it's a single Range request)
-        let mut cd_data = vec![0u8; cd_size as usize];
-        self.reader.read_at(cd_offset, &mut cd_data).await?;
-
-        // Parse each Central Directory File Header entry
-        let mut entries = Vec::with_capacity(total_entries as usize);
-        let mut cursor = Cursor::new(&cd_data);
-
-        for _ in 0..total_entries {
-            let entry = self.parse_cdfh(&mut cursor)?;
-            entries.push(entry);
+        // Some archivers record a non-zero disk number in single-disk
+        // archives. Only reject the archive as spanned if its directory
+        // isn't entirely on this disk and reachable in the source.
+        let (disk_number, disk_with_cd, disk_entries) = disks;
+        if disk_number != 0 || disk_with_cd != 0 {
+            let complete = disk_with_cd == disk_number
+                && disk_entries == total_entries
+                && cd_offset < cd_end
+                && (total_entries == 0
+                    || self.read_metadata(cd_offset, 4).await? == CDFH_SIGNATURE);
+            if !complete {
+                bail!(ZipError::SpannedArchive { disk: disk_number });
+            }
+            self.warnings
+                .push(Warning::DiskNumberIgnored { disk: disk_number });
         }
 
-        Ok(entries)
+        let location = DirectoryLocation {
+            cd_offset,
+            cd_size,
+            total_entries,
+            cd_end,
+            comment_offset: eocd_offset + EndOfCentralDirectory::SIZE as u64,
+            comment_len: eocd.comment_len,
+        };
+        Ok(*self.location.get_or_init(|| location))
     }
 
     /// Parse a Central Directory File Header from a cursor.
@@ -218,6 +1141,7 @@ impl<R: ReadAt> ZipParser<R> {
     /// # Arguments
     ///
     /// * `cursor` - A cursor positioned at the start of a CDFH
+    /// * `base_offset` - Archive offset of the start of the cursor's data
     ///
     /// # Returns
     ///
@@ -226,7 +1150,9 @@ impl<R: ReadAt> ZipParser<R> {
     /// # Errors
     ///
     /// Returns an error if the header is invalid.
-    fn parse_cdfh(&self, cursor: &mut Cursor<&Vec<u8>>) -> Result<ZipFileEntry> {
+    fn parse_cdfh(&self, cursor: &mut Cursor<Vec<u8>>, base_offset: u64) -> Result<ZipFileEntry> {
+        let cdfh_offset = base_offset + cursor.position();
+
         // Read and verify the signature (PK\x01\x02)
         let mut sig = [0u8; 4];
         cursor.read_exact(&mut sig)?;
@@ -235,9 +1161,9 @@ impl<R: ReadAt> ZipParser<R> {
         }
 
         // Read fixed-size header fields
-        let _version_made_by = cursor.read_u16::<LittleEndian>()?;
-        let _version_needed = cursor.read_u16::<LittleEndian>()?;
-        let _flags = cursor.read_u16::<LittleEndian>()?;
+        let version_made_by = cursor.read_u16::<LittleEndian>()?;
+        let version_needed = cursor.read_u16::<LittleEndian>()?;
+        let flags = cursor.read_u16::<LittleEndian>()?;
         let compression_method = cursor.read_u16::<LittleEndian>()?;
         let last_mod_time = cursor.read_u16::<LittleEndian>()?;
         let last_mod_date = cursor.read_u16::<LittleEndian>()?;
@@ -249,14 +1175,30 @@ impl<R: ReadAt> ZipParser<R> {
         let file_comment_length = cursor.read_u16::<LittleEndian>()?;
         let _disk_number_start = cursor.read_u16::<LittleEndian>()?;
         let _internal_attrs = cursor.read_u16::<LittleEndian>()?;
-        let _external_attrs = cursor.read_u32::<LittleEndian>()?;
+        let external_attrs = cursor.read_u32::<LittleEndian>()?;
         let mut lfh_offset = cursor.read_u32::<LittleEndian>()? as u64;
 
+        // Refuse absurd names before touching them, leaving the cursor at
+        // the next record so that the entry can be skipped
+        if file_name_length as usize > self.max_name_length {
+            cursor.set_position(
+                cursor.position()
+                    + file_name_length as u64
+                    + extra_field_length as u64
+                    + file_comment_length as u64,
+            );
+            bail!(ZipError::NameTooLong {
+                offset: cdfh_offset,
+                length: file_name_length as usize,
+                max: self.max_name_length,
+            });
+        }
+
         // Read the variable-length file name
         let mut file_name_bytes = vec![0u8; file_name_length as usize];
         cursor.read_exact(&mut file_name_bytes)?;
-        // Use lossy conversion to handle non-UTF8 filenames gracefully
-        let file_name = String::from_utf8_lossy(&file_name_bytes).to_string();
+        let file_name =
+            normalize_name(self.charset.decode(&file_name_bytes, flags), &self.warnings);
 
         // Directory entries end with '/'
         let is_directory = file_name.ends_with('/');
@@ -264,10 +1206,16 @@ impl<R: ReadAt> ZipParser<R> {
         // Parse extra field for ZIP64 extended information
         // ZIP64 uses extra field ID 0x0001
         let extra_field_end = cursor.position() + extra_field_length as u64;
+        let mut unix_mtime = None;
+        let mut unix_uid = None;
+        let mut unix_gid = None;
+        let mut compression_method = CompressionMethod::from_u16(compression_method);
+        let mut aes = None;
 
         while cursor.position() + 4 <= extra_field_end {
             let header_id = cursor.read_u16::<LittleEndian>()?;
             let field_size = cursor.read_u16::<LittleEndian>()?;
+            let field_end = cursor.position() + field_size as u64;
 
             if header_id == 0x0001 {
                 // ZIP64 extended information extra field
@@ -282,8 +1230,37 @@ impl<R: ReadAt> ZipParser<R> {
                     lfh_offset = cursor.read_u64::<LittleEndian>()?;
                 }
                 // Skip any remaining ZIP64 fields (disk number start)
-                let remaining = extra_field_end.saturating_sub(cursor.position());
-                cursor.set_position(cursor.position() + remaining);
+                cursor.set_position(field_end.min(extra_field_end));
+            } else if header_id == AesInfo::HEADER_ID
+                && field_size >= 7
+                && compression_method.as_u16() == AesInfo::METHOD
+            {
+                // WinZip AES: vendor version, "AE", strength and the
+                // method the data was compressed with before encryption
+                let vendor_version = cursor.read_u16::<LittleEndian>()?;
+                let _vendor_id = cursor.read_u16::<LittleEndian>()?;
+                let strength = cursor.read_u8()?;
+                compression_method =
+                    CompressionMethod::from_u16(cursor.read_u16::<LittleEndian>()?);
+                aes = Some(AesInfo {
+                    vendor_version,
+                    strength,
+                });
+                cursor.set_position(field_end);
+            } else if header_id == EXTENDED_TIMESTAMP_ID {
+                let start = cursor.position() as usize;
+                let end = field_end.min(extra_field_end) as usize;
+                unix_mtime = cursor
+                    .get_ref()
+                    .get(start..end)
+                    .and_then(extended_timestamp_mtime);
+                cursor.set_position(field_end);
+            } else if header_id == 0x7875 && field_size >= 3 {
+                // Info-ZIP Unix extra field (type 3): variable-size UID/GID
+                let _version = cursor.read_u8()?;
+                unix_uid = read_var_uint(cursor, field_end)?;
+                unix_gid = read_var_uint(cursor, field_end)?;
+                cursor.set_position(field_end);
             } else {
                 // Skip unknown extra fields
                 cursor.set_position(cursor.position() + field_size as u64);
@@ -293,22 +1270,103 @@ impl<R: ReadAt> ZipParser<R> {
         // Ensure cursor is positioned after extra field
         cursor.set_position(extra_field_end);
 
-        // Skip over the file comment (we don't use it)
-        cursor.set_position(cursor.position() + file_comment_length as u64);
+        // Read the file comment, decoded like the name
+        let mut comment_bytes = vec![0u8; file_comment_length as usize];
+        cursor.read_exact(&mut comment_bytes)?;
+        let comment = self.charset.decode(&comment_bytes, flags);
 
         Ok(ZipFileEntry {
             file_name,
-            compression_method: CompressionMethod::from_u16(compression_method),
+            raw_file_name: file_name_bytes,
+            compression_method,
             compressed_size,
             uncompressed_size,
             crc32,
-            lfh_offset,
+            flags,
+            lfh_offset: self.absolute(lfh_offset),
+            cdfh_offset,
+            version_made_by,
+            version_needed,
+            external_attrs,
+            unix_mtime,
+            unix_uid,
+            unix_gid,
             last_mod_time,
             last_mod_date,
             is_directory,
+            comment,
+            aes,
         })
     }
 
+    /// Parse a single Central Directory File Header at a known offset.
+    ///
+    /// Reads just the one record instead of the whole Central Directory,
+    /// so an entry whose [`cdfh_offset`](ZipFileEntry::cdfh_offset) is
+    /// known from an earlier listing can be extracted with a couple of
+    /// small reads, without locating the EOCD first.
+    ///
+    /// # Arguments
+    ///
+    /// * `cdfh_offset` - Offset of the CDFH from the start of the archive
+    ///
+    /// # Returns
+    ///
+    /// The parsed [`ZipFileEntry`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the offset is out of bounds or no valid CDFH
+    /// is found there.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let entry = parser.parse_cdfh_at(cached.cdfh_offset).await?;
+    /// let data_offset = parser.get_data_offset(&entry).await?;
+    /// ```
+    pub async fn parse_cdfh_at(&self, cdfh_offset: u64) -> Result<ZipFileEntry> {
+        if cdfh_offset
+            .checked_add(CDFH_MIN_SIZE as u64)
+            .is_none_or(|end| end > self.size)
+        {
+            bail!(
+                "Central Directory File Header offset {} is out of bounds",
+                cdfh_offset
+            );
+        }
+
+        // Read the fixed part to learn the variable-length field sizes
+        let header = self.read_metadata(cdfh_offset, CDFH_MIN_SIZE).await?;
+        if &header[0..4] != CDFH_SIGNATURE {
+            bail!(
+                "Invalid Central Directory File Header at offset {}",
+                cdfh_offset
+            );
+        }
+
+        let mut cursor = Cursor::new(&header);
+        cursor.set_position(28); // Offset to filename length field
+        let file_name_length = cursor.read_u16::<LittleEndian>()? as u64;
+        let extra_field_length = cursor.read_u16::<LittleEndian>()? as u64;
+        let file_comment_length = cursor.read_u16::<LittleEndian>()? as u64;
+
+        let record_size =
+            CDFH_MIN_SIZE as u64 + file_name_length + extra_field_length + file_comment_length;
+        if cdfh_offset + record_size > self.size {
+            bail!(
+                "Central Directory File Header at offset {} extends past end of file",
+                cdfh_offset
+            );
+        }
+
+        // Read the whole record and parse it as usual
+        let record = self
+            .read_metadata(cdfh_offset, record_size as usize)
+            .await?;
+        self.parse_cdfh(&mut Cursor::new(record), cdfh_offset)
+    }
+
     /// Get the actual data offset for a file entry.
     ///
     /// The Local File Header (LFH) has variable-length fields (filename,
@@ -326,31 +1384,205 @@ impl<R: ReadAt> ZipParser<R> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the LFH is invalid.
+    /// Returns an error if the LFH is invalid, or if its name and extra
+    /// field lengths would put the file data past the end of the archive.
     pub async fn get_data_offset(&self, entry: &ZipFileEntry) -> Result<u64> {
         // Read the Local File Header
-        let mut lfh_buf = vec![0u8; LFH_SIZE];
-        self.reader.read_at(entry.lfh_offset, &mut lfh_buf).await?;
+        let lfh_buf = self.reader.read_vec(entry.lfh_offset, LFH_SIZE).await?;
 
+        self.data_offset_from_lfh(entry, &lfh_buf)
+    }
+
+    /// Get the data offsets for many entries at once.
+    ///
+    /// Equivalent to calling [`get_data_offset()`](Self::get_data_offset)
+    /// for each entry, but Local File Headers that lie close together are
+    /// fetched with a single read. For archives of small files this
+    /// typically turns one request per entry into a handful in total.
+    /// Each read spans at most 1 MiB, as it includes the data between
+    /// the headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The file entries from [`list_files()`]
+    ///
+    /// # Returns
+    ///
+    /// The data offset of each entry, in the same order as `entries`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any LFH is invalid or cannot be read, or puts
+    /// its file data past the end of the archive.
+    pub async fn data_offsets(&self, entries: &[ZipFileEntry]) -> Result<Vec<u64>> {
+        // Visit headers in archive order so neighbours can share a read
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        order.sort_by_key(|&i| entries[i].lfh_offset);
+
+        let mut offsets = vec![0u64; entries.len()];
+        let mut start = 0;
+        while start < order.len() {
+            // Extend the batch while the next header is close enough and
+            // the read stays within the span limit
+            let base = entries[order[start]].lfh_offset;
+            let mut end = start + 1;
+            while end < order.len() {
+                let prev = Self::lfh_end(entries[order[end - 1]].lfh_offset)?;
+                let next = entries[order[end]].lfh_offset;
+                if next.saturating_sub(prev) > MAX_BATCH_GAP
+                    || Self::lfh_end(next)? - base > MAX_BATCH_SPAN
+                {
+                    break;
+                }
+                end += 1;
+            }
+
+            // Read from the first header to the end of the last one
+            let last = entries[order[end - 1]].lfh_offset;
+            let buf = self
+                .reader
+                .read_vec(base, (Self::lfh_end(last)? - base) as usize)
+                .await?;
+
+            for &i in &order[start..end] {
+                let pos = (entries[i].lfh_offset - base) as usize;
+                offsets[i] = self.data_offset_from_lfh(&entries[i], &buf[pos..pos + LFH_SIZE])?;
+            }
+
+            start = end;
+        }
+
+        Ok(offsets)
+    }
+
+    /// Compute where the fixed part of a Local File Header ends.
+    ///
+    /// # Arguments
+    ///
+    /// * `lfh_offset` - Offset of the header in the archive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header would end past the largest
+    /// representable offset.
+    fn lfh_end(lfh_offset: u64) -> Result<u64> {
+        lfh_offset
+            .checked_add(LFH_SIZE as u64)
+            .ok_or_else(|| anyhow!("Local File Header offset {} is out of bounds", lfh_offset))
+    }
+
+    /// Compute where file data begins from a Local File Header.
+    ///
+    /// The name and extra field lengths come from the LFH itself and may
+    /// differ from the Central Directory's: tools like `zipalign` pad the
+    /// local extra field (with an 0xD935 record or plain zeros) so STORED
+    /// data in APKs lands on an aligned offset, without touching the
+    /// Central Directory. They are checked against the archive size before
+    /// being trusted.
+    ///
+    /// Data may end well before the Central Directory: APKs and other
+    /// signed archives put a signing block between the last entry and the
+    /// directory. Only data that runs into the directory itself is
+    /// treated as corruption.
+    ///
+    /// The CRC-32 and sizes always come from the Central Directory. Writers
+    /// that stream their output set general purpose bit 3 and leave them
+    /// zero in the LFH; otherwise a disagreement with the Central Directory
+    /// is recorded as a [`Warning::LocalHeaderMismatch`].
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry the header belongs to
+    /// * `lfh_buf` - The fixed-size part of the header
+    ///
+    /// # Returns
+    ///
+    /// The byte offset where the compressed file data begins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The LFH signature is invalid
+    /// - The header's name and extra field run past the end of the archive
+    /// - The entry's compressed data would extend past the end of the archive
+    ///   or into the Central Directory
+    fn data_offset_from_lfh(&self, entry: &ZipFileEntry, lfh_buf: &[u8]) -> Result<u64> {
         // Verify LFH signature (PK\x03\x04)
         if &lfh_buf[0..4] != LFH_SIGNATURE {
             bail!("Invalid Local File Header");
         }
 
-        // Read the variable field lengths from fixed positions in LFH
-        let mut cursor = Cursor::new(&lfh_buf);
-        cursor.set_position(26); // Offset to filename length field
-
+        // Read the fields after the version needed from fixed positions
+        let mut cursor = Cursor::new(lfh_buf);
+        cursor.set_position(6);
+        let flags = cursor.read_u16::<LittleEndian>()?;
+        cursor.set_position(14);
+        let crc32 = cursor.read_u32::<LittleEndian>()?;
+        let compressed_size = cursor.read_u32::<LittleEndian>()?;
+        let uncompressed_size = cursor.read_u32::<LittleEndian>()?;
         let file_name_length = cursor.read_u16::<LittleEndian>()? as u64;
         let extra_field_length = cursor.read_u16::<LittleEndian>()? as u64;
 
         // Data starts after: LFH (30 bytes) + filename + extra field
-        let data_offset =
-            entry.lfh_offset + LFH_SIZE as u64 + file_name_length + extra_field_length;
+        let data_offset = Self::lfh_end(entry.lfh_offset)? + file_name_length + extra_field_length;
+        if data_offset > self.size {
+            bail!(
+                "Local File Header of {} at offset {} is truncated: {}-byte name and {}-byte extra field run past the end of the archive",
+                entry.file_name,
+                entry.lfh_offset,
+                file_name_length,
+                extra_field_length
+            );
+        }
+        if entry.compressed_size > self.size - data_offset {
+            bail!(
+                "Data of {} ({} bytes at offset {}) extends past the end of the archive ({} bytes)",
+                entry.file_name,
+                entry.compressed_size,
+                data_offset,
+                self.size
+            );
+        }
+        if let Some(location) = self.location.get()
+            && entry.lfh_offset < location.cd_offset
+            && data_offset + entry.compressed_size > location.cd_offset
+        {
+            bail!(
+                "Data of {} ({} bytes at offset {}) runs into the Central Directory at offset {}",
+                entry.file_name,
+                entry.compressed_size,
+                data_offset,
+                location.cd_offset
+            );
+        }
+
+        // Sizes of 0xFFFFFFFF are in the LFH's ZIP64 extra field instead
+        let deferred = (flags | entry.flags) & FLAG_DATA_DESCRIPTOR != 0;
+        let size_matches =
+            |local: u32, central: u64| local == 0xFFFFFFFF || local as u64 == central;
+        if !deferred
+            && (crc32 != entry.crc32
+                || !size_matches(compressed_size, entry.compressed_size)
+                || !size_matches(uncompressed_size, entry.uncompressed_size))
+        {
+            self.warnings.push(Warning::LocalHeaderMismatch {
+                name: entry.file_name.clone(),
+                local: (crc32, compressed_size as u64, uncompressed_size as u64),
+                central: (entry.crc32, entry.compressed_size, entry.uncompressed_size),
+            });
+        }
 
         Ok(data_offset)
     }
 
+    /// Turn an offset recorded in the archive into a position in the
+    /// source, accounting for the base offset and detected prepended data.
+    fn absolute(&self, offset: u64) -> u64 {
+        self.base_offset
+            .saturating_add(self.prepended.load(Ordering::Relaxed))
+            .saturating_add(offset)
+    }
+
     /// Get a reference to the underlying reader.
     ///
     /// Useful for reading file data after getting the offset
@@ -363,3 +1595,147 @@ impl<R: ReadAt> ZipParser<R> {
         &self.reader
     }
 }
+
+/// Strip a UTF-8 byte order mark and leading slashes from an entry name.
+///
+/// Some archivers prefix names with a BOM or store them as absolute
+/// paths. Treating such names as relative lets them match patterns and
+/// pass the Zip Slip checks done at extraction. Each change is recorded
+/// as a [`Warning::NameNormalized`]. A name that would become empty is
+/// left alone.
+///
+/// # Arguments
+///
+/// * `name` - The decoded entry name
+/// * `warnings` - Where to record the change
+///
+/// # Returns
+///
+/// The normalized name.
+pub(crate) fn normalize_name(name: String, warnings: &Warnings) -> String {
+    let normalized = name.strip_prefix('\u{FEFF}').unwrap_or(&name);
+    let normalized = normalized.trim_start_matches('/');
+    if normalized.len() == name.len() || normalized.is_empty() {
+        return name;
+    }
+
+    let normalized = normalized.to_string();
+    warnings.push(Warning::NameNormalized {
+        original: name,
+        name: normalized.clone(),
+    });
+    normalized
+}
+
+/// Read a length-prefixed little-endian integer from an extra field.
+///
+/// Used by the Info-ZIP Unix extra field, which stores UID and GID as a
+/// size byte followed by that many bytes.
+///
+/// # Arguments
+///
+/// * `cursor` - A cursor positioned at the size byte
+/// * `field_end` - Position where the enclosing extra field ends
+///
+/// # Returns
+///
+/// The value, or `None` if it doesn't fit in the field or in a `u32`.
+///
+/// # Errors
+///
+/// Returns an error if the data cannot be read.
+fn read_var_uint(cursor: &mut Cursor<Vec<u8>>, field_end: u64) -> Result<Option<u32>> {
+    if cursor.position() >= field_end {
+        return Ok(None);
+    }
+    let size = cursor.read_u8()? as u64;
+    if size > 4 || cursor.position() + size > field_end {
+        cursor.set_position(field_end);
+        return Ok(None);
+    }
+
+    let mut value = 0u32;
+    for i in 0..size {
+        value |= (cursor.read_u8()? as u32) << (8 * i);
+    }
+    Ok(Some(value))
+}
+
+/// Lazy stream of entries from a Central Directory.
+///
+/// Created by [`ZipParser::entries`]. Each call to `next` parses one
+/// Central Directory File Header from the already-fetched directory data.
+/// The stream ends at the first record without a CDFH signature, and
+/// stops after the first parse error.
+pub struct CentralDirectoryEntries<'a, R: ReadAt> {
+    /// The parser used to decode each record
+    parser: &'a ZipParser<R>,
+    /// Archive offset of the start of the Central Directory
+    cd_offset: u64,
+    /// Cursor over the raw Central Directory bytes
+    cursor: Cursor<Vec<u8>>,
+    /// Number of records parsed so far
+    parsed: u64,
+    /// Set once the end of the directory (or an error) has been reached
+    done: bool,
+    /// Total number of records declared by the EOCD
+    total_entries: u64,
+}
+
+impl<R: ReadAt> CentralDirectoryEntries<'_, R> {
+    /// Get the total number of entries declared by the archive.
+    ///
+    /// This is known up front from the EOCD, so it can be used to report
+    /// how many entries remain without parsing them.
+    pub fn total_entries(&self) -> u64 {
+        self.total_entries
+    }
+}
+
+impl<R: ReadAt> Iterator for CentralDirectoryEntries<'_, R> {
+    type Item = Result<ZipFileEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.done {
+                return None;
+            }
+
+            // The directory ends at the first record that isn't a CDFH
+            // (normally the ZIP64 EOCD or EOCD signature)
+            let data = self.cursor.get_ref();
+            let pos = self.cursor.position() as usize;
+            if data.get(pos..pos + 4) != Some(CDFH_SIGNATURE) {
+                self.done = true;
+                if self.parsed != self.total_entries {
+                    self.parser.warnings.push(Warning::EntryCountMismatch {
+                        declared: self.total_entries,
+                        found: self.parsed,
+                    });
+                }
+                return None;
+            }
+
+            let entry = self.parser.parse_cdfh(&mut self.cursor, self.cd_offset);
+            match entry {
+                Ok(_) => self.parsed += 1,
+                Err(ref e) => match e.downcast_ref::<ZipError>() {
+                    // The record was read in full, so the next one can be parsed
+                    Some(&ZipError::NameTooLong { offset, length, .. })
+                        if self.parser.skip_long_names =>
+                    {
+                        self.parsed += 1;
+                        self.parser
+                            .warnings
+                            .push(Warning::LongNameSkipped { offset, length });
+                        continue;
+                    }
+                    // Records are variable-length, so nothing after a bad one
+                    // can be trusted
+                    _ => self.done = true,
+                },
+            }
+            return Some(entry);
+        }
+    }
+}
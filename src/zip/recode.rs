@@ -0,0 +1,142 @@
+//! Conversion of text file contents between character encodings.
+//!
+//! Archives made on legacy systems often hold text in the local code
+//! page, e.g. Shift-JIS on Japanese Windows. [`Recode`] converts the
+//! contents of such entries while they are extracted. This is separate
+//! from [`Charset`](super::Charset), which only affects entry names.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let recode = Recode::parse("shift_jis:utf-8")?;
+//! let extractor = ZipExtractor::new(reader).with_recode(recode);
+//! ```
+
+use anyhow::{Result, bail};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+
+use crate::pattern::Pattern;
+
+/// A conversion of file contents from one encoding to another.
+#[derive(Debug, Clone)]
+pub struct Recode {
+    /// Encoding of the stored data
+    from: &'static Encoding,
+    /// Encoding to write
+    to: &'static Encoding,
+    /// Entries to convert; empty to decide by content
+    patterns: Vec<Pattern>,
+}
+
+impl Recode {
+    /// Parse a conversion given as `FROM:TO`, e.g. `shift_jis:utf-8`.
+    ///
+    /// Both sides accept any WHATWG encoding label. Without
+    /// [`with_patterns()`](Self::with_patterns) entries are converted
+    /// only if they look like text in the source encoding.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The conversion
+    ///
+    /// # Returns
+    ///
+    /// The parsed conversion.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `:` is missing, an encoding is unknown, or
+    /// the target can't be encoded to (UTF-16 and `replacement`).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let Some((from, to)) = spec.split_once(':') else {
+            bail!("Expected an encoding conversion as FROM:TO, got {}", spec);
+        };
+        let from = encoding_for_label(from)?;
+        let to = encoding_for_label(to)?;
+        if to.output_encoding() != to {
+            bail!("Cannot encode file contents as {}", to.name());
+        }
+
+        Ok(Self {
+            from,
+            to,
+            patterns: Vec::new(),
+        })
+    }
+
+    /// Convert exactly the entries matching any of the patterns.
+    ///
+    /// Matching entries are converted even if they don't look like text;
+    /// bytes that aren't valid in the source encoding become U+FFFD.
+    ///
+    /// # Arguments
+    ///
+    /// * `patterns` - Patterns matched against entry names
+    ///
+    /// # Returns
+    ///
+    /// The conversion limited to the matching entries.
+    pub fn with_patterns(mut self, patterns: Vec<Pattern>) -> Self {
+        self.patterns = patterns;
+        self
+    }
+
+    /// Convert an entry's contents if the conversion applies to it.
+    ///
+    /// With patterns, the entry name decides. Without, the data is
+    /// converted only if it contains no NUL bytes (except for UTF-16
+    /// sources) and is entirely valid in the source encoding, which
+    /// leaves binary files untouched. Characters the target encoding
+    /// lacks are written as HTML numeric character references.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The entry's name
+    /// * `data` - The entry's decoded contents
+    ///
+    /// # Returns
+    ///
+    /// The converted contents, or `data` unchanged.
+    pub fn apply(&self, name: &str, data: Vec<u8>) -> Vec<u8> {
+        let text = if self.patterns.is_empty() {
+            let utf16 = self.from == UTF_16LE || self.from == UTF_16BE;
+            if !utf16 && data.contains(&0) {
+                return data;
+            }
+            match self
+                .from
+                .decode_without_bom_handling_and_without_replacement(&data)
+            {
+                Some(text) => text,
+                None => return data,
+            }
+        } else if self.patterns.iter().any(|p| p.matches(name)) {
+            self.from.decode_without_bom_handling(&data).0
+        } else {
+            return data;
+        };
+
+        let (bytes, _, _) = self.to.encode(&text);
+        bytes.into_owned()
+    }
+}
+
+/// Look up an encoding by its WHATWG label.
+///
+/// # Arguments
+///
+/// * `label` - The encoding name, case-insensitive
+///
+/// # Returns
+///
+/// The encoding.
+///
+/// # Errors
+///
+/// Returns an error if the label is unknown.
+fn encoding_for_label(label: &str) -> Result<&'static Encoding> {
+    match Encoding::for_label(label.trim().as_bytes()) {
+        Some(encoding) => Ok(encoding),
+        None => bail!("Unknown encoding: {}", label),
+    }
+}
@@ -0,0 +1,202 @@
+//! Per-entry extraction results.
+//!
+//! Extraction of a whole archive produces an [`ExtractReport`] recording
+//! what happened to each selected entry. The report is the single source
+//! for anything derived after extraction, such as manifests and summaries.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::structures::{CompressionMethod, ZipFileEntry};
+use crate::warning::Warning;
+
+/// Reason an entry was not extracted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The destination already exists and overwriting is not allowed
+    AlreadyExists,
+    /// Identical content was already extracted to the given path
+    Duplicate(PathBuf),
+    /// The destination's parent directory does not exist and creating
+    /// directories is not allowed
+    MissingDirectory(PathBuf),
+    /// The entry uses a compression method that can't be decompressed
+    UnsupportedMethod(CompressionMethod),
+    /// A previous run recorded the entry as extracted (`--resume`) and
+    /// the file on disk still matches
+    Resumed,
+    /// The destination already has the entry's content
+    /// (`--overwrite-if-different`)
+    Unchanged,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::AlreadyExists => write!(f, "file exists"),
+            SkipReason::Duplicate(original) => {
+                write!(f, "duplicate of {}", original.display())
+            }
+            SkipReason::MissingDirectory(dir) => {
+                write!(f, "directory {} does not exist", dir.display())
+            }
+            SkipReason::UnsupportedMethod(method) => match method.registered_name() {
+                Some(name) if method.is_obsolete() => write!(
+                    f,
+                    "compression method {} ({}) is obsolete and unsupported",
+                    method.as_u16(),
+                    name
+                ),
+                Some(name) => write!(
+                    f,
+                    "unsupported compression method {} ({})",
+                    method.as_u16(),
+                    name
+                ),
+                None => write!(f, "unsupported compression method {}", method.as_u16()),
+            },
+            SkipReason::Resumed => write!(f, "already extracted"),
+            SkipReason::Unchanged => write!(f, "identical file exists"),
+        }
+    }
+}
+
+/// What happened to a single entry during extraction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryOutcome {
+    /// The entry was written to the given path
+    Extracted(PathBuf),
+    /// The entry was written to standard output
+    Piped,
+    /// The entry was not extracted
+    Skipped(SkipReason),
+    /// Extracting the entry failed with the given error
+    Failed(String),
+}
+
+/// Result of extracting a single entry.
+#[derive(Debug, Clone)]
+pub struct EntryReport {
+    /// The entry that was processed
+    pub entry: ZipFileEntry,
+    /// What happened to it
+    pub outcome: EntryOutcome,
+}
+
+/// Aggregate counts over an [`ExtractReport`].
+///
+/// Created with [`ExtractReport::summary`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExtractSummary {
+    /// Number of entries written to disk or standard output
+    pub extracted: usize,
+    /// Total uncompressed size of the extracted entries in bytes
+    pub extracted_bytes: u64,
+    /// Number of entries that were skipped
+    pub skipped: usize,
+    /// Number of entries that failed to extract
+    pub failed: usize,
+}
+
+/// Results of extracting a set of entries, in processing order.
+///
+/// ## Example
+///
+/// ```ignore
+/// for (entry, path) in report.extracted() {
+///     println!("{} -> {}", entry.file_name, path.display());
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractReport {
+    /// One result per processed entry
+    pub entries: Vec<EntryReport>,
+    /// Warnings raised during the run, in the order they occurred
+    pub warnings: Vec<Warning>,
+}
+
+impl ExtractReport {
+    /// Create an empty report.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the outcome for an entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry that was processed
+    /// * `outcome` - What happened to it
+    pub fn push(&mut self, entry: &ZipFileEntry, outcome: EntryOutcome) {
+        self.entries.push(EntryReport {
+            entry: entry.clone(),
+            outcome,
+        });
+    }
+
+    /// Record a warning raised during the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `warning` - The warning to record
+    pub fn warn(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// Iterate over entries that were written to disk.
+    ///
+    /// # Returns
+    ///
+    /// Pairs of (entry, path it was written to).
+    pub fn extracted(&self) -> impl Iterator<Item = (&ZipFileEntry, &Path)> {
+        self.entries.iter().filter_map(|r| match &r.outcome {
+            EntryOutcome::Extracted(path) => Some((&r.entry, path.as_path())),
+            _ => None,
+        })
+    }
+
+    /// Iterate over entries that failed to extract.
+    ///
+    /// # Returns
+    ///
+    /// Pairs of (entry, error message).
+    pub fn failed(&self) -> impl Iterator<Item = (&ZipFileEntry, &str)> {
+        self.entries.iter().filter_map(|r| match &r.outcome {
+            EntryOutcome::Failed(error) => Some((&r.entry, error.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Count the outcomes in the report.
+    ///
+    /// # Returns
+    ///
+    /// The number of extracted, skipped and failed entries, and the
+    /// total size of the extracted ones.
+    pub fn summary(&self) -> ExtractSummary {
+        let mut summary = ExtractSummary::default();
+        for r in &self.entries {
+            match r.outcome {
+                EntryOutcome::Extracted(_) | EntryOutcome::Piped => {
+                    summary.extracted += 1;
+                    summary.extracted_bytes += r.entry.uncompressed_size;
+                }
+                EntryOutcome::Skipped(_) => summary.skipped += 1,
+                EntryOutcome::Failed(_) => summary.failed += 1,
+            }
+        }
+        summary
+    }
+
+    /// Iterate over entries that were skipped.
+    ///
+    /// # Returns
+    ///
+    /// Pairs of (entry, reason it was skipped).
+    pub fn skipped(&self) -> impl Iterator<Item = (&ZipFileEntry, &SkipReason)> {
+        self.entries.iter().filter_map(|r| match &r.outcome {
+            EntryOutcome::Skipped(reason) => Some((&r.entry, reason)),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,372 @@
+//! Best-effort recovery of entries from damaged archives.
+//!
+//! When the End of Central Directory record or the Central Directory
+//! itself is damaged, the archive can't be read the normal way. Salvaging
+//! instead scans the whole file for Local File Header signatures and
+//! rebuilds each entry from its local header alone.
+//!
+//! This is best-effort: local headers carry less metadata than the
+//! Central Directory (no permissions or owners), entries whose header was
+//! overwritten are lost, and a signature that happens to occur inside
+//! compressed data may be mistaken for a header. Entries that can't be
+//! recovered are reported as warnings and left out.
+//!
+//! Scanning reads the entire archive, so it's slow for remote archives.
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use flate2::{Decompress, FlushDecompress, Status};
+use std::io::Cursor;
+
+use crate::io::ReadAt;
+use crate::warning::{Warning, Warnings};
+use anyhow::{Result, bail};
+
+use super::charset::Charset;
+use super::parser::normalize_name;
+use super::structures::*;
+
+/// Size of each read while scanning the archive
+const SCAN_CHUNK: u64 = 1024 * 1024;
+
+/// General purpose flag: the entry is encrypted
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+/// General purpose flag: CRC-32 and sizes follow the data
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+
+/// Recover entries by scanning the archive for Local File Headers.
+///
+/// Headers found inside the data of an entry that was already recovered
+/// are ignored, so stored archives nested inside the archive don't
+/// contribute their own entries.
+///
+/// # Arguments
+///
+/// * `reader` - The archive
+/// * `size` - Total size of the archive in bytes
+/// * `charset` - Character set for names without the UTF-8 flag
+/// * `warnings` - Where to record headers that couldn't be recovered
+///
+/// # Returns
+///
+/// The recovered entries, in archive order.
+///
+/// # Errors
+///
+/// Returns an error only if the archive cannot be read.
+pub(crate) async fn salvage_entries<R: ReadAt + ?Sized>(
+    reader: &R,
+    size: u64,
+    charset: Charset,
+    warnings: &Warnings,
+) -> Result<Vec<ZipFileEntry>> {
+    let (headers, descriptors) = scan_signatures(reader, size).await?;
+
+    let mut entries = Vec::new();
+    let mut covered_until = 0;
+    for offset in headers {
+        if offset < covered_until {
+            continue;
+        }
+        match salvage_entry(reader, size, offset, &descriptors, charset, warnings).await {
+            Ok((entry, data_end)) => {
+                covered_until = data_end;
+                entries.push(entry);
+            }
+            Err(e) => warnings.push(Warning::SalvageFailed {
+                offset,
+                reason: format!("{:#}", e),
+            }),
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Find every Local File Header and data descriptor signature.
+///
+/// # Returns
+///
+/// The offsets of header signatures and of data descriptor signatures,
+/// both in ascending order.
+async fn scan_signatures<R: ReadAt + ?Sized>(
+    reader: &R,
+    size: u64,
+) -> Result<(Vec<u64>, Vec<u64>)> {
+    let mut headers = Vec::new();
+    let mut descriptors = Vec::new();
+
+    let mut pos = 0;
+    while pos < size {
+        // Overlap chunks by 3 bytes so signatures spanning a boundary
+        // are seen exactly once
+        let len = (SCAN_CHUNK + 3).min(size - pos);
+        let chunk = reader.read_vec(pos, len as usize).await?;
+        for (i, window) in chunk.windows(4).enumerate() {
+            if window == LFH_SIGNATURE {
+                headers.push(pos + i as u64);
+            } else if window == DATA_DESCRIPTOR_SIGNATURE {
+                descriptors.push(pos + i as u64);
+            }
+        }
+        pos += SCAN_CHUNK;
+    }
+
+    Ok((headers, descriptors))
+}
+
+/// Rebuild one entry from its Local File Header.
+///
+/// # Arguments
+///
+/// * `reader` - The archive
+/// * `size` - Total size of the archive in bytes
+/// * `lfh_offset` - Offset of the header's signature
+/// * `descriptors` - Offsets of all data descriptor signatures
+/// * `warnings` - Where to record normalized entry names
+///
+/// # Returns
+///
+/// The entry and the offset just past its data.
+///
+/// # Errors
+///
+/// Returns an error if the header is truncated or implausible, the entry
+/// is encrypted, or the end of its data can't be determined.
+async fn salvage_entry<R: ReadAt + ?Sized>(
+    reader: &R,
+    size: u64,
+    lfh_offset: u64,
+    descriptors: &[u64],
+    charset: Charset,
+    warnings: &Warnings,
+) -> Result<(ZipFileEntry, u64)> {
+    if LFH_SIZE as u64 > size - lfh_offset {
+        bail!("truncated Local File Header");
+    }
+    let header = reader.read_vec(lfh_offset, LFH_SIZE).await?;
+
+    let mut cursor = Cursor::new(&header[4..]);
+    let version_needed = cursor.read_u16::<LittleEndian>()?;
+    let flags = cursor.read_u16::<LittleEndian>()?;
+    let compression_method = CompressionMethod::from_u16(cursor.read_u16::<LittleEndian>()?);
+    let last_mod_time = cursor.read_u16::<LittleEndian>()?;
+    let last_mod_date = cursor.read_u16::<LittleEndian>()?;
+    let mut crc32 = cursor.read_u32::<LittleEndian>()?;
+    let mut compressed_size = cursor.read_u32::<LittleEndian>()? as u64;
+    let mut uncompressed_size = cursor.read_u32::<LittleEndian>()? as u64;
+    let file_name_length = cursor.read_u16::<LittleEndian>()? as usize;
+    let extra_field_length = cursor.read_u16::<LittleEndian>()? as usize;
+
+    if file_name_length == 0 {
+        bail!("empty file name");
+    }
+    let data_offset = lfh_offset + (LFH_SIZE + file_name_length + extra_field_length) as u64;
+    if data_offset > size {
+        bail!("name and extra field run past the end of the archive");
+    }
+    if flags & FLAG_ENCRYPTED != 0 {
+        bail!("entry is encrypted");
+    }
+
+    let vars = reader
+        .read_vec(
+            lfh_offset + LFH_SIZE as u64,
+            file_name_length + extra_field_length,
+        )
+        .await?;
+    let file_name = normalize_name(charset.decode(&vars[..file_name_length], flags), warnings);
+    let is_directory = file_name.ends_with('/');
+
+    // Local extra fields: ZIP64 sizes and the extended timestamp
+    let mut unix_mtime = None;
+    let mut cursor = Cursor::new(&vars[file_name_length..]);
+    while cursor.position() + 4 <= extra_field_length as u64 {
+        let header_id = cursor.read_u16::<LittleEndian>()?;
+        let field_size = cursor.read_u16::<LittleEndian>()? as u64;
+        let field_end = cursor.position() + field_size;
+        if field_end > extra_field_length as u64 {
+            break;
+        }
+
+        if header_id == 0x0001 {
+            // In the LFH both sizes are present whenever either overflows
+            let overflows = uncompressed_size == 0xFFFFFFFF || compressed_size == 0xFFFFFFFF;
+            if overflows && field_size >= 16 {
+                uncompressed_size = cursor.read_u64::<LittleEndian>()?;
+                compressed_size = cursor.read_u64::<LittleEndian>()?;
+            }
+        } else if header_id == EXTENDED_TIMESTAMP_ID {
+            let start = cursor.position() as usize;
+            unix_mtime = extended_timestamp_mtime(&cursor.get_ref()[start..field_end as usize]);
+        }
+        cursor.set_position(field_end);
+    }
+
+    // With a data descriptor the header's CRC and sizes may be zero, so
+    // find the real values after the data
+    if flags & FLAG_DATA_DESCRIPTOR != 0 && compressed_size == 0 && !is_directory {
+        (crc32, compressed_size, uncompressed_size) = match compression_method {
+            CompressionMethod::Deflate => {
+                let (compressed, uncompressed) = measure_deflate(reader, size, data_offset).await?;
+                let crc = descriptor_crc(reader, size, data_offset + compressed)
+                    .await
+                    .unwrap_or(crc32);
+                (crc, compressed, uncompressed)
+            }
+            CompressionMethod::Stored => {
+                find_stored_descriptor(reader, size, data_offset, descriptors).await?
+            }
+            CompressionMethod::Unknown(_) => {
+                bail!("cannot find the end of data using an unsupported compression method")
+            }
+        };
+    }
+
+    if compressed_size > size - data_offset {
+        bail!("data runs past the end of the archive");
+    }
+
+    let entry = ZipFileEntry {
+        file_name,
+        raw_file_name: vars[..file_name_length].to_vec(),
+        compression_method,
+        compressed_size,
+        uncompressed_size,
+        crc32,
+        flags,
+        lfh_offset,
+        // Not recorded in the local header
+        cdfh_offset: 0,
+        version_made_by: 0,
+        version_needed,
+        external_attrs: 0,
+        unix_mtime,
+        unix_uid: None,
+        unix_gid: None,
+        last_mod_time,
+        last_mod_date,
+        is_directory,
+        // Comments are only stored in the Central Directory
+        comment: String::new(),
+        // Encrypted entries are rejected above
+        aes: None,
+    };
+    Ok((entry, data_offset + compressed_size))
+}
+
+/// Measure a DEFLATE stream by decompressing it until its end marker.
+///
+/// # Returns
+///
+/// The compressed and uncompressed sizes of the stream.
+///
+/// # Errors
+///
+/// Returns an error if the data is not valid DEFLATE or the stream
+/// doesn't end before the end of the archive.
+async fn measure_deflate<R: ReadAt + ?Sized>(
+    reader: &R,
+    size: u64,
+    data_offset: u64,
+) -> Result<(u64, u64)> {
+    let mut inflater = Decompress::new(false);
+    let mut out = vec![0u8; 64 * 1024];
+
+    let mut pos = data_offset;
+    while pos < size {
+        let len = SCAN_CHUNK.min(size - pos);
+        let chunk = reader.read_vec(pos, len as usize).await?;
+        let chunk_start = inflater.total_in();
+        loop {
+            let consumed = inflater.total_in() - chunk_start;
+            let produced = inflater.total_out();
+            let status = inflater.decompress(
+                &chunk[consumed as usize..],
+                &mut out,
+                FlushDecompress::None,
+            )?;
+            if status == Status::StreamEnd {
+                return Ok((inflater.total_in(), inflater.total_out()));
+            }
+            // No progress means the chunk is used up and more input is needed
+            if inflater.total_in() - chunk_start == consumed && inflater.total_out() == produced {
+                if consumed < len {
+                    bail!("DEFLATE stream is corrupt");
+                }
+                break;
+            }
+        }
+        pos += len;
+    }
+
+    bail!("DEFLATE stream runs past the end of the archive")
+}
+
+/// Read the CRC-32 from the data descriptor at the given offset.
+///
+/// # Returns
+///
+/// The CRC-32, or `None` if no descriptor fits before the end of the
+/// archive.
+async fn descriptor_crc<R: ReadAt + ?Sized>(reader: &R, size: u64, offset: u64) -> Option<u32> {
+    if size.saturating_sub(offset) < 8 {
+        return None;
+    }
+    let buf = reader.read_vec(offset, 8).await.ok()?;
+    // The signature is optional
+    let crc = if &buf[0..4] == DATA_DESCRIPTOR_SIGNATURE {
+        &buf[4..8]
+    } else {
+        &buf[0..4]
+    };
+    Some(u32::from_le_bytes(crc.try_into().ok()?))
+}
+
+/// Find the data descriptor that ends a STORED entry's data.
+///
+/// STORED data has no end marker, so look for a signed descriptor whose
+/// sizes match its distance from the start of the data.
+///
+/// # Returns
+///
+/// The CRC-32, compressed size and uncompressed size from the descriptor.
+///
+/// # Errors
+///
+/// Returns an error if no matching descriptor is found.
+async fn find_stored_descriptor<R: ReadAt + ?Sized>(
+    reader: &R,
+    size: u64,
+    data_offset: u64,
+    descriptors: &[u64],
+) -> Result<(u32, u64, u64)> {
+    for &offset in descriptors.iter().filter(|&&o| o >= data_offset) {
+        let distance = offset - data_offset;
+        let available = size - offset;
+
+        // Classic descriptor with 32-bit sizes, then the ZIP64 form
+        if available >= 16 {
+            let buf = reader.read_vec(offset, 16).await?;
+            let mut cursor = Cursor::new(&buf[4..]);
+            let crc32 = cursor.read_u32::<LittleEndian>()?;
+            let compressed = cursor.read_u32::<LittleEndian>()? as u64;
+            let uncompressed = cursor.read_u32::<LittleEndian>()? as u64;
+            if compressed == distance && uncompressed == distance {
+                return Ok((crc32, distance, distance));
+            }
+        }
+        if available >= 24 {
+            let buf = reader.read_vec(offset, 24).await?;
+            let mut cursor = Cursor::new(&buf[4..]);
+            let crc32 = cursor.read_u32::<LittleEndian>()?;
+            let compressed = cursor.read_u64::<LittleEndian>()?;
+            let uncompressed = cursor.read_u64::<LittleEndian>()?;
+            if compressed == distance && uncompressed == distance {
+                return Ok((crc32, distance, distance));
+            }
+        }
+    }
+
+    bail!("no data descriptor found for STORED data")
+}
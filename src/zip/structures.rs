@@ -24,6 +24,17 @@ use std::io::Cursor;
 
 use anyhow::{Result, bail};
 
+use super::datetime::DosDateTime;
+
+/// General purpose flag: the entry is encrypted
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+/// General purpose flag: CRC-32 and sizes follow the data
+pub(crate) const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+
+/// General purpose flag: the entry uses PKWARE strong encryption
+const FLAG_STRONG_ENCRYPTION: u16 = 0x0040;
+
 /// ZIP compression methods.
 ///
 /// ZIP supports various compression methods, identified by a 16-bit integer.
@@ -68,6 +79,70 @@ impl CompressionMethod {
         }
     }
 
+    /// Get a short human-readable name for this method.
+    ///
+    /// # Returns
+    ///
+    /// The method name, e.g. `"Stored"`, `"Deflate"` or `"Imploded"`, or
+    /// `"Unknown"` for unregistered methods.
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionMethod::Stored => "Stored",
+            CompressionMethod::Deflate => "Deflate",
+            CompressionMethod::Unknown(_) => self.registered_name().unwrap_or("Unknown"),
+        }
+    }
+
+    /// Check whether this is one of the legacy PKZIP methods 1 to 7
+    /// (Shrunk, Reduced, Imploded, Tokenized).
+    ///
+    /// These predate Deflate and are no longer produced by any current
+    /// tool, so they won't be supported.
+    ///
+    /// # Returns
+    ///
+    /// `true` for methods 1 to 7.
+    pub fn is_obsolete(&self) -> bool {
+        matches!(self, CompressionMethod::Unknown(1..=7))
+    }
+
+    /// Get the registered name of a method this implementation can't
+    /// decompress.
+    ///
+    /// Lets error messages say "BZIP2" rather than just "12", which tells
+    /// the user which tool can handle the entry.
+    ///
+    /// # Returns
+    ///
+    /// The method's name from the APPNOTE registry, or `None` for
+    /// supported or unregistered methods.
+    pub fn registered_name(&self) -> Option<&'static str> {
+        let CompressionMethod::Unknown(method) = self else {
+            return None;
+        };
+        let name = match method {
+            1 => "Shrunk",
+            2..=5 => "Reduced",
+            6 => "Imploded",
+            7 => "Tokenized",
+            9 => "Deflate64",
+            10 => "PKWARE DCL Imploded",
+            12 => "BZIP2",
+            14 => "LZMA",
+            18 => "IBM TERSE",
+            19 => "IBM LZ77",
+            93 => "Zstandard",
+            94 => "MP3",
+            95 => "XZ",
+            96 => "JPEG",
+            97 => "WavPack",
+            98 => "PPMd",
+            99 => "AES encryption",
+            _ => return None,
+        };
+        Some(name)
+    }
+
     /// Convert a CompressionMethod variant to its raw method ID.
     ///
     /// # Returns
@@ -82,6 +157,60 @@ impl CompressionMethod {
     }
 }
 
+/// DEFLATE compression level hint.
+///
+/// For DEFLATE entries, bits 1 and 2 of the general purpose flags record
+/// which compression level the archiver used. The hint is not needed for
+/// decompression, but is shown in verbose listings (like `unzip -v`).
+///
+/// | Bit 2 | Bit 1 | Level |
+/// |-------|-------|-------|
+/// | 0 | 0 | Normal (`-en`) |
+/// | 0 | 1 | Maximum (`-exx`/`-ex`) |
+/// | 1 | 0 | Fast (`-ef`) |
+/// | 1 | 1 | Super Fast (`-es`) |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeflateLevelHint {
+    /// Normal compression
+    Normal,
+    /// Maximum compression
+    Maximum,
+    /// Fast compression
+    Fast,
+    /// Super fast compression
+    SuperFast,
+}
+
+impl DeflateLevelHint {
+    /// Decode the level hint from general purpose flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - The general purpose bit flags of an entry
+    ///
+    /// # Returns
+    ///
+    /// The level hint encoded in bits 1-2.
+    pub fn from_flags(flags: u16) -> Self {
+        match (flags >> 1) & 0b11 {
+            0 => DeflateLevelHint::Normal,
+            1 => DeflateLevelHint::Maximum,
+            2 => DeflateLevelHint::Fast,
+            _ => DeflateLevelHint::SuperFast,
+        }
+    }
+
+    /// Get the single-letter code used by `unzip -v` (`N`, `X`, `F`, `S`).
+    pub fn as_char(&self) -> char {
+        match self {
+            DeflateLevelHint::Normal => 'N',
+            DeflateLevelHint::Maximum => 'X',
+            DeflateLevelHint::Fast => 'F',
+            DeflateLevelHint::SuperFast => 'S',
+        }
+    }
+}
+
 /// End of Central Directory (EOCD) record.
 ///
 /// This structure appears at the very end of a ZIP file and contains
@@ -258,6 +387,11 @@ impl Zip64EOCDLocator {
 /// | 32 | 8 | Total entries |
 /// | 40 | 8 | Central Directory size |
 /// | 48 | 8 | Central Directory offset |
+/// | 56 | n | Extensible data sector |
+///
+/// The extensible data sector fills the rest of the record as given by
+/// its size field. Version 2 records, written with Central Directory
+/// encryption, keep the encryption parameters there.
 pub struct Zip64EOCD {
     /// Size of this record (excluding first 12 bytes)
     pub eocd64_size: u64,
@@ -277,6 +411,8 @@ pub struct Zip64EOCD {
     pub cd_size: u64,
     /// Offset to Central Directory
     pub cd_offset: u64,
+    /// Extensible data sector following the fixed fields
+    pub extensible_data: Vec<u8>,
 }
 
 impl Zip64EOCD {
@@ -284,12 +420,20 @@ impl Zip64EOCD {
     pub const SIGNATURE: &'static [u8] = b"PK\x06\x06";
     /// Minimum size of ZIP64 EOCD record
     pub const MIN_SIZE: usize = 56;
+    /// Bytes before the part of the record counted by its size field
+    pub const HEADER_SIZE: usize = 12;
+    /// Size of the fixed fields a version 2 record adds to the
+    /// extensible data sector (method, sizes, algorithm, bit length,
+    /// flags, hash algorithm and hash length)
+    const V2_FIELDS_SIZE: usize = 28;
 
     /// Parse a ZIP64 EOCD from raw bytes.
     ///
     /// # Arguments
     ///
-    /// * `data` - Byte slice containing the ZIP64 EOCD record
+    /// * `data` - Byte slice containing the ZIP64 EOCD record; bytes
+    ///   beyond [`MIN_SIZE`](Self::MIN_SIZE), up to the size given by
+    ///   the record, become the extensible data sector
     ///
     /// # Returns
     ///
@@ -308,9 +452,13 @@ impl Zip64EOCD {
         }
 
         let mut cursor = Cursor::new(&data[4..]);
+        let eocd64_size = cursor.read_u64::<LittleEndian>()?;
+        let record_len = eocd64_size
+            .saturating_add(Self::HEADER_SIZE as u64)
+            .min(data.len() as u64) as usize;
 
         Ok(Self {
-            eocd64_size: cursor.read_u64::<LittleEndian>()?,
+            eocd64_size,
             version_made_by: cursor.read_u16::<LittleEndian>()?,
             version_needed: cursor.read_u16::<LittleEndian>()?,
             disk_number: cursor.read_u32::<LittleEndian>()?,
@@ -319,8 +467,34 @@ impl Zip64EOCD {
             total_entries: cursor.read_u64::<LittleEndian>()?,
             cd_size: cursor.read_u64::<LittleEndian>()?,
             cd_offset: cursor.read_u64::<LittleEndian>()?,
+            extensible_data: data[Self::MIN_SIZE..record_len.max(Self::MIN_SIZE)].to_vec(),
         })
     }
+
+    /// Get the total size of the record, signature included.
+    ///
+    /// # Returns
+    ///
+    /// The size from the record's size field plus the 12 bytes it
+    /// doesn't count, saturating on overflow.
+    pub fn record_len(&self) -> u64 {
+        self.eocd64_size.saturating_add(Self::HEADER_SIZE as u64)
+    }
+
+    /// Check whether this is a version 2 record.
+    ///
+    /// Version 2 records are written when the Central Directory is
+    /// encrypted (version needed to extract 6.2 or later) and carry the
+    /// encryption parameters in the extensible data sector. Their
+    /// Central Directory can't be parsed without decrypting it first.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the version needed is at least 6.2 and the extensible
+    /// data sector holds the version 2 fields.
+    pub fn is_v2(&self) -> bool {
+        (self.version_needed & 0xFF) >= 62 && self.extensible_data.len() >= Self::V2_FIELDS_SIZE
+    }
 }
 
 /// Central Directory File Header signature: "PK\x01\x02"
@@ -335,6 +509,97 @@ pub const LFH_SIGNATURE: &[u8] = b"PK\x03\x04";
 /// Size of Local File Header (30 bytes, fixed portion)
 pub const LFH_SIZE: usize = 30;
 
+/// Data descriptor signature, optionally preceding the CRC-32 and sizes
+/// written after an entry's data when general purpose bit 3 is set
+pub const DATA_DESCRIPTOR_SIGNATURE: &[u8] = b"PK\x07\x08";
+
+/// Header ID of the Info-ZIP extended timestamp extra field
+pub(crate) const EXTENDED_TIMESTAMP_ID: u16 = 0x5455;
+
+/// Read the modification time from an extended timestamp extra field.
+///
+/// The field holds Unix times, free of the DOS format's two-second
+/// resolution and missing time zone. It starts with a flags byte saying
+/// which of the modification (bit 0), access (bit 1) and creation
+/// (bit 2) times are set. The Local File Header variant carries every
+/// flagged time, while the Central Directory variant keeps the flags but
+/// only the modification time; since that time comes first, both are
+/// read the same way.
+///
+/// # Arguments
+///
+/// * `data` - The field's data, after its header ID and size
+///
+/// # Returns
+///
+/// The modification time in seconds since the Unix epoch, or `None` if
+/// the field doesn't carry one.
+pub(crate) fn extended_timestamp_mtime(data: &[u8]) -> Option<i64> {
+    let (&flags, times) = data.split_first()?;
+    if flags & 0x01 == 0 {
+        return None;
+    }
+    let mtime = times.get(..4)?;
+    Some(i32::from_le_bytes(mtime.try_into().ok()?) as i64)
+}
+
+/// WinZip AES encryption parameters of an entry.
+///
+/// Parsed from the AE-x extra field (header ID 0x9901). Entries
+/// encrypted this way record method 99 in their headers; the method the
+/// data was compressed with before encryption is kept in the extra field
+/// and becomes the entry's
+/// [`compression_method`](ZipFileEntry::compression_method).
+///
+/// ## Encrypted Data Layout
+///
+/// | Size | Description |
+/// |------|-------------|
+/// | 8, 12 or 16 | Salt, half the key length |
+/// | 2 | Password verification value |
+/// | n | Encrypted compressed data |
+/// | 10 | Authentication code (HMAC-SHA1 of the encrypted data) |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AesInfo {
+    /// Vendor version: 1 for AE-1, 2 for AE-2, which stores no CRC-32
+    pub vendor_version: u16,
+    /// Key strength: 1, 2 or 3 for 128, 192 or 256-bit keys
+    pub strength: u8,
+}
+
+impl AesInfo {
+    /// Header ID of the AE-x extra field
+    pub const HEADER_ID: u16 = 0x9901;
+    /// Compression method recorded for AES-encrypted entries
+    pub const METHOD: u16 = 99;
+    /// Size of the password verification value
+    pub const VERIFIER_SIZE: usize = 2;
+    /// Size of the authentication code after the encrypted data
+    pub const AUTH_CODE_SIZE: usize = 10;
+
+    /// Get the AES key length in bytes.
+    ///
+    /// # Returns
+    ///
+    /// 16, 24 or 32, or `None` for an undefined strength.
+    pub fn key_len(&self) -> Option<usize> {
+        match self.strength {
+            1 => Some(16),
+            2 => Some(24),
+            3 => Some(32),
+            _ => None,
+        }
+    }
+
+    /// Check whether the entry's CRC-32 is meaningful.
+    ///
+    /// AE-2 writers store zero instead, relying on the authentication
+    /// code, which also avoids leaking information about the plaintext.
+    pub fn has_crc(&self) -> bool {
+        self.vendor_version != 2
+    }
+}
+
 /// Parsed ZIP file entry information.
 ///
 /// This structure contains all the metadata needed to extract a file
@@ -355,6 +620,9 @@ pub const LFH_SIZE: usize = 30;
 pub struct ZipFileEntry {
     /// The file name (may include path components)
     pub file_name: String,
+    /// The file name as stored, before decoding with the character set
+    /// or the UTF-8 flag, for callers that want to decode it themselves
+    pub raw_file_name: Vec<u8>,
     /// Compression method used for this entry
     pub compression_method: CompressionMethod,
     /// Size of compressed data in bytes
@@ -363,14 +631,38 @@ pub struct ZipFileEntry {
     pub uncompressed_size: u64,
     /// CRC-32 checksum of uncompressed data
     pub crc32: u32,
+    /// General purpose bit flags
+    pub flags: u16,
     /// Offset to Local File Header from start of archive
     pub lfh_offset: u64,
+    /// Offset to this entry's Central Directory File Header from start
+    /// of archive
+    pub cdfh_offset: u64,
+    /// "Version made by" field; the high byte identifies the host system
+    pub version_made_by: u16,
+    /// "Version needed to extract" field; the low byte is the minimum
+    /// specification version, times ten
+    pub version_needed: u16,
+    /// External file attributes (host-specific, e.g. Unix mode bits)
+    pub external_attrs: u32,
+    /// Modification time in seconds since the Unix epoch, from the
+    /// extended timestamp extra field (0x5455)
+    pub unix_mtime: Option<i64>,
+    /// Owner user ID, from the Info-ZIP Unix extra field (0x7875)
+    pub unix_uid: Option<u32>,
+    /// Owner group ID, from the Info-ZIP Unix extra field (0x7875)
+    pub unix_gid: Option<u32>,
     /// Last modification time in DOS format
     pub last_mod_time: u16,
     /// Last modification date in DOS format
     pub last_mod_date: u16,
     /// True if this entry represents a directory
     pub is_directory: bool,
+    /// File comment from the Central Directory, empty if there is none
+    pub comment: String,
+    /// WinZip AES encryption parameters, if the entry is encrypted that
+    /// way
+    pub aes: Option<AesInfo>,
 }
 
 impl ZipFileEntry {
@@ -381,6 +673,9 @@ impl ZipFileEntry {
     /// - Bits 5-8: Month (1-12)
     /// - Bits 9-15: Year offset from 1980
     ///
+    /// The fields aren't validated; [`modified()`](Self::modified)
+    /// returns the date and time checked and combined.
+    ///
     /// # Returns
     ///
     /// A tuple of (year, month, day).
@@ -405,6 +700,9 @@ impl ZipFileEntry {
     /// - Bits 5-10: Minute (0-59)
     /// - Bits 11-15: Hour (0-23)
     ///
+    /// The fields aren't validated; [`modified()`](Self::modified)
+    /// returns the date and time checked and combined.
+    ///
     /// # Returns
     ///
     /// A tuple of (hour, minute, second).
@@ -421,4 +719,145 @@ impl ZipFileEntry {
         let hour = ((self.last_mod_time >> 11) & 0x1F) as u8;
         (hour, minute, second)
     }
+
+    /// Get the modification date and time from the DOS fields.
+    ///
+    /// # Returns
+    ///
+    /// The date and time, or `None` if the fields don't form a valid
+    /// one (e.g. an all-zero date).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// if let Some(modified) = entry.modified() {
+    ///     println!("{}  {}", modified, entry.file_name);
+    /// }
+    /// ```
+    pub fn modified(&self) -> Option<DosDateTime> {
+        DosDateTime::from_dos(self.last_mod_date, self.last_mod_time).ok()
+    }
+
+    /// Get the Unix mode recorded for the entry.
+    ///
+    /// Only archives created on Unix (host system 3 in "version made by")
+    /// store a mode, in the high 16 bits of the external attributes.
+    ///
+    /// # Returns
+    ///
+    /// The full mode including file type bits, or `None` if the archive
+    /// doesn't record one.
+    pub fn unix_mode(&self) -> Option<u32> {
+        let mode = self.external_attrs >> 16;
+        if self.version_made_by >> 8 == 3 && mode != 0 {
+            Some(mode)
+        } else {
+            None
+        }
+    }
+
+    /// Check whether a data descriptor follows the entry's data.
+    ///
+    /// Writers that stream their output set general purpose bit 3 and
+    /// record the CRC-32 and sizes after the data, since they aren't
+    /// known when the Local File Header is written.
+    pub fn has_data_descriptor(&self) -> bool {
+        self.flags & FLAG_DATA_DESCRIPTOR != 0
+    }
+
+    /// Check whether the entry is encrypted.
+    ///
+    /// True for traditional PKWARE encryption, WinZip AES and PKWARE
+    /// strong encryption alike; [`aes`](Self::aes) tells WinZip AES
+    /// apart.
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & FLAG_ENCRYPTED != 0
+    }
+
+    /// Name a feature the entry needs that this implementation lacks.
+    ///
+    /// Looks at "version needed to extract" and the strong encryption
+    /// flag. Traditional PKWARE encryption and WinZip AES encryption are
+    /// supported and not reported here.
+    /// Writers often set the version higher than necessary, so it only
+    /// counts when it is reserved for features that are never optional:
+    /// the encryption schemes of versions 5.0 to 6.2, and anything newer
+    /// than 6.3, the latest version defined. Unsupported compression
+    /// methods are reported separately.
+    ///
+    /// # Returns
+    ///
+    /// A description of the feature, or `None` if nothing rules out
+    /// extraction.
+    pub fn unsupported_feature(&self) -> Option<&'static str> {
+        let by_version = match self.version_needed & 0xFF {
+            50 | 52 => Some("strong encryption"),
+            51 if self.aes.is_some() => None,
+            51 => Some("AES encryption"),
+            61 => Some("non-OAEP key wrapping"),
+            62 => Some("Central Directory encryption"),
+            64.. => Some("a feature newer than version 6.3"),
+            _ => None,
+        };
+        let strong_encryption = self.flags & FLAG_STRONG_ENCRYPTION != 0;
+        by_version.or_else(|| strong_encryption.then_some("strong encryption"))
+    }
+
+    /// Check whether the entry is a symbolic link.
+    ///
+    /// Symlinks are stored as Unix entries with the `S_IFLNK` file type;
+    /// their data is the link target.
+    pub fn is_symlink(&self) -> bool {
+        self.unix_mode()
+            .is_some_and(|mode| mode & 0o170000 == 0o120000)
+    }
+
+    /// Get the modification time as seconds since the Unix epoch.
+    ///
+    /// Uses the extended timestamp extra field when present. Otherwise
+    /// the DOS date and time are used; they carry no time zone and are
+    /// interpreted as UTC.
+    ///
+    /// # Returns
+    ///
+    /// The modification time, or `None` if the DOS fields are invalid.
+    pub fn modified_unix(&self) -> Option<i64> {
+        if self.unix_mtime.is_some() {
+            return self.unix_mtime;
+        }
+
+        self.modified().map(|modified| modified.to_unix())
+    }
+
+    /// Get the DEFLATE compression level hint.
+    ///
+    /// # Returns
+    ///
+    /// The level hint from the general purpose flags, or `None` if the
+    /// entry is not DEFLATE-compressed.
+    pub fn deflate_level_hint(&self) -> Option<DeflateLevelHint> {
+        match self.compression_method {
+            CompressionMethod::Deflate => Some(DeflateLevelHint::from_flags(self.flags)),
+            _ => None,
+        }
+    }
+
+    /// Get the method label shown in verbose listings.
+    ///
+    /// Mirrors the method column of `unzip -v`: `Stored`, `Defl:N`,
+    /// `Defl:X`, `Defl:F`, `Defl:S`, or `Unk:NNN` for other methods.
+    ///
+    /// # Returns
+    ///
+    /// A short label describing the compression method.
+    pub fn method_label(&self) -> String {
+        match self.compression_method {
+            CompressionMethod::Stored => "Stored".to_string(),
+            CompressionMethod::Deflate => {
+                let hint = DeflateLevelHint::from_flags(self.flags);
+                format!("Defl:{}", hint.as_char())
+            }
+            CompressionMethod::Unknown(method) => format!("Unk:{:03}", method),
+        }
+    }
 }
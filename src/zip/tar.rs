@@ -0,0 +1,233 @@
+//! Writing of tar archives.
+//!
+//! [`ZipExtractor::write_tar_gz()`](super::ZipExtractor::write_tar_gz)
+//! converts entries to a tar stream with this module. Members use the
+//! POSIX ustar format; names and link targets too long for it are stored
+//! with the GNU long name extension, which all common tar readers
+//! understand.
+
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::AsyncWrite;
+
+use super::structures::ZipFileEntry;
+
+/// Size of a tar block; headers and data are padded to whole blocks
+const BLOCK: usize = 512;
+
+/// Name of the pseudo-member carrying a GNU long name or link target
+const LONG_LINK: &[u8] = b"././@LongLink";
+
+/// The kind of tar member written for an entry.
+pub(crate) enum Member<'a> {
+    /// A regular file whose data follows the header
+    File,
+    /// A directory
+    Directory,
+    /// A symbolic link to the given target
+    Symlink(&'a str),
+}
+
+/// Writer producing a tar stream member by member.
+///
+/// The data of a [`Member::File`] is written through the [`Write`]
+/// implementation after [`append()`](Self::append), followed by
+/// [`pad()`](Self::pad).
+pub(crate) struct TarWriter<W: Write> {
+    /// Where the tar stream goes
+    inner: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    /// Create a writer producing a tar stream into `inner`.
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Write the header for an entry.
+    ///
+    /// Names, sizes, permissions, modification times and owner IDs are
+    /// taken from the entry. Entries without Unix permissions get
+    /// 0644 (files) or 0755 (directories).
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The entry to describe
+    /// * `member` - The kind of member to write
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub(crate) fn append(&mut self, entry: &ZipFileEntry, member: &Member) -> io::Result<()> {
+        let (typeflag, size, default_mode, link) = match member {
+            Member::File => (b'0', entry.uncompressed_size, 0o644, ""),
+            Member::Directory => (b'5', 0, 0o755, ""),
+            Member::Symlink(target) => (b'2', 0, 0o777, *target),
+        };
+        let mode = entry.unix_mode().map_or(default_mode, |mode| mode & 0o7777);
+        let mtime = entry.modified_unix().unwrap_or(0).max(0) as u64;
+
+        let mut header = [0u8; BLOCK];
+        let name = entry.file_name.as_bytes();
+        match split_name(name) {
+            Some((prefix, name)) => {
+                header[..name.len()].copy_from_slice(name);
+                header[345..345 + prefix.len()].copy_from_slice(prefix);
+            }
+            None => {
+                self.write_long(b'L', name)?;
+                header[..100].copy_from_slice(&name[..100]);
+            }
+        }
+        if link.len() > 100 {
+            self.write_long(b'K', link.as_bytes())?;
+        }
+        let link = &link.as_bytes()[..link.len().min(100)];
+        header[157..157 + link.len()].copy_from_slice(link);
+
+        write_number(&mut header[100..108], mode as u64);
+        write_number(&mut header[108..116], entry.unix_uid.unwrap_or(0) as u64);
+        write_number(&mut header[116..124], entry.unix_gid.unwrap_or(0) as u64);
+        write_number(&mut header[124..136], size);
+        write_number(&mut header[136..148], mtime);
+        header[156] = typeflag;
+        self.write_header(header)
+    }
+
+    /// Pad a member's data to a whole number of blocks.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of data bytes written for the member
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub(crate) fn pad(&mut self, size: u64) -> io::Result<()> {
+        let rest = (size % BLOCK as u64) as usize;
+        if rest != 0 {
+            self.inner.write_all(&[0u8; BLOCK][rest..])?;
+        }
+        Ok(())
+    }
+
+    /// End the archive with two zero blocks.
+    ///
+    /// # Returns
+    ///
+    /// The underlying writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[0u8; 2 * BLOCK])?;
+        Ok(self.inner)
+    }
+
+    /// Write a GNU long name (`L`) or long link target (`K`) member.
+    fn write_long(&mut self, typeflag: u8, value: &[u8]) -> io::Result<()> {
+        let mut header = [0u8; BLOCK];
+        header[..LONG_LINK.len()].copy_from_slice(LONG_LINK);
+        write_number(&mut header[100..108], 0o644);
+        write_number(&mut header[108..116], 0);
+        write_number(&mut header[116..124], 0);
+        // The value is stored with a terminating NUL
+        write_number(&mut header[124..136], value.len() as u64 + 1);
+        write_number(&mut header[136..148], 0);
+        header[156] = typeflag;
+        self.write_header(header)?;
+
+        self.inner.write_all(value)?;
+        self.inner.write_all(&[0])?;
+        self.pad(value.len() as u64 + 1)
+    }
+
+    /// Fill in the magic and checksum of a header and write it.
+    fn write_header(&mut self, mut header: [u8; BLOCK]) -> io::Result<()> {
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+
+        // The checksum is computed with its own field set to spaces
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&b| b as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+
+        self.inner.write_all(&header)
+    }
+}
+
+impl<W: Write> Write for TarWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Adapter writing synchronously to a [`Write`] from async code.
+///
+/// Flushes are ignored, so a compressing writer underneath isn't forced
+/// to end a block at every chunk.
+pub(crate) struct BlockingWrite<'a, W: Write>(pub(crate) &'a mut W);
+
+impl<W: Write> AsyncWrite for BlockingWrite<'_, W> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().0.write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Split a name into the ustar prefix and name fields.
+///
+/// # Returns
+///
+/// The prefix (possibly empty) and the name, or `None` if the name
+/// doesn't fit.
+fn split_name(name: &[u8]) -> Option<(&[u8], &[u8])> {
+    if name.len() <= 100 {
+        return Some((&[], name));
+    }
+    // Split at a slash, ignoring a directory's trailing one
+    let trimmed = name.strip_suffix(b"/").unwrap_or(name);
+    trimmed
+        .iter()
+        .enumerate()
+        .filter(|&(i, &b)| b == b'/' && (1..=155).contains(&i) && name.len() - i - 1 <= 100)
+        .map(|(i, _)| (&name[..i], &name[i + 1..]))
+        .next()
+}
+
+/// Write a number into a header field.
+///
+/// Uses NUL-terminated octal when the value fits, and otherwise the GNU
+/// base-256 encoding (high bit of the first byte set, big-endian).
+fn write_number(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    if digits >= 22 || value < 1 << (3 * digits) {
+        let text = format!("{:0width$o}\0", value, width = digits);
+        field.copy_from_slice(text.as_bytes());
+    } else {
+        field.fill(0);
+        let bytes = value.to_be_bytes();
+        let len = field.len();
+        let n = bytes.len().min(len - 1);
+        field[len - n..].copy_from_slice(&bytes[bytes.len() - n..]);
+        field[0] |= 0x80;
+    }
+}
@@ -0,0 +1,433 @@
+//! Creation of ZIP archives.
+//!
+//! [`ZipWriter`] writes entries one after another into any [`AsyncWrite`]
+//! sink, then the Central Directory and the EOCD record. Entries are
+//! stored or compressed with DEFLATE, and ZIP64 records are written only
+//! where a size, an offset or the number of entries needs them, so tools
+//! can repack entries after transforming them and read the result back
+//! with [`ZipExtractor`](super::ZipExtractor).
+//!
+//! Each entry's data is passed in whole, so its CRC-32 and sizes are
+//! written in the Local File Header and no data descriptors are needed.
+//!
+//! ## Example
+//!
+//! ```ignore
+//! let file = tokio::fs::File::create("out.zip").await?;
+//! let mut writer = ZipWriter::new(file);
+//! writer.add_directory("docs/", FileOptions::default()).await?;
+//! writer
+//!     .add_file("docs/README.md", b"Hello", FileOptions::default())
+//!     .await?;
+//! writer.finish().await?;
+//! ```
+
+use std::borrow::Cow;
+use std::io::Write;
+
+use anyhow::{Result, bail};
+use byteorder::{LittleEndian, WriteBytesExt};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use tokio::io::AsyncWrite;
+
+use super::datetime::DosDateTime;
+use super::structures::{
+    CDFH_SIGNATURE, CompressionMethod, EndOfCentralDirectory, LFH_SIGNATURE, Zip64EOCD,
+    Zip64EOCDLocator,
+};
+
+/// Largest value of a 32-bit size or offset field; this value and larger
+/// ones are stored in ZIP64 records
+const ZIP64_LIMIT: u64 = 0xFFFFFFFF;
+
+/// Largest entry count of the EOCD record
+const ZIP64_ENTRIES_LIMIT: u64 = 0xFFFF;
+
+/// Header ID of the ZIP64 extended information extra field
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+/// General purpose flag: the name is UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+
+/// Version made by: Unix (3), APPNOTE 6.3
+const VERSION_MADE_BY: u16 = 3 << 8 | 63;
+
+/// Version needed to extract stored entries
+const VERSION_STORED: u16 = 10;
+
+/// Version needed to extract directories and deflated entries
+const VERSION_DEFLATE: u16 = 20;
+
+/// Version needed to extract entries with ZIP64 fields
+const VERSION_ZIP64: u16 = 45;
+
+/// Unix file type of regular files
+const S_IFREG: u32 = 0o100000;
+
+/// Unix file type of directories
+const S_IFDIR: u32 = 0o040000;
+
+/// MS-DOS directory attribute
+const DOS_DIRECTORY: u32 = 0x10;
+
+/// Settings for an entry written by [`ZipWriter`].
+#[derive(Debug, Clone)]
+pub struct FileOptions {
+    /// How to compress the data: [`CompressionMethod::Stored`] or
+    /// [`CompressionMethod::Deflate`] (the default). Ignored for
+    /// directories, which have no data.
+    pub method: CompressionMethod,
+    /// Modification time, or `None` for 1980-01-01 00:00:00
+    pub modified: Option<DosDateTime>,
+    /// Unix permission bits, or `None` for 0644 (files) and 0755
+    /// (directories)
+    pub unix_mode: Option<u32>,
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        Self {
+            method: CompressionMethod::Deflate,
+            modified: None,
+            unix_mode: None,
+        }
+    }
+}
+
+/// An entry already written, kept for its Central Directory record.
+struct WrittenEntry {
+    /// The name as stored
+    name: Vec<u8>,
+    /// General purpose flags
+    flags: u16,
+    /// Compression method ID
+    method: u16,
+    /// Version needed to extract
+    version_needed: u16,
+    /// Packed DOS modification date
+    date: u16,
+    /// Packed DOS modification time
+    time: u16,
+    /// CRC-32 of the uncompressed data
+    crc32: u32,
+    /// Size of the data as stored
+    compressed_size: u64,
+    /// Size of the data after decompression
+    uncompressed_size: u64,
+    /// Offset of the Local File Header
+    offset: u64,
+    /// External attributes: Unix mode and DOS attributes
+    external_attrs: u32,
+}
+
+/// Writer creating a ZIP archive.
+///
+/// Add entries with [`add_file()`](Self::add_file) and
+/// [`add_directory()`](Self::add_directory), then call
+/// [`finish()`](Self::finish) to write the Central Directory. An archive
+/// whose writer is dropped without finishing is incomplete.
+pub struct ZipWriter<W: AsyncWrite + Unpin> {
+    /// Where the archive goes
+    inner: W,
+    /// Number of bytes written so far
+    offset: u64,
+    /// Entries written so far
+    entries: Vec<WrittenEntry>,
+    /// Archive comment
+    comment: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> ZipWriter<W> {
+    /// Create a writer producing an archive into `inner`.
+    ///
+    /// The archive starts at the sink's current position; offsets are
+    /// relative to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The sink to write to
+    ///
+    /// # Returns
+    ///
+    /// A writer with no entries.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            offset: 0,
+            entries: Vec::new(),
+            comment: Vec::new(),
+        }
+    }
+
+    /// Set the archive comment written after the EOCD record.
+    ///
+    /// # Arguments
+    ///
+    /// * `comment` - The comment, at most 65535 bytes
+    ///
+    /// # Returns
+    ///
+    /// The writer with the comment set.
+    pub fn with_comment(mut self, comment: impl Into<Vec<u8>>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Add a file entry.
+    ///
+    /// The data is compressed in memory with the method from `options`
+    /// and written along with its Local File Header.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The entry name, with `/` separating directories
+    /// * `data` - The file contents
+    /// * `options` - Compression method, modification time and mode
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The name is empty, ends with `/` or is longer than 65535 bytes
+    /// - The compression method is neither Stored nor Deflate
+    /// - Writing fails
+    pub async fn add_file(&mut self, name: &str, data: &[u8], options: FileOptions) -> Result<()> {
+        if name.ends_with('/') {
+            bail!("File name {} ends with '/'", name);
+        }
+        let mode = S_IFREG | options.unix_mode.unwrap_or(0o644);
+        self.add(name, data, &options, mode << 16).await
+    }
+
+    /// Add a directory entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The directory name; a trailing `/` is added if missing
+    /// * `options` - Modification time and mode; the method is ignored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty or longer than 65535 bytes,
+    /// or if writing fails.
+    pub async fn add_directory(&mut self, name: &str, options: FileOptions) -> Result<()> {
+        let name = if name.ends_with('/') {
+            Cow::Borrowed(name)
+        } else {
+            Cow::Owned(format!("{}/", name))
+        };
+        let mode = S_IFDIR | options.unix_mode.unwrap_or(0o755);
+        let options = FileOptions {
+            method: CompressionMethod::Stored,
+            ..options
+        };
+        self.add(&name, &[], &options, mode << 16 | DOS_DIRECTORY)
+            .await
+    }
+
+    /// Write the Central Directory and the EOCD record.
+    ///
+    /// A ZIP64 EOCD record and locator precede the EOCD record if there
+    /// are 65535 entries or more, or the Central Directory's size or
+    /// offset doesn't fit in 32 bits.
+    ///
+    /// # Returns
+    ///
+    /// The sink, flushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the comment is longer than 65535 bytes or
+    /// writing fails.
+    pub async fn finish(mut self) -> Result<W> {
+        let Ok(comment_len) = u16::try_from(self.comment.len()) else {
+            bail!(
+                "Archive comment of {} bytes is too long",
+                self.comment.len()
+            );
+        };
+
+        let cd_offset = self.offset;
+        let mut cd = Vec::new();
+        for entry in &self.entries {
+            write_cdfh(&mut cd, entry)?;
+        }
+        self.write(&cd).await?;
+        let cd_size = cd.len() as u64;
+        let count = self.entries.len() as u64;
+
+        let mut end = Vec::new();
+        if count >= ZIP64_ENTRIES_LIMIT || cd_size >= ZIP64_LIMIT || cd_offset >= ZIP64_LIMIT {
+            let eocd64_offset = cd_offset + cd_size;
+            end.write_all(Zip64EOCD::SIGNATURE)?;
+            end.write_u64::<LittleEndian>((Zip64EOCD::MIN_SIZE - Zip64EOCD::HEADER_SIZE) as u64)?;
+            end.write_u16::<LittleEndian>(VERSION_MADE_BY)?;
+            end.write_u16::<LittleEndian>(VERSION_ZIP64)?;
+            end.write_u32::<LittleEndian>(0)?; // This disk
+            end.write_u32::<LittleEndian>(0)?; // Disk with the Central Directory
+            end.write_u64::<LittleEndian>(count)?;
+            end.write_u64::<LittleEndian>(count)?;
+            end.write_u64::<LittleEndian>(cd_size)?;
+            end.write_u64::<LittleEndian>(cd_offset)?;
+
+            end.write_all(Zip64EOCDLocator::SIGNATURE)?;
+            end.write_u32::<LittleEndian>(0)?; // Disk with the ZIP64 EOCD
+            end.write_u64::<LittleEndian>(eocd64_offset)?;
+            end.write_u32::<LittleEndian>(1)?; // Total disks
+        }
+
+        let count = count.min(ZIP64_ENTRIES_LIMIT) as u16;
+        end.write_all(EndOfCentralDirectory::SIGNATURE)?;
+        end.write_u16::<LittleEndian>(0)?; // This disk
+        end.write_u16::<LittleEndian>(0)?; // Disk with the Central Directory
+        end.write_u16::<LittleEndian>(count)?;
+        end.write_u16::<LittleEndian>(count)?;
+        end.write_u32::<LittleEndian>(cd_size.min(ZIP64_LIMIT) as u32)?;
+        end.write_u32::<LittleEndian>(cd_offset.min(ZIP64_LIMIT) as u32)?;
+        end.write_u16::<LittleEndian>(comment_len)?;
+        end.write_all(&self.comment)?;
+        self.write(&end).await?;
+
+        tokio::io::AsyncWriteExt::flush(&mut self.inner).await?;
+        Ok(self.inner)
+    }
+
+    /// Compress and write an entry, recording it for the Central
+    /// Directory.
+    async fn add(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        options: &FileOptions,
+        external_attrs: u32,
+    ) -> Result<()> {
+        if name.is_empty() || name == "/" {
+            bail!("Entry names cannot be empty");
+        }
+        if name.len() > u16::MAX as usize {
+            bail!("Entry name of {} bytes is too long", name.len());
+        }
+
+        let stored = match options.method {
+            CompressionMethod::Stored => Cow::Borrowed(data),
+            CompressionMethod::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                Cow::Owned(encoder.finish()?)
+            }
+            method => bail!("Cannot write {} entries", method.name()),
+        };
+        let mut crc = Crc::new();
+        crc.update(data);
+
+        let (date, time) = options.modified.map_or((0x21, 0), |m| m.to_dos());
+        let is_directory = name.ends_with('/');
+        let mut entry = WrittenEntry {
+            name: name.as_bytes().to_vec(),
+            flags: if name.is_ascii() { 0 } else { FLAG_UTF8 },
+            method: options.method.as_u16(),
+            version_needed: VERSION_STORED,
+            date,
+            time,
+            crc32: crc.sum(),
+            compressed_size: stored.len() as u64,
+            uncompressed_size: data.len() as u64,
+            offset: self.offset,
+            external_attrs,
+        };
+        let zip64_sizes =
+            entry.compressed_size >= ZIP64_LIMIT || entry.uncompressed_size >= ZIP64_LIMIT;
+        entry.version_needed = if zip64_sizes || entry.offset >= ZIP64_LIMIT {
+            VERSION_ZIP64
+        } else if is_directory || options.method == CompressionMethod::Deflate {
+            VERSION_DEFLATE
+        } else {
+            VERSION_STORED
+        };
+
+        // The Local File Header has both sizes in its ZIP64 field, if any
+        let mut header = Vec::with_capacity(LFH_SIGNATURE.len() + 26 + name.len() + 20);
+        header.write_all(LFH_SIGNATURE)?;
+        header.write_u16::<LittleEndian>(entry.version_needed)?;
+        header.write_u16::<LittleEndian>(entry.flags)?;
+        header.write_u16::<LittleEndian>(entry.method)?;
+        header.write_u16::<LittleEndian>(entry.time)?;
+        header.write_u16::<LittleEndian>(entry.date)?;
+        header.write_u32::<LittleEndian>(entry.crc32)?;
+        if zip64_sizes {
+            header.write_u32::<LittleEndian>(ZIP64_LIMIT as u32)?;
+            header.write_u32::<LittleEndian>(ZIP64_LIMIT as u32)?;
+        } else {
+            header.write_u32::<LittleEndian>(entry.compressed_size as u32)?;
+            header.write_u32::<LittleEndian>(entry.uncompressed_size as u32)?;
+        }
+        header.write_u16::<LittleEndian>(entry.name.len() as u16)?;
+        header.write_u16::<LittleEndian>(if zip64_sizes { 20 } else { 0 })?;
+        header.write_all(&entry.name)?;
+        if zip64_sizes {
+            header.write_u16::<LittleEndian>(ZIP64_EXTRA_ID)?;
+            header.write_u16::<LittleEndian>(16)?;
+            header.write_u64::<LittleEndian>(entry.uncompressed_size)?;
+            header.write_u64::<LittleEndian>(entry.compressed_size)?;
+        }
+
+        self.write(&header).await?;
+        self.write(&stored).await?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write bytes to the sink, keeping track of the offset.
+    async fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        // Qualified, as byteorder's and std's traits are in scope for
+        // the header buffers too
+        tokio::io::AsyncWriteExt::write_all(&mut self.inner, bytes).await?;
+        self.offset += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Append an entry's Central Directory File Header to `out`.
+///
+/// The ZIP64 extra field holds exactly the values whose 32-bit fields
+/// are 0xFFFFFFFF, in the order the APPNOTE gives.
+fn write_cdfh(out: &mut Vec<u8>, entry: &WrittenEntry) -> Result<()> {
+    let mut zip64 = Vec::new();
+    let mut field = |value: u64| -> Result<u32> {
+        if value >= ZIP64_LIMIT {
+            zip64.write_u64::<LittleEndian>(value)?;
+            Ok(ZIP64_LIMIT as u32)
+        } else {
+            Ok(value as u32)
+        }
+    };
+    let uncompressed_size = field(entry.uncompressed_size)?;
+    let compressed_size = field(entry.compressed_size)?;
+    let offset = field(entry.offset)?;
+    let extra_len = if zip64.is_empty() { 0 } else { 4 + zip64.len() };
+
+    out.write_all(CDFH_SIGNATURE)?;
+    out.write_u16::<LittleEndian>(VERSION_MADE_BY)?;
+    out.write_u16::<LittleEndian>(entry.version_needed)?;
+    out.write_u16::<LittleEndian>(entry.flags)?;
+    out.write_u16::<LittleEndian>(entry.method)?;
+    out.write_u16::<LittleEndian>(entry.time)?;
+    out.write_u16::<LittleEndian>(entry.date)?;
+    out.write_u32::<LittleEndian>(entry.crc32)?;
+    out.write_u32::<LittleEndian>(compressed_size)?;
+    out.write_u32::<LittleEndian>(uncompressed_size)?;
+    out.write_u16::<LittleEndian>(entry.name.len() as u16)?;
+    out.write_u16::<LittleEndian>(extra_len as u16)?;
+    out.write_u16::<LittleEndian>(0)?; // Comment length
+    out.write_u16::<LittleEndian>(0)?; // Disk number start
+    out.write_u16::<LittleEndian>(0)?; // Internal attributes
+    out.write_u32::<LittleEndian>(entry.external_attrs)?;
+    out.write_u32::<LittleEndian>(offset)?;
+    out.write_all(&entry.name)?;
+    if !zip64.is_empty() {
+        out.write_u16::<LittleEndian>(ZIP64_EXTRA_ID)?;
+        out.write_u16::<LittleEndian>(zip64.len() as u16)?;
+        out.write_all(&zip64)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! Decryption of entries using traditional PKWARE encryption (ZipCrypto).
+//!
+//! The cipher keeps three 32-bit keys, initialized from the password and
+//! updated with every plaintext byte. Encrypted data starts with a
+//! 12-byte header whose last byte, once decrypted, must equal the high
+//! byte of the entry's CRC-32, or of its DOS modification time when the
+//! CRC-32 follows the data in a data descriptor. That check weeds out
+//! most wrong passwords; the rest fail the CRC-32 check after
+//! decompression. The cipher is weak and only supported for reading
+//! legacy archives.
+
+use anyhow::{Result, bail};
+
+use super::error::ZipError;
+use super::structures::ZipFileEntry;
+
+/// Size of the encryption header preceding the data
+pub(crate) const HEADER_SIZE: usize = 12;
+
+/// CRC-32 lookup table used by the key updates
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// Build the table for the standard reflected CRC-32 polynomial.
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Update a CRC-32 with one byte, without the usual pre- and
+/// post-inversion.
+fn crc32_byte(crc: u32, byte: u8) -> u32 {
+    (crc >> 8) ^ CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize]
+}
+
+/// The cipher state.
+struct Keys([u32; 3]);
+
+impl Keys {
+    /// Initialize the keys from a password.
+    fn new(password: &[u8]) -> Self {
+        let mut keys = Self([0x12345678, 0x23456789, 0x34567890]);
+        for &byte in password {
+            keys.update(byte);
+        }
+        keys
+    }
+
+    /// Mix a plaintext byte into the keys.
+    fn update(&mut self, byte: u8) {
+        let [k0, k1, k2] = &mut self.0;
+        *k0 = crc32_byte(*k0, byte);
+        *k1 = k1
+            .wrapping_add(*k0 & 0xFF)
+            .wrapping_mul(134775813)
+            .wrapping_add(1);
+        *k2 = crc32_byte(*k2, (*k1 >> 24) as u8);
+    }
+
+    /// Decrypt one byte.
+    fn decrypt(&mut self, byte: u8) -> u8 {
+        let temp = (self.0[2] | 2) as u16;
+        let plain = byte ^ (temp.wrapping_mul(temp ^ 1) >> 8) as u8;
+        self.update(plain);
+        plain
+    }
+}
+
+/// Decrypt an entry's data held in memory.
+///
+/// # Arguments
+///
+/// * `entry` - The entry the data belongs to
+/// * `password` - The password to try
+/// * `data` - The entry's `compressed_size` bytes of stored data
+///
+/// # Returns
+///
+/// The compressed data, ready to be decompressed with the entry's
+/// compression method.
+///
+/// # Errors
+///
+/// Returns [`ZipError::WrongPassword`] if the header's check byte doesn't
+/// match, and an error if the data is shorter than the header.
+pub(crate) fn decrypt(entry: &ZipFileEntry, password: &str, data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < HEADER_SIZE {
+        bail!(
+            "Encrypted data of {} is truncated: {} bytes, at least {} expected",
+            entry.file_name,
+            data.len(),
+            HEADER_SIZE
+        );
+    }
+
+    let (header, ciphertext) = data.split_at(HEADER_SIZE);
+    let mut decryptor = Decryptor::new(entry, password, header)?;
+    let mut plaintext = ciphertext.to_vec();
+    decryptor.decrypt(&mut plaintext);
+    Ok(plaintext)
+}
+
+/// Incremental decryption of an entry's data.
+///
+/// The data can be fed in chunks of any size, so entries are decrypted
+/// as they are streamed.
+pub(crate) struct Decryptor {
+    /// The cipher state after the data decrypted so far
+    keys: Keys,
+}
+
+impl Decryptor {
+    /// Decrypt the encryption header and check the password.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The encrypted entry
+    /// * `password` - The password to try
+    /// * `header` - The [`HEADER_SIZE`] bytes preceding the encrypted data
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ZipError::WrongPassword`] if the header's check byte
+    /// doesn't match.
+    pub(crate) fn new(entry: &ZipFileEntry, password: &str, header: &[u8]) -> Result<Self> {
+        let mut keys = Keys::new(password.as_bytes());
+        let mut plain = [0u8; HEADER_SIZE];
+        for (plain, &byte) in plain.iter_mut().zip(&header[..HEADER_SIZE]) {
+            *plain = keys.decrypt(byte);
+        }
+
+        let check = if entry.has_data_descriptor() {
+            (entry.last_mod_time >> 8) as u8
+        } else {
+            (entry.crc32 >> 24) as u8
+        };
+        if plain[HEADER_SIZE - 1] != check {
+            bail!(ZipError::WrongPassword {
+                name: entry.file_name.clone(),
+            });
+        }
+        Ok(Self { keys })
+    }
+
+    /// Decrypt the next chunk of data in place.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Encrypted data following the previous chunk
+    pub(crate) fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data {
+            *byte = self.keys.decrypt(*byte);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Delays between retries of failed HTTP requests.
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use runzip::{Backoff, HttpRangeReader, ReadAt};
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn fixed_and_linear_delays() {
+    let fixed = Backoff::Fixed { delay: 250 * MS };
+    assert_eq!(fixed.delay(1), 250 * MS);
+    assert_eq!(fixed.delay(7), 250 * MS);
+
+    let linear = Backoff::Linear {
+        step: 100 * MS,
+        max_delay: 350 * MS,
+    };
+    let delays: Vec<Duration> = (1..=5).map(|n| linear.delay(n)).collect();
+    assert_eq!(delays, [100 * MS, 200 * MS, 300 * MS, 350 * MS, 350 * MS]);
+}
+
+#[test]
+fn exponential_delays_are_capped() {
+    let backoff = Backoff::Exponential {
+        base: 100 * MS,
+        max_delay: Duration::from_secs(1),
+        jitter: false,
+    };
+    let delays: Vec<Duration> = (0..=5).map(|n| backoff.delay(n)).collect();
+    // Attempt 0 counts as the first retry
+    assert_eq!(
+        delays,
+        [100 * MS, 100 * MS, 200 * MS, 400 * MS, 800 * MS, 1000 * MS]
+    );
+    // Shifts past 32 bits saturate instead of wrapping
+    assert_eq!(backoff.delay(40), Duration::from_secs(1));
+    assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+}
+
+#[test]
+fn jitter_stays_between_half_and_all_of_the_delay() {
+    let backoff = Backoff::default();
+    assert_eq!(
+        backoff,
+        Backoff::Exponential {
+            base: 500 * MS,
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    );
+    for attempt in 1..=10 {
+        let full = (500 * MS * 2u32.pow(attempt - 1)).min(Duration::from_secs(30));
+        let delay = backoff.delay(attempt);
+        assert!(
+            delay >= full / 2 && delay <= full,
+            "{:?} for {}",
+            delay,
+            attempt
+        );
+    }
+}
+
+/// Answer the first two requests after HEAD and the size probe with 503
+/// Service Unavailable.
+fn unavailable_twice(index: usize, _: &str) -> Option<Vec<u8>> {
+    (2..=3)
+        .contains(&index)
+        .then(|| b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n".to_vec())
+}
+
+#[tokio::test]
+async fn reader_waits_as_configured() {
+    let server = common::serve_with(b"0123456789".to_vec(), unavailable_twice).await;
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .backoff(Backoff::Fixed { delay: 150 * MS })
+        .build()
+        .await
+        .unwrap();
+
+    let start = Instant::now();
+    let mut buf = [0u8; 3];
+    reader.read_at(1, &mut buf).await.unwrap();
+    let elapsed = start.elapsed();
+    assert_eq!(&buf, b"123");
+    assert!(elapsed >= 300 * MS && elapsed < 3000 * MS, "{:?}", elapsed);
+}
@@ -0,0 +1,45 @@
+//! Archives embedded at a known offset with `with_base_offset`.
+
+mod common;
+
+use std::sync::Arc;
+
+use runzip::{MemoryReader, ZipExtractor};
+
+use common::{TestEntry, build_zip};
+
+#[tokio::test]
+async fn embedded_archives_are_read_from_their_base() {
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"hello"),
+            TestEntry::deflated("b.txt", b"world world world"),
+        ],
+        &[],
+    );
+    let mut container = vec![0x55u8; 500];
+    container.extend_from_slice(&zip);
+
+    let extractor = ZipExtractor::new(Arc::new(MemoryReader::new(container)))
+        .with_base_offset(500)
+        .unwrap();
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        extractor.extract_to_memory(&entries[0]).await.unwrap(),
+        b"hello"
+    );
+    assert_eq!(
+        extractor.extract_to_memory(&entries[1]).await.unwrap(),
+        b"world world world"
+    );
+}
+
+#[tokio::test]
+async fn base_offsets_must_lie_within_the_source() {
+    let zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+    let len = zip.len() as u64;
+    let extractor = ZipExtractor::new(Arc::new(MemoryReader::new(zip)));
+    let err = extractor.with_base_offset(len + 1).err().unwrap();
+    assert!(err.to_string().contains("lies beyond the end"), "{:#}", err);
+}
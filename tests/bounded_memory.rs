@@ -0,0 +1,72 @@
+//! Memory use of streaming extraction, measured with a counting allocator.
+
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use common::{TestEntry, build_zip, extractor};
+
+/// Tracks the bytes currently allocated and the peak since a reset.
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let now = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(now, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Bytes allocated at the peak of `f`, beyond what was allocated before.
+async fn peak_during<F: Future>(f: F) -> (F::Output, usize) {
+    let before = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let output = f.await;
+    (output, PEAK.load(Ordering::Relaxed) - before)
+}
+
+// A single test, so that no other test allocates at the same time
+#[tokio::test]
+async fn streaming_allocates_a_bounded_amount() {
+    const MIB: usize = 1024 * 1024;
+    let data = vec![b'x'; 16 * MIB];
+    let zip = build_zip(
+        &[
+            TestEntry::new("stored.bin", &data),
+            TestEntry::deflated("deflated.bin", &data),
+            // Declares 16 MiB but stores 5 bytes
+            TestEntry {
+                payload: Some(b"short".to_vec()),
+                ..TestEntry::new("lying.bin", &data)
+            },
+        ],
+        &[],
+    );
+    drop(data);
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    for entry in &entries[..2] {
+        let mut sink = tokio::io::sink();
+        let (written, peak) = peak_during(extractor.extract_to_writer(entry, &mut sink)).await;
+        assert_eq!(written.unwrap(), 16 * MIB as u64);
+        assert!(peak < 2 * MIB, "{} allocated {}", entry.file_name, peak);
+    }
+
+    let mut sink = tokio::io::sink();
+    let (result, peak) = peak_during(extractor.extract_to_writer(&entries[2], &mut sink)).await;
+    assert!(result.is_err());
+    assert!(peak < MIB, "{}", peak);
+}
@@ -0,0 +1,122 @@
+//! Locating and reading the Central Directory.
+
+mod common;
+
+use std::fs;
+
+use common::{
+    Layout, TestEntry, build_zip, build_zip_with, eocd_offset, open, runzip, scratch_dir,
+};
+
+fn three_files(layout: &Layout) -> Vec<u8> {
+    build_zip_with(
+        &[
+            TestEntry::new("a.txt", b"first"),
+            TestEntry::new("b.txt", b"second"),
+            TestEntry::new("c.txt", b"third"),
+        ],
+        layout,
+    )
+}
+
+#[test]
+fn slightly_wrong_cd_size_is_recovered_with_a_warning() {
+    let dir = scratch_dir("wrong-cd-size");
+    let actual = (three_files(&Layout::default()).len() - 3 * (30 + 5) - 22 - 16) as u32;
+
+    for cd_size in [actual - 10, actual + 10] {
+        let zip = three_files(&Layout {
+            cd_size: Some(cd_size),
+            ..Layout::default()
+        });
+        fs::write(dir.join("a.zip"), zip).unwrap();
+
+        let output = runzip(&dir, &["-l", "a.zip"]);
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .collect::<Vec<_>>(),
+            ["a.txt", "b.txt", "c.txt"]
+        );
+        assert!(
+            String::from_utf8_lossy(&output.stderr).contains(&format!(
+                "Central Directory size is {} bytes but {} bytes precede the end record",
+                cd_size, actual
+            )),
+            "{:?}",
+            output
+        );
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn directory_far_larger_than_declared_is_rejected() {
+    let dir = scratch_dir("huge-cd");
+    // Point the directory at a 200 KB entry that starts like a CDFH
+    let mut data = b"PK\x01\x02".to_vec();
+    data.resize(200_000, 0x55);
+    let mut zip = build_zip(&[TestEntry::new("big.bin", &data)], &[]);
+    let eocd = eocd_offset(&zip);
+    zip[eocd + 16..eocd + 20].copy_from_slice(&37u32.to_le_bytes());
+
+    let err = open(&dir, "a.zip", &zip).list_files().await.unwrap_err();
+    assert!(
+        err.to_string().contains("bytes precede the end record"),
+        "{:#}",
+        err
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn unmarked_zip64_records_are_not_part_of_the_directory() {
+    // `zip -` writes ZIP64 records before an EOCD whose fields all fit
+    let output = runzip(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")),
+        &["-l", "tests/data/piped.zip"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(output.stdout, b"-\n");
+    assert!(output.stderr.is_empty(), "{:?}", output);
+}
+
+#[tokio::test]
+async fn single_record_is_parsed_at_a_known_offset() {
+    let dir = scratch_dir("parse-cdfh-at");
+    let path = dir.join("a.zip");
+    let zip = three_files(&Layout::default());
+    fs::write(&path, &zip).unwrap();
+    let reader = std::sync::Arc::new(runzip::LocalFileReader::new(&path).unwrap());
+    let parser = runzip::zip::ZipParser::new(reader);
+
+    // A cached listing tells where each record is
+    let listed = parser.list_files().await.unwrap();
+    for entry in &listed {
+        let parsed = parser.parse_cdfh_at(entry.cdfh_offset).await.unwrap();
+        assert_eq!(format!("{:?}", parsed), format!("{:?}", entry));
+    }
+    assert_eq!(listed[0].cdfh_offset, common::cd_offset(&zip) as u64);
+
+    // A Local File Header is not a directory record
+    let err = parser.parse_cdfh_at(0).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Invalid Central Directory File Header"),
+        "{}",
+        err
+    );
+    for offset in [
+        fs::metadata(&path).unwrap().len() - 10,
+        u64::MAX - 10,
+        u64::MAX,
+    ] {
+        let err = parser.parse_cdfh_at(offset).await.unwrap_err();
+        assert!(err.to_string().contains("out of bounds"), "{}", err);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
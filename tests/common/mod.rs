@@ -0,0 +1,431 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+
+use flate2::Compression;
+use flate2::Crc;
+use flate2::write::DeflateEncoder;
+use runzip::{LocalFileReader, MemoryReader, ZipExtractor};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+/// An entry for [`build_zip()`].
+///
+/// Headers are consistent with `data` unless a field overrides them.
+pub struct TestEntry<'a> {
+    /// Entry name, as stored
+    pub name: &'a [u8],
+    /// Entry contents
+    pub data: &'a [u8],
+    /// Compression method; `data` is deflated for method 8
+    pub method: u16,
+    /// General purpose flags
+    pub flags: u16,
+    /// DOS modification time and date
+    pub time: u16,
+    pub date: u16,
+    /// Unix mode, recorded with a Unix "version made by"
+    pub unix_mode: Option<u32>,
+    /// Extra field of the Central Directory record
+    pub extra: Vec<u8>,
+    /// Extra field of the Local File Header
+    pub local_extra: Vec<u8>,
+    /// File comment
+    pub comment: &'a [u8],
+    /// Version needed to extract
+    pub version_needed: u16,
+    /// Bytes written as the entry's data, if not the (compressed) `data`
+    pub payload: Option<Vec<u8>>,
+    /// CRC-32 recorded in the headers, if not that of `data`
+    pub crc32: Option<u32>,
+    /// Compressed size recorded in the Central Directory, if not the
+    /// payload's length
+    pub cd_size: Option<u32>,
+    /// Uncompressed size recorded in the Central Directory, if not the
+    /// length of `data`; sizes beyond 32 bits go in a ZIP64 extra field
+    pub uncompressed_size: Option<u64>,
+    /// Local File Header offset recorded in a ZIP64 extra field of the
+    /// Central Directory, if not the actual offset
+    pub lfh_offset: Option<u64>,
+}
+
+impl<'a> TestEntry<'a> {
+    /// Create a STORED entry with consistent headers.
+    pub fn new(name: &'a str, data: &'a [u8]) -> Self {
+        Self::raw(name.as_bytes(), data)
+    }
+
+    /// Create a STORED entry whose name is not valid UTF-8.
+    pub fn raw(name: &'a [u8], data: &'a [u8]) -> Self {
+        Self {
+            name,
+            data,
+            method: 0,
+            flags: 0,
+            time: 0,
+            date: 0x21,
+            unix_mode: None,
+            extra: Vec::new(),
+            local_extra: Vec::new(),
+            comment: b"",
+            version_needed: 20,
+            payload: None,
+            crc32: None,
+            cd_size: None,
+            uncompressed_size: None,
+            lfh_offset: None,
+        }
+    }
+
+    /// Create a DEFLATE entry.
+    pub fn deflated(name: &'a str, data: &'a [u8]) -> Self {
+        Self {
+            method: 8,
+            ..Self::new(name, data)
+        }
+    }
+
+    /// Create a directory entry; `name` ends with `/`.
+    pub fn dir(name: &'a str) -> Self {
+        Self::new(name, b"")
+    }
+
+    /// Record a Unix mode for this entry.
+    pub fn mode(self, mode: u32) -> Self {
+        Self {
+            unix_mode: Some(mode),
+            ..self
+        }
+    }
+
+    /// Bytes written for this entry's data.
+    pub fn payload(&self) -> Vec<u8> {
+        if let Some(payload) = &self.payload {
+            return payload.clone();
+        }
+        match self.method {
+            8 => deflate(self.data),
+            _ => self.data.to_vec(),
+        }
+    }
+
+    /// CRC-32 recorded for this entry.
+    pub fn crc32(&self) -> u32 {
+        self.crc32.unwrap_or_else(|| crc32(self.data))
+    }
+}
+
+/// Layout of an archive built by [`build_zip_with()`].
+#[derive(Default)]
+pub struct Layout<'a> {
+    /// Bytes before the first entry, such as a self-extractor stub
+    pub prefix: &'a [u8],
+    /// Bytes between the last entry's data and the Central Directory
+    pub gap: &'a [u8],
+    /// Archive comment
+    pub comment: &'a [u8],
+    /// Disk number recorded in the End of Central Directory
+    pub disk_number: u16,
+    /// Central Directory size recorded in the End of Central Directory,
+    /// if not the actual size
+    pub cd_size: Option<u32>,
+}
+
+/// Build an archive by hand.
+///
+/// `gap` is written between the last entry's data and the Central
+/// Directory, where signed archives keep their signing block.
+pub fn build_zip(entries: &[TestEntry], gap: &[u8]) -> Vec<u8> {
+    build_zip_with(
+        entries,
+        &Layout {
+            gap,
+            ..Layout::default()
+        },
+    )
+}
+
+/// Build an archive by hand with the given layout.
+pub fn build_zip_with(entries: &[TestEntry], layout: &Layout) -> Vec<u8> {
+    let mut out = layout.prefix.to_vec();
+    let mut cd = Vec::new();
+    for entry in entries {
+        let payload = entry.payload();
+        let crc = entry.crc32();
+        let offset = out.len() as u32;
+        let size = payload.len() as u32;
+        let uncompressed = entry.data.len() as u32;
+
+        out.extend_from_slice(b"PK\x03\x04");
+        out.extend_from_slice(&entry.version_needed.to_le_bytes());
+        out.extend_from_slice(&entry.flags.to_le_bytes());
+        out.extend_from_slice(&entry.method.to_le_bytes());
+        out.extend_from_slice(&entry.time.to_le_bytes());
+        out.extend_from_slice(&entry.date.to_le_bytes());
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&uncompressed.to_le_bytes());
+        out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(entry.local_extra.len() as u16).to_le_bytes());
+        out.extend_from_slice(entry.name);
+        out.extend_from_slice(&entry.local_extra);
+        out.extend_from_slice(&payload);
+
+        // ZIP64 fields go in a single extra field, in this order
+        let mut zip64 = Vec::new();
+        let cd_uncompressed = match entry.uncompressed_size {
+            Some(size) if size >= u32::MAX as u64 => {
+                zip64.extend_from_slice(&size.to_le_bytes());
+                u32::MAX
+            }
+            Some(size) => size as u32,
+            None => uncompressed,
+        };
+        let offset = match entry.lfh_offset {
+            Some(lfh_offset) => {
+                zip64.extend_from_slice(&lfh_offset.to_le_bytes());
+                u32::MAX
+            }
+            None => offset,
+        };
+        let mut extra = entry.extra.clone();
+        if !zip64.is_empty() {
+            extra.extend_from_slice(&[0x01, 0x00, zip64.len() as u8, 0]);
+            extra.extend_from_slice(&zip64);
+        }
+        let (made_by, attributes) = match entry.unix_mode {
+            Some(mode) => (3 << 8 | 20, mode << 16),
+            None => (20, 0),
+        };
+        cd.extend_from_slice(b"PK\x01\x02");
+        cd.extend_from_slice(&(made_by as u16).to_le_bytes());
+        cd.extend_from_slice(&entry.version_needed.to_le_bytes());
+        cd.extend_from_slice(&entry.flags.to_le_bytes());
+        cd.extend_from_slice(&entry.method.to_le_bytes());
+        cd.extend_from_slice(&entry.time.to_le_bytes());
+        cd.extend_from_slice(&entry.date.to_le_bytes());
+        cd.extend_from_slice(&crc.to_le_bytes());
+        cd.extend_from_slice(&entry.cd_size.unwrap_or(size).to_le_bytes());
+        cd.extend_from_slice(&cd_uncompressed.to_le_bytes());
+        cd.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+        cd.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        cd.extend_from_slice(&(entry.comment.len() as u16).to_le_bytes());
+        cd.extend_from_slice(&[0; 4]); // Disk, internal attributes
+        cd.extend_from_slice(&attributes.to_le_bytes());
+        cd.extend_from_slice(&offset.to_le_bytes());
+        cd.extend_from_slice(entry.name);
+        cd.extend_from_slice(&extra);
+        cd.extend_from_slice(entry.comment);
+    }
+
+    out.extend_from_slice(layout.gap);
+    let cd_offset = out.len() as u32;
+    out.extend_from_slice(&cd);
+    out.extend_from_slice(b"PK\x05\x06");
+    // This disk, and the disk holding the Central Directory
+    out.extend_from_slice(&layout.disk_number.to_le_bytes());
+    out.extend_from_slice(&layout.disk_number.to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    let cd_size = layout.cd_size.unwrap_or(cd.len() as u32);
+    out.extend_from_slice(&cd_size.to_le_bytes());
+    out.extend_from_slice(&cd_offset.to_le_bytes());
+    out.extend_from_slice(&(layout.comment.len() as u16).to_le_bytes());
+    out.extend_from_slice(layout.comment);
+    out
+}
+
+/// Build an End of Central Directory record without a comment.
+pub fn eocd(entries: u16, cd_size: u32, cd_offset: u32) -> Vec<u8> {
+    let mut eocd = b"PK\x05\x06".to_vec();
+    eocd.extend_from_slice(&[0; 4]); // Disk numbers
+    eocd.extend_from_slice(&entries.to_le_bytes());
+    eocd.extend_from_slice(&entries.to_le_bytes());
+    eocd.extend_from_slice(&cd_size.to_le_bytes());
+    eocd.extend_from_slice(&cd_offset.to_le_bytes());
+    eocd.extend_from_slice(&0u16.to_le_bytes());
+    eocd
+}
+
+/// Offset of the End of Central Directory in an archive without a comment.
+pub fn eocd_offset(zip: &[u8]) -> usize {
+    zip.len() - 22
+}
+
+/// Offset of the Central Directory recorded by an archive's EOCD.
+pub fn cd_offset(zip: &[u8]) -> usize {
+    let eocd = eocd_offset(zip);
+    u32::from_le_bytes(zip[eocd + 16..eocd + 20].try_into().unwrap()) as usize
+}
+
+/// Compress `data` as raw DEFLATE.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// CRC-32 of `data`.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+/// Build an APK Signing Block holding one ID-value pair.
+pub fn apk_signing_block(value: &[u8]) -> Vec<u8> {
+    let pair_len = 4 + value.len() as u64;
+    // The size fields count everything after the first one
+    let size = 8 + pair_len + 8 + 16;
+    let mut block = Vec::new();
+    block.extend_from_slice(&size.to_le_bytes());
+    block.extend_from_slice(&pair_len.to_le_bytes());
+    block.extend_from_slice(&0x7109871au32.to_le_bytes());
+    block.extend_from_slice(value);
+    block.extend_from_slice(&size.to_le_bytes());
+    block.extend_from_slice(b"APK Sig Block 42");
+    block
+}
+
+/// Open an in-memory archive.
+pub fn extractor(data: Vec<u8>) -> ZipExtractor<MemoryReader> {
+    ZipExtractor::new(Arc::new(MemoryReader::new(data)))
+}
+
+/// Create an empty scratch directory for one test.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("runzip-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write `zip` to `name` in `dir` and open it.
+pub fn open(dir: &Path, name: &str, zip: &[u8]) -> ZipExtractor<LocalFileReader> {
+    let path = dir.join(name);
+    std::fs::write(&path, zip).unwrap();
+    ZipExtractor::new(Arc::new(LocalFileReader::new(&path).unwrap()))
+}
+
+/// Run the runzip binary in `dir`.
+pub fn runzip(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_runzip"))
+        .args(args)
+        .current_dir(dir)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap()
+}
+
+/// Standard output of a run, which must have succeeded.
+pub fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// A minimal HTTP server for [`HttpRangeReader`](runzip::HttpRangeReader)
+/// tests.
+pub struct MockServer {
+    /// URL of the served file
+    pub url: String,
+    /// Header block of every request received, in order
+    pub requests: Arc<Mutex<Vec<String>>>,
+}
+
+/// A canned response for one request, replacing the normal one.
+///
+/// The connection is closed after a canned response, so it may stop short
+/// of its `Content-Length` to simulate a dropped connection.
+pub type Override = fn(index: usize, request: &str) -> Option<Vec<u8>>;
+
+/// Serve `data` at `/archive.zip`, answering HEAD and Range requests.
+pub async fn serve(data: Vec<u8>) -> MockServer {
+    serve_with(data, |_, _| None).await
+}
+
+/// Like [`serve()`], but `respond` may answer any request itself, given
+/// its index (counting from 0) and header block.
+pub async fn serve_with(data: Vec<u8>, respond: Override) -> MockServer {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/archive.zip", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let data = Arc::new(data);
+
+    let log = requests.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let (log, data) = (log.clone(), data.clone());
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                loop {
+                    let mut head = String::new();
+                    while !head.ends_with("\r\n\r\n") {
+                        if stream.read_line(&mut head).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                    }
+                    let index = {
+                        let mut log = log.lock().unwrap();
+                        log.push(head.clone());
+                        log.len() - 1
+                    };
+                    let (response, close) = match respond(index, &head) {
+                        Some(response) => (response, true),
+                        None => (default_response(&head, &data), false),
+                    };
+                    if stream.get_mut().write_all(&response).await.is_err() || close {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    MockServer { url, requests }
+}
+
+/// Answer a HEAD request with the size, and a GET with the requested range.
+pub fn default_response(head: &str, data: &[u8]) -> Vec<u8> {
+    if head.starts_with("HEAD") {
+        return format!(
+            "HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: {}\r\n\r\n",
+            data.len()
+        )
+        .into_bytes();
+    }
+
+    let range = head
+        .lines()
+        .find_map(|line| {
+            line.to_ascii_lowercase()
+                .strip_prefix("range: bytes=")
+                .map(str::to_string)
+        })
+        .and_then(|range| {
+            let (start, end) = range.trim().split_once('-')?;
+            Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+        });
+    let Some((start, end)) = range else {
+        return format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", data.len())
+            .into_bytes()
+            .into_iter()
+            .chain(data.iter().copied())
+            .collect();
+    };
+    let end = end.min(data.len() - 1);
+    let mut response = format!(
+        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\n\r\n",
+        start,
+        end,
+        data.len(),
+        end + 1 - start
+    )
+    .into_bytes();
+    response.extend_from_slice(&data[start..=end]);
+    response
+}
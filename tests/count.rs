@@ -0,0 +1,51 @@
+//! Counting entries with `--count`.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir, stdout};
+
+#[test]
+fn count_modes_and_sizes() {
+    let dir = scratch_dir("count");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("docs/"),
+            TestEntry::new("docs/a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    assert_eq!(stdout(&runzip(&dir, &["--count", "a.zip"])), "3\n");
+    assert_eq!(stdout(&runzip(&dir, &["--count=files", "a.zip"])), "2\n");
+    assert_eq!(stdout(&runzip(&dir, &["--count=dirs", "a.zip"])), "1\n");
+    assert_eq!(
+        stdout(&runzip(&dir, &["--count=files", "--sum-sizes", "a.zip"])),
+        "2\t9\n"
+    );
+    assert!(!runzip(&dir, &["--sum-sizes", "a.zip"]).status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn summed_sizes_do_not_overflow() {
+    let dir = scratch_dir("count-overflow");
+    let huge = |name| TestEntry {
+        uncompressed_size: Some(u64::MAX),
+        ..TestEntry::new(name, b"x")
+    };
+    let zip = build_zip(&[huge("a"), huge("b")], &[]);
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let total = 2 * u64::MAX as u128;
+    assert_eq!(
+        stdout(&runzip(&dir, &["--count", "--sum-sizes", "a.zip"])),
+        format!("2\t{}\n", total)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,78 @@
+//! CRC-32 checks on extracted data.
+
+mod common;
+
+use common::{TestEntry, build_zip, extractor};
+
+/// Data DEFLATE can't shrink, so the encoder stores it in raw blocks
+/// and a flipped byte still decodes, to different data.
+fn incompressible(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+/// Both methods of storing `data` as `a.bin`.
+fn archives(data: &[u8]) -> [Vec<u8>; 2] {
+    [
+        build_zip(&[TestEntry::new("a.bin", data)], &[]),
+        build_zip(&[TestEntry::deflated("a.bin", data)], &[]),
+    ]
+}
+
+/// Flip a byte in the middle of the only entry's data.
+fn flip_data_byte(zip: &mut [u8]) {
+    let field = |at: usize| u16::from_le_bytes([zip[at], zip[at + 1]]) as usize;
+    let compressed = u32::from_le_bytes(zip[18..22].try_into().unwrap()) as usize;
+    let data_start = 30 + field(26) + field(28);
+    zip[data_start + compressed / 2] ^= 0x20;
+}
+
+#[tokio::test]
+async fn crc_of_a_good_archive_is_accepted() {
+    let data = incompressible(1000);
+    for zip in archives(&data) {
+        let extractor = extractor(zip);
+        let entry = extractor.list_files().await.unwrap().remove(0);
+        assert_eq!(extractor.extract_to_memory(&entry).await.unwrap(), data);
+        extractor.verify(&entry).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn byte_flipped_entries_fail_the_crc_check() {
+    let data = incompressible(1000);
+    for mut zip in archives(&data) {
+        flip_data_byte(&mut zip);
+
+        let checked = extractor(zip.clone());
+        let entry = checked.list_files().await.unwrap().remove(0);
+        let err = checked.extract_to_memory(&entry).await.unwrap_err();
+        assert!(
+            err.to_string().starts_with("CRC mismatch for a.bin"),
+            "{:#}",
+            err
+        );
+        let err = checked
+            .extract_into(&entry, &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().starts_with("CRC mismatch for a.bin"),
+            "{:#}",
+            err
+        );
+
+        // Opting out hands back the damaged data
+        let unchecked = extractor(zip).with_verify_crc(false);
+        let damaged = unchecked.extract_to_memory(&entry).await.unwrap();
+        assert_eq!(damaged.len(), data.len());
+        assert_ne!(damaged, data);
+    }
+}
@@ -0,0 +1,162 @@
+//! Locating entry data through the Local File Headers.
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+use runzip::zip::ZipParser;
+use runzip::{LocalFileReader, ReadAt, ZipExtractor};
+
+use common::{TestEntry, build_zip, scratch_dir};
+
+/// Reader counting reads and recording the longest one.
+struct CountingReader {
+    inner: LocalFileReader,
+    reads: AtomicU64,
+    longest: AtomicU64,
+}
+
+impl CountingReader {
+    fn open(path: &Path) -> Self {
+        Self {
+            inner: LocalFileReader::new(path).unwrap(),
+            reads: AtomicU64::new(0),
+            longest: AtomicU64::new(0),
+        }
+    }
+
+    fn reset(&self) {
+        self.reads.store(0, Ordering::Relaxed);
+        self.longest.store(0, Ordering::Relaxed);
+    }
+}
+
+#[async_trait::async_trait]
+impl ReadAt for CountingReader {
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.longest.fetch_max(buf.len() as u64, Ordering::Relaxed);
+        self.inner.read_at(offset, buf).await
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+}
+
+/// Write `zip` to `dir` and open a counting parser for it.
+fn parser(dir: &Path, zip: &[u8]) -> ZipParser<CountingReader> {
+    let path = dir.join("a.zip");
+    std::fs::write(&path, zip).unwrap();
+    ZipParser::new(Arc::new(CountingReader::open(&path)))
+}
+
+#[tokio::test]
+async fn batched_offsets_match_single_lookups_with_fewer_reads() {
+    let dir = scratch_dir("data-offsets");
+    let names: Vec<String> = (0..50).map(|i| format!("dir/file{}.txt", i)).collect();
+    let entries: Vec<_> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| TestEntry {
+            // Vary the header lengths so offsets aren't evenly spaced
+            local_extra: vec![0; i % 7],
+            ..TestEntry::new(name, name.as_bytes())
+        })
+        .collect();
+    let parser = parser(&dir, &build_zip(&entries, &[]));
+
+    // Look the entries up in an order other than the archive's
+    let mut listed = parser.list_files().await.unwrap();
+    listed.reverse();
+    parser.reader().reset();
+    let offsets = parser.data_offsets(&listed).await.unwrap();
+    let batched_reads = parser.reader().reads.load(Ordering::Relaxed);
+
+    parser.reader().reset();
+    for (entry, offset) in listed.iter().zip(&offsets) {
+        assert_eq!(*offset, parser.get_data_offset(entry).await.unwrap());
+    }
+    assert_eq!(parser.reader().reads.load(Ordering::Relaxed), 50);
+    assert_eq!(batched_reads, 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn batched_reads_are_capped() {
+    let dir = scratch_dir("data-offsets-capped");
+    // 200 files of 20 KiB: each gap is small, the whole span 4 MiB
+    let data = vec![0x55u8; 20 * 1024];
+    let names: Vec<String> = (0..200).map(|i| format!("f{}", i)).collect();
+    let entries: Vec<_> = names.iter().map(|n| TestEntry::new(n, &data)).collect();
+    let parser = parser(&dir, &build_zip(&entries, &[]));
+
+    let listed = parser.list_files().await.unwrap();
+    parser.reader().reset();
+    let offsets = parser.data_offsets(&listed).await.unwrap();
+    assert!(parser.reader().longest.load(Ordering::Relaxed) <= 1024 * 1024);
+    assert!(parser.reader().reads.load(Ordering::Relaxed) < 10);
+    for (entry, offset) in listed.iter().zip(&offsets) {
+        assert_eq!(*offset, parser.get_data_offset(entry).await.unwrap());
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn header_offsets_near_the_end_of_the_range_are_errors() {
+    let dir = scratch_dir("data-offsets-overflow");
+    let zip = build_zip(
+        &[
+            TestEntry {
+                lfh_offset: Some(u64::MAX - 100),
+                ..TestEntry::new("a", b"a")
+            },
+            TestEntry {
+                lfh_offset: Some(u64::MAX - 5),
+                ..TestEntry::new("b", b"b")
+            },
+        ],
+        &[],
+    );
+    let parser = parser(&dir, &zip);
+
+    let listed = parser.list_files().await.unwrap();
+    let err = parser.data_offsets(&listed).await.unwrap_err();
+    assert!(err.to_string().contains("is out of bounds"), "{:#}", err);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn selection_span_covers_the_selected_data() {
+    let dir = scratch_dir("selection-span");
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("docs/b.txt", b"beta"),
+            TestEntry::new("docs/c.txt", b"gamma"),
+            TestEntry::new("z.txt", b"zeta"),
+        ],
+        &[],
+    );
+    let path = dir.join("a.zip");
+    std::fs::write(&path, &zip).unwrap();
+    let extractor = ZipExtractor::new(Arc::new(LocalFileReader::new(&path).unwrap()));
+    let entries = extractor.list_files().await.unwrap();
+
+    // b.txt's data follows its 30-byte header and 10-byte name at 40
+    let docs = &entries[1..3];
+    let (start, end) = extractor.selection_byte_span(docs).await.unwrap();
+    assert_eq!(start, docs[0].lfh_offset + 40);
+    assert_eq!(end, docs[1].lfh_offset + 40 + 5);
+    assert_eq!(&zip[start as usize..start as usize + 4], b"beta");
+
+    assert_eq!(extractor.selection_byte_span(&[]).await.unwrap(), (0, 0));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
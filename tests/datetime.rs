@@ -0,0 +1,82 @@
+//! Validated DOS modification times and their conversions.
+
+use runzip::DosDateTime;
+
+#[test]
+fn dos_fields_round_trip() {
+    let dt = DosDateTime::new(2024, 2, 29, 23, 59, 58).unwrap();
+    let (date, time) = dt.to_dos();
+    assert_eq!(DosDateTime::from_dos(date, time).unwrap(), dt);
+
+    // Odd seconds are rounded down when packed
+    let (date, time) = DosDateTime::new(2024, 2, 29, 23, 59, 59).unwrap().to_dos();
+    assert_eq!(DosDateTime::from_dos(date, time).unwrap(), dt);
+}
+
+#[test]
+fn system_time_round_trips_across_the_range() {
+    for dt in [
+        DosDateTime::new(1980, 1, 1, 0, 0, 0).unwrap(),
+        DosDateTime::new(2000, 2, 29, 12, 30, 14).unwrap(),
+        DosDateTime::new(2107, 12, 31, 23, 59, 58).unwrap(),
+    ] {
+        assert_eq!(
+            DosDateTime::from_system_time(dt.to_system_time()).unwrap(),
+            dt
+        );
+        assert_eq!(DosDateTime::from_unix(dt.to_unix()).unwrap(), dt);
+    }
+    assert_eq!(
+        DosDateTime::new(1980, 1, 1, 0, 0, 0).unwrap().to_unix(),
+        315532800
+    );
+}
+
+#[test]
+fn invalid_fields_are_rejected() {
+    assert!(DosDateTime::new(1979, 12, 31, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2108, 1, 1, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2024, 0, 1, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2024, 13, 1, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2023, 2, 29, 0, 0, 0).is_err());
+    // Not a leap year: divisible by 100 but not 400
+    assert!(DosDateTime::new(2100, 2, 29, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2024, 4, 31, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2024, 1, 0, 0, 0, 0).is_err());
+    assert!(DosDateTime::new(2024, 1, 1, 24, 0, 0).is_err());
+    assert!(DosDateTime::new(2024, 1, 1, 0, 60, 0).is_err());
+    assert!(DosDateTime::new(2024, 1, 1, 0, 0, 60).is_err());
+
+    // The all-zero date some writers store for an unknown time
+    assert!(DosDateTime::from_dos(0, 0).is_err());
+    assert!(DosDateTime::from_unix(0).is_err());
+    assert!(DosDateTime::from_unix(i64::MIN / 2).is_err());
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_round_trips() {
+    use chrono::NaiveDateTime;
+
+    let dt = DosDateTime::new(2024, 2, 29, 23, 59, 58).unwrap();
+    let naive = NaiveDateTime::from(dt);
+    assert_eq!(naive.to_string(), "2024-02-29 23:59:58");
+    assert_eq!(DosDateTime::try_from(naive).unwrap(), dt);
+
+    // Fractions of a second are dropped
+    let fraction = naive + chrono::Duration::milliseconds(900);
+    assert_eq!(DosDateTime::try_from(fraction).unwrap(), dt);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn chrono_out_of_range_is_rejected() {
+    use chrono::NaiveDate;
+
+    for (y, m, d) in [(1979, 12, 31), (2108, 1, 1), (-5, 1, 1), (70000, 1, 1)] {
+        let naive = NaiveDate::from_ymd_opt(y, m, d).and_then(|date| date.and_hms_opt(0, 0, 0));
+        if let Some(naive) = naive {
+            assert!(DosDateTime::try_from(naive).is_err(), "{}", naive);
+        }
+    }
+}
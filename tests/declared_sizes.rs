@@ -0,0 +1,107 @@
+//! Entries whose declared sizes don't match their data.
+
+mod common;
+
+use runzip::ZipError;
+
+use common::{TestEntry, build_zip, open, scratch_dir};
+
+fn assert_size_exceeded(err: anyhow::Error, expected: u64) {
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::SizeExceeded { declared, .. }) if *declared == expected
+        ),
+        "{:#}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn data_longer_than_declared_is_rejected() {
+    let dir = scratch_dir("size-exceeded");
+    let data = b"hello world, hello world";
+    for entry in [
+        TestEntry::new("a.txt", data),
+        TestEntry::deflated("a.txt", data),
+    ] {
+        let zip = build_zip(
+            &[TestEntry {
+                uncompressed_size: Some(5),
+                ..entry
+            }],
+            &[],
+        );
+        let extractor = open(&dir, "a.zip", &zip);
+        let entries = extractor.list_files().await.unwrap();
+        let err = extractor.extract_to_memory(&entries[0]).await.unwrap_err();
+        assert_size_exceeded(err, 5);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn huge_declared_size_is_not_allocated() {
+    let dir = scratch_dir("size-huge");
+    for entry in [
+        TestEntry::new("a.txt", b"hello world"),
+        TestEntry::deflated("a.txt", b"hello world"),
+    ] {
+        let zip = build_zip(
+            &[TestEntry {
+                uncompressed_size: Some(1 << 50),
+                ..entry
+            }],
+            &[],
+        );
+        let extractor = open(&dir, "a.zip", &zip);
+        let entries = extractor.list_files().await.unwrap();
+        assert_eq!(entries[0].uncompressed_size, 1 << 50);
+        // Reserving the declared size would abort the process
+        if let Ok(data) = extractor.extract_to_memory(&entries[0]).await {
+            assert_eq!(data, b"hello world");
+        }
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn compressed_data_past_the_archive_is_rejected() {
+    let dir = scratch_dir("size-past-end");
+    let zip = build_zip(
+        &[TestEntry {
+            cd_size: Some(0x7FFF_FFFF),
+            ..TestEntry::new("a.txt", b"hello world")
+        }],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+    let err = extractor.extract_to_memory(&entries[0]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Data of a.txt (2147483647 bytes at offset 35) extends past the end of the archive (119 bytes)"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn local_header_lengths_past_the_archive_are_rejected() {
+    let dir = scratch_dir("size-lfh-lengths");
+    let mut zip = build_zip(&[TestEntry::new("a.txt", b"hello world")], &[]);
+    // Extra field length of the Local File Header
+    zip[28..30].copy_from_slice(&0xFFFFu16.to_le_bytes());
+
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+    let err = extractor.extract_to_memory(&entries[0]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Local File Header of a.txt at offset 0 is truncated: 5-byte name and 65535-byte extra field run past the end of the archive"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,139 @@
+//! Checking data descriptors against the Central Directory.
+
+mod common;
+
+use std::path::Path;
+use std::sync::Arc;
+
+use runzip::{LocalFileReader, Warning, ZipExtractor};
+
+use common::{TestEntry, build_zip, crc32, extractor};
+
+/// A STORED entry with bit 3 set, followed by the given descriptor.
+fn with_descriptor(name: &'static str, descriptor: Vec<u8>) -> TestEntry<'static> {
+    let mut payload = b"hello".to_vec();
+    payload.extend_from_slice(&descriptor);
+    TestEntry {
+        flags: 0x0008,
+        payload: Some(payload),
+        cd_size: Some(5),
+        ..TestEntry::new(name, b"hello")
+    }
+}
+
+/// A descriptor with 4-byte sizes, optionally with its signature.
+fn descriptor(signature: bool, crc: u32, size: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    if signature {
+        out.extend_from_slice(b"PK\x07\x08");
+    }
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out.extend_from_slice(&size.to_le_bytes());
+    out
+}
+
+#[tokio::test]
+async fn descriptors_must_match_when_data_is_verified() {
+    let crc = crc32(b"hello");
+    let mut zip64 = b"PK\x07\x08".to_vec();
+    zip64.extend_from_slice(&crc.to_le_bytes());
+    zip64.extend_from_slice(&5u64.to_le_bytes());
+    zip64.extend_from_slice(&5u64.to_le_bytes());
+    let zip = build_zip(
+        &[
+            with_descriptor("signed.txt", descriptor(true, crc, 5)),
+            with_descriptor("unsigned.txt", descriptor(false, crc, 5)),
+            with_descriptor("zip64.txt", zip64),
+            with_descriptor("bad-crc.txt", descriptor(true, crc ^ 1, 5)),
+            with_descriptor("bad-size.txt", descriptor(false, crc, 4)),
+        ],
+        &[],
+    );
+
+    // Unverified, nothing is checked
+    let unchecked = extractor(zip.clone()).with_verify_crc(false);
+    let entries = unchecked.list_files().await.unwrap();
+    for entry in &entries {
+        assert_eq!(unchecked.extract_to_memory(entry).await.unwrap(), b"hello");
+    }
+
+    // Verifying data checks descriptors too, as does asking for just that
+    let checked = extractor(zip.clone());
+    let descriptors_only = extractor(zip)
+        .with_verify_crc(false)
+        .with_check_descriptors(true);
+    for extractor in [&checked, &descriptors_only] {
+        for entry in &entries[..3] {
+            assert_eq!(extractor.extract_to_memory(entry).await.unwrap(), b"hello");
+        }
+        for entry in &entries[3..] {
+            assert!(extractor.extract_to_memory(entry).await.is_err());
+            let mut out = Vec::new();
+            let err = extractor
+                .extract_to_writer(entry, &mut out)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Data descriptor"), "{:#}", err);
+            assert!(out.is_empty());
+        }
+    }
+
+    let err = checked.extract_to_memory(&entries[3]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Data descriptor for bad-crc.txt disagrees with the Central Directory: \
+             CRC-32 {:08x}, 5 compressed and 5 uncompressed bytes, expected {:08x}, 5 and 5",
+            crc ^ 1,
+            crc
+        )
+    );
+    let err = checked.extract_to_memory(&entries[4]).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("4 compressed and 4 uncompressed bytes"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn piped_archives_use_the_central_directory() {
+    // Written by `seq 1 2000 | zip -q - - | cat`: bit 3 is set, the local
+    // header's CRC-32 is zero and its sizes are left to a ZIP64 field
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/streamed.zip");
+    let extractor = ZipExtractor::new(Arc::new(LocalFileReader::new(Path::new(path)).unwrap()));
+    let entries = extractor.list_files().await.unwrap();
+    assert!(entries[0].has_data_descriptor());
+
+    let expected: String = (1..=2000).map(|i| format!("{}\n", i)).collect();
+    let data = extractor.extract_to_memory(&entries[0]).await.unwrap();
+    assert!(data == expected.as_bytes());
+    assert!(extractor.take_warnings().is_empty());
+}
+
+#[tokio::test]
+async fn local_headers_disagreeing_without_a_descriptor_are_reported() {
+    let mut zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+    let crc = crc32(b"hello");
+    zip[14..18].copy_from_slice(&(crc ^ 1).to_le_bytes());
+
+    // The Central Directory's values are used
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(
+        extractor.extract_to_memory(&entries[0]).await.unwrap(),
+        b"hello"
+    );
+    let warnings = extractor.take_warnings();
+    assert!(
+        matches!(
+            &warnings[..],
+            [Warning::LocalHeaderMismatch { name, local: (local, 5, 5), central: (central, 5, 5) }]
+                if name == "a.txt" && *local == crc ^ 1 && *central == crc
+        ),
+        "{:?}",
+        warnings
+    );
+}
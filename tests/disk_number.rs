@@ -0,0 +1,83 @@
+//! Disk numbers in the End of Central Directory.
+
+mod common;
+
+use runzip::{Warning, ZipError};
+
+use common::{
+    Layout, TestEntry, build_zip_with, eocd_offset, extractor, runzip, scratch_dir, stdout,
+};
+
+/// An archive whose end record claims to be on disk `disk`.
+fn on_disk(disk: u16) -> Vec<u8> {
+    build_zip_with(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::deflated("b.txt", b"bravo bravo bravo"),
+        ],
+        &Layout {
+            disk_number: disk,
+            ..Layout::default()
+        },
+    )
+}
+
+#[tokio::test]
+async fn complete_archives_with_a_disk_number_are_read() {
+    let extractor = extractor(on_disk(3));
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        extractor.extract_to_memory(&entries[1]).await.unwrap(),
+        b"bravo bravo bravo"
+    );
+    assert!(matches!(
+        extractor.take_warnings()[..],
+        [Warning::DiskNumberIgnored { disk: 3 }]
+    ));
+}
+
+#[tokio::test]
+async fn spanned_archives_are_rejected() {
+    // The directory starts on an earlier disk
+    let mut zip = on_disk(1);
+    let eocd = eocd_offset(&zip);
+    zip[eocd + 6..eocd + 8].copy_from_slice(&0u16.to_le_bytes());
+    let err = extractor(zip).list_files().await.unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::SpannedArchive { disk: 1 })
+        ),
+        "{:#}",
+        err
+    );
+    assert!(
+        err.to_string().contains("disk 2 of a multi-disk"),
+        "{}",
+        err
+    );
+
+    // Only some of the entries are on this disk
+    let mut zip = on_disk(1);
+    let eocd = eocd_offset(&zip);
+    zip[eocd + 8..eocd + 10].copy_from_slice(&1u16.to_le_bytes());
+    let err = extractor(zip).list_files().await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<ZipError>(),
+        Some(ZipError::SpannedArchive { .. })
+    ));
+}
+
+#[test]
+fn cli_extracts_with_a_warning() {
+    let dir = scratch_dir("disk-number");
+    std::fs::write(dir.join("a.zip"), on_disk(2)).unwrap();
+
+    let output = runzip(&dir, &["-d", "out", "a.zip"]);
+    stdout(&output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("claims disk number 2"));
+    assert_eq!(std::fs::read(dir.join("out/a.txt")).unwrap(), b"alpha");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,349 @@
+//! Entry metadata decoded from the Central Directory.
+
+mod common;
+
+use runzip::Charset;
+use runzip::zip::{CompressionMethod, DeflateLevelHint};
+
+use common::{Layout, TestEntry, build_zip, build_zip_with, open, scratch_dir};
+
+#[test]
+fn deflate_level_hint_is_decoded_from_bits_1_and_2() {
+    let hints = [0b000, 0b010, 0b100, 0b110].map(DeflateLevelHint::from_flags);
+    assert_eq!(
+        hints,
+        [
+            DeflateLevelHint::Normal,
+            DeflateLevelHint::Maximum,
+            DeflateLevelHint::Fast,
+            DeflateLevelHint::SuperFast,
+        ]
+    );
+    assert_eq!(hints.map(|hint| hint.as_char()), ['N', 'X', 'F', 'S']);
+    // Other flags don't affect the hint
+    assert_eq!(
+        DeflateLevelHint::from_flags(0x0809),
+        DeflateLevelHint::Normal
+    );
+}
+
+#[tokio::test]
+async fn method_labels_follow_unzip() {
+    let dir = scratch_dir("method-labels");
+    let zip = build_zip(
+        &[
+            TestEntry::new("stored", b"data"),
+            TestEntry {
+                flags: 0b010,
+                ..TestEntry::deflated("max", b"data")
+            },
+            TestEntry {
+                flags: 0b110,
+                ..TestEntry::deflated("fastest", b"data")
+            },
+        ],
+        &[],
+    );
+    let entries = open(&dir, "a.zip", &zip).list_files().await.unwrap();
+
+    assert_eq!(entries[0].deflate_level_hint(), None);
+    assert_eq!(
+        entries[1].deflate_level_hint(),
+        Some(DeflateLevelHint::Maximum)
+    );
+    let labels: Vec<_> = entries.iter().map(|e| e.method_label()).collect();
+    assert_eq!(labels, ["Stored", "Defl:X", "Defl:S"]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unsupported_methods_have_registered_names() {
+    assert_eq!(
+        CompressionMethod::Unknown(12).registered_name(),
+        Some("BZIP2")
+    );
+    assert_eq!(
+        CompressionMethod::Unknown(3).registered_name(),
+        Some("Reduced")
+    );
+    assert_eq!(CompressionMethod::Unknown(200).registered_name(), None);
+    assert_eq!(CompressionMethod::Deflate.registered_name(), None);
+}
+
+#[tokio::test]
+async fn unsupported_method_errors_name_the_method_and_entry() {
+    let dir = scratch_dir("unsupported-method");
+    let zip = build_zip(
+        &[
+            TestEntry {
+                method: 12,
+                ..TestEntry::new("a.bz2", b"data")
+            },
+            TestEntry {
+                method: 200,
+                ..TestEntry::new("b.bin", b"data")
+            },
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let err = extractor.extract_to_memory(&entries[0]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unsupported compression method 12 (BZIP2) for a.bz2"
+    );
+    let err = extractor.extract_to_memory(&entries[1]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unsupported compression method 200 for b.bin"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn aligned_local_extra_fields_are_skipped() {
+    let dir = scratch_dir("aligned-lfh");
+    // zipalign-style padding: an 0xD935 record, then plain zeros
+    let zip = build_zip(
+        &[
+            TestEntry {
+                local_extra: vec![0x35, 0xD9, 2, 0, 4, 0],
+                ..TestEntry::new("a.txt", b"alpha")
+            },
+            TestEntry {
+                local_extra: vec![0; 3],
+                ..TestEntry::new("b.txt", b"beta")
+            },
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(
+        extractor.extract_to_memory(&entries[0]).await.unwrap(),
+        b"alpha"
+    );
+    assert_eq!(
+        extractor.extract_to_memory(&entries[1]).await.unwrap(),
+        b"beta"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn byte_order_marks_and_leading_slashes_are_stripped() {
+    let dir = scratch_dir("normalized-names");
+    let zip = build_zip(
+        &[
+            TestEntry::new("\u{FEFF}bom.txt", b"a"),
+            TestEntry::new("//etc/abs.txt", b"b"),
+            TestEntry::new("/", b""),
+            TestEntry::new("plain.txt", b"c"),
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let names: Vec<String> = extractor
+        .list_files()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.file_name)
+        .collect();
+    // A name that would become empty is left alone
+    assert_eq!(names, ["bom.txt", "etc/abs.txt", "/", "plain.txt"]);
+
+    let warnings: Vec<String> = extractor
+        .take_warnings()
+        .iter()
+        .map(|w| w.to_string())
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "Stripped \"\\u{feff}bom.txt\" to bom.txt",
+            "Stripped \"//etc/abs.txt\" to etc/abs.txt",
+        ]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn names_without_the_utf8_flag_use_the_chosen_charset() {
+    let dir = scratch_dir("charset");
+    // "日本.txt" in Shift-JIS, and the same name in flagged UTF-8
+    let sjis = b"\x93\xfa\x96\x7b.txt";
+    let zip = build_zip(
+        &[
+            TestEntry::raw(sjis, b"a"),
+            TestEntry {
+                flags: 0x0800,
+                ..TestEntry::new("日本.txt", b"b")
+            },
+        ],
+        &[],
+    );
+    let names = |extractor: runzip::ZipExtractor<_>| async move {
+        extractor
+            .list_files()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.file_name)
+            .collect::<Vec<_>>()
+    };
+
+    let default = open(&dir, "a.zip", &zip);
+    assert_eq!(names(default).await, ["ô·û{.txt", "日本.txt"]);
+    let charset = Charset::for_label(" Shift_JIS ").unwrap();
+    let sjis = open(&dir, "a.zip", &zip).with_charset(charset);
+    assert_eq!(names(sjis).await, ["日本.txt", "日本.txt"]);
+
+    assert_eq!(Charset::for_label("IBM437").unwrap(), Charset::Cp437);
+    let err = Charset::for_label("klingon").unwrap_err();
+    assert_eq!(err.to_string(), "Unknown character set: klingon");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn raw_names_are_kept_as_stored() {
+    let dir = scratch_dir("raw-names");
+    // "café.txt" in CP437, the same name in flagged UTF-8, and a name
+    // only the caller knows how to decode
+    let zip = build_zip(
+        &[
+            TestEntry::raw(b"caf\x82.txt", b"a"),
+            TestEntry {
+                flags: 0x0800,
+                ..TestEntry::new("café.txt", b"b")
+            },
+            TestEntry::raw(b"\x93\xfa\x96\x7b.txt", b"c"),
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(entries[0].file_name, "café.txt");
+    assert_eq!(entries[0].raw_file_name, b"caf\x82.txt");
+    assert_eq!(entries[1].file_name, "café.txt");
+    assert_eq!(entries[1].raw_file_name, "café.txt".as_bytes());
+    assert_eq!(entries[2].raw_file_name, b"\x93\xfa\x96\x7b.txt");
+
+    // Salvaged entries keep them too
+    let salvaged = extractor.salvage().await.unwrap();
+    let raw: Vec<_> = salvaged.iter().map(|e| e.raw_file_name.clone()).collect();
+    let expected: Vec<_> = entries.iter().map(|e| e.raw_file_name.clone()).collect();
+    assert_eq!(raw, expected);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn archive_and_entry_comments_are_decoded() {
+    let dir = scratch_dir("comments");
+    let zip = build_zip_with(
+        &[
+            TestEntry {
+                comment: b"first \x81",
+                ..TestEntry::new("a.txt", b"a")
+            },
+            TestEntry::new("b.txt", b"b"),
+            TestEntry {
+                flags: 0x0800,
+                comment: "naïve".as_bytes(),
+                ..TestEntry::new("c.txt", b"c")
+            },
+        ],
+        &Layout {
+            comment: b"archive \x82",
+            ..Layout::default()
+        },
+    );
+
+    let (archive, comments) = open(&dir, "a.zip", &zip).comments().await.unwrap();
+    assert_eq!(archive, "archive é");
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments["a.txt"], "first ü");
+    assert_eq!(comments["c.txt"], "naïve");
+
+    let (archive, comments) = open(&dir, "b.zip", &build_zip(&[], &[]))
+        .comments()
+        .await
+        .unwrap();
+    assert!(archive.is_empty() && comments.is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn contains_looks_names_up_without_listing() {
+    let mut zip = build_zip(
+        &[
+            TestEntry::new("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0"),
+            TestEntry::dir("docs/"),
+            TestEntry::new("docs/README.md", b"readme"),
+        ],
+        &[],
+    );
+    let extractor = common::extractor(zip.clone());
+    assert!(extractor.contains("META-INF/MANIFEST.MF").await.unwrap());
+    assert!(extractor.contains("docs/").await.unwrap());
+    assert!(!extractor.contains("docs").await.unwrap());
+    assert!(!extractor.contains("meta-inf/manifest.mf").await.unwrap());
+
+    let extractor = common::extractor(zip.clone()).with_case_sensitive(false);
+    assert!(extractor.contains("meta-inf/manifest.mf").await.unwrap());
+    assert!(extractor.contains("DOCS/readme.MD").await.unwrap());
+    assert!(!extractor.contains("docs/README").await.unwrap());
+
+    // The search stops at the first match, before a damaged record
+    let last = zip.windows(4).rposition(|w| w == b"PK\x01\x02").unwrap();
+    zip[last + 28..last + 30].copy_from_slice(&[0xFF, 0xFF]);
+    let extractor = common::extractor(zip);
+    assert!(extractor.contains("META-INF/MANIFEST.MF").await.unwrap());
+    assert!(extractor.contains("missing").await.is_err());
+}
+
+#[tokio::test]
+async fn legacy_methods_are_reported_as_obsolete() {
+    use runzip::SkipReason;
+
+    let zip = build_zip(
+        &[
+            TestEntry {
+                method: 6,
+                ..TestEntry::new("old.txt", b"data")
+            },
+            TestEntry {
+                method: 12,
+                ..TestEntry::new("new.bz2", b"data")
+            },
+        ],
+        &[],
+    );
+    let extractor = common::extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let methods: Vec<_> = entries.iter().map(|e| e.compression_method).collect();
+    let names: Vec<_> = methods.iter().map(|m| m.name()).collect();
+    assert_eq!(names, ["Imploded", "BZIP2"]);
+    assert!((1..=7).all(|id| CompressionMethod::from_u16(id).is_obsolete()));
+    assert!(!methods[1].is_obsolete() && !CompressionMethod::Deflate.is_obsolete());
+
+    let err = extractor.extract_to_memory(&entries[0]).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Compression method 6 (Imploded) is obsolete and unsupported for old.txt"
+    );
+    assert_eq!(
+        SkipReason::UnsupportedMethod(methods[0]).to_string(),
+        "compression method 6 (Imploded) is obsolete and unsupported"
+    );
+}
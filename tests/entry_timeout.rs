@@ -0,0 +1,96 @@
+//! Bounding the time spent on one entry with `with_entry_timeout`.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+use runzip::{MemoryReader, ReadAt, ZipError, ZipExtractor};
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+/// A reader that, once armed, stalls on reads at or after `stall_from`.
+struct StallingReader {
+    inner: MemoryReader,
+    stall_from: u64,
+    armed: AtomicBool,
+}
+
+#[async_trait::async_trait]
+impl ReadAt for StallingReader {
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        if self.armed.load(Ordering::Relaxed) && offset >= self.stall_from {
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        }
+        self.inner.read_at(offset, buf).await
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+}
+
+#[tokio::test]
+async fn stalled_entries_time_out_and_leave_no_file() {
+    let dir = scratch_dir("entry-timeout");
+    // Several streamed chunks, of which only the first arrives
+    let data = vec![b'x'; 1 << 20];
+    let zip = build_zip(&[TestEntry::new("big.bin", &data)], &[]);
+    let reader = Arc::new(StallingReader {
+        stall_from: 30 + 7 + 256 * 1024,
+        inner: MemoryReader::new(zip),
+        armed: AtomicBool::new(false),
+    });
+    let extractor =
+        ZipExtractor::new(reader.clone()).with_entry_timeout(Duration::from_millis(200));
+    let entry = extractor.list_files().await.unwrap().remove(0);
+    reader.armed.store(true, Ordering::Relaxed);
+
+    let path = dir.join("big.bin");
+    let err = extractor.extract_to_file(&entry, &path).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ZipError>(),
+        Some(&ZipError::Timeout {
+            name: "big.bin".to_string(),
+            timeout: Duration::from_millis(200),
+        })
+    );
+    assert_eq!(err.to_string(), "Extracting big.bin took longer than 0.2s");
+    assert!(!path.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn entries_within_the_timeout_are_unaffected() {
+    let zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+    let extractor = ZipExtractor::new(Arc::new(MemoryReader::new(zip)))
+        .with_entry_timeout(Duration::from_secs(60));
+    let entry = extractor.list_files().await.unwrap().remove(0);
+    let mut out = Vec::new();
+    extractor.extract_to_writer(&entry, &mut out).await.unwrap();
+    assert_eq!(out, b"hello");
+}
+
+#[test]
+fn cli_rejects_a_zero_timeout() {
+    let dir = scratch_dir("entry-timeout-cli");
+    std::fs::write(
+        dir.join("a.zip"),
+        build_zip(&[TestEntry::new("a.txt", b"hello")], &[]),
+    )
+    .unwrap();
+
+    assert!(
+        !runzip(&dir, &["--entry-timeout", "0", "a.zip"])
+            .status
+            .success()
+    );
+    let output = runzip(&dir, &["--entry-timeout", "60", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(std::fs::read(dir.join("a.txt")).unwrap(), b"hello");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,54 @@
+//! Configurable search window for the End of Central Directory.
+
+mod common;
+
+use common::{Layout, TestEntry, build_zip, build_zip_with, open, scratch_dir};
+
+#[tokio::test]
+async fn window_must_reach_the_end_record() {
+    let dir = scratch_dir("eocd-window");
+    let zip = build_zip_with(
+        &[TestEntry::new("a.txt", b"alpha")],
+        &Layout {
+            comment: &[b'c'; 1000],
+            ..Layout::default()
+        },
+    );
+
+    let small = open(&dir, "a.zip", &zip)
+        .with_eocd_search_window(500)
+        .unwrap();
+    assert!(small.list_files().await.is_err());
+    let enough = open(&dir, "a.zip", &zip)
+        .with_eocd_search_window(1022)
+        .unwrap();
+    assert_eq!(enough.list_files().await.unwrap().len(), 1);
+
+    let Err(err) = open(&dir, "a.zip", &zip).with_eocd_search_window(21) else {
+        panic!("window below the EOCD size accepted");
+    };
+    assert_eq!(
+        err.to_string(),
+        "EOCD search window must be at least 22 bytes, got 21"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn larger_window_skips_long_appended_data() {
+    let dir = scratch_dir("eocd-window-large");
+    let zip = [
+        build_zip(&[TestEntry::new("a.txt", b"alpha")], &[]),
+        vec![0xAA; 100_000],
+    ]
+    .concat();
+
+    assert!(open(&dir, "a.zip", &zip).list_files().await.is_err());
+    let extractor = open(&dir, "a.zip", &zip)
+        .with_eocd_search_window(200_000)
+        .unwrap();
+    assert_eq!(extractor.list_files().await.unwrap().len(), 1);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
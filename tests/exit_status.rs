@@ -0,0 +1,83 @@
+//! Exit status of the command-line tool.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+/// Build an archive of two small files.
+fn two_files() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::new("a.txt", b"first file"),
+            TestEntry::new("b.txt", b"second file"),
+        ],
+        &[],
+    )
+}
+
+#[test]
+fn clean_extraction_exits_with_0() {
+    let dir = scratch_dir("exit-ok");
+    fs::write(dir.join("ok.zip"), two_files()).unwrap();
+
+    let output = runzip(&dir, &["-d", "out", "ok.zip"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert_eq!(fs::read(dir.join("out/b.txt")).unwrap(), b"second file");
+
+    let output = runzip(&dir, &["-t", "ok.zip"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn skipped_entries_exit_with_1() {
+    let dir = scratch_dir("exit-skipped");
+    fs::write(dir.join("ok.zip"), two_files()).unwrap();
+    fs::create_dir(dir.join("out")).unwrap();
+    fs::write(dir.join("out/a.txt"), b"keep me").unwrap();
+
+    // -n never overwrites, so a.txt is skipped
+    let output = runzip(&dir, &["-n", "-d", "out", "ok.zip"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(fs::read(dir.join("out/a.txt")).unwrap(), b"keep me");
+    assert_eq!(fs::read(dir.join("out/b.txt")).unwrap(), b"second file");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn corrupt_entries_exit_with_2() {
+    let dir = scratch_dir("exit-corrupt");
+    let mut zip = two_files();
+    // Damage the first file's data, right after its 30-byte header and name
+    zip[30 + "a.txt".len()] ^= 0xFF;
+    fs::write(dir.join("corrupt.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["-d", "out", "corrupt.zip"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("CRC mismatch for a.txt"));
+    // The other entry is still extracted
+    assert_eq!(fs::read(dir.join("out/b.txt")).unwrap(), b"second file");
+
+    let output = runzip(&dir, &["-t", "corrupt.zip"]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn unusable_archives_exit_with_3() {
+    let dir = scratch_dir("exit-fatal");
+    fs::write(dir.join("not.zip"), b"this is not a zip file").unwrap();
+
+    let output = runzip(&dir, &["not.zip"]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+
+    let output = runzip(&dir, &["missing.zip"]);
+    assert_eq!(output.status.code(), Some(3), "{:?}", output);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,88 @@
+//! Modification times from the Info-ZIP extended timestamp extra field.
+
+mod common;
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use common::{TestEntry, build_zip, cd_offset, extractor, open, runzip, scratch_dir};
+
+/// An odd number of seconds, which DOS timestamps can't hold.
+const MTIME: i32 = 1_600_000_001;
+
+/// An extended timestamp field with the given flags and times.
+fn timestamp(flags: u8, times: &[i32]) -> Vec<u8> {
+    let mut field = vec![0x55, 0x54];
+    field.extend_from_slice(&(1 + 4 * times.len() as u16).to_le_bytes());
+    field.push(flags);
+    for time in times {
+        field.extend_from_slice(&time.to_le_bytes());
+    }
+    field
+}
+
+/// An entry whose Local File Header has all three times while its
+/// Central Directory record keeps the flags but only the mtime.
+fn stamped(name: &str) -> TestEntry<'_> {
+    TestEntry {
+        extra: timestamp(0x07, &[MTIME]),
+        local_extra: timestamp(0x07, &[MTIME, 1, 2]),
+        ..TestEntry::new(name, b"data")
+    }
+}
+
+#[tokio::test]
+async fn central_directory_mtimes_are_preferred_over_dos_times() {
+    let zip = build_zip(
+        &[
+            stamped("a.txt"),
+            // Access time only: the DOS time is used
+            TestEntry {
+                extra: timestamp(0x02, &[MTIME]),
+                ..TestEntry::new("b.txt", b"data")
+            },
+            // Truncated: ignored without disturbing the next field
+            TestEntry {
+                extra: [&[0x55, 0x54, 3, 0, 0x01, 0, 0][..], &[0x0a, 0, 0, 0]].concat(),
+                ..TestEntry::new("c.txt", b"data")
+            },
+        ],
+        &[],
+    );
+    let entries = extractor(zip).list_files().await.unwrap();
+    let dos = entries[1].modified().unwrap().to_unix();
+
+    assert_eq!(entries[0].unix_mtime, Some(MTIME as i64));
+    assert_eq!(entries[0].modified_unix(), Some(MTIME as i64));
+    assert_eq!(entries[1].unix_mtime, None);
+    assert_eq!(entries[1].modified_unix(), Some(dos));
+    assert_eq!(entries[2].unix_mtime, None);
+}
+
+#[tokio::test]
+async fn local_header_mtimes_are_read_when_salvaging() {
+    let dir = scratch_dir("extended-timestamp-salvage");
+    let zip = build_zip(&[stamped("a.txt"), TestEntry::new("b.txt", b"data")], &[]);
+    let extractor = open(&dir, "a.zip", &zip[..cd_offset(&zip)]);
+
+    let entries = extractor.salvage().await.unwrap();
+    assert_eq!(entries[0].unix_mtime, Some(MTIME as i64));
+    assert_eq!(entries[1].unix_mtime, None);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_restores_the_extended_mtime() {
+    let dir = scratch_dir("extended-timestamp-cli");
+    std::fs::write(dir.join("a.zip"), build_zip(&[stamped("a.txt")], &[])).unwrap();
+
+    let output = runzip(&dir, &["-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    let modified = std::fs::metadata(dir.join("out/a.txt"))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(modified, UNIX_EPOCH + Duration::from_secs(MTIME as u64));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
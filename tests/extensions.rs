@@ -0,0 +1,80 @@
+//! Selecting entries by extension with `--include-ext` and `--exclude-ext`.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir, stdout};
+
+/// Collect the paths of the files below `dir`, relative to it.
+fn walk(dir: &Path, prefix: &str, files: &mut Vec<String>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let entry = entry.unwrap();
+        let name = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+        if entry.file_type().unwrap().is_dir() {
+            walk(&entry.path(), &format!("{}/", name), files);
+        } else {
+            files.push(name);
+        }
+    }
+}
+
+/// Extract with `args` and list the files written, sorted.
+fn extracted(args: &[&str]) -> Vec<String> {
+    let dir = scratch_dir(&format!("ext-{}", args.join("-").replace(['.', ','], "_")));
+    let zip = build_zip(
+        &[
+            TestEntry::new("app.log", b"log"),
+            TestEntry::new("logs/OLD.LOG", b"log"),
+            TestEntry::new("cache.tmp", b"tmp"),
+            TestEntry::new("main.rs", b"code"),
+            TestEntry::new("src/lib.rs", b"code"),
+            TestEntry::new("Makefile", b"make"),
+            TestEntry::new(".tmp", b"dotfile"),
+            TestEntry::new("logs.d/readme", b"none"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let mut full = args.to_vec();
+    full.extend(["-d", "out", "a.zip"]);
+    stdout(&runzip(&dir, &full));
+    let mut files = Vec::new();
+    walk(&dir.join("out"), "", &mut files);
+    files.sort();
+    fs::remove_dir_all(&dir).unwrap();
+    files
+}
+
+#[test]
+fn excluded_extensions_are_skipped_regardless_of_case() {
+    assert_eq!(
+        extracted(&["--exclude-ext", "log,.TMP"]),
+        [".tmp", "Makefile", "logs.d/readme", "main.rs", "src/lib.rs"]
+    );
+}
+
+#[test]
+fn included_extensions_leave_out_names_without_one() {
+    assert_eq!(
+        extracted(&["--include-ext", "rs,log"]),
+        ["app.log", "logs/OLD.LOG", "main.rs", "src/lib.rs"]
+    );
+}
+
+#[test]
+fn extension_filters_combine_with_other_filters() {
+    assert_eq!(
+        extracted(&[
+            "--include-ext",
+            "rs,log",
+            "--exclude-ext",
+            "log",
+            "-x",
+            "src/*"
+        ]),
+        ["main.rs"]
+    );
+}
@@ -0,0 +1,471 @@
+//! Extraction options of the command-line tool.
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{TestEntry, build_zip, crc32, runzip, scratch_dir, stdout};
+
+/// Names of the files below `dir`, relative to it and sorted.
+fn tree(dir: &Path) -> Vec<String> {
+    fn walk(root: &Path, dir: &Path, names: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(root, &path, names);
+            } else {
+                let name = path.strip_prefix(root).unwrap().to_string_lossy();
+                names.push(name.replace('\\', "/"));
+            }
+        }
+    }
+    let mut names = Vec::new();
+    walk(dir, dir, &mut names);
+    names.sort();
+    names
+}
+
+/// Write an archive of three small files to `a.zip` in `dir`.
+fn three_files(dir: &Path) {
+    let zip = build_zip(
+        &[
+            TestEntry::new("docs/readme.txt", b"readme"),
+            TestEntry::new("src/main.c", b"int main;"),
+            TestEntry::new("build.sh", b"make"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+}
+
+#[test]
+fn destination_map_renames_matching_entries() {
+    let dir = scratch_dir("destination-map");
+    three_files(&dir);
+    fs::write(dir.join("map.txt"), "src/main.c\tcode/app.c\n").unwrap();
+
+    let output = runzip(
+        &dir,
+        &["--destination-map", "map.txt", "-d", "out", "a.zip"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.join("out")),
+        ["build.sh", "code/app.c", "docs/readme.txt"]
+    );
+    assert_eq!(fs::read(dir.join("out/code/app.c")).unwrap(), b"int main;");
+
+    let output = runzip(
+        &dir,
+        &[
+            "--destination-map",
+            "map.txt",
+            "--map-only",
+            "-d",
+            "only",
+            "a.zip",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(tree(&dir.join("only")), ["code/app.c"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn destination_map_targets_cannot_escape_the_output_directory() {
+    let dir = scratch_dir("destination-map-escape");
+    three_files(&dir);
+    fs::write(dir.join("map.txt"), "build.sh\t../escaped.sh\n").unwrap();
+
+    let output = runzip(
+        &dir,
+        &["--destination-map", "map.txt", "-d", "out", "a.zip"],
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Refusing to extract outside the output directory")
+    );
+    assert!(!dir.join("escaped.sh").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn manifest_lists_extracted_files_sorted_by_path() {
+    let dir = scratch_dir("manifest");
+    let zip = build_zip(
+        &[
+            TestEntry::new("src/main.c", b"int main;"),
+            TestEntry::dir("docs/"),
+            TestEntry::deflated("docs/readme.txt", b"readme readme readme"),
+            TestEntry::new("build.sh", b""),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--manifest", "manifest.txt", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    let expected = [
+        (b"".as_slice(), "build.sh"),
+        (b"readme readme readme", "docs/readme.txt"),
+        (b"int main;", "src/main.c"),
+    ]
+    .map(|(data, path)| format!("{:08x}  {}  {}\n", crc32(data), data.len(), path))
+    .concat();
+    assert_eq!(
+        fs::read_to_string(dir.join("manifest.txt")).unwrap(),
+        expected
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn flatten_into_writes_identical_files_once() {
+    let dir = scratch_dir("flatten-into");
+    let zip = build_zip(
+        &[
+            TestEntry::new("a/logo.png", b"png bytes"),
+            TestEntry::new("b/logo.png", b"png bytes"),
+            TestEntry::new("c/logo.png", b"other png"),
+            TestEntry::new("c/copy.png", b"other png"),
+            TestEntry::new("d/notes.txt", b"notes"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--flatten-into", "flat", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.join("flat")),
+        ["logo-1.png", "logo.png", "notes.txt"]
+    );
+    assert_eq!(fs::read(dir.join("flat/logo.png")).unwrap(), b"png bytes");
+    assert_eq!(fs::read(dir.join("flat/logo-1.png")).unwrap(), b"other png");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Deduplicated 2 file(s):"), "{}", stdout);
+    let flat = std::path::PathBuf::from("flat");
+    for (entry, original) in [("b/logo.png", "logo.png"), ("c/copy.png", "logo-1.png")] {
+        let line = format!("  {} -> {}", entry, flat.join(original).display());
+        assert!(stdout.contains(&line), "{}", stdout);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn no_directory_creation_skips_entries_needing_new_directories() {
+    let dir = scratch_dir("no-directory-creation");
+    three_files(&dir);
+    fs::create_dir_all(dir.join("out/docs")).unwrap();
+
+    let output = runzip(&dir, &["--no-directory-creation", "-d", "out", "a.zip"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    // docs/ exists, src/ doesn't
+    assert_eq!(tree(&dir.join("out")), ["build.sh", "docs/readme.txt"]);
+    assert!(!dir.join("out/src").exists());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Skipping: src/main.c (directory"),
+        "{:?}",
+        output
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// An archive with two good files and one in an unsupported format.
+fn mixed() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::new("a.txt", b"first"),
+            TestEntry::new("b.txt", b"second!"),
+            TestEntry {
+                // bzip2
+                method: 12,
+                ..TestEntry::new("c.bz2", b"BZh9")
+            },
+        ],
+        &[],
+    )
+}
+
+#[test]
+fn summary_reflects_a_mixed_outcome() {
+    let dir = scratch_dir("summary");
+    fs::write(dir.join("a.zip"), mixed()).unwrap();
+    fs::create_dir(dir.join("out")).unwrap();
+    fs::write(dir.join("out/a.txt"), b"keep").unwrap();
+
+    let output = runzip(&dir, &["-n", "-d", "out", "a.zip"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Extracted 1 file (7 bytes), skipped 1, 1 error\n"),
+        "{:?}",
+        output
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("error: c.bz2:"));
+
+    // Quiet mode has no summary
+    fs::remove_dir_all(dir.join("out")).unwrap();
+    let output = runzip(&dir, &["-q", "-x", "c.bz2", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Extracted"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn summary_includes_http_transfer_totals() {
+    let server = common::serve(mixed()).await;
+    let dir = scratch_dir("summary-http");
+    let url = server.url.clone();
+
+    let output = tokio::task::spawn_blocking(move || {
+        let output = runzip(&dir, &["-x", "c.bz2", "-d", "out", &url]);
+        fs::remove_dir_all(&dir).unwrap();
+        output
+    })
+    .await
+    .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| l.starts_with("Extracted")).unwrap();
+    assert!(
+        line.starts_with("Extracted 2 files (12 bytes), skipped 0, 0 errors; "),
+        "{}",
+        line
+    );
+    assert!(line.ends_with(" transferred"), "{}", line);
+}
+
+#[test]
+fn resume_skips_entries_finished_earlier() {
+    let dir = scratch_dir("resume");
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+            TestEntry::new("c.txt", b"gamma"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    // A crashed run: a.txt recorded, b.txt recorded but since damaged,
+    // c.txt's line cut short
+    fs::create_dir(dir.join("out")).unwrap();
+    fs::write(dir.join("out/a.txt"), b"alpha").unwrap();
+    fs::write(dir.join("out/b.txt"), b"be").unwrap();
+    let state = format!(
+        "{:08x}  a.txt\n{:08x}  b.txt\n{:04x}",
+        crc32(b"alpha"),
+        crc32(b"beta"),
+        crc32(b"gamma") >> 16
+    );
+    fs::write(dir.join("state"), state).unwrap();
+
+    let out = stdout(&runzip(
+        &dir,
+        &["--resume", "state", "-o", "-d", "out", "a.zip"],
+    ));
+    assert!(!out.contains("extracting: a.txt"), "{}", out);
+    assert!(
+        out.contains("extracting: b.txt") && out.contains("extracting: c.txt"),
+        "{}",
+        out
+    );
+    assert!(out.contains("skipped 1"), "{}", out);
+    assert_eq!(fs::read(dir.join("out/b.txt")).unwrap(), b"beta");
+
+    let state = fs::read_to_string(dir.join("state")).unwrap();
+    assert!(
+        state.ends_with(&format!("{:08x}  c.txt\n", crc32(b"gamma"))),
+        "{}",
+        state
+    );
+
+    // Everything is done now
+    let out = stdout(&runzip(
+        &dir,
+        &["--resume", "state", "-o", "-d", "out", "a.zip"],
+    ));
+    assert!(
+        out.contains("Extracted 0 files") && out.contains("skipped 3"),
+        "{}",
+        out
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn only_changed_files_are_overwritten() {
+    let dir = scratch_dir("overwrite-if-different");
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+            TestEntry::new("c.txt", b"gamma"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+    fs::create_dir(dir.join("out")).unwrap();
+    fs::write(dir.join("out/a.txt"), b"alpha").unwrap();
+    fs::write(dir.join("out/b.txt"), b"BETA").unwrap();
+
+    let output = runzip(&dir, &["--overwrite-if-different", "-d", "out", "a.zip"]);
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(!err.contains("exists"), "{}", err);
+    let out = stdout(&output);
+    assert!(!out.contains("extracting: a.txt"), "{}", out);
+    assert!(
+        out.contains("extracting: b.txt") && out.contains("extracting: c.txt"),
+        "{}",
+        out
+    );
+    assert_eq!(fs::read(dir.join("out/b.txt")).unwrap(), b"beta");
+    assert_eq!(tree(&dir.join("out")), ["a.txt", "b.txt", "c.txt"]);
+
+    let output = runzip(
+        &dir,
+        &["--overwrite-if-different", "-o", "-d", "out", "a.zip"],
+    );
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn excluded_directories_are_skipped_entirely() {
+    let dir = scratch_dir("exclude-dir");
+    three_files(&dir);
+
+    runzip(
+        &dir,
+        &[
+            "--exclude-dir",
+            "docs/",
+            "--exclude-dir",
+            "sr",
+            "-d",
+            "out",
+            "a.zip",
+        ],
+    );
+    assert_eq!(tree(&dir.join("out")), ["build.sh", "src/main.c"]);
+
+    // Even entries requested by name
+    runzip(
+        &dir,
+        &[
+            "--exclude-dir",
+            "src",
+            "-d",
+            "named",
+            "a.zip",
+            "src/main.c",
+            "build.sh",
+        ],
+    );
+    assert_eq!(tree(&dir.join("named")), ["build.sh"]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn benchmark_prints_phase_timings() {
+    let server = common::serve(mixed()).await;
+    let dir = scratch_dir("benchmark");
+    fs::write(dir.join("a.zip"), mixed()).unwrap();
+    let url = server.url.clone();
+
+    let (local, remote) = tokio::task::spawn_blocking(move || {
+        let local = runzip(&dir, &["--benchmark", "-x", "c.bz2", "-d", "out", "a.zip"]);
+        let remote = runzip(&dir, &["--benchmark", "-l", &url]);
+        fs::remove_dir_all(&dir).unwrap();
+        (local, remote)
+    })
+    .await
+    .unwrap();
+
+    let stderr = String::from_utf8_lossy(&local.stderr);
+    let phases: Vec<_> = stderr
+        .lines()
+        .skip_while(|l| *l != "Benchmark:")
+        .skip(1)
+        .map(|l| l.split_whitespace().next().unwrap())
+        .collect();
+    assert_eq!(
+        phases,
+        ["EOCD", "Central", "Extraction", "Total"],
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("requests"), "{}", stderr);
+
+    let stderr = String::from_utf8_lossy(&remote.stderr);
+    let total = stderr.lines().find(|l| l.starts_with("  Total "));
+    assert!(
+        total.is_some_and(|l| l.contains(" requests ")),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn auto_strip_drops_a_single_wrapper_directory() {
+    let dir = scratch_dir("auto-strip");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("project-1.0/"),
+            TestEntry::new("project-1.0/README", b"readme"),
+            TestEntry::new("project-1.0/src/a.c", b"int a;"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("wrapped.zip"), zip).unwrap();
+    three_files(&dir);
+
+    let output = runzip(&dir, &["--auto-strip", "-d", "out", "wrapped.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(tree(&dir.join("out")), ["README", "src/a.c"]);
+
+    // Without a single wrapper, paths are kept
+    let output = runzip(&dir, &["--auto-strip", "-d", "plain", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.join("plain")),
+        ["build.sh", "docs/readme.txt", "src/main.c"]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn add_prefix_namespaces_the_extracted_files() {
+    let dir = scratch_dir("add-prefix");
+    three_files(&dir);
+
+    let output = runzip(&dir, &["--add-prefix", "v1/x", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        tree(&dir.join("out")),
+        ["v1/x/build.sh", "v1/x/docs/readme.txt", "v1/x/src/main.c"]
+    );
+
+    // Prefixes that could escape the output directory are refused
+    for prefix in ["../up", "/abs"] {
+        let output = runzip(&dir, &["--add-prefix", prefix, "-d", "bad", "a.zip"]);
+        assert!(!output.status.success(), "{}", prefix);
+        assert!(!dir.join("bad").exists() && !dir.join("up").exists());
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
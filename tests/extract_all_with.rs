@@ -0,0 +1,83 @@
+//! Extraction routed by a closure with `extract_all_with`.
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use runzip::zip::sanitize_path;
+
+use common::{TestEntry, build_zip, open, scratch_dir};
+
+#[test]
+fn paths_are_sanitized() {
+    assert_eq!(
+        sanitize_path("./docs/./a.txt").unwrap(),
+        Path::new("docs/a.txt")
+    );
+    for name in ["../etc/passwd", "a/../../b", "/etc/passwd", ".", ""] {
+        assert!(sanitize_path(name).is_err(), "{}", name);
+    }
+}
+
+#[tokio::test]
+async fn entries_go_where_the_closure_routes_them() {
+    let dir = scratch_dir("extract-all-with");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("docs/"),
+            TestEntry::new("docs/a.txt", b"alpha"),
+            TestEntry::new("src/main.c", b"int main;"),
+            TestEntry::new("build.sh", b"make"),
+            TestEntry::new("evil.txt", b"escape"),
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let out = dir.join("out");
+
+    let report = extractor
+        .extract_all_with(&out, |entry| {
+            let name = Path::new(&entry.file_name);
+            match entry.file_name.as_str() {
+                "build.sh" => None,
+                "evil.txt" => Some(PathBuf::from("../evil.txt")),
+                _ if name.extension().is_some_and(|ext| ext == "txt") => {
+                    Some(Path::new("text").join(name.file_name()?))
+                }
+                _ => Some(name.to_path_buf()),
+            }
+        })
+        .await
+        .unwrap();
+
+    let extracted: Vec<_> = report
+        .extracted()
+        .map(|(entry, path)| (entry.file_name.as_str(), path.strip_prefix(&out).unwrap()))
+        .collect();
+    assert_eq!(
+        extracted,
+        [
+            ("docs/", Path::new("docs")),
+            ("docs/a.txt", Path::new("text/a.txt")),
+            ("src/main.c", Path::new("src/main.c")),
+        ]
+    );
+    let failed: Vec<_> = report.failed().collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0.file_name, "evil.txt");
+    assert!(
+        failed[0].1.contains("Refusing to extract outside"),
+        "{}",
+        failed[0].1
+    );
+    assert_eq!(report.summary().skipped, 0);
+
+    assert!(out.join("docs").is_dir());
+    assert_eq!(fs::read(out.join("text/a.txt")).unwrap(), b"alpha");
+    assert_eq!(fs::read(out.join("src/main.c")).unwrap(), b"int main;");
+    assert!(!out.join("build.sh").exists());
+    assert!(!dir.join("evil.txt").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
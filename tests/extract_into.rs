@@ -0,0 +1,43 @@
+//! Decoding entries into a reusable buffer with `extract_into`.
+
+mod common;
+
+use common::{TestEntry, build_zip, extractor};
+
+#[tokio::test]
+async fn buffer_is_refilled_for_each_entry() {
+    let big = vec![b'x'; 10_000];
+    let zip = build_zip(
+        &[
+            TestEntry::deflated("big.txt", &big),
+            TestEntry::new("small.txt", b"small"),
+            TestEntry {
+                crc32: Some(0),
+                ..TestEntry::new("bad.txt", b"corrupt")
+            },
+        ],
+        &[],
+    );
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let mut buf = b"leftover".to_vec();
+    extractor.extract_into(&entries[0], &mut buf).await.unwrap();
+    assert_eq!(buf, big);
+    let capacity = buf.capacity();
+
+    extractor.extract_into(&entries[1], &mut buf).await.unwrap();
+    assert_eq!(buf, b"small");
+    assert_eq!(buf.capacity(), capacity);
+
+    // The CRC-32 is checked
+    let err = extractor
+        .extract_into(&entries[2], &mut buf)
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("CRC mismatch for bad.txt"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,69 @@
+//! Keeping small entries in memory and spilling large ones with
+//! `extract_spilling`.
+
+mod common;
+
+use runzip::ExtractedData;
+
+use common::{TestEntry, build_zip, extractor, scratch_dir};
+
+#[tokio::test]
+async fn entries_spill_once_they_exceed_the_threshold() {
+    let dir = scratch_dir("spilling");
+    let large: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::new("small.txt", b"small"),
+            TestEntry::deflated("large.bin", &large),
+        ],
+        &[],
+    );
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let small = extractor
+        .extract_spilling(&entries[0], 1024, &dir)
+        .await
+        .unwrap();
+    assert_eq!(small, ExtractedData::InMemory(b"small".to_vec()));
+
+    let ExtractedData::OnDisk(path) = extractor
+        .extract_spilling(&entries[1], 1024, &dir)
+        .await
+        .unwrap()
+    else {
+        panic!("large.bin was kept in memory");
+    };
+    assert!(path.starts_with(&dir));
+    assert!(std::fs::read(&path).unwrap() == large);
+
+    // Exactly the threshold still fits
+    let exact = extractor
+        .extract_spilling(&entries[0], 5, &dir)
+        .await
+        .unwrap();
+    assert_eq!(exact, ExtractedData::InMemory(b"small".to_vec()));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn failed_extractions_remove_the_spill_file() {
+    let dir = scratch_dir("spilling-failed");
+    let data = vec![b'x'; 10_000];
+    let mut entry = TestEntry::deflated("bad.txt", &data);
+    entry.crc32 = Some(0);
+    let zip = build_zip(&[entry], &[]);
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    assert!(
+        extractor
+            .extract_spilling(&entries[0], 100, &dir)
+            .await
+            .is_err()
+    );
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,53 @@
+//! Streaming entries to files with `extract_to_file`.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, open, scratch_dir};
+
+#[tokio::test]
+async fn entries_are_streamed_and_corrupt_output_removed() {
+    let dir = scratch_dir("extract-to-file");
+    let data: Vec<u8> = (0..700_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::deflated("big.bin", &data),
+            TestEntry {
+                crc32: Some(0),
+                ..TestEntry::deflated("bad-crc.bin", &data)
+            },
+            TestEntry {
+                uncompressed_size: Some(1000),
+                ..TestEntry::deflated("too-long.bin", &data)
+            },
+            TestEntry {
+                uncompressed_size: Some(1_000_000),
+                ..TestEntry::deflated("too-short.bin", &data)
+            },
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let out = dir.join("out");
+    extractor
+        .extract_to_file(&entries[0], &out.join("big.bin"))
+        .await
+        .unwrap();
+    assert!(fs::read(out.join("big.bin")).unwrap() == data);
+
+    for (entry, message) in entries[1..].iter().zip([
+        "CRC mismatch for bad-crc.bin",
+        "too-long.bin decompresses to more than its declared size of 1000 bytes",
+        "Size mismatch for too-short.bin: expected 1000000 bytes, got 700000",
+    ]) {
+        let path = out.join(&entry.file_name);
+        let err = extractor.extract_to_file(entry, &path).await.unwrap_err();
+        assert!(format!("{:#}", err).contains(message), "{:#}", err);
+        assert!(!path.exists(), "{} was left behind", entry.file_name);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
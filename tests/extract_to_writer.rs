@@ -0,0 +1,74 @@
+//! Streaming entries into a writer with `extract_to_writer`.
+
+mod common;
+
+use runzip::ZipError;
+
+use common::{TestEntry, build_zip, extractor};
+
+#[tokio::test]
+async fn entries_are_streamed_in_chunks() {
+    // Several 256 KiB chunks' worth
+    let data: Vec<u8> = (0..600_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::new("stored.bin", &data),
+            TestEntry::deflated("deflated.bin", &data),
+        ],
+        &[],
+    );
+    let extractor = extractor(zip);
+    for entry in extractor.list_files().await.unwrap() {
+        let mut out = Vec::new();
+        let written = extractor.extract_to_writer(&entry, &mut out).await.unwrap();
+        assert_eq!(written, data.len() as u64);
+        assert!(out == data, "{}", entry.file_name);
+    }
+}
+
+#[tokio::test]
+async fn stored_sizes_must_agree() {
+    let zip = build_zip(
+        &[
+            // More data stored than declared
+            TestEntry {
+                uncompressed_size: Some(3),
+                ..TestEntry::new("long.txt", b"hello")
+            },
+            // Less data stored than declared
+            TestEntry {
+                payload: Some(b"hel".to_vec()),
+                ..TestEntry::new("short.txt", b"hello")
+            },
+        ],
+        &[],
+    );
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let mut out = Vec::new();
+    let err = extractor
+        .extract_to_writer(&entries[0], &mut out)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::SizeExceeded { declared: 3, .. })
+        ),
+        "{:#}",
+        err
+    );
+
+    let err = extractor
+        .extract_to_writer(&entries[1], &mut out)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "short.txt is stored with 3 bytes of data but declares 5 bytes"
+    );
+
+    // Both were rejected before anything was written
+    assert!(out.is_empty());
+}
@@ -0,0 +1,48 @@
+//! `file://` URLs as the input archive.
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Parser;
+use runzip::Cli;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir, stdout};
+
+fn local_path(file: &str) -> anyhow::Result<PathBuf> {
+    Cli::parse_from(["runzip", file]).local_path()
+}
+
+#[cfg(unix)]
+#[test]
+fn file_urls_are_converted_to_paths() {
+    assert_eq!(local_path("a.zip").unwrap(), PathBuf::from("a.zip"));
+    assert_eq!(
+        local_path("file:///tmp/my%20archive.zip").unwrap(),
+        PathBuf::from("/tmp/my archive.zip")
+    );
+    assert_eq!(
+        local_path("FILE://localhost/tmp/a.zip").unwrap(),
+        PathBuf::from("/tmp/a.zip")
+    );
+
+    let err = local_path("file://example.com/a.zip").unwrap_err();
+    assert!(err.to_string().contains("only local paths"), "{}", err);
+}
+
+#[test]
+fn archive_is_listed_from_a_file_url() {
+    let dir = scratch_dir("file-url");
+    let zip = build_zip(&[TestEntry::new("a.txt", b"alpha")], &[]);
+    fs::write(dir.join("my archive.zip"), zip).unwrap();
+
+    let path = dir.canonicalize().unwrap().join("my archive.zip");
+    let url = reqwest::Url::from_file_path(&path).unwrap();
+    assert!(url.as_str().contains("%20"), "{}", url);
+    let output = runzip(&dir, &["-l", url.as_str()]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(stdout(&output).contains("a.txt"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
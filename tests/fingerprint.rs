@@ -0,0 +1,53 @@
+//! Archive fingerprints.
+
+mod common;
+
+use common::{TestEntry, build_zip, extractor};
+
+async fn fingerprint(files: &[(&str, &[u8])]) -> String {
+    let entries: Vec<_> = files
+        .iter()
+        .map(|&(name, data)| TestEntry::new(name, data))
+        .collect();
+    extractor(build_zip(&entries, &[]))
+        .fingerprint()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn fingerprint_follows_the_central_directory() {
+    let first = fingerprint(&[("a.txt", b"hello"), ("b.txt", b"world")]).await;
+
+    // Stable for the same archive
+    assert_eq!(
+        fingerprint(&[("a.txt", b"hello"), ("b.txt", b"world")]).await,
+        first
+    );
+    let parts: Vec<_> = first.split('-').collect();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(parts[0].len(), 8);
+
+    // Any change to an entry changes it
+    for changed in [
+        [("a.txt", b"hello"), ("b.txt", b"World")],
+        [("a.txt", b"hello"), ("c.txt", b"world")],
+        [("b.txt", b"world"), ("a.txt", b"hello")],
+    ] {
+        let changed = changed.map(|(name, data)| (name, &data[..]));
+        assert_ne!(fingerprint(&changed).await, first);
+    }
+}
+
+#[tokio::test]
+async fn fingerprint_reads_no_file_data() {
+    let zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+    let mut corrupt = zip.clone();
+    // The data follows the 30-byte header and the name
+    corrupt[35..40].copy_from_slice(b"HELLO");
+
+    assert_eq!(
+        extractor(zip).fingerprint().await.unwrap(),
+        extractor(corrupt).fingerprint().await.unwrap()
+    );
+}
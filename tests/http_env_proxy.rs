@@ -0,0 +1,38 @@
+//! Proxies configured in the environment.
+
+mod common;
+
+use runzip::{HttpRangeReader, ReadAt};
+
+use common::serve;
+
+#[tokio::test]
+async fn no_proxy_applies_to_the_environment_proxy_with_credentials() {
+    let server = serve(b"0123456789".to_vec()).await;
+
+    // Nothing listens on port 9 of localhost, so the proxy can't be used
+    // SAFETY: this is the only test in this binary, so nothing reads the
+    // environment concurrently
+    unsafe {
+        std::env::set_var("HTTP_PROXY", "http://127.0.0.1:9");
+        std::env::set_var("NO_PROXY", "127.0.0.1");
+    }
+
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .proxy_auth("user", "pass")
+        .build()
+        .await
+        .unwrap();
+    let mut buf = [0u8; 4];
+    assert_eq!(reader.read_at(3, &mut buf).await.unwrap(), 4);
+    assert_eq!(&buf, b"3456");
+
+    // Reached directly: the requests name a path, not an absolute URL
+    let requests = server.requests.lock().unwrap();
+    assert!(requests.iter().all(|r| r.contains(" /archive.zip ")));
+    assert!(
+        requests
+            .iter()
+            .all(|r| !r.to_ascii_lowercase().contains("proxy-authorization"))
+    );
+}
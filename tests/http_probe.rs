@@ -0,0 +1,74 @@
+//! The one-byte Range request sent when an HTTP reader is built.
+
+mod common;
+
+use std::time::Duration;
+
+use runzip::{Backoff, HttpRangeReader, ReadAt, Warning};
+
+use common::{serve, serve_with};
+
+fn fast_retries(url: &str) -> runzip::HttpRangeReaderBuilder {
+    HttpRangeReader::builder(url.to_string()).backoff(Backoff::Fixed {
+        delay: Duration::from_millis(1),
+    })
+}
+
+#[tokio::test]
+async fn probe_is_counted_like_any_read() {
+    let server = serve(vec![7u8; 100]).await;
+    let reader = fast_retries(&server.url).build().await.unwrap();
+    assert_eq!(reader.size(), 100);
+    assert_eq!(reader.request_count(), 1);
+    assert_eq!(reader.transferred_bytes(), 1);
+    assert!(reader.take_warnings().is_empty());
+}
+
+#[tokio::test]
+async fn probe_is_retried_on_a_retry_status() {
+    // Request 0 is the HEAD request, 1 the probe
+    let server = serve_with(vec![7u8; 100], |index, _| {
+        (index == 1)
+            .then(|| b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n".to_vec())
+    })
+    .await;
+    let reader = fast_retries(&server.url).build().await.unwrap();
+    assert_eq!(reader.request_count(), 2);
+    assert!(matches!(
+        reader.take_warnings().as_slice(),
+        [Warning::Retry { attempt: 1, .. }]
+    ));
+}
+
+#[tokio::test]
+async fn probe_answered_with_the_whole_file_fails() {
+    let server = serve_with(vec![7u8; 100], |_, head| {
+        head.starts_with("GET").then(|| {
+            let mut response = b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n".to_vec();
+            response.extend_from_slice(&[7u8; 100]);
+            response
+        })
+    })
+    .await;
+    let err = fast_retries(&server.url).build().await.err().unwrap();
+    assert!(err.to_string().contains("200 OK"), "{:#}", err);
+}
+
+#[tokio::test]
+async fn probe_corrects_a_wrong_head_size() {
+    let server = serve_with(vec![7u8; 100], |_, head| {
+        head.starts_with("HEAD").then(|| {
+            b"HTTP/1.1 200 OK\r\nAccept-Ranges: bytes\r\nContent-Length: 40\r\n\r\n".to_vec()
+        })
+    })
+    .await;
+    let reader = fast_retries(&server.url).build().await.unwrap();
+    assert_eq!(reader.size(), 100);
+    assert!(matches!(
+        reader.take_warnings().as_slice(),
+        [Warning::SizeCorrected {
+            reported: 40,
+            actual: 100
+        }]
+    ));
+}
@@ -0,0 +1,45 @@
+//! Reading remote archives through an HTTP proxy.
+
+mod common;
+
+use runzip::{HttpRangeReader, ReadAt};
+
+use common::serve;
+
+#[tokio::test]
+async fn range_requests_are_relayed_through_the_proxy() {
+    // The mock answers the absolute-form requests a proxy receives
+    let proxy = serve(b"0123456789".to_vec()).await;
+    let proxy_url = proxy.url.trim_end_matches("/archive.zip").to_string();
+
+    // The host doesn't resolve, so only the proxy can reach it
+    let reader = HttpRangeReader::builder("http://archive.invalid/archive.zip".to_string())
+        .proxy(proxy_url)
+        .proxy_auth("user", "pass")
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(reader.size(), 10);
+
+    let mut buf = [0u8; 4];
+    assert_eq!(reader.read_at(3, &mut buf).await.unwrap(), 4);
+    assert_eq!(&buf, b"3456");
+
+    let requests = proxy.requests.lock().unwrap();
+    assert!(requests[0].starts_with("HEAD http://archive.invalid/archive.zip "));
+    // The last GET follows the one-byte size probe
+    let get = requests
+        .iter()
+        .rfind(|r| r.starts_with("GET http://archive.invalid/archive.zip "))
+        .unwrap()
+        .to_ascii_lowercase();
+    assert!(get.contains("range: bytes=3-6"), "{}", get);
+    // Basic credentials for "user:pass"
+    assert!(
+        requests
+            .iter()
+            .all(|r| r.contains("proxy-authorization: Basic dXNlcjpwYXNz")),
+        "{:?}",
+        requests
+    );
+}
@@ -0,0 +1,259 @@
+//! Range requests of the HTTP reader against a mock server.
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use runzip::{HttpRangeReader, ReadAt, Warning, ZipExtractor};
+
+use common::{MockServer, TestEntry, build_zip, default_response};
+
+/// Open the archive served by `server`.
+async fn open(server: &MockServer) -> ZipExtractor<HttpRangeReader> {
+    let reader = HttpRangeReader::new(server.url.clone()).await.unwrap();
+    ZipExtractor::new(Arc::new(reader))
+}
+
+/// Byte range requested by a request header block.
+fn range(request: &str) -> Option<(u64, u64)> {
+    let range = request.lines().find_map(|line| {
+        line.to_ascii_lowercase()
+            .strip_prefix("range: bytes=")
+            .map(str::to_string)
+    })?;
+    let (start, end) = range.trim().split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?))
+}
+
+static RESUMED: OnceLock<Vec<u8>> = OnceLock::new();
+/// Where the data of the only entry of [`RESUMED`] starts, after its
+/// 30-byte header and name
+const DATA_OFFSET: u64 = 30 + "big.bin".len() as u64;
+
+/// 100 KB of data that doesn't repeat within a chunk.
+fn big_data() -> Vec<u8> {
+    (0..100_000u32).map(|i| (i * 7 + i / 251) as u8).collect()
+}
+
+#[tokio::test]
+async fn stored_entry_resumes_after_a_dropped_connection() {
+    static DROPPED: AtomicBool = AtomicBool::new(false);
+    let data = big_data();
+    let zip = RESUMED.get_or_init(|| build_zip(&[TestEntry::new("big.bin", &data)], &[]));
+
+    let server = common::serve_with(zip.clone(), |_, request| {
+        let (start, _) = range(request)?;
+        if start != DATA_OFFSET || DROPPED.swap(true, Ordering::Relaxed) {
+            return None;
+        }
+        // Send the headers and half the body, then drop the connection
+        let mut response = default_response(request, RESUMED.get().unwrap());
+        response.truncate(response.len() - 50_000);
+        Some(response)
+    })
+    .await;
+
+    let extractor = open(&server).await;
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(
+        extractor.extract_to_memory(&entries[0]).await.unwrap(),
+        data
+    );
+
+    // The retry only asked for the bytes not received before the drop
+    let ranges: Vec<_> = server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|r| range(r))
+        .filter(|(start, _)| (DATA_OFFSET..DATA_OFFSET + 100_000).contains(start))
+        .collect();
+    let end = DATA_OFFSET + 100_000 - 1;
+    assert_eq!(ranges, [(DATA_OFFSET, end), (DATA_OFFSET + 50_000, end)]);
+}
+
+#[tokio::test]
+async fn stats_scope_counts_bytes_read_within_it() {
+    let server = common::serve((0..=255u8).collect()).await;
+    let reader = HttpRangeReader::new(server.url.clone()).await.unwrap();
+    let mut buf = [0u8; 16];
+    reader.read_at(0, &mut buf).await.unwrap();
+
+    let scope = reader.stats_scope();
+    assert_eq!(scope.transferred_bytes(), 0);
+    reader.read_at(100, &mut buf).await.unwrap();
+    reader.read_at(200, &mut buf[..10]).await.unwrap();
+    assert_eq!(scope.transferred_bytes(), 26);
+
+    // A later scope only sees its own reads, and the first keeps counting
+    let inner = reader.stats_scope();
+    reader.read_at(250, &mut buf).await.unwrap();
+    assert_eq!(inner.transferred_bytes(), 6);
+    assert_eq!(scope.transferred_bytes(), 32);
+    // The size probe read one byte
+    assert_eq!(reader.transferred_bytes(), 49);
+}
+
+#[tokio::test]
+async fn range_not_satisfiable_names_the_requested_range() {
+    // The file shrank to 5 bytes after the size probe
+    let server = common::serve_with(b"0123456789".to_vec(), |index, request| {
+        (index > 1 && request.starts_with("GET")).then(|| {
+            b"HTTP/1.1 416 Range Not Satisfiable\r\nContent-Range: bytes */5\r\nContent-Length: 0\r\n\r\n"
+                .to_vec()
+        })
+    })
+    .await;
+    let reader = HttpRangeReader::new(server.url.clone()).await.unwrap();
+
+    let mut buf = [0u8; 4];
+    let err = reader.read_at(6, &mut buf).await.unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.contains("Requested range bytes=6-9 exceeds the remote file"),
+        "{}",
+        message
+    );
+    assert!(message.contains("Content-Range: bytes */5"), "{}", message);
+
+    // Reads at or past the end return nothing without a request
+    let requests = server.requests.lock().unwrap().len();
+    assert_eq!(reader.read_at(10, &mut buf).await.unwrap(), 0);
+    assert_eq!(server.requests.lock().unwrap().len(), requests);
+}
+
+/// Answer the first request after HEAD and the size probe with 502 Bad
+/// Gateway.
+fn bad_gateway_once(index: usize, _: &str) -> Option<Vec<u8>> {
+    (index == 2).then(|| b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\n\r\n".to_vec())
+}
+
+#[tokio::test]
+async fn configured_retry_statuses_are_retried() {
+    let server = common::serve_with(b"0123456789".to_vec(), bad_gateway_once).await;
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .retry_statuses([502, 503, 504])
+        .build()
+        .await
+        .unwrap();
+
+    let mut buf = [0u8; 3];
+    assert_eq!(reader.read_at(1, &mut buf).await.unwrap(), 3);
+    assert_eq!(&buf, b"123");
+    assert_eq!(server.requests.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn other_statuses_fail_immediately() {
+    let server = common::serve_with(b"0123456789".to_vec(), bad_gateway_once).await;
+    let reader = HttpRangeReader::new(server.url.clone()).await.unwrap();
+
+    let mut buf = [0u8; 3];
+    let err = reader.read_at(1, &mut buf).await.unwrap_err();
+    assert!(err.to_string().contains("502"), "{}", err);
+    assert_eq!(server.requests.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn retries_are_reported_as_warnings() {
+    let server = common::serve_with(b"0123456789".to_vec(), bad_gateway_once).await;
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .retry_statuses([502])
+        .build()
+        .await
+        .unwrap();
+
+    let mut buf = [0u8; 3];
+    reader.read_at(1, &mut buf).await.unwrap();
+    let warnings = reader.take_warnings();
+    assert!(
+        matches!(warnings[..], [Warning::Retry { attempt: 1, .. }]),
+        "{:?}",
+        warnings
+    );
+    assert!(reader.take_warnings().is_empty());
+}
+
+#[tokio::test]
+async fn every_attempt_counts_as_a_request() {
+    let server = common::serve_with(b"0123456789".to_vec(), bad_gateway_once).await;
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .retry_statuses([502])
+        .build()
+        .await
+        .unwrap();
+    // The size probe
+    assert_eq!(reader.request_count(), 1);
+
+    let mut buf = [0u8; 3];
+    let scope = reader.stats_scope();
+    reader.read_at(1, &mut buf).await.unwrap();
+    assert_eq!(scope.request_count(), 2);
+    reader.read_at(5, &mut buf).await.unwrap();
+    assert_eq!(scope.request_count(), 3);
+    assert_eq!(reader.request_count(), 4);
+}
+
+/// Ranges of the GET requests `server` received after the size probe.
+fn requested_ranges(server: &MockServer) -> Vec<(u64, u64)> {
+    server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|r| range(r))
+        .skip(1)
+        .collect()
+}
+
+#[tokio::test]
+async fn reads_are_split_into_bounded_ranges() {
+    let data: Vec<u8> = (0..=255u8).collect();
+    let server = common::serve(data.clone()).await;
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .max_range_size(100)
+        .build()
+        .await
+        .unwrap();
+
+    let mut buf = vec![0u8; 250];
+    assert_eq!(reader.read_at(3, &mut buf).await.unwrap(), 250);
+    assert_eq!(buf, data[3..253]);
+    assert_eq!(
+        requested_ranges(&server),
+        [(3, 102), (103, 202), (203, 252)]
+    );
+}
+
+#[tokio::test]
+async fn small_reads_are_padded_and_reused() {
+    let data: Vec<u8> = (0..=255u8).collect();
+    let server = common::serve(data.clone()).await;
+    let reader = HttpRangeReader::builder(server.url.clone())
+        .min_range_size(64)
+        .build()
+        .await
+        .unwrap();
+
+    let mut buf = [0u8; 8];
+    reader.read_at(10, &mut buf).await.unwrap();
+    assert_eq!(buf, data[10..18]);
+    // Served from the padded range
+    reader.read_at(60, &mut buf).await.unwrap();
+    assert_eq!(buf, data[60..68]);
+    assert_eq!(requested_ranges(&server), [(10, 73)]);
+
+    // Past it, and clamped to the end of the file
+    reader.read_at(70, &mut buf).await.unwrap();
+    assert_eq!(buf, data[70..78]);
+    assert_eq!(reader.read_at(250, &mut buf).await.unwrap(), 6);
+    assert_eq!(buf[..6], data[250..]);
+    assert_eq!(requested_ranges(&server), [(10, 73), (70, 133), (250, 255)]);
+
+    // Large reads aren't padded
+    let mut big = vec![0u8; 100];
+    reader.read_at(100, &mut big).await.unwrap();
+    assert_eq!(requested_ranges(&server)[3], (100, 199));
+}
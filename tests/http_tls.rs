@@ -0,0 +1,129 @@
+//! HTTPS sources with a privately issued certificate.
+
+mod common;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use runzip::{HttpRangeReader, ReadAt};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+use common::default_response;
+
+const DATA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data");
+
+/// Serve `data` over HTTPS with a certificate for 127.0.0.1 issued by
+/// `tests/data/test-ca.pem`.
+fn serve_tls(data: &'static [u8]) -> String {
+    let certs = CertificateDer::pem_file_iter(format!("{}/localhost.pem", DATA))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let key = PrivateKeyDer::from_pem_file(format!("{}/localhost.key", DATA)).unwrap();
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+    let config = Arc::new(config);
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}/archive.zip", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let config = config.clone();
+            std::thread::spawn(move || {
+                let Ok(connection) = ServerConnection::new(config) else {
+                    return;
+                };
+                let mut stream = BufReader::new(StreamOwned::new(connection, stream.unwrap()));
+                loop {
+                    let mut head = String::new();
+                    while !head.ends_with("\r\n\r\n") {
+                        if stream.read_line(&mut head).unwrap_or(0) == 0 {
+                            return;
+                        }
+                    }
+                    let response = default_response(&head, data);
+                    if stream.get_mut().write_all(&response).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+    url
+}
+
+#[tokio::test]
+async fn untrusted_certificates_are_rejected_by_default() {
+    let url = serve_tls(b"0123456789");
+    let Err(err) = HttpRangeReader::new(url).await else {
+        panic!("connected to an untrusted server");
+    };
+    assert!(format!("{:?}", err).contains("certificate"), "{:?}", err);
+}
+
+#[tokio::test]
+async fn added_root_certificate_is_trusted() {
+    let url = serve_tls(b"0123456789");
+    let reader = HttpRangeReader::builder(url)
+        .add_root_certificate(format!("{}/test-ca.pem", DATA))
+        .build()
+        .await
+        .unwrap();
+    let mut buf = [0u8; 4];
+    assert_eq!(reader.read_at(2, &mut buf).await.unwrap(), 4);
+    assert_eq!(&buf, b"2345");
+}
+
+#[tokio::test]
+async fn insecure_mode_accepts_any_certificate() {
+    let url = serve_tls(b"0123456789");
+    let reader = HttpRangeReader::builder(url)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(reader.size(), 10);
+}
+
+#[tokio::test]
+async fn missing_certificate_files_are_reported() {
+    let result = HttpRangeReader::builder("https://127.0.0.1:9/archive.zip".to_string())
+        .add_root_certificate(format!("{}/missing.pem", DATA))
+        .build()
+        .await;
+    let Err(err) = result else {
+        panic!("built a reader without its CA certificate");
+    };
+    assert!(
+        err.to_string().contains("Failed to read CA certificate"),
+        "{:#}",
+        err
+    );
+}
+
+#[test]
+fn cli_warns_when_verification_is_disabled() {
+    let zip = common::build_zip(&[common::TestEntry::new("a.txt", b"hello")], &[]);
+    let url = serve_tls(Box::leak(zip.into_boxed_slice()));
+    let dir = std::path::Path::new(DATA);
+
+    let output = common::runzip(dir, &["--insecure", "-l", &url]);
+    assert_eq!(common::stdout(&output), "a.txt\n");
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("warning: --insecure: TLS certificate verification is disabled")
+    );
+
+    let ca = format!("{}/test-ca.pem", DATA);
+    let output = common::runzip(dir, &["--cacert", &ca, "-l", &url]);
+    assert_eq!(common::stdout(&output), "a.txt\n");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("warning"));
+}
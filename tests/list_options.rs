@@ -0,0 +1,121 @@
+//! Bounding listings with `list_files_with`.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use runzip::{CancelToken, ListOptions, MemoryReader, ReadAt, ZipError, ZipExtractor};
+
+use common::{TestEntry, build_zip, cd_offset, extractor};
+
+/// A reader that stalls on reads covering the start of the Central
+/// Directory.
+struct SlowDirectory {
+    inner: MemoryReader,
+    cd_offset: u64,
+    stalled: AtomicBool,
+}
+
+#[async_trait::async_trait]
+impl ReadAt for SlowDirectory {
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        if (offset..offset + buf.len() as u64).contains(&self.cd_offset) {
+            self.stalled.store(true, Ordering::Relaxed);
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+        }
+        self.inner.read_at(offset, buf).await
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+}
+
+/// An archive whose Central Directory is larger than the tail read
+/// used to find its end record.
+fn many_entries() -> Vec<u8> {
+    let names: Vec<String> = (0..2000).map(|i| format!("file{:04}.txt", i)).collect();
+    let entries: Vec<TestEntry> = names.iter().map(|n| TestEntry::new(n, b"")).collect();
+    build_zip(&entries, &[])
+}
+
+fn slow_extractor() -> (ZipExtractor<SlowDirectory>, Arc<SlowDirectory>) {
+    let zip = many_entries();
+    let reader = Arc::new(SlowDirectory {
+        cd_offset: cd_offset(&zip) as u64,
+        inner: MemoryReader::new(zip),
+        stalled: AtomicBool::new(false),
+    });
+    (ZipExtractor::new(reader.clone()), reader)
+}
+
+#[tokio::test]
+async fn cancelling_a_slow_listing_stops_it() {
+    let (extractor, reader) = slow_extractor();
+    let cancel = CancelToken::new();
+    let canceller = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let options = ListOptions {
+        timeout: None,
+        cancel: Some(cancel.clone()),
+    };
+    let err = extractor.list_files_with(options).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<ZipError>(), Some(&ZipError::Cancelled));
+    assert!(reader.stalled.load(Ordering::Relaxed));
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    // The token stays cancelled
+    assert!(cancel.is_cancelled());
+    let options = ListOptions {
+        timeout: None,
+        cancel: Some(cancel),
+    };
+    let err = common::extractor(many_entries())
+        .list_files_with(options)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<ZipError>(), Some(&ZipError::Cancelled));
+}
+
+#[tokio::test]
+async fn slow_listings_time_out() {
+    let (extractor, _) = slow_extractor();
+    let options = ListOptions {
+        timeout: Some(Duration::from_millis(100)),
+        cancel: Some(CancelToken::new()),
+    };
+    let err = extractor.list_files_with(options).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ZipError>(),
+        Some(&ZipError::ListTimeout {
+            timeout: Duration::from_millis(100),
+        })
+    );
+    assert_eq!(err.to_string(), "Listing the archive took longer than 0.1s");
+}
+
+#[tokio::test]
+async fn default_options_list_like_list_files() {
+    let extractor = extractor(many_entries());
+    let options = ListOptions {
+        timeout: Some(Duration::from_secs(60)),
+        ..ListOptions::default()
+    };
+    let bounded = extractor.list_files_with(options).await.unwrap();
+    let plain = extractor.list_files().await.unwrap();
+    assert_eq!(bounded.len(), 2000);
+    assert!(
+        bounded
+            .iter()
+            .zip(&plain)
+            .all(|(a, b)| a.file_name == b.file_name)
+    );
+}
@@ -0,0 +1,233 @@
+//! Archive listings (`-l`, `-v`) on the command line.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, cd_offset, runzip, scratch_dir, stdout};
+
+#[test]
+fn entries_limit_lists_the_first_entries() {
+    let dir = scratch_dir("entries-limit");
+    let names: Vec<String> = (0..10).map(|i| format!("f{}.txt", i)).collect();
+    let entries: Vec<_> = names.iter().map(|n| TestEntry::new(n, b"x")).collect();
+    fs::write(dir.join("many.zip"), build_zip(&entries, &[])).unwrap();
+
+    let out = stdout(&runzip(&dir, &["-l", "--entries-limit", "3", "many.zip"]));
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        ["f0.txt", "f1.txt", "f2.txt", "... and 7 more"]
+    );
+
+    // Entries past the limit are never parsed, so damage there goes unnoticed
+    let mut zip = build_zip(&entries, &[]);
+    let fifth = cd_offset(&zip) + 4 * (46 + "f0.txt".len());
+    // A name running past the end of the directory
+    zip[fifth + 28..fifth + 30].fill(0xFF);
+    fs::write(dir.join("damaged.zip"), zip).unwrap();
+    let out = stdout(&runzip(
+        &dir,
+        &["-l", "--entries-limit", "3", "damaged.zip"],
+    ));
+    assert!(out.ends_with("... and 7 more\n"), "{}", out);
+    assert!(!runzip(&dir, &["-l", "damaged.zip"]).status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn verbose_listing_shows_the_method() {
+    let dir = scratch_dir("verbose-method");
+    let zip = build_zip(
+        &[
+            TestEntry::new("stored.txt", b"data"),
+            TestEntry {
+                flags: 0b100,
+                ..TestEntry::deflated("fast.txt", b"data data data data")
+            },
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let out = stdout(&runzip(&dir, &["-v", "a.zip"]));
+    let line = |name: &str| out.lines().find(|l| l.ends_with(name)).unwrap().to_string();
+    assert!(line("stored.txt").contains(" Stored "), "{}", out);
+    assert!(line("fast.txt").contains(" Defl:F "), "{}", out);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn trailing_slashes_can_be_stripped_from_directories() {
+    let dir = scratch_dir("strip-trailing-slash");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("docs/"),
+            TestEntry::new("docs/a.txt", b"alpha"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    assert_eq!(
+        stdout(&runzip(&dir, &["-l", "a.zip"])),
+        "docs/\ndocs/a.txt\n"
+    );
+    assert_eq!(
+        stdout(&runzip(&dir, &["-l", "--strip-trailing-slash", "a.zip"])),
+        "docs\ndocs/a.txt\n"
+    );
+    let verbose = stdout(&runzip(&dir, &["-v", "--strip-trailing-slash", "a.zip"]));
+    assert!(!verbose.contains("docs/ "), "{}", verbose);
+
+    // Directories are still extracted as directories
+    let output = runzip(&dir, &["--strip-trailing-slash", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("out/docs").is_dir());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn charset_option_decodes_listed_names() {
+    let dir = scratch_dir("charset-cli");
+    let zip = build_zip(&[TestEntry::raw(b"\x93\xfa\x96\x7b.txt", b"a")], &[]);
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    assert_eq!(
+        stdout(&runzip(&dir, &["-l", "--charset", "shift_jis", "a.zip"])),
+        "日本.txt\n"
+    );
+    let output = runzip(&dir, &["-l", "--charset", "klingon", "a.zip"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown character set"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn depth_limits_listing_and_extraction() {
+    let dir = scratch_dir("depth");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("a/"),
+            TestEntry::new("a/b.txt", b"b"),
+            TestEntry::new("a/c/d.txt", b"d"),
+            TestEntry::new("e.txt", b"e"),
+            TestEntry::new("f.txt", b"f"),
+            TestEntry::new("g/h.txt", b"h"),
+            TestEntry::new("i.txt", b"i"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    assert_eq!(
+        stdout(&runzip(&dir, &["-l", "--depth", "1", "a.zip"])),
+        "a/\ne.txt\nf.txt\ni.txt\n"
+    );
+    // The footer counts only entries within the depth
+    assert_eq!(
+        stdout(&runzip(
+            &dir,
+            &["-l", "--depth", "1", "--entries-limit", "2", "a.zip"]
+        )),
+        "a/\ne.txt\n... and 2 more\n"
+    );
+    assert!(
+        !runzip(&dir, &["-l", "--depth", "0", "a.zip"])
+            .status
+            .success()
+    );
+
+    let output = runzip(&dir, &["--depth", "2", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("out/a/b.txt").exists() && dir.join("out/g/h.txt").exists());
+    assert!(!dir.join("out/a/c").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn csv_listing_quotes_names_when_needed() {
+    let dir = scratch_dir("csv");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("docs/"),
+            TestEntry::deflated("docs/a,b.txt", b"alpha alpha alpha"),
+            TestEntry::new("say \"hi\".txt", b"hi"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let out = stdout(&runzip(&dir, &["--csv", "a.zip"]));
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines[0], "name,size,compressed,method,crc,modified,is_dir");
+    assert_eq!(
+        lines[1],
+        "docs/,0,0,Stored,00000000,1980-01-01 00:00:00,true"
+    );
+    assert!(lines[2].starts_with("\"docs/a,b.txt\",17,"), "{}", lines[2]);
+    assert_eq!(
+        lines[3],
+        format!(
+            "\"say \"\"hi\"\".txt\",2,2,Stored,{:08x},1980-01-01 00:00:00,false",
+            common::crc32(b"hi")
+        )
+    );
+    assert_eq!(lines.len(), 4);
+
+    // No footer, even when entries are left out
+    let out = stdout(&runzip(
+        &dir,
+        &[
+            "--csv",
+            "--entries-limit",
+            "1",
+            "--strip-trailing-slash",
+            "a.zip",
+        ],
+    ));
+    assert_eq!(
+        out,
+        "name,size,compressed,method,crc,modified,is_dir\ndocs,0,0,Stored,00000000,1980-01-01 00:00:00,true\n"
+    );
+    assert!(!runzip(&dir, &["--csv", "-l", "a.zip"]).status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn verbose_ratios_handle_any_declared_size() {
+    let dir = scratch_dir("listing-ratios");
+    let zip = build_zip(
+        &[
+            // Grew by half, e.g. through encryption overhead
+            TestEntry {
+                payload: Some(vec![0; 150]),
+                ..TestEntry::new("grown.bin", &[0; 100])
+            },
+            // ZIP64 sizes whose percentages and totals overflow 64 bits
+            TestEntry {
+                extra: [&[0x01, 0x00, 16, 0][..], &[0xFF; 8], &[0xFF; 8]].concat(),
+                cd_size: Some(u32::MAX),
+                uncompressed_size: Some(u32::MAX as u64),
+                ..TestEntry::new("huge.bin", b"x")
+            },
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let out = stdout(&runzip(&dir, &["-v", "a.zip"]));
+    let line = |name: &str| out.lines().find(|l| l.ends_with(name)).unwrap().to_string();
+    assert!(line("grown.bin").contains(" -50% "), "{}", out);
+    assert!(line("huge.bin").contains("   0% "), "{}", out);
+    // The totals line saturates instead of overflowing
+    let totals = out.lines().last().unwrap();
+    assert!(totals.ends_with("2 files"), "{}", out);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,47 @@
+//! Positioned reads of the local file reader.
+
+mod common;
+
+use std::fs;
+use std::sync::Arc;
+
+use runzip::{LocalFileReader, ReadAt};
+
+use common::scratch_dir;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_reads_get_their_own_ranges() {
+    let dir = scratch_dir("local-reader");
+    let data: Vec<u8> = (0..1_000_000u32).map(|i| (i % 253) as u8).collect();
+    fs::write(dir.join("data.bin"), &data).unwrap();
+    let reader = Arc::new(LocalFileReader::new(&dir.join("data.bin")).unwrap());
+    let data = Arc::new(data);
+
+    let reads: Vec<_> = (0..64u64)
+        .map(|i| {
+            let (reader, data) = (reader.clone(), data.clone());
+            tokio::spawn(async move {
+                for round in 0..20u64 {
+                    let offset = (i * 15_013 + round * 7_919) % 990_000;
+                    let mut buf = vec![0u8; 4096];
+                    let n = reader.read_at(offset, &mut buf).await.unwrap();
+                    assert!(n > 0);
+                    let start = offset as usize;
+                    assert_eq!(buf[..n], data[start..start + n], "offset {}", offset);
+                }
+            })
+        })
+        .collect();
+    for read in reads {
+        read.await.unwrap();
+    }
+
+    // Reads are independent of any file position
+    let mut buf = [0u8; 4];
+    assert_eq!(reader.read_at(999_998, &mut buf).await.unwrap(), 2);
+    assert_eq!(reader.read_at(1_000_000, &mut buf).await.unwrap(), 0);
+    assert_eq!(reader.read_at(3, &mut buf).await.unwrap(), 4);
+    assert_eq!(buf, [3, 4, 5, 6]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,89 @@
+//! Entries with overlong names.
+
+mod common;
+
+use runzip::{Warning, ZipError};
+
+use common::{TestEntry, build_zip, extractor, runzip, scratch_dir, stdout};
+
+fn archive() -> Vec<u8> {
+    let long = "x".repeat(5000);
+    build_zip(
+        &[
+            TestEntry::new("a.txt", b"a"),
+            TestEntry::new(&long, b"long"),
+            TestEntry::new("b.txt", b"b"),
+        ],
+        &[],
+    )
+}
+
+#[tokio::test]
+async fn long_names_reject_the_archive_by_default() {
+    let err = extractor(archive()).list_files().await.unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(&ZipError::NameTooLong {
+                length: 5000,
+                max: 4096,
+                ..
+            })
+        ),
+        "{:#}",
+        err
+    );
+
+    // A raised limit accepts them
+    let entries = extractor(archive())
+        .with_max_name_length(5000)
+        .list_files()
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 3);
+}
+
+#[tokio::test]
+async fn long_names_can_be_skipped() {
+    let extractor = extractor(archive()).with_skip_long_names(true);
+    let names: Vec<_> = extractor
+        .list_files()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.file_name)
+        .collect();
+    assert_eq!(names, ["a.txt", "b.txt"]);
+    assert!(matches!(
+        extractor.take_warnings()[..],
+        [Warning::LongNameSkipped { length: 5000, .. }]
+    ));
+
+    let names: Vec<_> = extractor
+        .with_max_name_length(4)
+        .list_files()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.file_name)
+        .collect();
+    assert!(names.is_empty());
+}
+
+#[test]
+fn cli_skips_long_names_on_request() {
+    let dir = scratch_dir("long-names");
+    std::fs::write(dir.join("test.zip"), archive()).unwrap();
+
+    let output = runzip(&dir, &["-l", "test.zip"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("5000 bytes long"));
+
+    let output = runzip(&dir, &["-l", "--skip-long-names", "test.zip"]);
+    let listing = stdout(&output);
+    assert!(listing.contains("a.txt") && listing.contains("b.txt"));
+    assert!(!listing.contains("xxx"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Skipping entry at offset"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
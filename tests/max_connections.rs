@@ -0,0 +1,82 @@
+//! The limit on concurrent HTTP Range requests.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use runzip::{HttpRangeReader, ReadAt};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use common::default_response;
+
+/// Serve `data` slowly, recording the most GET requests in flight at once.
+async fn serve_slowly(data: Vec<u8>, peak: Arc<AtomicUsize>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/archive.zip", listener.local_addr().unwrap());
+    let (data, in_flight) = (Arc::new(data), Arc::new(AtomicUsize::new(0)));
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let (data, in_flight, peak) = (data.clone(), in_flight.clone(), peak.clone());
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                loop {
+                    let mut head = String::new();
+                    while !head.ends_with("\r\n\r\n") {
+                        if stream.read_line(&mut head).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                    }
+                    if head.starts_with("GET") {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    }
+                    let response = default_response(&head, &data);
+                    if stream.get_mut().write_all(&response).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    url
+}
+
+/// Read 8 ranges at once and return the most requests seen in flight.
+async fn peak_connections(max_connections: Option<usize>) -> usize {
+    let peak = Arc::new(AtomicUsize::new(0));
+    let url = serve_slowly((0..=255u8).collect(), peak.clone()).await;
+    let mut builder = HttpRangeReader::builder(url);
+    if let Some(max) = max_connections {
+        builder = builder.max_connections(max);
+    }
+    let reader = Arc::new(builder.build().await.unwrap());
+
+    let reads: Vec<_> = (0..8u64)
+        .map(|i| {
+            let reader = reader.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 4];
+                reader.read_at(i * 10, &mut buf).await.unwrap();
+                assert_eq!(buf[0], (i * 10) as u8);
+            })
+        })
+        .collect();
+    for read in reads {
+        read.await.unwrap();
+    }
+    peak.load(Ordering::SeqCst)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_in_flight_are_capped() {
+    assert_eq!(peak_connections(Some(2)).await, 2);
+    assert_eq!(peak_connections(Some(0)).await, 1);
+    assert_eq!(peak_connections(None).await, 4);
+}
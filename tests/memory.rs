@@ -0,0 +1,72 @@
+//! Reading archives held in memory, whole or only their tail.
+
+mod common;
+
+use std::sync::Arc;
+
+use runzip::{MemoryReader, ReadAt, ZipExtractor};
+
+use common::{TestEntry, build_zip, cd_offset};
+
+fn archive() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::deflated("dir/b.txt", &[b'b'; 4096]),
+        ],
+        &[],
+    )
+}
+
+#[tokio::test]
+async fn reads_before_the_tail_fail() {
+    let reader = MemoryReader::tail(b"6789".to_vec(), 10).unwrap();
+    assert_eq!(reader.size(), 10);
+
+    let mut buf = [0u8; 2];
+    reader.read_at(7, &mut buf).await.unwrap();
+    assert_eq!(&buf, b"78");
+    assert!(reader.read_at(5, &mut buf).await.is_err());
+
+    assert!(MemoryReader::tail(vec![0; 11], 10).is_err());
+}
+
+#[tokio::test]
+async fn whole_buffer_is_extracted() {
+    let zip = archive();
+    let extractor = ZipExtractor::new(Arc::new(MemoryReader::new(zip)));
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(entries.len(), 2);
+    let data = extractor.extract_to_memory(&entries[1]).await.unwrap();
+    assert_eq!(data, vec![b'b'; 4096]);
+}
+
+#[tokio::test]
+async fn entries_are_listed_from_the_tail() {
+    let zip = archive();
+    let tail = &zip[cd_offset(&zip)..];
+    let entries = ZipExtractor::list_from_tail(tail, zip.len() as u64)
+        .await
+        .unwrap();
+    let names: Vec<_> = entries.iter().map(|e| e.file_name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "dir/b.txt"]);
+}
+
+#[tokio::test]
+async fn tail_missing_part_of_the_directory_is_an_error() {
+    let zip = archive();
+    let tail = &zip[cd_offset(&zip) + 1..];
+    let err = ZipExtractor::list_from_tail(tail, zip.len() as u64)
+        .await
+        .unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("Cannot list from the last"),
+        "{:#}",
+        err
+    );
+
+    let err = ZipExtractor::list_from_tail(&zip[zip.len() - 21..], zip.len() as u64)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too short"), "{:#}", err);
+}
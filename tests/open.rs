@@ -0,0 +1,116 @@
+//! Validating archives eagerly with `ZipExtractor::open`, and prefetching
+//! their metadata.
+
+mod common;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::Result;
+use runzip::{ReadAt, ZipExtractor};
+
+use common::{TestEntry, build_zip};
+
+/// In-memory reader counting its reads.
+struct CountingReader {
+    data: Vec<u8>,
+    reads: AtomicU64,
+}
+
+impl CountingReader {
+    fn new(data: Vec<u8>) -> Arc<Self> {
+        Arc::new(Self {
+            data,
+            reads: AtomicU64::new(0),
+        })
+    }
+
+    fn take_reads(&self) -> u64 {
+        self.reads.swap(0, Ordering::Relaxed)
+    }
+}
+
+#[async_trait::async_trait]
+impl ReadAt for CountingReader {
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        let start = (offset as usize).min(self.data.len());
+        let n = buf.len().min(self.data.len() - start);
+        buf[..n].copy_from_slice(&self.data[start..start + n]);
+        Ok(n)
+    }
+
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+}
+
+#[tokio::test]
+async fn non_zip_sources_are_rejected_up_front() {
+    let reader = CountingReader::new(b"just some text, certainly not an archive".to_vec());
+    assert!(ZipExtractor::open(reader).await.is_err());
+}
+
+#[tokio::test]
+async fn directory_location_is_found_once() {
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+        ],
+        &[],
+    );
+
+    let reader = CountingReader::new(zip.clone());
+    let lazy = ZipExtractor::new(reader.clone());
+    lazy.list_files().await.unwrap();
+    let uncached = reader.take_reads();
+
+    let reader = CountingReader::new(zip);
+    let eager = ZipExtractor::open(reader.clone()).await.unwrap();
+    assert!(reader.take_reads() > 0);
+    assert_eq!(eager.list_files().await.unwrap().len(), 2);
+    let cached = reader.take_reads();
+    assert!(cached < uncached, "{} vs {}", cached, uncached);
+    eager.list_files().await.unwrap();
+    assert_eq!(reader.take_reads(), cached);
+}
+
+#[tokio::test]
+async fn prefetched_metadata_serves_later_reads() {
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+        ],
+        &[],
+    );
+    let reader = CountingReader::new(zip);
+    let extractor = ZipExtractor::new(reader.clone());
+    let saved = extractor.prefetch_metadata().await.unwrap();
+    assert_eq!(reader.take_reads(), 1);
+    assert!(saved >= 1, "{}", saved);
+
+    assert_eq!(extractor.list_files().await.unwrap().len(), 2);
+    extractor.comments().await.unwrap();
+    assert_eq!(reader.take_reads(), 0);
+
+    // A second prefetch does nothing
+    assert_eq!(extractor.prefetch_metadata().await.unwrap(), 0);
+    assert_eq!(reader.take_reads(), 0);
+}
+
+#[tokio::test]
+async fn directory_beyond_the_prefetch_is_read_normally() {
+    let names: Vec<String> = (0..3000).map(|i| format!("{:0>100}", i)).collect();
+    let entries: Vec<_> = names.iter().map(|n| TestEntry::new(n, b"x")).collect();
+    let reader = CountingReader::new(build_zip(&entries, &[]));
+    let extractor = ZipExtractor::new(reader.clone());
+
+    // The end records are served from the prefetched bytes, but the start
+    // of the directory is checked outside them
+    assert_eq!(extractor.prefetch_metadata().await.unwrap(), 1);
+    assert_eq!(reader.take_reads(), 2);
+    assert_eq!(extractor.list_files().await.unwrap().len(), 3000);
+    assert_eq!(reader.take_reads(), 1);
+}
@@ -0,0 +1,53 @@
+//! Extracting into file handles the caller has already opened.
+
+mod common;
+
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use common::{TestEntry, build_zip, open, scratch_dir};
+
+#[tokio::test]
+async fn entry_is_written_at_the_current_position() {
+    let dir = scratch_dir("open-file");
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::deflated("big.bin", &data),
+            TestEntry {
+                crc32: Some(0),
+                ..TestEntry::new("bad.bin", b"corrupt")
+            },
+        ],
+        &[],
+    );
+    let extractor = open(&dir, "a.zip", &zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let path = dir.join("slot.bin");
+    let mut file = fs::File::create(&path).await.unwrap();
+    file.write_all(b"header").await.unwrap();
+    let written = extractor
+        .extract_to_open_file(&entries[0], &mut file)
+        .await
+        .unwrap();
+    assert_eq!(written, data.len() as u64);
+
+    // Corrupt data is streamed too, and reported afterwards
+    assert!(
+        extractor
+            .extract_to_open_file(&entries[1], &mut file)
+            .await
+            .is_err()
+    );
+    drop(file);
+
+    let contents = fs::read(&path).await.unwrap();
+    assert_eq!(&contents[..6], b"header");
+    assert_eq!(&contents[6..6 + data.len()], &data[..]);
+    assert_eq!(&contents[6 + data.len()..], b"corrupt");
+    // No directories were created next to the handle
+    assert!(!dir.join("big.bin").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
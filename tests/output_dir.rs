@@ -0,0 +1,78 @@
+//! Choosing and guarding the output directory from the command line.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+#[test]
+fn only_new_dirs_refuses_a_non_empty_directory_without_overwrite() {
+    let dir = scratch_dir("only-new-dirs");
+    fs::write(
+        dir.join("a.zip"),
+        build_zip(&[TestEntry::new("a.txt", b"new")], &[]),
+    )
+    .unwrap();
+    fs::create_dir(dir.join("out")).unwrap();
+    fs::write(dir.join("out/keep.txt"), b"old").unwrap();
+
+    let output = runzip(&dir, &["--only-new-dirs", "-d", "out", "a.zip"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists and is not empty"));
+    assert!(!dir.join("out/a.txt").exists());
+
+    let output = runzip(&dir, &["--only-new-dirs", "-o", "-d", "out", "a.zip"]);
+    assert!(output.status.success());
+    assert_eq!(fs::read(dir.join("out/a.txt")).unwrap(), b"new");
+    assert_eq!(fs::read(dir.join("out/keep.txt")).unwrap(), b"old");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn auto_dir_extracts_into_a_directory_named_after_the_archive() {
+    let dir = scratch_dir("auto-dir");
+    let zip = build_zip(&[TestEntry::new("docs/a.txt", b"hello")], &[]);
+    fs::write(dir.join("release-1.2.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--auto-dir", "--only-new-dirs", "release-1.2.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        fs::read(dir.join("release-1.2/docs/a.txt")).unwrap(),
+        b"hello"
+    );
+
+    // The directory now has content, so a second run refuses it
+    let output = runzip(&dir, &["--auto-dir", "--only-new-dirs", "release-1.2.zip"]);
+    assert!(!output.status.success());
+
+    // -d names the directory itself
+    let output = runzip(&dir, &["--auto-dir", "-d", "out", "release-1.2.zip"]);
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn auto_dir_names_follow_the_last_path_segment() {
+    use clap::Parser;
+    use runzip::Cli;
+
+    let resolve = |file: &str| {
+        let mut cli = Cli::parse_from(["runzip", "--auto-dir", file]);
+        cli.resolve_auto_dir().map(|()| cli.extract_dir.unwrap())
+    };
+    assert_eq!(resolve("builds/app.zip").unwrap(), "app");
+    assert_eq!(resolve("file:///tmp/app.zip").unwrap(), "app");
+    assert_eq!(
+        resolve("https://example.com/dl/app-2.0.zip?token=x").unwrap(),
+        "app-2.0"
+    );
+    assert_eq!(
+        resolve("https://example.com/dl/archive/").unwrap(),
+        "archive"
+    );
+    assert!(resolve("https://example.com/").is_err());
+    assert!(resolve("..").is_err());
+}
@@ -0,0 +1,118 @@
+//! The final check that output paths resolve inside the output directory.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::symlink;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+#[test]
+fn paths_through_planted_symlinks_are_refused() {
+    let dir = scratch_dir("outside-root");
+    // The entry name is clean, so only the final check can catch that
+    // the output directory already holds a link leading out of it
+    let zip = build_zip(&[TestEntry::new("link/evil.txt", b"evil")], &[]);
+    fs::write(dir.join("a.zip"), zip).unwrap();
+    fs::create_dir_all(dir.join("out")).unwrap();
+    fs::create_dir_all(dir.join("outside")).unwrap();
+    symlink("../outside", dir.join("out/link")).unwrap();
+
+    for flag in ["--no-overwrite-outside-root", "--preserve=links"] {
+        let output = runzip(&dir, &[flag, "-d", "out", "a.zip"]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{}", flag);
+        assert!(
+            stderr.contains("Refusing to write out/link/evil.txt"),
+            "{}",
+            stderr
+        );
+        assert!(!dir.join("outside/evil.txt").exists());
+    }
+
+    // Links inside the output directory are fine
+    fs::remove_file(dir.join("out/link")).unwrap();
+    fs::create_dir_all(dir.join("out/real")).unwrap();
+    symlink("real", dir.join("out/link")).unwrap();
+    let output = runzip(&dir, &["--no-overwrite-outside-root", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(fs::read(dir.join("out/real/evil.txt")).unwrap(), b"evil");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn chained_links_cannot_lead_out() {
+    let dir = scratch_dir("outside-root-chain");
+    // Each link looks harmless on its own: s/t is one level down in the
+    // archive, but s leads back to the top, so t really points above it
+    let zip = build_zip(
+        &[
+            TestEntry::new("s", b".").mode(0o120777),
+            TestEntry::new("s/t", b"..").mode(0o120777),
+            TestEntry::new("t/evil.txt", b"evil"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["-d", "out", "a.zip"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Refusing symlink s/t -> ..: target escapes"),
+        "{}",
+        stderr
+    );
+    // t/evil.txt lands in a plain directory instead
+    assert!(
+        !fs::symlink_metadata(dir.join("out/t"))
+            .unwrap()
+            .is_symlink()
+    );
+    assert!(!dir.join("evil.txt").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn links_are_checked_where_they_are_placed() {
+    let dir = scratch_dir("outside-root-junk");
+    // Two levels down in the archive `../..` stays inside, but -j puts
+    // the link at the top and --auto-strip one level up
+    let zip = build_zip(
+        &[
+            TestEntry::new("a/b/link", b"../..").mode(0o120777),
+            TestEntry::new("a/b/near", b"sibling").mode(0o120777),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    for args in ["-j", "--auto-strip"] {
+        let args = [args, "-d", "out", "a.zip"];
+        let output = runzip(&dir, &args);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(
+            stderr.contains("Refusing symlink a/b/link -> ../..: target escapes"),
+            "{}",
+            stderr
+        );
+        assert!(fs::symlink_metadata(dir.join("out/link")).is_err());
+        assert!(fs::symlink_metadata(dir.join("out/b/link")).is_err());
+        fs::remove_dir_all(dir.join("out")).unwrap();
+    }
+
+    // Without them, the same link stays inside the output directory
+    let output = runzip(&dir, &["-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        fs::read_link(dir.join("out/a/b/link")).unwrap(),
+        std::path::Path::new("../..")
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,80 @@
+//! Permissions of extracted files and directories.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+#[test]
+fn chmod_overrides_archive_modes() {
+    let dir = scratch_dir("chmod");
+    let zip = build_zip(
+        &[
+            TestEntry::dir("bin/").mode(0o40755),
+            TestEntry::new("bin/tool", b"#!/bin/sh\n").mode(0o100755),
+            TestEntry::new("notes.txt", b"dos file"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(
+        &dir,
+        &[
+            "--chmod",
+            "0640",
+            "--dir-chmod",
+            "0o750",
+            "-d",
+            "out",
+            "a.zip",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(mode(&dir.join("out/bin/tool")), 0o640);
+    assert_eq!(mode(&dir.join("out/notes.txt")), 0o640);
+    assert_eq!(mode(&dir.join("out/bin")), 0o750);
+
+    let output = runzip(&dir, &["--chmod", "0999", "a.zip"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid octal mode"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dir_mode_applies_to_created_directories_only() {
+    let dir = scratch_dir("dir-mode");
+    let zip = build_zip(
+        &[
+            TestEntry::new("a/b/c.txt", b"deep"),
+            TestEntry::new("kept/d.txt", b"existing parent"),
+            TestEntry::dir("stored/").mode(0o40700),
+            TestEntry::new("stored/e.txt", b"stored mode"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+    fs::create_dir_all(dir.join("out/kept")).unwrap();
+    fs::set_permissions(dir.join("out/kept"), fs::Permissions::from_mode(0o755)).unwrap();
+
+    // A mode wider than the umask allows is still applied exactly
+    let output = runzip(&dir, &["--dir-mode", "777", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(mode(&dir.join("out/a")), 0o777);
+    assert_eq!(mode(&dir.join("out/a/b")), 0o777);
+    assert_eq!(mode(&dir.join("out/kept")), 0o755);
+    assert_eq!(mode(&dir.join("out/stored")), 0o700);
+    assert_eq!(fs::read(dir.join("out/a/b/c.txt")).unwrap(), b"deep");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
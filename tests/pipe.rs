@@ -0,0 +1,90 @@
+//! Pipe mode (`-p`), which streams entries to standard output.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+/// Data spanning several stream chunks that deflate doesn't shrink much.
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+#[test]
+fn entries_are_streamed_with_markers() {
+    let dir = scratch_dir("pipe");
+    let big = noise(700_000);
+    let zip = build_zip(
+        &[
+            TestEntry::deflated("big.bin", &big),
+            TestEntry::new("small.txt", b"small"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["-p", "a.zip", "big.bin"]);
+    assert!(output.status.success());
+    assert!(output.stdout == big);
+
+    let output = runzip(&dir, &["-p", "a.zip"]);
+    assert!(output.status.success());
+    let mut expected = b"--- big.bin ---\n".to_vec();
+    expected.extend_from_slice(&big);
+    expected.extend_from_slice(b"--- small.txt ---\nsmall");
+    assert!(output.stdout == expected);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn checksum_mismatch_is_reported_after_the_data() {
+    let dir = scratch_dir("pipe-crc");
+    let big = noise(600_000);
+    let zip = build_zip(
+        &[TestEntry {
+            crc32: Some(0),
+            ..TestEntry::deflated("big.bin", &big)
+        }],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["-p", "a.zip"]);
+    assert!(!output.status.success());
+    assert!(output.stdout == big);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("CRC mismatch for big.bin"), "{}", stderr);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn output_stops_at_the_declared_size() {
+    let dir = scratch_dir("pipe-size");
+    let big = noise(600_000);
+    let zip = build_zip(
+        &[TestEntry {
+            uncompressed_size: Some(300_000),
+            ..TestEntry::deflated("big.bin", &big)
+        }],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["-p", "a.zip"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.len() <= 300_000);
+    assert!(big.starts_with(&output.stdout));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
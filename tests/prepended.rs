@@ -0,0 +1,98 @@
+//! Archives behind data that their offsets don't account for, like a
+//! self-extractor stub.
+
+mod common;
+
+use std::sync::Arc;
+
+use runzip::zip::ZipParser;
+use runzip::{MemoryReader, ReadAt, Warning};
+
+use common::{TestEntry, build_zip, cd_offset, eocd, eocd_offset};
+
+fn test_entries() -> [TestEntry<'static>; 3] {
+    [
+        TestEntry::new("a.txt", b"alpha"),
+        TestEntry::deflated("b.txt", b"bravo bravo bravo"),
+        TestEntry::new("c.txt", b"charlie"),
+    ]
+}
+
+/// Replace the end record of `zip` with ZIP64 records.
+fn zip64(zip: &[u8], entries: u64) -> Vec<u8> {
+    let eocd_offset = eocd_offset(zip);
+    let cd_offset = cd_offset(zip) as u64;
+    let mut out = zip[..eocd_offset].to_vec();
+
+    let eocd64_offset = out.len() as u64;
+    out.extend_from_slice(b"PK\x06\x06");
+    out.extend_from_slice(&44u64.to_le_bytes());
+    out.extend_from_slice(&45u16.to_le_bytes());
+    out.extend_from_slice(&45u16.to_le_bytes());
+    out.extend_from_slice(&[0; 8]); // This disk, disk of the directory
+    out.extend_from_slice(&entries.to_le_bytes());
+    out.extend_from_slice(&entries.to_le_bytes());
+    out.extend_from_slice(&(eocd_offset as u64 - cd_offset).to_le_bytes());
+    out.extend_from_slice(&cd_offset.to_le_bytes());
+
+    out.extend_from_slice(b"PK\x06\x07");
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&eocd64_offset.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+
+    out.extend_from_slice(&eocd(0xFFFF, 0xFFFF_FFFF, 0xFFFF_FFFF));
+    out
+}
+
+/// Put a stub in front of `zip` and check that every entry is found and
+/// read, and that the shift is applied and reported once.
+async fn assert_stub_is_skipped(zip: Vec<u8>) {
+    let stub = vec![0x90u8; 3000];
+    let parser = ZipParser::new(Arc::new(MemoryReader::new([stub, zip].concat())));
+
+    // Concurrent first lookups and later public calls must not apply the
+    // shift again
+    let (a, b) = tokio::join!(parser.list_files(), parser.list_files());
+    assert_eq!(a.unwrap().len(), 3);
+    assert_eq!(b.unwrap().len(), 3);
+    let (eocd, eocd_offset) = parser.find_eocd().await.unwrap();
+    if eocd.is_zip64() {
+        parser.read_zip64_eocd(eocd_offset).await.unwrap();
+    }
+
+    let entries = parser.list_files().await.unwrap();
+    let expected = test_entries();
+    for (entry, expected) in entries.iter().zip(&expected) {
+        assert_eq!(entry.file_name.as_bytes(), expected.name);
+        let again = parser.parse_cdfh_at(entry.cdfh_offset).await.unwrap();
+        assert_eq!(again.lfh_offset, entry.lfh_offset);
+        let offset = parser.get_data_offset(entry).await.unwrap();
+        let data = parser
+            .reader()
+            .read_vec(offset, entry.compressed_size as usize)
+            .await
+            .unwrap();
+        assert_eq!(data, expected.payload());
+    }
+
+    let shifts: Vec<u64> = parser
+        .take_warnings()
+        .into_iter()
+        .filter_map(|w| match w {
+            Warning::PrependedData { length } => Some(length),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(shifts, [3000]);
+}
+
+#[tokio::test]
+async fn stubs_before_regular_archives_are_skipped() {
+    assert_stub_is_skipped(build_zip(&test_entries(), &[])).await;
+}
+
+#[tokio::test]
+async fn stubs_before_zip64_archives_are_skipped() {
+    let zip = build_zip(&test_entries(), &[]);
+    assert_stub_is_skipped(zip64(&zip, 3)).await;
+}
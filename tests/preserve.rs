@@ -0,0 +1,175 @@
+//! Restoring metadata recorded in the archive with `--preserve`.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use runzip::Preserve;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+/// 2020-06-15 12:00:00 in MS-DOS format.
+const DOS_TIME: u16 = 12 << 11;
+const DOS_DATE: u16 = (40 << 9) | (6 << 5) | 15;
+
+fn archive(dir: &Path) {
+    let zip = build_zip(
+        &[
+            TestEntry {
+                time: DOS_TIME,
+                date: DOS_DATE,
+                ..TestEntry::new("tool", b"#!/bin/sh\n").mode(0o100700)
+            },
+            TestEntry::new("link", b"tool").mode(0o120777),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn preserve_lists_are_parsed() {
+    assert_eq!("all".parse::<Preserve>().unwrap(), Preserve::ALL);
+    assert_eq!("none".parse::<Preserve>().unwrap(), Preserve::NONE);
+    let preserve: Preserve = "times, links".parse().unwrap();
+    assert!(preserve.times && preserve.links && !preserve.perms && !preserve.owner);
+    assert_eq!(preserve.to_string(), "times,links");
+    assert_eq!(Preserve::default().to_string(), "perms,times,links");
+
+    let err = "perms,acl".parse::<Preserve>().unwrap_err();
+    assert!(err.to_string().contains("Unknown preserve option 'acl'"));
+}
+
+#[test]
+fn default_restores_modes_times_and_links() {
+    let dir = scratch_dir("preserve-default");
+    archive(&dir);
+
+    let output = runzip(&dir, &["-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    let tool = dir.join("out/tool");
+    assert_eq!(
+        fs::metadata(&tool).unwrap().permissions().mode() & 0o777,
+        0o700
+    );
+    // Allow for the time zone the DOS time is interpreted in
+    let expected = UNIX_EPOCH + Duration::from_secs(1_592_222_400);
+    let diff = modified(&tool)
+        .duration_since(expected)
+        .unwrap_or_else(|e| e.duration());
+    assert!(diff <= Duration::from_secs(14 * 3600), "{:?}", diff);
+    let link = dir.join("out/link");
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("tool"));
+
+    // Without links, the link is written as a regular file holding its
+    // target
+    let output = runzip(&dir, &["--preserve", "perms,times", "-d", "plain", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    let link = dir.join("plain/link");
+    assert!(!fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read(&link).unwrap(), b"tool");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn none_and_links_select_what_is_restored() {
+    let dir = scratch_dir("preserve-none");
+    archive(&dir);
+
+    let before = SystemTime::now() - Duration::from_secs(60);
+    let output = runzip(&dir, &["--preserve", "none", "-d", "none", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(modified(&dir.join("none/tool")) >= before);
+
+    let output = runzip(&dir, &["--preserve", "links", "-d", "links", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    let link = dir.join("links/link");
+    assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("tool"));
+
+    let output = runzip(&dir, &["--preserve", "acl", "a.zip"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn links_escaping_the_output_directory_are_refused() {
+    let dir = scratch_dir("preserve-escape");
+    let zip = build_zip(
+        &[
+            TestEntry::new("up", b"../outside").mode(0o120777),
+            TestEntry::new("abs", b"/etc/passwd").mode(0o120777),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--preserve", "links", "-d", "out", "a.zip"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("Refusing symlink up -> ../outside"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("Refusing symlink abs -> /etc/passwd: absolute target"),
+        "{}",
+        stderr
+    );
+    assert!(fs::symlink_metadata(dir.join("out/up")).is_err());
+    assert!(fs::symlink_metadata(dir.join("out/abs")).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn directory_times_survive_their_contents() {
+    let dir = scratch_dir("preserve-dirs");
+    let dated = |entry: TestEntry<'static>| TestEntry {
+        time: DOS_TIME,
+        date: DOS_DATE,
+        ..entry
+    };
+    let zip = build_zip(
+        &[
+            dated(TestEntry::dir("docs/").mode(0o40750)),
+            dated(TestEntry::dir("docs/sub/")),
+            TestEntry::new("docs/a.txt", b"alpha"),
+            TestEntry::new("docs/sub/b.txt", b"beta"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    let expected = UNIX_EPOCH + Duration::from_secs(1_592_222_400);
+    for path in ["out/docs", "out/docs/sub"] {
+        let diff = modified(&dir.join(path))
+            .duration_since(expected)
+            .unwrap_or_else(|e| e.duration());
+        assert!(
+            diff <= Duration::from_secs(14 * 3600),
+            "{}: {:?}",
+            path,
+            diff
+        );
+    }
+    let docs = fs::metadata(dir.join("out/docs")).unwrap();
+    assert_eq!(docs.permissions().mode() & 0o777, 0o750);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
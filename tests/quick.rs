@@ -0,0 +1,72 @@
+//! One-call extraction of a single named entry.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, scratch_dir, serve};
+
+fn archive() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::dir("docs/"),
+            TestEntry::deflated("docs/README.md", b"# runzip\n"),
+            TestEntry {
+                crc32: Some(0),
+                ..TestEntry::new("bad.txt", b"corrupt")
+            },
+        ],
+        &[],
+    )
+}
+
+#[tokio::test]
+async fn named_entry_is_extracted_from_a_path() {
+    let dir = scratch_dir("quick-path");
+    let zip = dir.join("a.zip");
+    fs::write(&zip, archive()).unwrap();
+
+    let dest = dir.join("out/nested/README.md");
+    let written = runzip::extract_file_from_path(&zip, "docs/README.md", &dest)
+        .await
+        .unwrap();
+    assert_eq!(written, 9);
+    assert_eq!(fs::read(&dest).unwrap(), b"# runzip\n");
+
+    let err = runzip::extract_file_from_path(&zip, "missing.txt", dir.join("x"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "No entry named missing.txt in the archive");
+    let err = runzip::extract_file_from_path(&zip, "docs/", dir.join("x"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "docs/ is a directory, not a file");
+
+    // Corrupt data never reaches the destination
+    let err = runzip::extract_file_from_path(&zip, "bad.txt", dir.join("bad.txt"))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().starts_with("CRC mismatch for bad.txt"),
+        "{}",
+        err
+    );
+    assert!(!dir.join("bad.txt").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn named_entry_is_extracted_from_a_url() {
+    let dir = scratch_dir("quick-url");
+    let server = serve(archive()).await;
+
+    let dest = dir.join("README.md");
+    let written = runzip::extract_file_from_url(&server.url, "docs/README.md", &dest)
+        .await
+        .unwrap();
+    assert_eq!(written, 9);
+    assert_eq!(fs::read(&dest).unwrap(), b"# runzip\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,187 @@
+//! Exact reads through `ReadAt::read_vec` and `ReadAt::read_many`.
+
+mod common;
+
+use anyhow::Result;
+use runzip::{LocalFileReader, ReadAt};
+
+use common::scratch_dir;
+
+/// In-memory reader returning at most `chunk` bytes per call.
+struct ChunkedReader {
+    data: Vec<u8>,
+    chunk: usize,
+}
+
+#[async_trait::async_trait]
+impl ReadAt for ChunkedReader {
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let start = (offset as usize).min(self.data.len());
+        let n = buf.len().min(self.chunk).min(self.data.len() - start);
+        buf[..n].copy_from_slice(&self.data[start..start + n]);
+        Ok(n)
+    }
+
+    fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+}
+
+fn reader() -> ChunkedReader {
+    ChunkedReader {
+        data: (0..=99).collect(),
+        chunk: 7,
+    }
+}
+
+#[tokio::test]
+async fn short_reads_are_retried() {
+    let buf = reader().read_vec(10, 50).await.unwrap();
+    assert_eq!(buf, (10..60).collect::<Vec<u8>>());
+}
+
+#[tokio::test]
+async fn eof_before_the_requested_length_is_an_error() {
+    let err = reader().read_vec(90, 20).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Unexpected end of data: read 10 of 20 bytes at offset 90"
+    );
+}
+
+/// Scattered requests: unordered, adjacent, overlapping, far apart and
+/// empty.
+fn requests() -> Vec<(u64, usize)> {
+    vec![
+        (50, 10),
+        (3, 4),
+        (7, 2),
+        (5, 6),
+        (9000, 100),
+        (60, 0),
+        (20_000, 1),
+    ]
+}
+
+async fn read_many(reader: &impl ReadAt, requests: &[(u64, usize)]) -> Result<Vec<Vec<u8>>> {
+    let mut bufs: Vec<Vec<u8>> = requests.iter().map(|&(_, len)| vec![0; len]).collect();
+    let mut slices: Vec<(u64, &mut [u8])> = requests
+        .iter()
+        .zip(bufs.iter_mut())
+        .map(|(&(offset, _), buf)| (offset, &mut buf[..]))
+        .collect();
+    reader.read_many(&mut slices).await?;
+    Ok(bufs)
+}
+
+fn expected(data: &[u8], requests: &[(u64, usize)]) -> Vec<Vec<u8>> {
+    requests
+        .iter()
+        .map(|&(offset, len)| data[offset as usize..offset as usize + len].to_vec())
+        .collect()
+}
+
+#[tokio::test]
+async fn default_read_many_fills_every_buffer() {
+    let reader = ChunkedReader {
+        data: (0..30_000u32).map(|i| i as u8).collect(),
+        chunk: 7,
+    };
+    let bufs = read_many(&reader, &requests()).await.unwrap();
+    assert_eq!(bufs, expected(&reader.data, &requests()));
+}
+
+#[tokio::test]
+async fn local_read_many_matches_single_reads() {
+    let dir = scratch_dir("read-many");
+    let data: Vec<u8> = (0..30_000u32).map(|i| (i * 7) as u8).collect();
+    let path = dir.join("data.bin");
+    std::fs::write(&path, &data).unwrap();
+    let reader = LocalFileReader::new(&path).unwrap();
+
+    let bufs = read_many(&reader, &requests()).await.unwrap();
+    assert_eq!(bufs, expected(&data, &requests()));
+
+    // More adjacent ranges than fit in one preadv call
+    let many: Vec<(u64, usize)> = (0..3000).map(|i| (i * 3, 2)).collect();
+    let bufs = read_many(&reader, &many).await.unwrap();
+    assert_eq!(bufs, expected(&data, &many));
+
+    let err = read_many(&reader, &[(29_990, 20)]).await.unwrap_err();
+    assert!(
+        err.to_string().contains("Unexpected end of data"),
+        "{}",
+        err
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Reader claiming to be longer than its data, as a server clamping
+/// ranges to a shorter file than it announced would be.
+struct OverstatedReader {
+    data: Vec<u8>,
+    claimed: u64,
+}
+
+#[async_trait::async_trait]
+impl ReadAt for OverstatedReader {
+    async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        let start = (offset as usize).min(self.data.len());
+        let n = buf.len().min(self.data.len() - start);
+        buf[..n].copy_from_slice(&self.data[start..start + n]);
+        Ok(n)
+    }
+
+    fn size(&self) -> u64 {
+        self.claimed
+    }
+}
+
+#[tokio::test]
+async fn short_reads_near_eof_are_parser_errors() {
+    use common::{TestEntry, build_zip};
+    use runzip::zip::ZipParser;
+    use std::sync::Arc;
+
+    // The end record is expected past the real data
+    let zip = build_zip(&[TestEntry::new("a.txt", b"alpha")], &[]);
+    let parser = ZipParser::new(Arc::new(OverstatedReader {
+        claimed: zip.len() as u64 + 10,
+        data: zip,
+    }));
+    let err = parser.list_files().await.unwrap_err();
+    assert!(
+        err.to_string().contains("Unexpected end of data"),
+        "{:#}",
+        err
+    );
+
+    // A Local File Header starting 10 bytes before the end
+    let len = build_zip(
+        &[TestEntry {
+            lfh_offset: Some(0),
+            ..TestEntry::new("a.txt", b"alpha")
+        }],
+        &[],
+    )
+    .len();
+    let zip = build_zip(
+        &[TestEntry {
+            lfh_offset: Some(len as u64 - 10),
+            ..TestEntry::new("a.txt", b"alpha")
+        }],
+        &[],
+    );
+    let parser = ZipParser::new(Arc::new(OverstatedReader {
+        claimed: zip.len() as u64,
+        data: zip,
+    }));
+    let entries = parser.list_files().await.unwrap();
+    let err = parser.get_data_offset(&entries[0]).await.unwrap_err();
+    assert!(
+        err.to_string().contains("Unexpected end of data"),
+        "{:#}",
+        err
+    );
+}
@@ -0,0 +1,110 @@
+//! Converting file contents between encodings on extraction.
+
+mod common;
+
+use std::fs;
+
+use runzip::{Pattern, Recode};
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+/// "日本語" in Shift-JIS
+const SJIS: &[u8] = b"\x93\xfa\x96\x7b\x8c\xea";
+
+#[test]
+fn conversions_are_parsed() {
+    assert!(Recode::parse("Shift_JIS:UTF-8").is_ok());
+    for (spec, message) in [
+        ("shift_jis", "Expected an encoding conversion as FROM:TO"),
+        ("klingon:utf-8", "Unknown encoding: klingon"),
+        ("utf-8:utf-16le", "Cannot encode file contents as UTF-16LE"),
+    ] {
+        let err = Recode::parse(spec).unwrap_err();
+        assert!(err.to_string().contains(message), "{}", err);
+    }
+}
+
+#[test]
+fn only_text_is_converted_by_default() {
+    let recode = Recode::parse("shift_jis:utf-8").unwrap();
+    assert_eq!(recode.apply("a.txt", SJIS.to_vec()), "日本語".as_bytes());
+
+    // NUL bytes and invalid sequences mark binary data
+    let binary = b"\x93\xfa\0".to_vec();
+    assert_eq!(recode.apply("a.bin", binary.clone()), binary);
+    let invalid = b"\x93\xfa\x85".to_vec();
+    assert_eq!(recode.apply("a.bin", invalid.clone()), invalid);
+
+    // Characters missing from the target become references
+    let recode = Recode::parse("utf-8:shift_jis").unwrap();
+    assert_eq!(
+        recode.apply("a.txt", "日😀".as_bytes().to_vec()),
+        b"\x93\xfa&#128512;"
+    );
+}
+
+#[test]
+fn patterns_choose_the_converted_files() {
+    let recode = Recode::parse("shift_jis:utf-8")
+        .unwrap()
+        .with_patterns(vec![Pattern::parse("*.txt")]);
+    assert_eq!(recode.apply("a.txt", SJIS.to_vec()), "日本語".as_bytes());
+    assert_eq!(recode.apply("a.csv", SJIS.to_vec()), SJIS);
+
+    // Matching files are converted even if they look binary
+    assert_eq!(
+        recode.apply("b.txt", b"\x93\xfa\0".to_vec()),
+        "日\0".as_bytes()
+    );
+}
+
+#[test]
+fn cli_recodes_extracted_and_piped_files() {
+    let dir = scratch_dir("recode");
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", SJIS),
+            TestEntry::new("b.csv", SJIS),
+            TestEntry::new("c.bin", b"\x93\xfa\0"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--recode", "sjis:utf-8", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        fs::read(dir.join("out/a.txt")).unwrap(),
+        "日本語".as_bytes()
+    );
+    assert_eq!(
+        fs::read(dir.join("out/b.csv")).unwrap(),
+        "日本語".as_bytes()
+    );
+    assert_eq!(fs::read(dir.join("out/c.bin")).unwrap(), b"\x93\xfa\0");
+
+    let output = runzip(
+        &dir,
+        &[
+            "--recode",
+            "sjis:utf-8",
+            "--recode-only",
+            "*.txt",
+            "-p",
+            "a.zip",
+            "a.txt",
+            "b.csv",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let mut expected = b"--- a.txt ---\n".to_vec();
+    expected.extend_from_slice("日本語".as_bytes());
+    expected.extend_from_slice(b"--- b.csv ---\n");
+    expected.extend_from_slice(SJIS);
+    assert_eq!(output.stdout, expected);
+
+    let output = runzip(&dir, &["--recode-only", "*.txt", "-l", "a.zip"]);
+    assert!(!output.status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,55 @@
+//! Joining entry paths onto the output directory with `safe_join`.
+
+mod common;
+
+use std::path::Path;
+
+use runzip::zip::safe_join;
+
+#[test]
+fn safe_paths_are_joined_below_the_root() {
+    assert_eq!(
+        safe_join(Path::new("out"), "./docs/a.txt").unwrap(),
+        Path::new("out/docs/a.txt")
+    );
+    assert_eq!(
+        safe_join(Path::new(""), "a.txt").unwrap(),
+        Path::new("a.txt")
+    );
+    for name in ["../a.txt", "docs/../../a.txt", "/etc/passwd"] {
+        let err = safe_join(Path::new("out"), name).unwrap_err();
+        assert!(
+            err.to_string().contains("Refusing to extract outside"),
+            "{}",
+            err
+        );
+    }
+}
+
+#[cfg(windows)]
+#[test]
+fn junctions_below_the_root_are_refused() {
+    use std::process::Command;
+
+    let dir = common::scratch_dir("safe-join-junction");
+    let (out, elsewhere) = (dir.join("out"), dir.join("elsewhere"));
+    std::fs::create_dir_all(&out).unwrap();
+    std::fs::create_dir_all(&elsewhere).unwrap();
+    let status = Command::new("cmd")
+        .args(["/C", "mklink", "/J"])
+        .arg(out.join("link"))
+        .arg(&elsewhere)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let err = safe_join(&out, "link/a.txt").unwrap_err();
+    assert!(
+        err.to_string().contains("junction or symbolic link"),
+        "{}",
+        err
+    );
+    assert!(safe_join(&out, "other/a.txt").is_ok());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,89 @@
+//! Recovering entries from damaged archives with `--salvage`.
+
+mod common;
+
+use std::fs;
+
+use runzip::Warning;
+
+use common::{TestEntry, build_zip, cd_offset, open, runzip, scratch_dir, stdout};
+
+/// An archive cut off before its Central Directory, ending with the
+/// start of another header.
+fn damaged() -> Vec<u8> {
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::deflated("b.txt", &[b'b'; 1000]),
+            TestEntry {
+                flags: 1,
+                ..TestEntry::new("secret.txt", b"encrypted")
+            },
+        ],
+        &[],
+    );
+    let mut damaged = zip[..cd_offset(&zip)].to_vec();
+    damaged.extend_from_slice(b"PK\x03\x04\x14\x00");
+    damaged
+}
+
+#[tokio::test]
+async fn intact_entries_are_recovered() {
+    let dir = scratch_dir("salvage");
+    let zip = damaged();
+    let extractor = open(&dir, "a.zip", &zip);
+    assert!(extractor.list_files().await.is_err());
+
+    let entries = extractor.salvage().await.unwrap();
+    let names: Vec<&str> = entries.iter().map(|e| e.file_name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "b.txt"]);
+    assert_eq!(
+        extractor.extract_to_memory(&entries[0]).await.unwrap(),
+        b"alpha"
+    );
+    assert_eq!(
+        extractor.extract_to_memory(&entries[1]).await.unwrap(),
+        [b'b'; 1000]
+    );
+
+    let warnings = extractor.take_warnings();
+    let reasons: Vec<String> = warnings
+        .iter()
+        .map(|w| match w {
+            Warning::SalvageFailed { reason, .. } => reason.clone(),
+            other => panic!("unexpected warning {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        ["entry is encrypted", "truncated Local File Header"]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_lists_and_extracts_salvaged_entries() {
+    let dir = scratch_dir("salvage-cli");
+    fs::write(dir.join("a.zip"), damaged()).unwrap();
+
+    let listing = stdout(&runzip(&dir, &["--salvage", "-l", "a.zip"]));
+    assert!(
+        listing.contains("a.txt") && listing.contains("b.txt"),
+        "{}",
+        listing
+    );
+    assert!(!listing.contains("secret.txt"), "{}", listing);
+
+    let output = runzip(&dir, &["--salvage", "-d", "out", "a.zip"]);
+    assert_eq!(fs::read(dir.join("out/a.txt")).unwrap(), b"alpha");
+    assert_eq!(fs::read(dir.join("out/b.txt")).unwrap(), [b'b'; 1000]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Cannot salvage entry at offset"),
+        "{}",
+        stderr
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
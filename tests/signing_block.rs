@@ -0,0 +1,52 @@
+//! Archives with an APK Signing Block before the Central Directory.
+
+mod common;
+
+use common::{TestEntry, apk_signing_block, build_zip, extractor};
+
+#[tokio::test]
+async fn extracts_entries_before_a_signing_block() {
+    let block = apk_signing_block(&[0xAB; 300]);
+    let zip = build_zip(
+        &[
+            TestEntry::new("AndroidManifest.xml", b"<manifest/>"),
+            TestEntry::new("classes.dex", b"dex\n035\0"),
+        ],
+        &block,
+    );
+    let extractor = extractor(zip);
+
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        extractor.extract_to_memory(&entries[0]).await.unwrap(),
+        b"<manifest/>"
+    );
+    assert_eq!(
+        extractor.extract_to_memory(&entries[1]).await.unwrap(),
+        b"dex\n035\0"
+    );
+}
+
+#[tokio::test]
+async fn rejects_data_running_into_the_central_directory() {
+    let block = apk_signing_block(&[0xAB; 300]);
+    // The recorded size covers the signing block and part of the directory
+    let size = (5 + block.len() + 10) as u32;
+    let zip = build_zip(
+        &[TestEntry {
+            cd_size: Some(size),
+            ..TestEntry::new("a.txt", b"hello")
+        }],
+        &block,
+    );
+    let extractor = extractor(zip);
+
+    let entries = extractor.list_files().await.unwrap();
+    let err = extractor.extract_to_memory(&entries[0]).await.unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("runs into the Central Directory"),
+        "{:#}",
+        err
+    );
+}
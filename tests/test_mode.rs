@@ -0,0 +1,167 @@
+//! Integrity tests (`-t`) on the command line.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir, serve, stdout};
+
+/// An archive whose `bad.txt` has a damaged CRC-32.
+fn partly_damaged() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::new("keep/a.txt", b"first"),
+            TestEntry {
+                crc32: Some(0xDEADBEEF),
+                ..TestEntry::new("bad.txt", b"damaged")
+            },
+            TestEntry::deflated("keep/b.txt", b"second second second"),
+        ],
+        &[],
+    )
+}
+
+#[test]
+fn test_mode_verifies_only_selected_entries() {
+    let dir = scratch_dir("test-selected");
+    fs::write(dir.join("a.zip"), partly_damaged()).unwrap();
+
+    let out = stdout(&runzip(&dir, &["-t", "a.zip", "keep/*"]));
+    assert!(out.contains("testing: keep/a.txt"), "{}", out);
+    assert!(out.contains("testing: keep/b.txt"), "{}", out);
+    assert!(!out.contains("bad.txt"), "{}", out);
+    assert!(out.contains("No errors detected"), "{}", out);
+
+    let out = stdout(&runzip(&dir, &["-t", "a.zip", "-x", "bad.txt"]));
+    assert!(!out.contains("bad.txt"), "{}", out);
+
+    let output = runzip(&dir, &["-t", "a.zip"]);
+    assert!(!output.status.success());
+    let out = String::from_utf8_lossy(&output.stdout);
+    assert!(out.contains("testing: bad.txt"), "{}", out);
+    assert!(out.contains("CRC mismatch for bad.txt"), "{}", out);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn large_entries_are_streamed_and_sizes_checked() {
+    let dir = scratch_dir("test-streamed");
+    // Many 256 KiB chunks once decompressed
+    let data: Vec<u8> = (0..3_000_000u32).map(|i| (i * 13 % 241) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::deflated("big.bin", &data),
+            TestEntry {
+                uncompressed_size: Some(data.len() as u64 + 1),
+                ..TestEntry::deflated("short.bin", &data)
+            },
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let out = stdout(&runzip(&dir, &["-t", "a.zip", "big.bin"]));
+    assert!(out.contains("testing: big.bin"), "{}", out);
+    assert!(out.contains("OK"), "{}", out);
+
+    let output = runzip(&dir, &["-t", "a.zip"]);
+    assert!(!output.status.success());
+    let out = String::from_utf8_lossy(&output.stdout);
+    assert!(out.contains("Size mismatch for short.bin"), "{}", out);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("1 of 2 tested files failed"),
+        "{:?}",
+        output
+    );
+    // Nothing is written
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mode_only_fetches_selected_entries() {
+    let zip = partly_damaged();
+    // bad.txt's data follows the first entry
+    let bad_data = (30 + "keep/a.txt".len() + 5 + 30 + "bad.txt".len()) as u64;
+    let server = serve(zip).await;
+    let dir = scratch_dir("test-selected-http");
+
+    let url = server.url.clone();
+    let output = tokio::task::spawn_blocking(move || runzip(&dir, &["-t", &url, "keep/*"]))
+        .await
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+
+    // Data is fetched entry by entry, starting at each entry's data
+    let starts: Vec<u64> = server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|request| {
+            let line = request.lines().find(|l| l.starts_with("range: bytes="))?;
+            line["range: bytes=".len()..]
+                .split('-')
+                .next()?
+                .parse()
+                .ok()
+        })
+        .collect();
+    let first_data = (30 + "keep/a.txt".len()) as u64;
+    assert!(starts.contains(&first_data), "{:?}", starts);
+    assert!(!starts.contains(&bad_data), "{:?}", starts);
+}
+
+#[tokio::test]
+async fn progress_is_reported_while_verifying() {
+    // Several 256 KiB chunks' worth
+    let data: Vec<u8> = (0..600_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::deflated("big.bin", &data),
+            TestEntry {
+                crc32: Some(0),
+                ..TestEntry::new("bad.bin", &data)
+            },
+        ],
+        &[],
+    );
+    let extractor = common::extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    let mut reports = Vec::new();
+    extractor
+        .verify_with_progress(&entries[0], |done| reports.push(done))
+        .await
+        .unwrap();
+    assert!(reports.len() > 1, "{:?}", reports);
+    assert!(reports.is_sorted());
+    assert_eq!(reports.last(), Some(&(data.len() as u64)));
+
+    // Damage is still detected, even with checking turned off for
+    // extraction
+    let extractor = extractor.with_verify_crc(false);
+    let err = extractor
+        .verify_with_progress(&entries[1], |_| {})
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("CRC mismatch"), "{}", err);
+}
+
+#[test]
+fn cli_shows_the_percentage_checked() {
+    let dir = scratch_dir("test-progress");
+    fs::write(dir.join("a.zip"), partly_damaged()).unwrap();
+
+    let output = runzip(&dir, &["-t", "--progress", "a.zip", "keep/*"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout(&output).contains("testing: keep/b.txt"));
+    assert!(stderr.ends_with("100%\n"), "{:?}", stderr);
+
+    // Only meaningful with -t
+    assert!(!runzip(&dir, &["--progress", "a.zip"]).status.success());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
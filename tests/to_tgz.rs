@@ -0,0 +1,131 @@
+//! Converting archives to tar.gz with `--to-tgz`.
+
+mod common;
+
+use std::fs;
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+/// A tar member: type flag, name, mode, link target and data.
+type TarMember = (u8, String, u32, String, Vec<u8>);
+
+/// Parse a tar.gz stream, resolving GNU long names.
+fn read_tgz(tgz: &[u8]) -> Vec<TarMember> {
+    let mut tar = Vec::new();
+    GzDecoder::new(tgz).read_to_end(&mut tar).unwrap();
+    assert_eq!(tar.len() % 512, 0);
+
+    let field = |header: &[u8], range: std::ops::Range<usize>| {
+        let bytes = &header[range];
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8(bytes[..end].to_vec()).unwrap()
+    };
+    let octal = |s: String| u64::from_str_radix(s.trim(), 8).unwrap();
+
+    let mut members = Vec::new();
+    let mut long_name = None;
+    let mut pos = 0;
+    while tar[pos..pos + 512].iter().any(|&b| b != 0) {
+        let header = &tar[pos..pos + 512];
+        assert_eq!(&header[257..263], b"ustar\0");
+        let size = octal(field(header, 124..136)) as usize;
+        let data = tar[pos + 512..pos + 512 + size].to_vec();
+        pos += 512 + size.div_ceil(512) * 512;
+
+        let typeflag = header[156];
+        if typeflag == b'L' {
+            long_name = Some(field(&data, 0..data.len()));
+            continue;
+        }
+        let name = long_name.take().unwrap_or_else(|| {
+            let prefix = field(header, 345..500);
+            let name = field(header, 0..100);
+            if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            }
+        });
+        let mode = octal(field(header, 100..108)) as u32;
+        members.push((typeflag, name, mode, field(header, 157..257), data));
+    }
+    members
+}
+
+#[test]
+fn selected_files_and_their_directories_are_converted() {
+    let dir = scratch_dir("to-tgz");
+    let long = "x".repeat(120);
+    let big: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+    let zip = build_zip(
+        &[
+            TestEntry::dir("dir/").mode(0o40700),
+            TestEntry::dir("dir/sub/"),
+            TestEntry::new("dir/sub/a.txt", b"alpha").mode(0o100640),
+            TestEntry::deflated("dir/big.bin", &big),
+            TestEntry::new("dir/link", b"sub/a.txt").mode(0o120777),
+            TestEntry::new(&long, b"long"),
+            TestEntry::new("other.txt", b"other"),
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--to-tgz", "out.tgz", "a.zip", "dir/*", &long]);
+    assert!(output.status.success(), "{:?}", output);
+    let members = read_tgz(&fs::read(dir.join("out.tgz")).unwrap());
+
+    let summary: Vec<_> = members
+        .iter()
+        .map(|(typeflag, name, mode, link, _)| (*typeflag, name.as_str(), *mode, link.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (b'5', "dir/", 0o700, ""),
+            (b'5', "dir/sub/", 0o755, ""),
+            (b'0', "dir/sub/a.txt", 0o640, ""),
+            (b'0', "dir/big.bin", 0o644, ""),
+            (b'2', "dir/link", 0o777, "sub/a.txt"),
+            (b'0', long.as_str(), 0o644, ""),
+        ]
+    );
+    assert_eq!(members[2].4, b"alpha");
+    assert!(members[3].4 == big);
+    assert_eq!(members[5].4, b"long");
+
+    // To stdout
+    let output = runzip(&dir, &["--to-tgz", "-", "a.zip", "other.txt"]);
+    assert!(output.status.success(), "{:?}", output);
+    let members = read_tgz(&output.stdout);
+    assert_eq!(members.len(), 1);
+    assert_eq!(
+        (members[0].1.as_str(), &members[0].4[..]),
+        ("other.txt", &b"other"[..])
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn corrupt_entries_fail_the_conversion() {
+    let dir = scratch_dir("to-tgz-crc");
+    let zip = build_zip(
+        &[TestEntry {
+            crc32: Some(0),
+            ..TestEntry::new("a.txt", b"alpha")
+        }],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+
+    let output = runzip(&dir, &["--to-tgz", "out.tgz", "a.zip"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("CRC mismatch for a.txt"), "{}", stderr);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
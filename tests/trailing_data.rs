@@ -0,0 +1,86 @@
+//! Archives followed by bytes that aren't part of them.
+
+mod common;
+
+use std::sync::Arc;
+
+use runzip::{HttpRangeReader, ZipExtractor};
+
+use common::{TestEntry, build_zip, cd_offset, eocd, open, scratch_dir, serve};
+
+fn archive() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+            TestEntry::new("c.txt", b"gamma"),
+        ],
+        &[],
+    )
+}
+
+async fn list(name: &str, zip: &[u8]) -> Vec<String> {
+    let dir = scratch_dir(name);
+    let names = open(&dir, "trailing.zip", zip)
+        .list_files()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.file_name)
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    names
+}
+
+#[tokio::test]
+async fn trailing_junk_is_ignored() {
+    let zip = [archive(), vec![0xAA; 100]].concat();
+    assert_eq!(
+        list("trailing-junk", &zip).await,
+        ["a.txt", "b.txt", "c.txt"]
+    );
+}
+
+#[tokio::test]
+async fn remote_archive_with_trailing_junk_is_listed() {
+    let server = serve([archive(), vec![0xAA; 100]].concat()).await;
+    let reader = HttpRangeReader::new(server.url.clone()).await.unwrap();
+    let files = ZipExtractor::new(Arc::new(reader))
+        .list_files()
+        .await
+        .unwrap();
+    assert_eq!(files.len(), 3);
+}
+
+#[tokio::test]
+async fn fake_eocds_in_the_junk_are_skipped() {
+    let zip = archive();
+    let cd = cd_offset(&zip) as u32;
+
+    // Points at the real directory, but its size runs past the record
+    let junk = [eocd(1, 0x00FF_FFFF, cd), vec![0xAA; 10]].concat();
+    assert_eq!(
+        list("trailing-oversized", &[zip.clone(), junk].concat())
+            .await
+            .len(),
+        3
+    );
+
+    // A CDFH signature followed by a name running past the end
+    let mut junk = b"PK\x01\x02".to_vec();
+    junk.extend_from_slice(&[0; 24]);
+    junk.extend_from_slice(&0xFFFFu16.to_le_bytes());
+    junk.extend_from_slice(&[0; 16]);
+    junk.extend(eocd(1, 46, zip.len() as u32));
+    junk.extend_from_slice(&[0xAA; 10]);
+    assert_eq!(
+        list("trailing-unparsable", &[zip.clone(), junk].concat())
+            .await
+            .len(),
+        3
+    );
+
+    // Marked ZIP64 without any ZIP64 records
+    let junk = [eocd(0xFFFF, 0, 0), vec![0xAA; 10]].concat();
+    assert_eq!(list("trailing-zip64", &[zip, junk].concat()).await.len(), 3);
+}
@@ -0,0 +1,84 @@
+//! Recovering entries labeled with the wrong compression method.
+
+mod common;
+
+use std::fs;
+
+use runzip::Warning;
+use runzip::zip::CompressionMethod;
+
+use common::{TestEntry, build_zip, deflate, open, runzip, scratch_dir};
+
+const TEXT: &[u8] = b"mislabeled, mislabeled, mislabeled";
+
+/// A stored entry labeled DEFLATE and a deflated one labeled STORED.
+fn mislabeled() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry {
+                method: 8,
+                payload: Some(TEXT.to_vec()),
+                ..TestEntry::new("stored.txt", TEXT)
+            },
+            TestEntry {
+                payload: Some(deflate(TEXT)),
+                ..TestEntry::new("deflated.txt", TEXT)
+            },
+        ],
+        &[],
+    )
+}
+
+#[tokio::test]
+async fn other_methods_are_tried_only_when_enabled() {
+    let dir = scratch_dir("try-methods");
+    let zip = mislabeled();
+
+    let strict = open(&dir, "a.zip", &zip);
+    for entry in strict.list_files().await.unwrap() {
+        let result = strict.extract_to_memory(&entry).await;
+        assert!(result.is_err() || result.unwrap() != TEXT);
+    }
+
+    let lenient = open(&dir, "a.zip", &zip).with_try_methods(true);
+    let entries = lenient.list_files().await.unwrap();
+    for entry in &entries {
+        assert_eq!(lenient.extract_to_memory(entry).await.unwrap(), TEXT);
+    }
+    assert_eq!(
+        lenient.take_warnings(),
+        [
+            Warning::MethodMismatch {
+                name: "stored.txt".to_string(),
+                declared: CompressionMethod::Deflate,
+                actual: CompressionMethod::Stored,
+            },
+            Warning::MethodMismatch {
+                name: "deflated.txt".to_string(),
+                declared: CompressionMethod::Stored,
+                actual: CompressionMethod::Deflate,
+            },
+        ]
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn cli_reports_the_recovered_method() {
+    let dir = scratch_dir("try-methods-cli");
+    fs::write(dir.join("a.zip"), mislabeled()).unwrap();
+
+    let output = runzip(&dir, &["--try-methods", "-d", "out", "a.zip"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(fs::read(dir.join("out/stored.txt")).unwrap(), TEXT);
+    assert_eq!(fs::read(dir.join("out/deflated.txt")).unwrap(), TEXT);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("stored.txt is labeled Deflate (method 8) but decodes as Stored"),
+        "{}",
+        stderr
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
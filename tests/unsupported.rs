@@ -0,0 +1,78 @@
+//! Entries using compression methods runzip can't decode.
+
+mod common;
+
+use std::fs;
+
+use common::{TestEntry, build_zip, runzip, scratch_dir};
+
+fn archive(dir: &std::path::Path) {
+    let zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry {
+                method: 12,
+                ..TestEntry::new("b.bz2", b"not really bzip2")
+            },
+        ],
+        &[],
+    );
+    fs::write(dir.join("a.zip"), zip).unwrap();
+}
+
+#[test]
+fn unsupported_entries_fail_by_default() {
+    let dir = scratch_dir("unsupported-fail");
+    archive(&dir);
+
+    for args in [
+        &["-d", "out", "a.zip"][..],
+        &["--fail-on-unsupported", "a.zip"],
+    ] {
+        let output = runzip(&dir, args);
+        assert_eq!(output.status.code(), Some(2), "{:?}", output);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("Unsupported compression method 12 (BZIP2)"),
+            "{}",
+            stderr
+        );
+    }
+
+    let output = runzip(
+        &dir,
+        &["--skip-unsupported", "--fail-on-unsupported", "a.zip"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot be used with"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn skipped_entries_exit_with_status_1() {
+    let dir = scratch_dir("unsupported-skip");
+    archive(&dir);
+
+    let output = runzip(&dir, &["--skip-unsupported", "-d", "out", "a.zip"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert_eq!(fs::read(dir.join("out/a.txt")).unwrap(), b"alpha");
+    assert!(!dir.join("out/b.bz2").exists());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Skipping: b.bz2 (unsupported compression method 12 (BZIP2))"),
+        "{}",
+        stderr
+    );
+
+    let output = runzip(&dir, &["--skip-unsupported", "-t", "a.zip"]);
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("b.bz2") && stdout.contains("skipped"),
+        "{}",
+        stdout
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
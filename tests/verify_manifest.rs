@@ -0,0 +1,127 @@
+//! Checking files against a trusted SHA-256 manifest.
+
+mod common;
+
+use aws_lc_rs::digest;
+
+use common::{TestEntry, build_zip, extractor, runzip, scratch_dir, stdout};
+
+fn sha256_hex(data: &[u8]) -> String {
+    digest::digest(&digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn archive() -> Vec<u8> {
+    build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::deflated("dir/b.txt", &b"bravo ".repeat(1000)),
+        ],
+        &[],
+    )
+}
+
+#[tokio::test]
+async fn sha256_hashes_the_extracted_contents() {
+    let extractor = extractor(archive());
+    let entries = extractor.list_files().await.unwrap();
+    let hash: String = extractor
+        .sha256(&entries[1])
+        .await
+        .unwrap()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    assert_eq!(hash, sha256_hex(&b"bravo ".repeat(1000)));
+}
+
+#[test]
+fn one_wrong_hash_fails_the_run() {
+    let dir = scratch_dir("verify-manifest-wrong");
+    std::fs::write(dir.join("test.zip"), archive()).unwrap();
+    std::fs::write(
+        dir.join("trusted.sha256"),
+        format!(
+            "{}  a.txt\n{}  dir/b.txt\n",
+            sha256_hex(b"alpha"),
+            sha256_hex(b"tampered")
+        ),
+    )
+    .unwrap();
+
+    for mode in [&["-d", "out"][..], &["-t"]] {
+        let mut args = mode.to_vec();
+        args.extend(["--verify-manifest", "trusted.sha256", "test.zip"]);
+        let output = runzip(&dir, &args);
+        assert!(!output.status.success(), "{:?}", output);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("dir/b.txt: SHA-256 mismatch"), "{}", stderr);
+        assert!(!stderr.contains("a.txt: SHA-256"), "{}", stderr);
+        assert!(
+            stderr.contains("1 of 2 manifest entries failed"),
+            "{}",
+            stderr
+        );
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn matching_manifests_pass_and_missing_entries_fail() {
+    let dir = scratch_dir("verify-manifest-ok");
+    std::fs::write(dir.join("test.zip"), archive()).unwrap();
+    std::fs::write(
+        dir.join("trusted.sha256"),
+        format!(
+            "# trusted\n{} *./a.txt\n\n{}  dir/b.txt\n",
+            sha256_hex(b"alpha"),
+            sha256_hex(&b"bravo ".repeat(1000)).to_uppercase()
+        ),
+    )
+    .unwrap();
+
+    let output = runzip(
+        &dir,
+        &[
+            "-d",
+            "out",
+            "--verify-manifest",
+            "trusted.sha256",
+            "test.zip",
+        ],
+    );
+    assert!(stdout(&output).contains("Verified 2 files against the manifest."));
+
+    // A listed file left out of the selection is reported
+    let output = runzip(
+        &dir,
+        &[
+            "-t",
+            "--verify-manifest",
+            "trusted.sha256",
+            "test.zip",
+            "a.txt",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("dir/b.txt: listed in the manifest but not selected")
+    );
+
+    // Malformed lines are rejected before anything is extracted
+    std::fs::write(dir.join("bad.sha256"), "not a hash  a.txt\n").unwrap();
+    let output = runzip(
+        &dir,
+        &["-d", "bad", "--verify-manifest", "bad.sha256", "test.zip"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bad.sha256:1"));
+    assert!(!dir.join("bad").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
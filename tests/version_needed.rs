@@ -0,0 +1,70 @@
+//! Entries needing features that can't be extracted.
+
+mod common;
+
+use runzip::ZipError;
+
+use common::{TestEntry, build_zip, extractor};
+
+fn entry(version_needed: u16, flags: u16) -> TestEntry<'static> {
+    TestEntry {
+        version_needed,
+        flags,
+        ..TestEntry::new("a.txt", b"hello")
+    }
+}
+
+#[tokio::test]
+async fn required_features_are_checked_before_decoding() {
+    let zip = build_zip(
+        &[
+            entry(20, 0),
+            entry(45, 0),
+            entry(3 << 8 | 63, 0),
+            entry(51, 0),
+            entry(62, 0),
+            entry(3 << 8 | 64, 0),
+            entry(20, 0x0041),
+        ],
+        &[],
+    );
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+
+    // Versions above what the data needs are common and harmless
+    for entry in &entries[..3] {
+        assert_eq!(entry.unsupported_feature(), None);
+        assert_eq!(extractor.extract_to_memory(entry).await.unwrap(), b"hello");
+    }
+
+    let features: Vec<_> = entries[3..]
+        .iter()
+        .map(|e| e.unsupported_feature().unwrap())
+        .collect();
+    assert_eq!(
+        features,
+        [
+            "AES encryption",
+            "Central Directory encryption",
+            "a feature newer than version 6.3",
+            "strong encryption"
+        ]
+    );
+
+    let err = extractor.extract_to_memory(&entries[3]).await.unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::UnsupportedFeature {
+                version_needed: 51,
+                ..
+            })
+        ),
+        "{:#}",
+        err
+    );
+    assert_eq!(
+        err.to_string(),
+        "a.txt requires AES encryption (version needed to extract 5.1), which is not supported"
+    );
+}
@@ -0,0 +1,72 @@
+//! Warnings collected while reading an archive.
+
+mod common;
+
+use runzip::Warning;
+
+use common::{
+    Layout, TestEntry, build_zip, build_zip_with, cd_offset, eocd_offset, open, scratch_dir,
+};
+
+#[tokio::test]
+async fn parser_warnings_are_collected_not_printed() {
+    let dir = scratch_dir("warnings");
+    let entries = [TestEntry::new("a.txt", b"alpha")];
+    let zip = build_zip(&entries, &[]);
+    let cd_size = eocd_offset(&zip) - cd_offset(&zip);
+    let zip = build_zip_with(
+        &entries,
+        &Layout {
+            cd_size: Some(cd_size as u32 - 4),
+            ..Layout::default()
+        },
+    );
+
+    let extractor = open(&dir, "a.zip", &zip);
+    extractor.list_files().await.unwrap();
+    let warnings = extractor.take_warnings();
+    assert_eq!(
+        warnings,
+        [Warning::CentralDirectorySizeMismatch {
+            declared: cd_size as u64 - 4,
+            actual: cd_size as u64,
+        }]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        format!(
+            "Central Directory size is {} bytes but {} bytes precede the end record",
+            cd_size - 4,
+            cd_size
+        )
+    );
+    assert!(extractor.take_warnings().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn entry_count_mismatch_is_a_warning() {
+    let dir = scratch_dir("warnings-count");
+    let mut zip = build_zip(
+        &[
+            TestEntry::new("a.txt", b"alpha"),
+            TestEntry::new("b.txt", b"beta"),
+        ],
+        &[],
+    );
+    let eocd = eocd_offset(&zip);
+    zip[eocd + 8..eocd + 12].copy_from_slice(&[3, 0, 3, 0]);
+
+    let extractor = open(&dir, "a.zip", &zip);
+    assert_eq!(extractor.list_files().await.unwrap().len(), 2);
+    assert_eq!(
+        extractor.take_warnings(),
+        [Warning::EntryCountMismatch {
+            declared: 3,
+            found: 2,
+        }]
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
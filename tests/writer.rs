@@ -0,0 +1,124 @@
+//! Writing archives with `ZipWriter` and reading them back.
+
+mod common;
+
+use runzip::{CompressionMethod, DosDateTime, FileOptions, ZipWriter};
+
+use common::{eocd_offset, extractor};
+
+#[tokio::test]
+async fn stored_deflated_and_directory_entries_round_trip() {
+    let modified = DosDateTime::new(2024, 5, 17, 13, 45, 30).unwrap();
+    let text = b"All work and no play makes Jack a dull boy. ".repeat(50);
+
+    let mut writer = ZipWriter::new(Vec::new()).with_comment("build 42");
+    writer
+        .add_directory(
+            "docs",
+            FileOptions {
+                modified: Some(modified),
+                ..FileOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    writer
+        .add_file(
+            "docs/stored.bin",
+            b"\x00\x01\x02 raw bytes",
+            FileOptions {
+                method: CompressionMethod::Stored,
+                unix_mode: Some(0o755),
+                ..FileOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+    writer
+        .add_file("docs/deflated.txt", &text, FileOptions::default())
+        .await
+        .unwrap();
+    writer
+        .add_file("empty", b"", FileOptions::default())
+        .await
+        .unwrap();
+    let zip = writer.finish().await.unwrap();
+
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+    let names: Vec<_> = entries.iter().map(|e| e.file_name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["docs/", "docs/stored.bin", "docs/deflated.txt", "empty"]
+    );
+
+    let dir = &entries[0];
+    assert!(dir.is_directory);
+    assert_eq!(dir.modified(), Some(modified));
+    assert_eq!(dir.unix_mode(), Some(0o040755));
+
+    let stored = &entries[1];
+    assert_eq!(stored.compression_method, CompressionMethod::Stored);
+    assert_eq!(stored.unix_mode(), Some(0o100755));
+    assert_eq!(
+        extractor.extract_to_memory(stored).await.unwrap(),
+        b"\x00\x01\x02 raw bytes"
+    );
+
+    let deflated = &entries[2];
+    assert_eq!(deflated.compression_method, CompressionMethod::Deflate);
+    assert!(deflated.compressed_size < deflated.uncompressed_size);
+    assert_eq!(deflated.unix_mode(), Some(0o100644));
+    assert_eq!(extractor.extract_to_memory(deflated).await.unwrap(), text);
+
+    assert!(
+        extractor
+            .extract_to_memory(&entries[3])
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(extractor.tail_comment().await.unwrap(), "build 42");
+    assert!(extractor.take_warnings().is_empty());
+}
+
+/// Write `count` empty entries and return the archive.
+async fn archive_of(count: u64) -> Vec<u8> {
+    let mut writer = ZipWriter::new(Vec::new());
+    let options = FileOptions {
+        method: CompressionMethod::Stored,
+        ..FileOptions::default()
+    };
+    for i in 0..count {
+        writer
+            .add_file(&i.to_string(), b"", options.clone())
+            .await
+            .unwrap();
+    }
+    writer.finish().await.unwrap()
+}
+
+/// Check whether an archive without a comment has ZIP64 end records.
+fn has_zip64_end(zip: &[u8]) -> bool {
+    let locator = eocd_offset(zip) - 20;
+    &zip[locator..locator + 4] == b"PK\x06\x07"
+}
+
+#[tokio::test]
+async fn entry_count_limit_switches_to_zip64() {
+    // The end record's 16-bit count can't hold 0xFFFF entries, which
+    // marks the real count as being in the ZIP64 record
+    let below = archive_of(0xFFFE).await;
+    assert!(!has_zip64_end(&below));
+
+    let zip = archive_of(0xFFFF).await;
+    assert!(has_zip64_end(&zip));
+    let eocd = eocd_offset(&zip);
+    assert_eq!(&zip[eocd + 8..eocd + 12], &[0xFF; 4]);
+
+    let extractor = extractor(zip);
+    let entries = extractor.list_files().await.unwrap();
+    assert_eq!(entries.len(), 0xFFFF);
+    assert_eq!(entries.last().unwrap().file_name, "65534");
+    assert!(extractor.take_warnings().is_empty());
+}
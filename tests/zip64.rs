@@ -0,0 +1,136 @@
+//! ZIP64 end records and their locator.
+
+mod common;
+
+use runzip::ZipError;
+
+use common::{TestEntry, build_zip, eocd, eocd_offset, open, scratch_dir};
+
+fn assert_malformed_zip64(err: anyhow::Error) {
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::MalformedZip64(_))
+        ),
+        "{:#}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn zip64_eocd_without_locator_is_malformed() {
+    let dir = scratch_dir("zip64-no-locator");
+
+    // Mark a regular archive's end record as ZIP64
+    let mut zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+    let eocd_offset = eocd_offset(&zip);
+    zip[eocd_offset + 8..eocd_offset + 12].fill(0xFF);
+    let err = open(&dir, "marked.zip", &zip)
+        .list_files()
+        .await
+        .unwrap_err();
+    assert_malformed_zip64(err);
+
+    // No room for a locator at all
+    let zip = eocd(0xFFFF, 0, 0);
+    let err = open(&dir, "short.zip", &zip)
+        .list_files()
+        .await
+        .unwrap_err();
+    assert_malformed_zip64(err);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Turn a single-entry archive into a ZIP64 one, with `extensible` as
+/// the ZIP64 end record's extensible data sector and `size` as its size
+/// field, if not the actual size.
+fn with_zip64_eocd(
+    zip: &[u8],
+    version_needed: u16,
+    extensible: &[u8],
+    size: Option<u64>,
+) -> Vec<u8> {
+    let eocd_offset = eocd_offset(zip);
+    let cd_offset = common::cd_offset(zip) as u64;
+    let cd_size = eocd_offset as u64 - cd_offset;
+    let mut out = zip[..eocd_offset].to_vec();
+
+    let eocd64_offset = out.len() as u64;
+    out.extend_from_slice(b"PK\x06\x06");
+    let actual = 44 + extensible.len() as u64;
+    out.extend_from_slice(&size.unwrap_or(actual).to_le_bytes());
+    out.extend_from_slice(&45u16.to_le_bytes());
+    out.extend_from_slice(&version_needed.to_le_bytes());
+    out.extend_from_slice(&[0; 8]); // This disk, disk of the directory
+    out.extend_from_slice(&1u64.to_le_bytes());
+    out.extend_from_slice(&1u64.to_le_bytes());
+    out.extend_from_slice(&cd_size.to_le_bytes());
+    out.extend_from_slice(&cd_offset.to_le_bytes());
+    out.extend_from_slice(extensible);
+
+    out.extend_from_slice(b"PK\x06\x07");
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&eocd64_offset.to_le_bytes());
+    out.extend_from_slice(&1u32.to_le_bytes());
+
+    out.extend_from_slice(&eocd(0xFFFF, 0xFFFF_FFFF, 0xFFFF_FFFF));
+    out
+}
+
+#[tokio::test]
+async fn extensible_data_is_read_with_the_record() {
+    let dir = scratch_dir("zip64-extensible");
+    let zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+
+    let plain = with_zip64_eocd(&zip, 45, &[], None);
+    let entries = open(&dir, "plain.zip", &plain).list_files().await.unwrap();
+    assert_eq!(entries[0].file_name, "a.txt");
+
+    let extended = with_zip64_eocd(&zip, 45, &[0xAB; 100], None);
+    let entries = open(&dir, "extended.zip", &extended)
+        .list_files()
+        .await
+        .unwrap();
+    assert_eq!(entries[0].file_name, "a.txt");
+
+    // Version 2 records are only written with an encrypted directory
+    let v2 = with_zip64_eocd(&zip, 62, &[0; 28], None);
+    let err = open(&dir, "v2.zip", &v2).list_files().await.unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<ZipError>(),
+            Some(ZipError::UnsupportedFeature {
+                feature: "Central Directory encryption",
+                ..
+            })
+        ),
+        "{:#}",
+        err
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn record_sizes_are_bounded() {
+    let dir = scratch_dir("zip64-record-size");
+    let zip = build_zip(&[TestEntry::new("a.txt", b"hello")], &[]);
+
+    // The record is really there, but its extensible data is oversized
+    let huge = with_zip64_eocd(&zip, 45, &vec![0; 100_000], None);
+    let err = open(&dir, "huge.zip", &huge)
+        .list_files()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too large"), "{:#}", err);
+    assert_malformed_zip64(err);
+
+    for (name, size) in [("small.zip", 40), ("past.zip", 200), ("max.zip", u64::MAX)] {
+        let zip = with_zip64_eocd(&zip, 45, &[], Some(size));
+        let err = open(&dir, name, &zip).list_files().await.unwrap_err();
+        assert_malformed_zip64(err);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,76 @@
+//! Printing just the archive comment with `--zip-comment`.
+
+mod common;
+
+use std::sync::Arc;
+
+use runzip::{HttpRangeReader, ZipExtractor};
+
+use common::{Layout, TestEntry, build_zip_with, extractor, runzip, scratch_dir, serve, stdout};
+
+fn archive(comment: &[u8]) -> Vec<u8> {
+    build_zip_with(
+        &[TestEntry::new("a.txt", b"hello")],
+        &Layout {
+            comment,
+            ..Layout::default()
+        },
+    )
+}
+
+/// GET requests after the HEAD request and the size probe.
+fn reads(requests: &[String]) -> Vec<&String> {
+    requests
+        .iter()
+        .filter(|r| r.starts_with("GET"))
+        .skip(1)
+        .collect()
+}
+
+#[tokio::test]
+async fn comment_is_read_with_a_single_tail_request() {
+    let zip = archive(b"build 1234");
+    let len = zip.len();
+    let server = serve(zip).await;
+    let reader = HttpRangeReader::new(server.url.clone()).await.unwrap();
+    let extractor = ZipExtractor::new(Arc::new(reader));
+
+    assert_eq!(extractor.tail_comment().await.unwrap(), "build 1234");
+    let requests = server.requests.lock().unwrap().clone();
+    let reads = reads(&requests);
+    assert_eq!(reads.len(), 1, "{:?}", reads);
+    assert!(
+        reads[0]
+            .to_ascii_lowercase()
+            .contains(&format!("-{}\r\n", len - 1)),
+        "{}",
+        reads[0]
+    );
+}
+
+#[tokio::test]
+async fn cli_prints_only_the_comment() {
+    let server = serve(archive(b"build 1234")).await;
+    let dir = scratch_dir("zip-comment");
+
+    let url = server.url.clone();
+    let run_dir = dir.clone();
+    let output = tokio::task::spawn_blocking(move || runzip(&run_dir, &["--zip-comment", &url]))
+        .await
+        .unwrap();
+    assert_eq!(stdout(&output), "build 1234\n");
+    assert_eq!(reads(&server.requests.lock().unwrap()).len(), 1);
+
+    // No comment prints nothing
+    std::fs::write(dir.join("a.zip"), archive(b"")).unwrap();
+    assert_eq!(stdout(&runzip(&dir, &["--zip-comment", "a.zip"])), "");
+    assert!(!dir.join("a.txt").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn tail_comment_needs_an_end_record() {
+    let extractor = extractor(vec![0u8; 100]);
+    assert!(extractor.tail_comment().await.is_err());
+}